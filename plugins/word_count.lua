@@ -0,0 +1,13 @@
+-- Example plugin: a word count panel and an upper-case transform.
+
+app.register_panel("Word count", function(note)
+  local words = 0
+  for _ in note:gmatch("%S+") do
+    words = words + 1
+  end
+  return words .. " words"
+end)
+
+app.register_transform("UPPERCASE", function(text)
+  return text:upper()
+end)
@@ -0,0 +1,146 @@
+use crate::config::AppearanceConfig;
+use eframe::egui;
+
+/// The parts of the window F6 and Shift+F6 move the keyboard focus
+/// between, in order. The editor keeps Tab for itself, so this is also
+/// the way out of it without a mouse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Region {
+    Menu,
+    Sidebar,
+    Editor,
+    Suggestions,
+}
+
+const REGIONS: [Region; 4] = [Region::Menu, Region::Sidebar, Region::Editor, Region::Suggestions];
+
+fn pending_id() -> egui::Id {
+    egui::Id::new("a11y_focus_pending")
+}
+
+fn shown_id(region: Region) -> egui::Id {
+    egui::Id::new(("a11y_region_shown", region))
+}
+
+fn current_id() -> egui::Id {
+    egui::Id::new("a11y_focus_current")
+}
+
+/// Moves the focus to the next or previous region on screen for F6 and
+/// Shift+F6, and opens the menu with F10. Returns whether F10 was pressed.
+pub fn handle_keys(ctx: &egui::Context, editor: egui::Id) -> bool {
+    let (forward, back, menu) = ctx.input_mut(|i| {
+        (
+            i.consume_key(egui::Modifiers::NONE, egui::Key::F6),
+            i.consume_key(egui::Modifiers::SHIFT, egui::Key::F6),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::F10),
+        )
+    });
+    if menu {
+        focus(ctx, Region::Menu);
+    }
+    if !forward && !back {
+        return menu;
+    }
+    let frame = ctx.frame_nr();
+    let shown: Vec<Region> = REGIONS
+        .into_iter()
+        .filter(|&r| ctx.data(|d| d.get_temp::<u64>(shown_id(r))).is_some_and(|at| at + 1 >= frame))
+        .collect();
+    if shown.is_empty() {
+        return menu;
+    }
+    let current = if ctx.memory(|m| m.has_focus(editor)) {
+        Region::Editor
+    } else {
+        ctx.data(|d| d.get_temp(current_id())).unwrap_or(Region::Editor)
+    };
+    let index = shown.iter().position(|&r| r == current).unwrap_or(0);
+    let next = if forward { (index + 1) % shown.len() } else { (index + shown.len() - 1) % shown.len() };
+    focus(ctx, shown[next]);
+    menu
+}
+
+/// Asks `region` to take the focus the next time it is drawn.
+pub fn focus(ctx: &egui::Context, region: Region) {
+    ctx.data_mut(|d| d.insert_temp(pending_id(), region));
+}
+
+/// Called by each region as it is drawn, with its first widget: notes the
+/// region as being on screen and focuses the widget when it is its turn.
+pub fn region(response: &egui::Response, region: Region) {
+    let ctx = &response.ctx;
+    ctx.data_mut(|d| d.insert_temp(shown_id(region), ctx.frame_nr()));
+    if ctx.data(|d| d.get_temp::<Region>(pending_id())) == Some(region) {
+        ctx.data_mut(|d| {
+            d.remove::<Region>(pending_id());
+            d.insert_temp(current_id(), region);
+        });
+        response.request_focus();
+        response.scroll_to_me(None);
+    }
+}
+
+/// Gives a widget the name screen readers announce, for ones whose text
+/// is only an icon or does not say enough on its own.
+pub fn name(response: &egui::Response, kind: egui::WidgetType, name: impl Into<String>) {
+    let name = name.into();
+    response.widget_info(|| egui::WidgetInfo::labeled(kind, &name));
+}
+
+/// Names a widget for screen readers without changing what it reports
+/// otherwise, e.g. to tell them which note the editor holds.
+pub fn label(ctx: &egui::Context, id: egui::Id, name: String) {
+    ctx.accesskit_node_builder(id, |node| node.set_name(name));
+}
+
+/// egui's dark theme, or one with white text on black, outlined controls
+/// and a bright selection for low vision.
+pub fn visuals(high_contrast: bool) -> egui::Visuals {
+    let mut visuals = egui::Visuals::dark();
+    if !high_contrast {
+        return visuals;
+    }
+    let (black, white, yellow) = (egui::Color32::BLACK, egui::Color32::WHITE, egui::Color32::from_rgb(255, 220, 0));
+    visuals.override_text_color = Some(white);
+    visuals.panel_fill = black;
+    visuals.window_fill = black;
+    visuals.extreme_bg_color = black;
+    visuals.faint_bg_color = egui::Color32::from_gray(24);
+    visuals.window_stroke = egui::Stroke::new(2.0, white);
+    visuals.hyperlink_color = egui::Color32::from_rgb(0, 255, 255);
+    visuals.warn_fg_color = yellow;
+    visuals.error_fg_color = egui::Color32::from_rgb(255, 110, 110);
+    // Deep blue keeps the white text on it readable.
+    visuals.selection.bg_fill = egui::Color32::from_rgb(0, 70, 230);
+    visuals.selection.stroke = egui::Stroke::new(2.0, yellow);
+    visuals.text_cursor.width = 3.0;
+    let widgets = &mut visuals.widgets;
+    for (state, width) in [(&mut widgets.noninteractive, 1.0), (&mut widgets.inactive, 1.5), (&mut widgets.hovered, 2.0), (&mut widgets.active, 2.5), (&mut widgets.open, 2.0)] {
+        state.bg_fill = black;
+        state.weak_bg_fill = black;
+        state.bg_stroke = egui::Stroke::new(width, white);
+        state.fg_stroke = egui::Stroke::new(width, white);
+    }
+    widgets.hovered.bg_stroke.color = yellow;
+    widgets.active.bg_stroke.color = yellow;
+    visuals
+}
+
+/// The editor's column for the configured characters per line, centered
+/// in `available`, or all of it.
+pub fn text_column(ui: &egui::Ui, available: egui::Rect, appearance: &AppearanceConfig) -> egui::Rect {
+    if appearance.max_line_chars == 0 {
+        return available;
+    }
+    let font = egui::TextStyle::Monospace.resolve(ui.style());
+    let char_width = ui.fonts(|fonts| fonts.glyph_width(&font, 'n')) + appearance.letter_spacing;
+    // The editor's own margin around the text.
+    let padding = 2.0 * ui.spacing().button_padding.x;
+    let width = appearance.max_line_chars as f32 * char_width + padding;
+    if width >= available.width() {
+        return available;
+    }
+    let margin = (available.width() - width) / 2.0;
+    egui::Rect::from_min_max(available.min + egui::vec2(margin, 0.0), available.max - egui::vec2(margin, 0.0))
+}
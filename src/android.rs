@@ -0,0 +1,31 @@
+// Android build of the note app: the desktop app compiled as a library
+// that the activity loads, entered through `android_main` instead of
+// `main`. Build it with `cargo apk build --example android --features android`.
+
+// `main` and the single-instance plumbing it drives go unused here.
+#![allow(dead_code)]
+
+include!("main.rs");
+
+#[cfg(target_os = "android")]
+#[unsafe(no_mangle)]
+fn android_main(app: winit::platform::android::activity::AndroidApp) {
+    use winit::platform::android::EventLoopBuilderExtAndroid;
+
+    // Notes and settings are kept in the app's private storage.
+    if let Some(dir) = app.internal_data_path()
+        && let Err(err) = std::env::set_current_dir(&dir)
+    {
+        eprintln!("Failed to enter {}: {}", dir.display(), err);
+    }
+    logs::init();
+    let options = NativeOptions {
+        event_loop_builder: Some(Box::new(move |builder| {
+            builder.with_android_app(app);
+        })),
+        ..Default::default()
+    };
+    if let Err(err) = eframe::run_native("Rust Note App", options, Box::new(|cc| Box::new(NoteApp::new(cc, None, None)))) {
+        error!("Failed to start: {}", err);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::flashcards::{self, Card};
+use crate::jobs::Job;
+use crate::sidebar::{NoteInfo, title};
+use crate::{frontmatter, vault};
+use std::io;
+use std::path::Path;
+
+/// Deck a note's cards go to: its `deck:` front matter field, or its
+/// folders and title as an Anki deck path like `school::biology`.
+pub fn deck_name(note: &Path, text: &str) -> String {
+    if let Some(deck) = frontmatter::get(text, "deck").filter(|d| !d.trim().is_empty()) {
+        return deck.trim().to_owned();
+    }
+    let key = vault::note_key(note);
+    let mut parts: Vec<&str> = key.split('/').collect();
+    parts.pop();
+    let title = title(note);
+    parts.push(&title);
+    parts.join("::")
+}
+
+/// Cards made from each heading and the paragraph right under it.
+pub fn heading_cards(note: &Path, text: &str) -> Vec<Card> {
+    let fences = crate::markdown::fenced_blocks(text);
+    let key = vault::note_key(note);
+    let mut cards = Vec::new();
+    let mut heading: Option<String> = None;
+    let mut paragraph = Vec::new();
+    let mut offset = 0;
+    let mut flush = |heading: &mut Option<String>, paragraph: &mut Vec<&str>| {
+        if let Some(front) = heading.take()
+            && !paragraph.is_empty()
+        {
+            cards.push(Card {
+                id: format!("{}#{}", key, front),
+                note: note.to_path_buf(),
+                front,
+                back: paragraph.join("\n"),
+            });
+        }
+        paragraph.clear();
+    };
+    for line in text.lines() {
+        let start = offset;
+        offset += line.len() + 1;
+        if fences.iter().any(|f| f.range.contains(&start)) {
+            continue;
+        }
+        let trimmed = line.trim();
+        if trimmed.starts_with('#') && trimmed.trim_start_matches('#').starts_with(' ') {
+            flush(&mut heading, &mut paragraph);
+            heading = Some(trimmed.trim_start_matches('#').trim().to_owned());
+        } else if trimmed.is_empty() {
+            if !paragraph.is_empty() {
+                // Only the first paragraph under a heading is the answer.
+                flush(&mut heading, &mut paragraph);
+            }
+        } else if heading.is_some() {
+            paragraph.push(trimmed);
+        }
+    }
+    flush(&mut heading, &mut paragraph);
+    cards
+}
+
+/// Anki reads fields as HTML when the file says so, keeping line breaks.
+fn field(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\t', " ")
+        .replace('\n', "<br>")
+}
+
+/// Writes every card in the vault as an Anki text file, one card per line
+/// with its deck and the note's tags. With `headings`, headings followed
+/// by a paragraph become cards too. Returns how many cards were written.
+pub fn export(notes: &[NoteInfo], dest: &Path, headings: bool, job: &Job) -> io::Result<usize> {
+    let mut out = String::from("#separator:tab\n#html:true\n#deck column:3\n#tags column:4\n");
+    let mut count = 0;
+    let notes: Vec<&NoteInfo> = notes.iter().filter(|n| !vault::is_archived(&n.path)).collect();
+    for (index, note) in notes.iter().enumerate() {
+        job.check()?;
+        job.progress(index, notes.len());
+        let mut cards = flashcards::parse(&note.path, &note.text);
+        if headings {
+            cards.extend(heading_cards(&note.path, &note.text));
+        }
+        if cards.is_empty() {
+            continue;
+        }
+        let deck = field(&deck_name(&note.path, &note.text));
+        // Anki tags cannot hold spaces and nest with `::`.
+        let tags: Vec<String> = note.tags.iter().map(|t| t.replace(' ', "_").replace('/', "::")).collect();
+        for card in cards {
+            out.push_str(&format!("{}\t{}\t{}\t{}\n", field(&card.front), field(&card.back), deck, tags.join(" ")));
+            count += 1;
+        }
+    }
+    std::fs::write(dest, out)?;
+    Ok(count)
+}
@@ -0,0 +1,101 @@
+use crate::jobs::Job;
+use crate::vault::{self, CONFIG_FILE, HISTORY_DIR, NOTES_DIR};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+/// Writes the vault (notes, attachments, config and optionally history)
+/// into a zip file. Returns the number of files written. A cancelled
+/// export leaves no file behind.
+pub fn export_vault(dest: &Path, include_history: bool, job: &Job) -> io::Result<usize> {
+    let result = write_vault(dest, include_history, job);
+    if result.is_err() {
+        let _ = std::fs::remove_file(dest);
+    }
+    result
+}
+
+fn write_vault(dest: &Path, include_history: bool, job: &Job) -> io::Result<usize> {
+    let mut zip = ZipWriter::new(File::create(dest)?);
+    let options = SimpleFileOptions::default();
+    let history = Path::new(HISTORY_DIR);
+
+    let mut files = vault::walk_files(Path::new(NOTES_DIR));
+    files.retain(|f| !f.starts_with(history));
+    if include_history {
+        files.extend(vault::walk_files(history));
+    }
+    let config = Path::new(CONFIG_FILE);
+    if config.is_file() {
+        files.push(config.to_path_buf());
+    }
+
+    for (index, file) in files.iter().enumerate() {
+        job.check()?;
+        job.progress(index, files.len());
+        let name = file.to_string_lossy().replace('\\', "/");
+        zip.start_file(name, options)?;
+        zip.write_all(&std::fs::read(file)?)?;
+    }
+    zip.finish()?;
+    Ok(files.len())
+}
+
+/// Restores a vault previously written by [`export_vault`], overwriting
+/// files with the same names. Returns the number of files restored. Files
+/// restored before a cancel are kept.
+pub fn import_vault(src: &Path, job: &Job) -> io::Result<usize> {
+    let mut zip = ZipArchive::new(File::open(src)?)?;
+    let mut restored = 0;
+    let mut notes = Vec::new();
+
+    // Everything but the notes first, so a journal in the archive is in
+    // place before the notes are written on top of it.
+    for index in 0..zip.len() {
+        job.check()?;
+        job.progress(index, zip.len());
+        let mut entry = zip.by_index(index)?;
+        let Some(name) = entry.enclosed_name() else {
+            continue;
+        };
+        let known = name.starts_with(NOTES_DIR) || name == Path::new(CONFIG_FILE);
+        if !known || entry.is_dir() {
+            continue;
+        }
+        if vault::is_note(&name) {
+            notes.push(index);
+            continue;
+        }
+        if let Some(parent) = name.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut entry, &mut File::create(&name)?)?;
+        restored += 1;
+    }
+    crate::journal::reload();
+
+    for (done, index) in notes.iter().enumerate() {
+        job.check()?;
+        job.progress(done, notes.len());
+        let mut entry = zip.by_index(*index)?;
+        let Some(name) = entry.enclosed_name() else {
+            continue;
+        };
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes)?;
+        match String::from_utf8(bytes) {
+            Ok(text) => crate::journal::write(&name, &text)?,
+            // Notes in other encodings are restored as they are; the journal only keeps text.
+            Err(err) => {
+                if let Some(parent) = name.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&name, err.into_bytes())?;
+            }
+        }
+        restored += 1;
+    }
+    Ok(restored)
+}
@@ -0,0 +1,78 @@
+use crate::i18n::t;
+use crate::metadata::Metadata;
+use crate::restructure::move_note;
+use crate::vault;
+use eframe::egui;
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing::error;
+
+/// Moves a note into the archive, keeping its folder structure.
+pub fn archive(note: &Path, metadata: &mut Metadata) -> io::Result<PathBuf> {
+    let to = Path::new(vault::ARCHIVE_DIR).join(vault::note_key(note));
+    move_note(note, &to, metadata)
+}
+
+/// Moves an archived note back to where it was archived from.
+pub fn restore(note: &Path, metadata: &mut Metadata) -> io::Result<PathBuf> {
+    let key = vault::note_key(note);
+    let relative = key.strip_prefix("archive/").unwrap_or(&key);
+    let to = Path::new(vault::NOTES_DIR).join(relative);
+    move_note(note, &to, metadata)
+}
+
+/// What the app should do after the user acted in the view.
+pub enum ArchivedEvent {
+    Open(PathBuf),
+    Restored(PathBuf),
+}
+
+/// Lists archived notes for browsing and restoring.
+#[derive(Default)]
+pub struct ArchivedView {
+    pub notes: Option<Vec<PathBuf>>,
+}
+
+impl ArchivedView {
+    pub fn run(&mut self) {
+        self.notes = Some(vault::note_files().into_iter().filter(|n| vault::is_archived(n)).collect());
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, metadata: &mut Metadata) -> Option<ArchivedEvent> {
+        let notes = self.notes.as_ref()?;
+        let mut open = true;
+        let mut event = None;
+
+        egui::Window::new(t("🗄 Archived Notes"))
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                if notes.is_empty() {
+                    ui.label(t("No archived notes."));
+                }
+                egui::ScrollArea::vertical().max_height(420.0).show(ui, |ui| {
+                    for note in notes {
+                        ui.horizontal(|ui| {
+                            let key = vault::note_key(note);
+                            if ui.link(key.strip_prefix("archive/").unwrap_or(&key)).clicked() {
+                                event = Some(ArchivedEvent::Open(note.clone()));
+                            }
+                            if ui.small_button(t("↩ Restore")).clicked() {
+                                match restore(note, metadata) {
+                                    Ok(path) => event = Some(ArchivedEvent::Restored(path)),
+                                    Err(err) => error!("Failed to restore note: {}", err),
+                                }
+                            }
+                        });
+                    }
+                });
+            });
+
+        if !open {
+            self.notes = None;
+        } else if matches!(event, Some(ArchivedEvent::Restored(_))) {
+            self.run();
+        }
+        event
+    }
+}
@@ -0,0 +1,218 @@
+use crate::config::{AiConfig, AiProvider};
+use crate::i18n::t;
+use crate::vault::{self, NOTES_DIR};
+use eframe::egui;
+use serde_json::{Value, json};
+use std::ops::Range;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+
+const SYSTEM_PROMPT: &str = "You are a writing assistant inside a note-taking app. Reply with the requested text only, in Markdown, without preamble.";
+/// Rough cap on vault text sent with a question, in bytes.
+const VAULT_CONTEXT_BUDGET: usize = 12_000;
+
+/// Sends one prompt to the configured provider and returns the reply text.
+pub fn complete(config: &AiConfig, prompt: &str) -> Result<String, String> {
+    let client = crate::http::client();
+    let request = match config.provider {
+        AiProvider::OpenAi => client
+            .post(&config.endpoint)
+            .bearer_auth(&config.api_key)
+            .json(&json!({
+                "model": config.model,
+                "messages": [
+                    {"role": "system", "content": SYSTEM_PROMPT},
+                    {"role": "user", "content": prompt},
+                ],
+            })),
+        AiProvider::Anthropic => client
+            .post(&config.endpoint)
+            .header("x-api-key", &config.api_key)
+            .header("anthropic-version", "2023-06-01")
+            .json(&json!({
+                "model": config.model,
+                "max_tokens": 2048,
+                "system": SYSTEM_PROMPT,
+                "messages": [{"role": "user", "content": prompt}],
+            })),
+        AiProvider::Ollama => client.post(&config.endpoint).json(&json!({
+            "model": config.model,
+            "stream": false,
+            "messages": [
+                {"role": "system", "content": SYSTEM_PROMPT},
+                {"role": "user", "content": prompt},
+            ],
+        })),
+    };
+
+    let body: Value = crate::http::send(request)
+        .map_err(|e| e.to_string())?
+        .json()
+        .map_err(|e| e.to_string())?;
+    if let Some(err) = body.get("error") {
+        return Err(err.get("message").unwrap_or(err).to_string());
+    }
+    let text = match config.provider {
+        AiProvider::OpenAi => body.pointer("/choices/0/message/content"),
+        AiProvider::Anthropic => body.pointer("/content/0/text"),
+        AiProvider::Ollama => body.pointer("/message/content"),
+    };
+    text.and_then(Value::as_str)
+        .map(|t| t.trim().to_owned())
+        .ok_or_else(|| "Unexpected response from provider".to_owned())
+}
+
+/// Notes sharing words with the question, concatenated up to a budget.
+fn vault_context(question: &str) -> String {
+    let words: Vec<String> = question
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 3)
+        .map(str::to_lowercase)
+        .collect();
+    let mut scored: Vec<(usize, String, String)> = vault::walk_files(Path::new(NOTES_DIR))
+        .into_iter()
+        .filter_map(|path| {
+            let text = std::fs::read_to_string(&path).ok()?;
+            let lower = text.to_lowercase();
+            let score = words.iter().filter(|w| lower.contains(w.as_str())).count();
+            (score > 0).then(|| (score, path.display().to_string(), text))
+        })
+        .collect();
+    scored.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+
+    let mut context = String::new();
+    for (_, path, text) in scored {
+        let remaining = VAULT_CONTEXT_BUDGET.saturating_sub(context.len());
+        if remaining == 0 {
+            break;
+        }
+        let excerpt: String = text.chars().take(remaining).collect();
+        context.push_str(&format!("\n--- {} ---\n{}\n", path, excerpt));
+    }
+    context
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Task {
+    Summarize,
+    Rewrite,
+    Continue,
+    Ask,
+}
+
+/// What the panel wants done with the reply.
+pub enum AssistantEdit {
+    Insert(String),
+    Replace(String),
+}
+
+#[derive(Default)]
+pub struct Assistant {
+    pub open: bool,
+    question: String,
+    reply: Option<Result<String, String>>,
+    pending: Option<Receiver<Result<String, String>>>,
+}
+
+impl Assistant {
+    fn start(&mut self, ctx: &egui::Context, config: &AiConfig, task: Task, note: &str, selection: &str) {
+        let prompt = match task {
+            Task::Summarize => format!("Summarize this note in a few bullet points:\n\n{}", note),
+            Task::Rewrite => format!("Rewrite this text to be clearer, keeping its meaning:\n\n{}", selection),
+            Task::Continue => format!("Continue writing this note from where it stops:\n\n{}", note),
+            Task::Ask => format!(
+                "Answer the question using these notes.\n{}\nQuestion: {}",
+                vault_context(&self.question),
+                self.question
+            ),
+        };
+        let (tx, rx) = mpsc::channel();
+        let config = config.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(complete(&config, &prompt));
+            ctx.request_repaint();
+        });
+        self.pending = Some(rx);
+        self.reply = None;
+    }
+
+    /// Right-hand side panel. `selection` is a byte range into `note`.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        config: &AiConfig,
+        note: &str,
+        selection: Option<Range<usize>>,
+    ) -> Option<AssistantEdit> {
+        if let Some(rx) = &self.pending
+            && let Ok(reply) = rx.try_recv()
+        {
+            self.reply = Some(reply);
+            self.pending = None;
+        }
+        if !self.open {
+            return None;
+        }
+
+        let selected = selection.filter(|r| !r.is_empty()).and_then(|r| note.get(r));
+        let mut edit = None;
+        egui::SidePanel::right("assistant").default_width(320.0).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.heading(t("✨ Assistant"));
+                if ui.small_button("✖").clicked() {
+                    self.open = false;
+                }
+            });
+            ui.separator();
+
+            let idle = self.pending.is_none();
+            ui.horizontal_wrapped(|ui| {
+                if ui.add_enabled(idle, egui::Button::new(t("Summarize"))).clicked() {
+                    self.start(ctx, config, Task::Summarize, note, "");
+                }
+                if ui.add_enabled(idle && selected.is_some(), egui::Button::new(t("Rewrite selection"))).clicked() {
+                    self.start(ctx, config, Task::Rewrite, note, selected.unwrap_or_default());
+                }
+                if ui.add_enabled(idle, egui::Button::new(t("Continue writing"))).clicked() {
+                    self.start(ctx, config, Task::Continue, note, "");
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.question).hint_text(t("Ask about your notes…")));
+                let ask = idle && !self.question.trim().is_empty();
+                if ui.add_enabled(ask, egui::Button::new(t("Ask"))).clicked() {
+                    self.start(ctx, config, Task::Ask, note, "");
+                }
+            });
+            ui.separator();
+
+            if !idle {
+                ui.spinner();
+            }
+            match &self.reply {
+                Some(Ok(text)) => {
+                    egui::ScrollArea::vertical().max_height(ui.available_height() - 40.0).show(ui, |ui| {
+                        ui.label(text);
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button(t("Insert at cursor")).clicked() {
+                            edit = Some(AssistantEdit::Insert(text.clone()));
+                        }
+                        if ui.add_enabled(selected.is_some(), egui::Button::new(t("Replace selection"))).clicked() {
+                            edit = Some(AssistantEdit::Replace(text.clone()));
+                        }
+                        if ui.button("📋").on_hover_text(t("Copy")).clicked() {
+                            ui.output_mut(|o| o.copied_text = text.clone());
+                        }
+                    });
+                }
+                Some(Err(err)) => {
+                    ui.colored_label(egui::Color32::LIGHT_RED, err);
+                }
+                None => {}
+            }
+        });
+        edit
+    }
+}
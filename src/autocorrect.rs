@@ -0,0 +1,174 @@
+use crate::config::AutocorrectConfig;
+use crate::frontmatter;
+
+/// A correction just made, kept until the next edit so it can be taken
+/// back.
+struct Correction {
+    /// Where the corrected word starts.
+    start: usize,
+    original: String,
+    replacement_len: usize,
+    /// Length of the space or punctuation that set it off; zero for a quote.
+    trigger_len: usize,
+    /// The note right after the correction.
+    text: String,
+}
+
+/// Whether `at` is in a code block, inline code or the front matter, where
+/// nothing is corrected.
+fn in_code(text: &str, at: usize) -> bool {
+    if frontmatter::range(text).is_some_and(|r| r.contains(&at)) {
+        return true;
+    }
+    let line_start = text[..at].rfind('\n').map_or(0, |i| i + 1);
+    let fenced = text[..line_start]
+        .lines()
+        .filter(|l| l.trim_start().starts_with("```") || l.trim_start().starts_with("~~~"))
+        .count()
+        % 2
+        == 1;
+    fenced || text[line_start..at].matches('`').count() % 2 == 1
+}
+
+/// `replacement` for `word` from the table: an exact match, or a lowercase
+/// entry for a capitalized word, which is capitalized to match.
+fn replacement(config: &AutocorrectConfig, word: &str) -> Option<String> {
+    if let Some((_, to)) = config.replacements.iter().find(|(from, _)| from == word) {
+        return Some(to.clone());
+    }
+    let mut chars = word.chars();
+    let capitalized = chars.next().is_some_and(char::is_uppercase) && chars.all(char::is_lowercase);
+    if !capitalized {
+        return None;
+    }
+    let lower = word.to_lowercase();
+    let (_, to) = config.replacements.iter().find(|(from, _)| *from == lower)?;
+    let mut chars = to.chars();
+    chars.next().map(|c| c.to_uppercase().chain(chars).collect())
+}
+
+/// The typographic form of a quote typed after `before`.
+fn smart_quote(quote: char, before: Option<char>) -> char {
+    let opening = before.is_none_or(|c| c.is_whitespace() || "([{“‘—–-".contains(c));
+    match (quote, opening) {
+        ('"', true) => '“',
+        ('"', false) => '”',
+        (_, true) => '‘',
+        (_, false) => '’',
+    }
+}
+
+/// Fixes words from a table and straightens out quotes as the note is
+/// typed. Each correction can be taken back straight after with Ctrl+Z,
+/// which keeps what was typed, or Backspace, which also removes the space
+/// or punctuation; the word is then left as it is.
+#[derive(Default)]
+pub struct Autocorrect {
+    /// The note as of the last edit.
+    seen: String,
+    last: Option<Correction>,
+    /// Start of a word whose correction was taken back.
+    declined: Option<usize>,
+}
+
+impl Autocorrect {
+    /// Whether Ctrl+Z would take back a correction rather than undo typing.
+    pub fn can_undo(&self, text: &str) -> bool {
+        self.last.as_ref().is_some_and(|last| last.text == text)
+    }
+
+    /// Takes back the last correction, keeping what was typed after it.
+    /// Returns the note and where the cursor goes.
+    pub fn undo(&mut self, text: &str) -> Option<(String, usize)> {
+        let last = self.last.take().filter(|last| last.text == text)?;
+        let mut restored = text.to_owned();
+        restored.replace_range(last.start..last.start + last.replacement_len, &last.original);
+        let cursor = last.start + last.original.len() + last.trigger_len;
+        self.declined = Some(last.start);
+        self.seen = restored.clone();
+        Some((restored, cursor))
+    }
+
+    /// Looks at an edit to the note and returns the corrected note and
+    /// where the cursor goes, if it calls for a correction.
+    pub fn update(&mut self, config: &AutocorrectConfig, text: &str) -> Option<(String, usize)> {
+        let old = std::mem::replace(&mut self.seen, text.to_owned());
+        if old == text {
+            return None;
+        }
+        let (start, end, insert) = crate::text::splice(&old, text);
+        let last = self.last.take();
+        // Backspace over the space or punctuation that set off a correction.
+        if let Some(last) = last
+            && last.text == old
+            && last.trigger_len > 0
+            && insert.is_empty()
+            && end == last.start + last.replacement_len + last.trigger_len
+            && start == end - last.trigger_len
+        {
+            let mut restored = text.to_owned();
+            restored.replace_range(last.start..last.start + last.replacement_len, &last.original);
+            let cursor = last.start + last.original.len();
+            self.declined = Some(last.start);
+            self.seen = restored.clone();
+            return Some((restored, cursor));
+        }
+
+        if !config.enabled || frontmatter::get(text, "autocorrect").is_some_and(|v| v == "false") {
+            return None;
+        }
+        let mut typed = insert.chars();
+        let (Some(typed), None) = (typed.next(), typed.next()) else {
+            return None;
+        };
+        if start != end || in_code(text, start) {
+            return None;
+        }
+        let before = text[..start].chars().next_back();
+
+        if config.smart_quotes && matches!(typed, '"' | '\'') {
+            let quote = smart_quote(typed, before);
+            let mut corrected = text.to_owned();
+            corrected.replace_range(start..start + 1, quote.encode_utf8(&mut [0; 4]));
+            self.last = Some(Correction {
+                start,
+                original: typed.to_string(),
+                replacement_len: quote.len_utf8(),
+                trigger_len: 0,
+                text: corrected.clone(),
+            });
+            self.seen = corrected.clone();
+            return Some((corrected, start + quote.len_utf8()));
+        }
+
+        if typed.is_alphanumeric() || typed == '_' {
+            return None;
+        }
+        let word_start = text[..start].char_indices().rev().take_while(|(_, c)| c.is_alphanumeric()).last()?.0;
+        if self.declined.take() == Some(word_start) {
+            self.declined = Some(word_start);
+            return None;
+        }
+        // Tags, mentions, paths and the like are not words to correct.
+        if text[..word_start].ends_with(['#', '@', '/', '\\', '_', '.', '-', '\'', '’']) {
+            return None;
+        }
+        let word = &text[word_start..start];
+        // "i.e." and other abbreviations.
+        if typed == '.' && word.chars().count() == 1 {
+            return None;
+        }
+        let to = replacement(config, word).filter(|to| to != word)?;
+        let mut corrected = text.to_owned();
+        corrected.replace_range(word_start..start, &to);
+        self.last = Some(Correction {
+            start: word_start,
+            original: word.to_owned(),
+            replacement_len: to.len(),
+            trigger_len: typed.len_utf8(),
+            text: corrected.clone(),
+        });
+        self.seen = corrected.clone();
+        Some((corrected, word_start + to.len() + typed.len_utf8()))
+    }
+}
@@ -0,0 +1,133 @@
+use crate::archive;
+use crate::config::BackupConfig;
+use crate::jobs::{self, Job, JobHandle};
+use crate::i18n::{t, tf};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::TryRecvError;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::error;
+
+const PREFIX: &str = "vault-";
+
+/// Zips the vault into the backup directory on a schedule, as a background job.
+pub struct BackupScheduler {
+    last: Option<SystemTime>,
+    running: Option<JobHandle<io::Result<PathBuf>>>,
+    error: Option<String>,
+}
+
+impl BackupScheduler {
+    pub fn new(config: &BackupConfig) -> Self {
+        Self {
+            last: backups(Path::new(&config.dir)).last().and_then(|p| p.metadata().ok()?.modified().ok()),
+            running: None,
+            error: None,
+        }
+    }
+
+    pub fn tick(&mut self, config: &BackupConfig) {
+        if let Some(rx) = &self.running {
+            match rx.try_recv() {
+                Ok(result) => {
+                    self.running = None;
+                    match result {
+                        Ok(_) => {
+                            self.last = Some(SystemTime::now());
+                            self.error = None;
+                        }
+                        // Cancelled part way: wait for the next interval
+                        // as below.
+                        Err(err) if err.kind() == io::ErrorKind::Interrupted => self.last = Some(SystemTime::now()),
+                        Err(err) => {
+                            error!("Backup failed: {}", err);
+                            self.error = Some(err.to_string());
+                            self.last = Some(SystemTime::now());
+                        }
+                    }
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => {
+                    // Cancelled: wait for the next interval rather than
+                    // starting over straight away.
+                    self.running = None;
+                    self.last = Some(SystemTime::now());
+                }
+            }
+            return;
+        }
+
+        if !config.enabled {
+            return;
+        }
+        let interval = Duration::from_secs(config.interval_minutes * 60);
+        let due = self
+            .last
+            .and_then(|last| last.elapsed().ok())
+            .is_none_or(|elapsed| elapsed >= interval);
+        if due {
+            let dir = PathBuf::from(&config.dir);
+            let keep = config.keep;
+            self.running = Some(jobs::spawn("Backing up the vault", move |job| run_backup(&dir, keep, job)));
+        }
+    }
+
+    /// The status bar text; `None` when backups are off and none is running.
+    pub fn status(&self, config: &BackupConfig) -> Option<String> {
+        if self.running.is_some() {
+            return Some(t("Backing up…"));
+        }
+        if !config.enabled {
+            return None;
+        }
+        if let Some(err) = &self.error {
+            return Some(tf("Backup failed: {}", &[err]));
+        }
+        Some(match self.last.and_then(|last| last.elapsed().ok()) {
+            Some(elapsed) => tf("Last backup: {}", &[&format_elapsed(elapsed)]),
+            None => t("No backups yet"),
+        })
+    }
+}
+
+fn run_backup(dir: &Path, keep: usize, job: &Job) -> io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let dest = dir.join(format!("{}{}.zip", PREFIX, stamp));
+    archive::export_vault(&dest, false, job)?;
+
+    let existing = backups(dir);
+    for old in existing.iter().take(existing.len().saturating_sub(keep)) {
+        std::fs::remove_file(old)?;
+    }
+    Ok(dest)
+}
+
+/// Backup archives in `dir`, oldest first.
+fn backups(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(PREFIX) && n.ends_with(".zip"))
+        })
+        .collect();
+    // Timestamps have the same width for centuries, so name order is age order.
+    files.sort();
+    files
+}
+
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    match secs {
+        0..60 => t("just now"),
+        60..3600 => tf("{} min ago", &[&(secs / 60)]),
+        3600..86400 => tf("{} h ago", &[&(secs / 3600)]),
+        _ => tf("{} days ago", &[&(secs / 86400)]),
+    }
+}
@@ -0,0 +1,140 @@
+/// Which way a paragraph's text runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+/// Hebrew, Arabic, Syriac, Thaana, N'Ko and the other right-to-left blocks.
+fn is_rtl(c: char) -> bool {
+    matches!(c as u32, 0x0590..=0x08FF | 0xFB1D..=0xFDFF | 0xFE70..=0xFEFF | 0x10800..=0x10FFF | 0x1E800..=0x1EFFF)
+}
+
+/// The direction a character sets, or `None` for digits, punctuation and
+/// other characters that take it from their surroundings.
+fn strong(c: char) -> Option<Direction> {
+    if is_rtl(c) {
+        Some(Direction::Rtl)
+    } else if c.is_alphabetic() {
+        Some(Direction::Ltr)
+    } else {
+        None
+    }
+}
+
+pub fn has_rtl(text: &str) -> bool {
+    text.chars().any(is_rtl)
+}
+
+/// The direction of the first letter in `text`, as browsers pick it for
+/// `dir="auto"`.
+pub fn detect(text: &str) -> Option<Direction> {
+    text.chars().find_map(strong)
+}
+
+/// The direction set with `dir: rtl` or `dir: ltr` in the front matter.
+pub fn note_override(text: &str) -> Option<Direction> {
+    match crate::frontmatter::get(text, "dir")?.to_ascii_lowercase().as_str() {
+        "rtl" => Some(Direction::Rtl),
+        "ltr" => Some(Direction::Ltr),
+        _ => None,
+    }
+}
+
+/// The direction of a whole note: its `dir` field, or that of the first
+/// letter after the front matter.
+pub fn note_direction(text: &str) -> Direction {
+    let body = crate::frontmatter::range(text).map_or(text, |r| &text[r.end..]);
+    note_override(text).or_else(|| detect(body)).unwrap_or(Direction::Ltr)
+}
+
+/// A right-to-left word drawn left to right, with brackets turned to face
+/// the way they do in its script.
+fn reversed(word: &str) -> String {
+    word.chars()
+        .rev()
+        .map(|c| match c {
+            '(' => ')',
+            ')' => '(',
+            '[' => ']',
+            ']' => '[',
+            '{' => '}',
+            '}' => '{',
+            '<' => '>',
+            '>' => '<',
+            c => c,
+        })
+        .collect()
+}
+
+/// Words of `text` for laying out one after another in `base` direction,
+/// each in the order its characters are drawn. Words against the base
+/// direction are kept together in one piece, so a Latin name in Hebrew
+/// text or a Hebrew phrase in English keeps its own word order; digits
+/// and punctuation side with the letters around them. A simplified form
+/// of the Unicode bidirectional algorithm, enough for prose.
+pub fn pieces(text: &str, base: Direction) -> Vec<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let strong: Vec<Option<Direction>> = words.iter().map(|w| detect(w)).collect();
+    let resolved: Vec<Direction> = (0..words.len())
+        .map(|i| {
+            strong[i].unwrap_or_else(|| {
+                let before = strong[..i].iter().rev().find_map(|d| *d);
+                let after = strong[i + 1..].iter().find_map(|d| *d);
+                match (before, after) {
+                    (Some(a), Some(b)) if a == b => a,
+                    _ => base,
+                }
+            })
+        })
+        .collect();
+
+    let draw = |word: &str, dir: Direction| if dir == Direction::Rtl && has_rtl(word) { reversed(word) } else { word.to_owned() };
+    let mut groups = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        if resolved[i] == base {
+            groups.push(draw(words[i], base));
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < words.len() && resolved[i] != base {
+            i += 1;
+        }
+        let mut run: Vec<String> = words[start..i].iter().map(|w| draw(w, resolved[start])).collect();
+        if resolved[start] == Direction::Rtl {
+            run.reverse();
+        }
+        groups.push(run.join(" "));
+    }
+
+    // Each piece carries the space that follows it, on the side it follows.
+    let last = groups.len().saturating_sub(1);
+    let space = |yes: bool| if yes { " " } else { "" };
+    groups
+        .into_iter()
+        .enumerate()
+        .map(|(k, group)| {
+            let before = space(k == 0 && text.starts_with(char::is_whitespace));
+            let after = space(k < last || text.ends_with(char::is_whitespace));
+            match base {
+                Direction::Ltr => format!("{}{}{}", before, group, after),
+                Direction::Rtl => format!("{}{}{}", after, group, before),
+            }
+        })
+        .collect()
+}
+
+/// `text` as one line in the order it is drawn, for labels that cannot be
+/// laid out right to left.
+pub fn visual(text: &str, base: Direction) -> String {
+    if !has_rtl(text) {
+        return text.to_owned();
+    }
+    let mut pieces = pieces(text, base);
+    if base == Direction::Rtl {
+        pieces.reverse();
+    }
+    pieces.concat().trim().to_owned()
+}
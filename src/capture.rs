@@ -0,0 +1,232 @@
+use crate::config::{CaptureConfig, CaptureTarget};
+use crate::i18n::{t, tf};
+use crate::vault::{self, NOTES_DIR};
+use eframe::egui;
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+use tracing::{error, warn};
+#[cfg(target_os = "android")]
+use crate::stubs::arboard;
+
+/// The note that captured text is appended to.
+pub fn target_path(config: &CaptureConfig) -> PathBuf {
+    match config.target {
+        CaptureTarget::Inbox => Path::new(NOTES_DIR).join(&config.inbox_note),
+        CaptureTarget::DailyNote => vault::daily_note_path(chrono::Local::now().date_naive()),
+    }
+}
+
+pub fn append(path: &Path, text: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let needs_newline = std::fs::read_to_string(path).is_ok_and(|s| !s.is_empty() && !s.ends_with('\n'));
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    if needs_newline {
+        writeln!(file)?;
+    }
+    writeln!(file, "{}", text.trim_end())?;
+    crate::journal::record(path)
+}
+
+/// System-wide hotkey and the small always-on-top window it opens.
+pub struct QuickCapture {
+    // Unregisters the hotkey when dropped.
+    _manager: Option<GlobalHotKeyManager>,
+    pressed: Option<Receiver<()>>,
+    open: bool,
+    text: String,
+}
+
+impl QuickCapture {
+    pub fn new(config: &CaptureConfig, ctx: &egui::Context) -> Self {
+        let mut capture = Self {
+            _manager: None,
+            pressed: None,
+            open: false,
+            text: String::new(),
+        };
+        if !config.enabled {
+            return capture;
+        }
+        let hotkey: HotKey = match config.hotkey.parse() {
+            Ok(hotkey) => hotkey,
+            Err(err) => {
+                warn!("Invalid capture hotkey {:?}: {}", config.hotkey, err);
+                return capture;
+            }
+        };
+        let manager = match GlobalHotKeyManager::new() {
+            Ok(manager) => manager,
+            Err(err) => {
+                warn!("Global hotkeys unavailable: {}", err);
+                return capture;
+            }
+        };
+        if let Err(err) = manager.register(hotkey) {
+            error!("Failed to register capture hotkey: {}", err);
+            return capture;
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        let id = hotkey.id();
+        GlobalHotKeyEvent::set_event_handler(Some(move |event: GlobalHotKeyEvent| {
+            if event.id == id && event.state == HotKeyState::Pressed {
+                let _ = tx.send(());
+                ctx.request_repaint();
+            }
+        }));
+        capture._manager = Some(manager);
+        capture.pressed = Some(rx);
+        capture
+    }
+
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, config: &CaptureConfig) {
+        if self.pressed.as_ref().is_some_and(|rx| rx.try_iter().count() > 0) {
+            self.open = true;
+        }
+        if !self.open {
+            return;
+        }
+
+        let target = target_path(config);
+        let viewport = egui::ViewportBuilder::default()
+            .with_title(t("Quick Capture"))
+            .with_inner_size([420.0, 160.0])
+            .with_always_on_top();
+        ctx.show_viewport_immediate(egui::ViewportId::from_hash_of("quick_capture"), viewport, |ctx, _| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.label(tf("Append to {}", &[&target.display()]));
+                let edit = ui.add(
+                    egui::TextEdit::multiline(&mut self.text)
+                        .desired_rows(3)
+                        .desired_width(f32::INFINITY),
+                );
+                edit.request_focus();
+                let submit = ui.input(|i| i.modifiers.command && i.key_pressed(egui::Key::Enter));
+                ui.horizontal(|ui| {
+                    if (ui.button(t("Capture (Ctrl+Enter)")).clicked() || submit) && !self.text.trim().is_empty() {
+                        match append(&target, &self.text) {
+                            Ok(()) => {
+                                self.text.clear();
+                                self.open = false;
+                            }
+                            Err(err) => error!("Failed to capture: {}", err),
+                        }
+                    }
+                    if ui.button(t("Cancel")).clicked() {
+                        self.open = false;
+                    }
+                });
+            });
+            if ctx.input(|i| i.viewport().close_requested() || i.key_pressed(egui::Key::Escape)) {
+                self.open = false;
+            }
+        });
+    }
+}
+
+/// How often the clipboard is checked while watching it.
+const CLIPBOARD_POLL: Duration = Duration::from_millis(500);
+
+/// Where copied text seems to come from, for the entry's footer.
+fn source_hint(text: &str) -> String {
+    let url = text.split_whitespace().find(|w| w.starts_with("http://") || w.starts_with("https://"));
+    match url.and_then(|u| u.split('/').nth(2)) {
+        Some(host) => format!("from {}", host),
+        None => "from the clipboard".to_owned(),
+    }
+}
+
+/// Copied text as a quote with the time and source hint under it.
+fn clipping(text: &str) -> String {
+    let quote: Vec<String> = text.trim().lines().map(|l| format!("> {}", l).trim_end().to_owned()).collect();
+    let time = chrono::Local::now().format("%Y-%m-%d %H:%M");
+    format!("\n{}\n\n— {}, {}", quote.join("\n"), time, source_hint(text))
+}
+
+/// Opt-in mode appending everything copied elsewhere to the capture note.
+#[derive(Default)]
+pub struct ClipboardWatcher {
+    /// Set to stop the polling thread.
+    stop: Option<Arc<AtomicBool>>,
+    copied: Option<Receiver<String>>,
+}
+
+impl ClipboardWatcher {
+    pub fn is_active(&self) -> bool {
+        self.stop.is_some()
+    }
+
+    pub fn toggle(&mut self, ctx: &egui::Context) {
+        if let Some(stop) = self.stop.take() {
+            stop.store(true, Ordering::Relaxed);
+            self.copied = None;
+            return;
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        let stopped = stop.clone();
+        std::thread::spawn(move || {
+            let mut clipboard = match arboard::Clipboard::new() {
+                Ok(clipboard) => clipboard,
+                Err(err) => {
+                    error!("Failed to watch the clipboard: {}", err);
+                    return;
+                }
+            };
+            // Only what is copied from now on is captured.
+            let mut last = clipboard.get_text().unwrap_or_default();
+            while !stopped.load(Ordering::Relaxed) {
+                std::thread::sleep(CLIPBOARD_POLL);
+                if let Ok(text) = clipboard.get_text()
+                    && text != last
+                {
+                    last = text.clone();
+                    if !text.trim().is_empty() && tx.send(text).is_ok() {
+                        ctx.request_repaint();
+                    }
+                }
+            }
+        });
+        self.stop = Some(stop);
+        self.copied = Some(rx);
+    }
+
+    /// Appends what was copied since the last frame, skipping copies made
+    /// in this app. Returns the capture note when it changed.
+    pub fn poll(&mut self, ctx: &egui::Context, config: &CaptureConfig) -> Option<PathBuf> {
+        let copied: Vec<String> = self.copied.as_ref()?.try_iter().collect();
+        if copied.is_empty() || ctx.input(|i| i.focused) {
+            return None;
+        }
+        let target = target_path(config);
+        for text in copied {
+            if let Err(err) = append(&target, &clipping(&text)) {
+                error!("Failed to capture clipboard: {}", err);
+                return None;
+            }
+        }
+        Some(target)
+    }
+}
+
+impl Drop for ClipboardWatcher {
+    fn drop(&mut self) {
+        if let Some(stop) = &self.stop {
+            stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use crate::config::CitationConfig;
+use crate::editor::Candidate;
+use eframe::egui;
+use std::ops::Range;
+use std::path::Path;
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+use tracing::error;
+
+/// How many completions the popup lists at most.
+const MAX_CANDIDATES: usize = 8;
+
+#[derive(Debug, Clone, Default)]
+pub struct Reference {
+    pub key: String,
+    pub author: String,
+    pub title: String,
+    pub year: String,
+    /// Journal, book or publisher.
+    pub container: String,
+}
+
+impl Reference {
+    /// Short author for in-text citations: "Doe", "Doe and Roe" or "Doe et al.".
+    pub fn short_author(&self) -> String {
+        let names: Vec<&str> = self.author.split(" and ").map(str::trim).filter(|n| !n.is_empty()).collect();
+        let last = |name: &str| match name.split_once(',') {
+            Some((last, _)) => last.trim().to_owned(),
+            None => name.split_whitespace().last().unwrap_or(name).to_owned(),
+        };
+        match names.as_slice() {
+            [] => self.key.clone(),
+            [one] => last(one),
+            [a, b] => format!("{} and {}", last(a), last(b)),
+            [first, ..] => format!("{} et al.", last(first)),
+        }
+    }
+
+    fn year_or_nd(&self) -> &str {
+        if self.year.is_empty() { "n.d." } else { &self.year }
+    }
+
+    fn bibliography_entry(&self) -> String {
+        let mut entry = format!("{} ({}). *{}*.", self.author, self.year_or_nd(), self.title);
+        if !self.container.is_empty() {
+            entry.push_str(&format!(" {}.", self.container));
+        }
+        entry
+    }
+}
+
+/// Reads the entries of a BibTeX database. Malformed entries are skipped
+/// rather than failing the whole file.
+pub fn parse_bibtex(source: &str) -> Vec<Reference> {
+    let mut references = Vec::new();
+    let mut rest = source;
+    while let Some(at) = rest.find('@') {
+        rest = &rest[at + 1..];
+        let Some(open) = rest.find(['{', '(']) else {
+            break;
+        };
+        let kind = rest[..open].trim().to_lowercase();
+        let Some(body_len) = balanced_len(&rest[open..]) else {
+            break;
+        };
+        let body = &rest[open + 1..open + body_len - 1];
+        rest = &rest[open + body_len..];
+        if matches!(kind.as_str(), "comment" | "string" | "preamble") {
+            continue;
+        }
+        let Some((key, fields)) = body.split_once(',') else {
+            continue;
+        };
+        let mut reference = Reference {
+            key: key.trim().to_owned(),
+            ..Default::default()
+        };
+        for (name, value) in fields_of(fields) {
+            match name.as_str() {
+                "author" | "editor" if reference.author.is_empty() => reference.author = value,
+                "title" => reference.title = value,
+                "year" => reference.year = value,
+                "date" if reference.year.is_empty() => reference.year = value.chars().take(4).collect(),
+                "journal" | "journaltitle" | "booktitle" | "publisher" if reference.container.is_empty() => {
+                    reference.container = value
+                }
+                _ => {}
+            }
+        }
+        if !reference.key.is_empty() {
+            references.push(reference);
+        }
+    }
+    references
+}
+
+/// Length of the bracketed group `text` starts with, brackets included.
+fn balanced_len(text: &str) -> Option<usize> {
+    let (open, close) = if text.starts_with('(') { ('(', ')') } else { ('{', '}') };
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i + 1);
+            }
+        }
+    }
+    None
+}
+
+/// `name = {value}`, `name = "value"` and `name = 1999` pairs.
+fn fields_of(mut text: &str) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+    while let Some(eq) = text.find('=') {
+        let name = text[..eq].trim().trim_start_matches(',').trim().to_lowercase();
+        let value = text[eq + 1..].trim_start();
+        let (raw, consumed) = if value.starts_with('{') {
+            match balanced_len(value) {
+                Some(len) => (&value[1..len - 1], len),
+                None => break,
+            }
+        } else if let Some(quoted) = value.strip_prefix('"') {
+            match quoted.find('"') {
+                Some(end) => (&quoted[..end], end + 2),
+                None => break,
+            }
+        } else {
+            let end = value.find(',').unwrap_or(value.len());
+            (&value[..end], end)
+        };
+        fields.push((name, clean(raw)));
+        text = &value[consumed..];
+    }
+    fields
+}
+
+/// Drops TeX grouping braces and common escapes.
+fn clean(value: &str) -> String {
+    let unescaped = value.replace("\\&", "&").replace("\\%", "%").replace("\\_", "_").replace(['{', '}'], "");
+    unescaped.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn fetch_zotero(url: &str) -> Result<String, String> {
+    let client = crate::http::client();
+    let get = |url: &str| {
+        crate::http::send(client.get(url).timeout(Duration::from_secs(20)))
+            .and_then(|r| r.error_for_status())
+            .and_then(|r| r.text())
+            .map_err(|e| e.to_string())
+    };
+    // The Zotero local API pages its results; other endpoints (e.g. Better
+    // BibTeX exports) answer with the whole library at once.
+    if !url.contains("/api/") {
+        return get(url);
+    }
+    let separator = if url.contains('?') { '&' } else { '?' };
+    let mut bibtex = String::new();
+    for start in (0..).step_by(100) {
+        let page = get(&format!("{}{}limit=100&start={}", url, separator, start))?;
+        if page.trim().is_empty() {
+            break;
+        }
+        bibtex.push_str(&page);
+        bibtex.push('\n');
+    }
+    Ok(bibtex)
+}
+
+fn load(config: &CitationConfig) -> Result<String, String> {
+    let mut bibtex = String::new();
+    if !config.bibtex_file.is_empty() {
+        bibtex = std::fs::read_to_string(&config.bibtex_file)
+            .map_err(|e| format!("{}: {}", config.bibtex_file, e))?;
+    }
+    if !config.zotero_url.is_empty() {
+        bibtex.push('\n');
+        bibtex.push_str(&fetch_zotero(&config.zotero_url).map_err(|e| format!("Zotero: {}", e))?);
+    }
+    Ok(bibtex)
+}
+
+/// References from the configured BibTeX file and Zotero library, reloaded
+/// in the background whenever those settings change.
+#[derive(Default)]
+pub struct Library {
+    pub references: Vec<Reference>,
+    /// The BibTeX the references were read from, handed to pandoc on export.
+    pub bibtex: String,
+    source: Option<CitationConfig>,
+    pending: Option<Receiver<Result<String, String>>>,
+}
+
+impl Library {
+    pub fn update(&mut self, ctx: &egui::Context, config: &CitationConfig) {
+        if let Some(rx) = &self.pending
+            && let Ok(result) = rx.try_recv()
+        {
+            match result {
+                Ok(bibtex) => {
+                    self.references = parse_bibtex(&bibtex);
+                    self.bibtex = bibtex;
+                }
+                Err(err) => error!("Failed to load citations: {}", err),
+            }
+            self.pending = None;
+        }
+
+        let source = (!config.bibtex_file.is_empty() || !config.zotero_url.is_empty()).then(|| config.clone());
+        let current = self.source.as_ref().map(|s| (&s.bibtex_file, &s.zotero_url));
+        if current == source.as_ref().map(|s| (&s.bibtex_file, &s.zotero_url)) {
+            return;
+        }
+        self.source = source;
+        self.references.clear();
+        self.bibtex.clear();
+        self.pending = None;
+        if let Some(config) = self.source.clone() {
+            let (tx, rx) = mpsc::channel();
+            let ctx = ctx.clone();
+            std::thread::spawn(move || {
+                let _ = tx.send(load(&config));
+                ctx.request_repaint();
+            });
+            self.pending = Some(rx);
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Reference> {
+        self.references.iter().find(|r| r.key == key)
+    }
+}
+
+fn is_key_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | ':' | '.' | '+' | '/')
+}
+
+/// A citation key starting at `text`, without trailing punctuation.
+fn key_at(text: &str) -> &str {
+    let end = text.find(|c: char| !is_key_char(c)).unwrap_or(text.len());
+    text[..end].trim_end_matches(['.', ':', '-', '/'])
+}
+
+/// `@key` completions for the word ending at `cursor` (a byte offset).
+/// Returns the range to replace and the matching references.
+pub fn complete(text: &str, cursor: usize, library: &Library) -> Option<(Range<usize>, Vec<Candidate>)> {
+    let before = text.get(..cursor)?;
+    let word_start = before
+        .char_indices()
+        .rev()
+        .find(|&(_, c)| !is_key_char(c))
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let at = word_start.checked_sub(1)?;
+    if before.as_bytes().get(at) != Some(&b'@') {
+        return None;
+    }
+    // Not an email address or a mention inside a word.
+    if before[..at].chars().next_back().is_some_and(|c| c.is_alphanumeric()) {
+        return None;
+    }
+    let prefix = before[word_start..].to_lowercase();
+    let candidates: Vec<Candidate> = library
+        .references
+        .iter()
+        .filter(|r| {
+            prefix.is_empty()
+                || r.key.to_lowercase().starts_with(&prefix)
+                || r.author.to_lowercase().contains(&prefix)
+                || r.title.to_lowercase().contains(&prefix)
+        })
+        .take(MAX_CANDIDATES)
+        .map(|r| Candidate {
+            label: format!("@{}  {} {}", r.key, r.short_author(), r.year),
+            detail: r.title.clone(),
+            insert: format!("@{}", r.key),
+        })
+        .collect();
+    (!candidates.is_empty()).then_some((at..cursor, candidates))
+}
+
+/// Replaces pandoc-style citations with author-year text and appends a
+/// reference list, for the preview. Code blocks are left alone.
+pub fn render(markdown: &str, library: &Library) -> String {
+    if library.references.is_empty() {
+        return markdown.to_owned();
+    }
+    let mut cited: Vec<&Reference> = Vec::new();
+    let mut out = String::with_capacity(markdown.len());
+    let mut last = 0;
+    for block in crate::markdown::fenced_blocks(markdown) {
+        out.push_str(&render_span(&markdown[last..block.range.start], library, &mut cited));
+        out.push_str(&markdown[block.range.clone()]);
+        last = block.range.end;
+    }
+    out.push_str(&render_span(&markdown[last..], library, &mut cited));
+
+    if !cited.is_empty() {
+        cited.sort_by(|a, b| (&a.author, &a.year).cmp(&(&b.author, &b.year)));
+        out.push_str("\n\n## References\n\n");
+        for reference in cited {
+            out.push_str(&format!("- {}\n", reference.bibliography_entry()));
+        }
+    }
+    out
+}
+
+fn render_span<'a>(text: &str, library: &'a Library, cited: &mut Vec<&'a Reference>) -> String {
+    let mut cite = |key: &str| -> Option<&'a Reference> {
+        let reference = library.get(key)?;
+        if !cited.iter().any(|r| r.key == reference.key) {
+            cited.push(reference);
+        }
+        Some(reference)
+    };
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find(['[', '@']) {
+        let (head, tail) = rest.split_at(i);
+        out.push_str(head);
+        let previous = out.chars().next_back();
+
+        // [see @doe99, p. 33; @roe04]
+        if tail.starts_with('[')
+            && let Some(end) = tail.find(']')
+            && tail[..end].contains('@')
+            && !tail[end + 1..].starts_with('(')
+        {
+            let items: Vec<String> = tail[1..end]
+                .split(';')
+                .map(|item| match item.find('@') {
+                    Some(at) => {
+                        let key = key_at(&item[at + 1..]);
+                        let locator = &item[at + 1 + key.len()..];
+                        let name = match cite(key) {
+                            Some(r) => format!("{} {}", r.short_author(), r.year_or_nd()),
+                            None => format!("{}?", key),
+                        };
+                        format!("{}{}{}", &item[..at], name, locator).trim().to_owned()
+                    }
+                    None => item.trim().to_owned(),
+                })
+                .collect();
+            out.push_str(&format!("({})", items.join("; ")));
+            rest = &tail[end + 1..];
+            continue;
+        }
+
+        // Bare @doe99 in running text, only for known keys.
+        if let Some(after) = tail.strip_prefix('@')
+            && !previous.is_some_and(|c| c.is_alphanumeric())
+        {
+            let key = key_at(after);
+            if let Some(reference) = cite(key) {
+                out.push_str(&format!("{} ({})", reference.short_author(), reference.year_or_nd()));
+                rest = &after[key.len()..];
+                continue;
+            }
+        }
+
+        out.push_str(&tail[..1]);
+        rest = &tail[1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Converts a note with pandoc, resolving citations against `bibtex`.
+/// The output format follows the extension of `dest`.
+pub fn export(markdown: &str, bibtex: &str, config: &CitationConfig, dest: &Path) -> Result<(), String> {
+    let mut command = Command::new("pandoc");
+    command.args(["--from", "markdown", "--standalone", "--citeproc", "-o"]).arg(dest);
+    let bibliography = std::env::temp_dir().join("noteapp-citations.bib");
+    if !bibtex.trim().is_empty() {
+        std::fs::write(&bibliography, bibtex).map_err(|e| e.to_string())?;
+        command.arg("--bibliography").arg(&bibliography);
+    }
+    // docx, odt and pdf typeset TeX math natively; HTML needs it as MathML.
+    if dest.extension().is_some_and(|e| e == "html" || e == "epub") {
+        command.arg("--mathml");
+    }
+    if !config.csl_style.is_empty() {
+        command.arg("--csl").arg(&config.csl_style);
+    }
+
+    let mut child = command
+        .stdin(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("pandoc: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        use std::io::Write;
+        stdin.write_all(markdown.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_owned())
+    }
+}
@@ -0,0 +1,127 @@
+use crate::config::EditorConfig;
+use crate::diff::{self, Chunk};
+use crate::i18n::t;
+use eframe::egui::{self, Color32, RichText};
+use std::path::{Path, PathBuf};
+
+/// Whether any cleanup is turned on.
+pub fn enabled(config: &EditorConfig) -> bool {
+    config.trim_trailing_whitespace || config.final_newline || config.collapse_blank_lines
+}
+
+/// `text` with the cleanups turned on in `config`. Code blocks are left as
+/// they are, and two trailing spaces before another line stay, since
+/// Markdown reads them as a line break.
+pub fn clean(text: &str, config: &EditorConfig) -> String {
+    let fences = crate::markdown::fenced_blocks(text);
+    let mut out = String::with_capacity(text.len());
+    let mut blank_run = 0;
+    let mut offset = 0;
+    let mut lines = text.split_inclusive('\n').peekable();
+    while let Some(line) = lines.next() {
+        let in_code = fences.iter().any(|f| f.range.contains(&offset));
+        offset += line.len();
+        if in_code {
+            blank_run = 0;
+            out.push_str(line);
+            continue;
+        }
+        let (content, ending) = match line.strip_suffix('\n') {
+            Some(content) => (content.strip_suffix('\r').unwrap_or(content), &line[line.trim_end_matches(['\r', '\n']).len()..]),
+            None => (line, ""),
+        };
+        let blank = content.trim().is_empty();
+        blank_run = if blank { blank_run + 1 } else { 0 };
+        if config.collapse_blank_lines && blank_run > 2 {
+            continue;
+        }
+        if config.trim_trailing_whitespace {
+            let trimmed = content.trim_end();
+            let hard_break = !blank
+                && content.ends_with("  ")
+                && lines.peek().is_some_and(|next| !next.trim().is_empty());
+            out.push_str(trimmed);
+            if hard_break {
+                out.push_str("  ");
+            }
+        } else {
+            out.push_str(content);
+        }
+        out.push_str(ending);
+    }
+    if config.final_newline && !out.is_empty() {
+        let end = out.trim_end_matches(['\n', '\r', ' ', '\t']).len();
+        // Whitespace at the end of a code block left open is the block's own.
+        let closed = |t: &str| t.trim_end().ends_with("```") || t.trim_end().ends_with("~~~");
+        if fences.last().is_none_or(|f| f.range.end < text.len() || closed(text)) {
+            out.truncate(end);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+pub enum CleanupChoice {
+    /// Clean this note now and from now on without asking.
+    Apply(PathBuf),
+    /// Turn the cleanups off.
+    TurnOff,
+}
+
+/// What the cleanups would change in a note, shown the first time they
+/// would change anything so their effect is not a surprise.
+pub struct CleanupPreview {
+    path: PathBuf,
+    chunks: Vec<Chunk>,
+}
+
+impl CleanupPreview {
+    pub fn new(path: &Path, before: &str, after: &str) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            chunks: diff::diff(before, after),
+        }
+    }
+
+    /// Returns the choice made, and whether the window is still open.
+    pub fn show(&mut self, ctx: &egui::Context) -> (Option<CleanupChoice>, bool) {
+        let mut open = true;
+        let mut choice = None;
+        let mut later = false;
+        egui::Window::new(t("🧹 Cleanup on save"))
+            .open(&mut open)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                ui.label(t("Saving will also tidy up whitespace in this note as set in the settings:"));
+                ui.separator();
+                egui::ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                    for chunk in &self.chunks {
+                        let Chunk::Changed { left, right } = chunk else {
+                            continue;
+                        };
+                        // Show the whitespace that goes or comes.
+                        let visible = |text: &str| text.replace(' ', "·").replace('\t', "→").replace('\n', "¶\n");
+                        for (text, color) in [(left, Color32::from_rgb(110, 30, 30)), (right, Color32::from_rgb(30, 90, 40))] {
+                            if !text.is_empty() {
+                                ui.label(RichText::new(visible(text).trim_end_matches('\n')).monospace().background_color(color).color(Color32::WHITE));
+                            }
+                        }
+                        ui.separator();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.button(t("Clean up and don't ask again")).clicked() {
+                        choice = Some(CleanupChoice::Apply(self.path.clone()));
+                    }
+                    if ui.button(t("Turn cleanups off")).clicked() {
+                        choice = Some(CleanupChoice::TurnOff);
+                    }
+                    if ui.button(t("Not now")).clicked() {
+                        later = true;
+                    }
+                });
+            });
+        let still_open = open && !later && choice.is_none();
+        (choice, still_open)
+    }
+}
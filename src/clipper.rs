@@ -0,0 +1,152 @@
+use crate::vault::{self, ATTACHMENTS_DIR, NOTES_DIR};
+use reqwest::Url;
+use scraper::{ElementRef, Html, Node, Selector};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::error;
+
+const USER_AGENT: &str = "Mozilla/5.0 (compatible; RustNoteApp clipper)";
+const SKIPPED_TAGS: [&str; 8] = ["script", "style", "nav", "aside", "footer", "form", "noscript", "iframe"];
+
+fn selector(css: &str) -> Selector {
+    Selector::parse(css).expect("static selector")
+}
+
+/// Fetches a web page, extracts its main content as Markdown with images
+/// saved as attachments, and writes it as a new note. Returns its path.
+pub fn clip(url: &str) -> Result<PathBuf, String> {
+    let base = Url::parse(url.trim()).map_err(|e| e.to_string())?;
+    let client = crate::http::client();
+    let html = get(&client, base.clone())
+        .and_then(|r| r.error_for_status())
+        .and_then(|r| r.text())
+        .map_err(|e| e.to_string())?;
+
+    let mut document = Html::parse_document(&html);
+    let title = page_title(&document).unwrap_or_else(|| base.host_str().unwrap_or("Clipping").to_owned());
+    let stem = vault::safe_file_name(&title);
+    let content = main_content(&document);
+    let content_id = content.id();
+
+    let assets = Path::new(ATTACHMENTS_DIR).join(&stem);
+    let images: Vec<_> = content.select(&selector("img[src]")).map(|img| img.id()).collect();
+    let saved = download_images(&client, &base, content, &assets);
+    // Point the images at the copies in the parsed page: serialized, a src
+    // has its `&`s escaped and no longer matches the attribute as read.
+    for (index, local) in saved {
+        if let Some(mut node) = document.tree.get_mut(images[index])
+            && let Node::Element(img) = node.value()
+            && let Some((_, src)) = img.attrs.iter_mut().find(|(name, _)| &*name.local == "src")
+        {
+            *src = local.into();
+        }
+    }
+    let content_html = document.tree.get(content_id).and_then(ElementRef::wrap).map(|c| c.html()).unwrap_or_default();
+
+    let converter = htmd::HtmlToMarkdown::builder().skip_tags(SKIPPED_TAGS.to_vec()).build();
+    let body = converter.convert(&content_html).map_err(|e| e.to_string())?;
+
+    let note = format!(
+        "---\ntitle: \"{}\"\nsource: {}\nclipped: {}\n---\n\n# {}\n\n{}\n",
+        title.replace('"', "\\\""),
+        base,
+        chrono::Local::now().format("%Y-%m-%d"),
+        title,
+        body.trim()
+    );
+    let path = vault::unique_path(Path::new(NOTES_DIR), &stem, "md");
+    crate::journal::write(&path, &note).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// The title of the page at `url`, for a link to it.
+pub fn fetch_title(url: &str) -> Result<String, String> {
+    let request = crate::http::client()
+        .get(url.trim())
+        .header(reqwest::header::USER_AGENT, USER_AGENT);
+    let html = crate::http::send(request)
+        .and_then(|r| r.error_for_status())
+        .and_then(|r| r.text())
+        .map_err(|e| e.to_string())?;
+    page_title(&Html::parse_document(&html)).ok_or_else(|| "The page has no title".to_owned())
+}
+
+fn get(client: &reqwest::blocking::Client, url: Url) -> reqwest::Result<reqwest::blocking::Response> {
+    crate::http::send(client.get(url).header(reqwest::header::USER_AGENT, USER_AGENT))
+}
+
+fn page_title(document: &Html) -> Option<String> {
+    let og = selector("meta[property='og:title']");
+    let title = selector("title");
+    let h1 = selector("h1");
+    document
+        .select(&og)
+        .find_map(|m| m.attr("content").map(str::to_owned))
+        .or_else(|| document.select(&title).next().map(|t| t.text().collect()))
+        .or_else(|| document.select(&h1).next().map(|t| t.text().collect()))
+        .map(|t: String| t.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|t| !t.is_empty())
+}
+
+/// A small readability heuristic: prefer semantic containers, otherwise
+/// the element whose direct paragraphs hold the most text.
+fn main_content(document: &Html) -> ElementRef<'_> {
+    let semantic = selector("article, main, [role=main]");
+    if let Some(element) = document.select(&semantic).next() {
+        return element;
+    }
+
+    let paragraphs = selector("p");
+    let mut scores: HashMap<_, usize> = HashMap::new();
+    for p in document.select(&paragraphs) {
+        if let Some(parent) = p.parent().and_then(ElementRef::wrap) {
+            *scores.entry(parent.id()).or_default() += p.text().map(str::len).sum::<usize>();
+        }
+    }
+    scores
+        .into_iter()
+        .max_by_key(|(_, score)| *score)
+        .and_then(|(id, _)| document.tree.get(id).and_then(ElementRef::wrap))
+        .unwrap_or_else(|| document.root_element())
+}
+
+/// Downloads each image in `content` into `dir`, returning
+/// (index among the images, vault-relative path) pairs for the ones that
+/// succeeded.
+fn download_images(
+    client: &reqwest::blocking::Client,
+    base: &Url,
+    content: ElementRef,
+    dir: &Path,
+) -> Vec<(usize, String)> {
+    let images = selector("img[src]");
+    let mut saved = Vec::new();
+    for (index, img) in content.select(&images).enumerate() {
+        let Some(src) = img.attr("src") else {
+            continue;
+        };
+        let Ok(url) = base.join(src) else {
+            continue;
+        };
+        let bytes = match get(client, url.clone()).and_then(|r| r.error_for_status()).and_then(|r| r.bytes()) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                error!("Failed to download {}: {}", url, err);
+                continue;
+            }
+        };
+        let ext = Path::new(url.path())
+            .extension()
+            .and_then(|e| e.to_str())
+            .filter(|e| e.len() <= 4)
+            .unwrap_or("png");
+        let file = dir.join(format!("image-{}.{}", index + 1, ext));
+        if std::fs::create_dir_all(dir).and_then(|()| std::fs::write(&file, &bytes)).is_err() {
+            continue;
+        }
+        let relative = file.strip_prefix(NOTES_DIR).unwrap_or(&file);
+        let link = crate::links::percent_encode(&relative.to_string_lossy().replace('\\', "/"));
+        saved.push((index, link));
+    }
+    saved
+}
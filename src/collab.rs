@@ -0,0 +1,390 @@
+use crate::crdt::{Doc, Elem, Id, Op};
+use crate::i18n::{t, tf};
+use crate::vault;
+use eframe::egui::{self, RichText};
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+use tracing::warn;
+
+/// Port a hosted session listens on unless changed.
+const DEFAULT_PORT: u16 = 7879;
+/// How long a joining peer has to send the session code.
+const HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What peers send each other, one JSON object per line.
+#[derive(Serialize, Deserialize)]
+enum Message {
+    /// The first line a joining peer sends, with the code the host shows.
+    Hello { code: String },
+    /// The host's document, sent once a peer connects.
+    Snapshot { note: String, elems: Vec<Elem> },
+    Ops(Vec<Op>),
+    /// The sender's cursor, as the character it is after.
+    Cursor(Option<Id>),
+}
+
+/// A connected peer. Messages go out through a thread of their own, so a
+/// slow peer never holds up the editor.
+struct Link {
+    /// Kept to shut the connection down when leaving.
+    stream: TcpStream,
+    outgoing: Sender<Message>,
+}
+
+enum Incoming {
+    Connected(Link),
+    Message(Message),
+    Failed(String),
+    Lost(String),
+    Disconnected,
+}
+
+/// A code for others to join a hosted session with. The host takes one
+/// attempt at it, so eight hex digits are plenty.
+fn session_code() -> String {
+    crate::server::generate_token()[..8].to_owned()
+}
+
+/// The shared note's file name, kept inside the notes folder whatever the
+/// host sent.
+fn note_name(note: &str) -> String {
+    let path = Path::new(note);
+    let extension = path.extension().and_then(|e| e.to_str()).filter(|e| ["md", "txt"].contains(e)).unwrap_or("md");
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    format!("{}.{}", vault::safe_file_name(stem), extension)
+}
+
+fn write_message(stream: &mut TcpStream, message: &Message) -> io::Result<()> {
+    let json = serde_json::to_string(message).map_err(io::Error::other)?;
+    writeln!(stream, "{}", json)
+}
+
+/// Reads the joining peer's hello and checks its code.
+fn admit(reader: &mut BufReader<TcpStream>, code: &str) -> io::Result<()> {
+    reader.get_ref().set_read_timeout(Some(HELLO_TIMEOUT))?;
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    reader.get_ref().set_read_timeout(None)?;
+    match serde_json::from_str(&line) {
+        Ok(Message::Hello { code: sent }) if sent == code => Ok(()),
+        _ => Err(io::Error::other("someone tried to join with the wrong code; host again for a new one")),
+    }
+}
+
+/// Writes queued messages to `stream` until the session is left.
+fn write_messages(mut stream: TcpStream, outgoing: Receiver<Message>, tx: &Sender<Incoming>, ctx: &egui::Context) {
+    for message in outgoing {
+        if let Err(err) = write_message(&mut stream, &message) {
+            let _ = tx.send(Incoming::Lost(err.to_string()));
+            ctx.request_repaint();
+            return;
+        }
+    }
+}
+
+/// Reads messages from `reader` until the connection closes.
+fn read_messages(reader: BufReader<TcpStream>, tx: &Sender<Incoming>, ctx: &egui::Context) {
+    for line in reader.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        match serde_json::from_str(&line) {
+            Ok(message) => {
+                let _ = tx.send(Incoming::Message(message));
+                ctx.request_repaint();
+            }
+            Err(err) => warn!("Ignoring bad session message: {}", err),
+        }
+    }
+    let _ = tx.send(Incoming::Disconnected);
+    ctx.request_repaint();
+}
+
+/// Opens the connection: `Ok` listens on a port and admits one peer that
+/// knows `code`, `Err` dials an address and sends it.
+fn open(target: Result<u16, String>, code: &str) -> io::Result<(TcpStream, BufReader<TcpStream>)> {
+    let mut stream = match &target {
+        Ok(port) => TcpListener::bind(("0.0.0.0", *port)).and_then(|l| l.accept()).map(|(stream, _)| stream)?,
+        Err(address) => TcpStream::connect(address.as_str())?,
+    };
+    let mut reader = BufReader::new(stream.try_clone()?);
+    if target.is_ok() {
+        admit(&mut reader, code)?;
+    } else {
+        write_message(&mut stream, &Message::Hello { code: code.to_owned() })?;
+    }
+    Ok((stream, reader))
+}
+
+/// Connects on a background thread, see `open`. The link arrives as
+/// `Incoming::Connected`.
+fn connect(target: Result<u16, String>, code: String, ctx: &egui::Context) -> Receiver<Incoming> {
+    let (tx, rx) = mpsc::channel();
+    let ctx = ctx.clone();
+    std::thread::spawn(move || {
+        let opened = open(target, &code).and_then(|(stream, reader)| Ok((stream.try_clone()?, stream, reader)));
+        let reader = match opened {
+            Ok((writer, stream, reader)) => {
+                let (outgoing, queued) = mpsc::channel();
+                // The session was left while waiting.
+                if tx.send(Incoming::Connected(Link { stream, outgoing })).is_err() {
+                    return;
+                }
+                ctx.request_repaint();
+                let (tx, ctx) = (tx.clone(), ctx.clone());
+                std::thread::spawn(move || write_messages(writer, queued, &tx, &ctx));
+                reader
+            }
+            Err(err) => {
+                let _ = tx.send(Incoming::Failed(err.to_string()));
+                ctx.request_repaint();
+                return;
+            }
+        };
+        read_messages(reader, &tx, &ctx);
+    });
+    rx
+}
+
+/// What the editor should do after a round of syncing.
+pub enum SyncEvent {
+    /// Joined a session: show the host's note under its name.
+    Joined { note: String, text: String },
+    /// The other side edited: the new text and where the local cursor moved.
+    Remote { text: String, cursor: Option<usize> },
+}
+
+/// A live editing session with one peer over TCP.
+struct Session {
+    /// `None` until a joining peer has received the host's snapshot.
+    doc: Option<Doc>,
+    /// The note being shared, as its path in the vault.
+    note: String,
+    /// The code a peer joins with, shown while hosting.
+    code: Option<String>,
+    link: Option<Link>,
+    incoming: Receiver<Incoming>,
+    status: String,
+    remote_cursor: Option<Option<Id>>,
+    sent_cursor: Option<Option<Id>>,
+}
+
+impl Session {
+    /// Queues a message for the peer; the writer thread reports failures.
+    fn send(&mut self, message: Message) {
+        if let Some(link) = &self.link
+            && link.outgoing.send(message).is_err()
+        {
+            self.link = None;
+        }
+    }
+}
+
+fn site_id() -> u64 {
+    let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64);
+    nanos ^ ((std::process::id() as u64) << 32)
+}
+
+/// Window for hosting or joining a session, and the session itself.
+pub struct CollabView {
+    pub open: bool,
+    port: u16,
+    address: String,
+    /// The host's session code, as typed to join.
+    code: String,
+    session: Option<Session>,
+}
+
+impl Default for CollabView {
+    fn default() -> Self {
+        Self {
+            open: false,
+            port: DEFAULT_PORT,
+            address: String::new(),
+            code: String::new(),
+            session: None,
+        }
+    }
+}
+
+impl CollabView {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.session.is_some()
+    }
+
+    /// Ends the session, if there is one.
+    pub fn leave(&mut self) {
+        if let Some(link) = self.session.take().and_then(|s| s.link) {
+            let _ = link.stream.shutdown(std::net::Shutdown::Both);
+        }
+    }
+
+    /// Sends local edits and cursor moves, then applies the peer's. `cursor`
+    /// and the returned cursor are byte offsets into the text.
+    pub fn sync(&mut self, text: &str, cursor: Option<usize>) -> Option<SyncEvent> {
+        let session = self.session.as_mut()?;
+        if let Some(doc) = session.doc.as_mut()
+            && doc.text() != text
+        {
+            let ops = doc.edit(text);
+            session.send(Message::Ops(ops));
+        }
+        let chars = cursor.and_then(|cursor| text.get(..cursor)).map(|before| before.chars().count());
+        let anchor = session.doc.as_ref().zip(chars).map(|(doc, chars)| doc.anchor(chars));
+        if session.link.is_some() && anchor.is_some() && session.sent_cursor != anchor {
+            session.sent_cursor = anchor;
+            session.send(Message::Cursor(anchor.flatten()));
+        }
+
+        let mut event = None;
+        let mut remote = false;
+        while let Ok(incoming) = session.incoming.try_recv() {
+            match incoming {
+                Incoming::Connected(link) => {
+                    session.link = Some(link);
+                    session.status = t("Connected");
+                    if let Some(doc) = &session.doc {
+                        let snapshot = Message::Snapshot {
+                            note: session.note.clone(),
+                            elems: doc.elems().to_vec(),
+                        };
+                        session.send(snapshot);
+                    }
+                }
+                // Only the host sends one, once.
+                Incoming::Message(Message::Snapshot { note, elems }) if session.doc.is_none() => {
+                    let doc = Doc::from_elems(site_id(), elems);
+                    let note = note_name(&note);
+                    event = Some(SyncEvent::Joined { note: note.clone(), text: doc.text() });
+                    session.note = note;
+                    session.doc = Some(doc);
+                }
+                Incoming::Message(Message::Snapshot { .. } | Message::Hello { .. }) => warn!("Ignoring an unexpected session message"),
+                Incoming::Message(Message::Ops(ops)) => {
+                    if let Some(doc) = session.doc.as_mut() {
+                        for op in &ops {
+                            if !doc.apply(op) {
+                                warn!("Session operation out of order: {:?}", op);
+                            }
+                        }
+                        remote = true;
+                    }
+                }
+                Incoming::Message(Message::Cursor(anchor)) => session.remote_cursor = Some(anchor),
+                Incoming::Failed(err) => {
+                    session.status = tf("Could not connect: {}", &[&err]);
+                    session.link = None;
+                }
+                Incoming::Lost(err) => {
+                    session.status = tf("Connection lost: {}", &[&err]);
+                    session.link = None;
+                    session.remote_cursor = None;
+                }
+                // A host that turns the code down just hangs up.
+                Incoming::Disconnected if session.doc.is_none() => {
+                    session.status = t("The host closed the connection; check the session code");
+                    session.link = None;
+                }
+                Incoming::Disconnected => {
+                    session.status = t("The other side left the session");
+                    session.link = None;
+                    session.remote_cursor = None;
+                }
+            }
+        }
+        if remote && let Some(doc) = &session.doc {
+            let text = doc.text();
+            let cursor = anchor.map(|anchor| {
+                let chars = doc.position(anchor);
+                text.char_indices().nth(chars).map_or(text.len(), |(i, _)| i)
+            });
+            event = Some(SyncEvent::Remote { text, cursor });
+        }
+        event
+    }
+
+    /// The peer's cursor as a char position in the current text.
+    pub fn remote_cursor(&self) -> Option<usize> {
+        let session = self.session.as_ref()?;
+        let anchor = session.remote_cursor?;
+        session.doc.as_ref().map(|doc| doc.position(anchor))
+    }
+
+    /// `text` and `note` are the open note, shared when hosting.
+    pub fn show(&mut self, ctx: &egui::Context, text: &str, note: Option<&str>) {
+        if !self.open {
+            return;
+        }
+        let mut open = true;
+
+        egui::Window::new(t("👥 Live Session")).open(&mut open).default_width(340.0).show(ctx, |ui| {
+            if let Some(session) = &self.session {
+                ui.label(&session.status);
+                if let Some(code) = session.code.as_ref().filter(|_| session.link.is_none()) {
+                    ui.horizontal(|ui| {
+                        ui.label(t("Session code:"));
+                        ui.label(RichText::new(code).monospace().strong().size(18.0));
+                    });
+                }
+                if session.link.is_some() {
+                    ui.label(RichText::new(tf("Editing {} together", &[&session.note])).weak());
+                }
+                if ui.button(t("Leave session")).clicked() {
+                    self.leave();
+                }
+                return;
+            }
+            ui.label(RichText::new(t("Edit the open note together with another instance on your network.")).small().weak());
+            ui.label(
+                RichText::new(t("The session code keeps others out, but the session is not encrypted: only use it on networks you trust."))
+                    .small()
+                    .weak(),
+            );
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut self.port).prefix(t("port ")));
+                if ui.add_enabled(note.is_some(), egui::Button::new(t("Host this note"))).clicked() {
+                    let code = session_code();
+                    self.session = Some(Session {
+                        doc: Some(Doc::new(site_id(), text)),
+                        note: note.unwrap_or_default().to_owned(),
+                        code: Some(code.clone()),
+                        link: None,
+                        incoming: connect(Ok(self.port), code, ctx),
+                        status: tf("Waiting for someone to join on port {}", &[&self.port]),
+                        remote_cursor: None,
+                        sent_cursor: None,
+                    });
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.address).hint_text(t("host:port or relay address")));
+                ui.add(egui::TextEdit::singleline(&mut self.code).hint_text(t("code")).desired_width(70.0));
+                let ready = !self.address.trim().is_empty() && !self.code.trim().is_empty();
+                if ui.add_enabled(ready, egui::Button::new(t("Join"))).clicked() {
+                    self.session = Some(Session {
+                        doc: None,
+                        note: String::new(),
+                        code: None,
+                        link: None,
+                        incoming: connect(Err(self.address.trim().to_owned()), self.code.trim().to_owned(), ctx),
+                        status: tf("Connecting to {}…", &[&self.address.trim()]),
+                        remote_cursor: None,
+                        sent_cursor: None,
+                    });
+                }
+            });
+        });
+
+        if !open {
+            self.open = false;
+        }
+    }
+}
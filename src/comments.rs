@@ -0,0 +1,241 @@
+use crate::i18n::{t, tf};
+use crate::metadata::Metadata;
+use eframe::egui::{self, RichText};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Characters of context kept on each side of a comment's text, to find
+/// it again when the quote alone is ambiguous.
+const CONTEXT: usize = 24;
+
+/// A remark attached to a span of a note's text.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Comment {
+    /// Byte range in the note; `None` once the text it was on is gone.
+    pub range: Option<Range<usize>>,
+    /// The commented text, for finding the range again.
+    pub quote: String,
+    pub before: String,
+    pub after: String,
+    pub body: String,
+    pub created: String,
+    pub resolved: bool,
+}
+
+/// Moves `range` through an edit that replaced `old[start..old_end]` with
+/// `new[start..new_end]`.
+fn shift_range(range: &Range<usize>, start: usize, old_end: usize, new_end: usize) -> Range<usize> {
+    let map = |at: usize| {
+        if at <= start {
+            at
+        } else if at >= old_end {
+            at + new_end - old_end
+        } else {
+            // Inside the replaced text: keep to the replacement.
+            at.min(new_end)
+        }
+    };
+    map(range.start)..map(range.end)
+}
+
+impl Comment {
+    pub fn new(text: &str, range: Range<usize>, body: String) -> Self {
+        let before: String = text[..range.start].chars().rev().take(CONTEXT).collect::<Vec<_>>().into_iter().rev().collect();
+        let after: String = text[range.end..].chars().take(CONTEXT).collect();
+        Self {
+            quote: text[range.clone()].to_owned(),
+            range: Some(range),
+            before,
+            after,
+            body,
+            created: chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+            resolved: false,
+        }
+    }
+
+    /// Moves the range through an edit that replaced `old[start..old_end]`
+    /// with `new[start..new_end]`.
+    fn shift(&mut self, start: usize, old_end: usize, new_end: usize) {
+        if let Some(range) = &mut self.range {
+            *range = shift_range(range, start, old_end, new_end);
+        }
+    }
+
+    /// Finds the quote again after edits that moved it, preferring the
+    /// occurrence with matching context nearest to where it was.
+    fn repair(&mut self, text: &str) {
+        if let Some(range) = &self.range
+            && text.get(range.clone()) == Some(self.quote.as_str())
+        {
+            return;
+        }
+        let was = self.range.as_ref().map_or(0, |r| r.start);
+        let score = |at: usize| {
+            let end = at + self.quote.len();
+            let context = text[..at].ends_with(&self.before) as usize + text[end..].starts_with(&self.after) as usize;
+            (std::cmp::Reverse(context), at.abs_diff(was))
+        };
+        self.range = if self.quote.is_empty() {
+            None
+        } else {
+            text.match_indices(&self.quote).map(|(at, _)| at).min_by_key(|&at| score(at)).map(|at| at..at + self.quote.len())
+        };
+    }
+}
+
+pub enum CommentEvent {
+    /// Select this byte range in the editor.
+    Select(Range<usize>),
+}
+
+/// The open note's comments: margin markers in the editor and a panel to
+/// read, add, resolve and delete them.
+#[derive(Default)]
+pub struct CommentsView {
+    pub open: bool,
+    note: Option<PathBuf>,
+    comments: Vec<Comment>,
+    /// The note text the ranges refer to.
+    text: String,
+    /// A comment being written for a range.
+    draft: Option<(Range<usize>, String)>,
+    show_resolved: bool,
+}
+
+impl CommentsView {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// Starts a comment on the selection.
+    pub fn comment_on(&mut self, selection: Range<usize>) {
+        if !selection.is_empty() {
+            self.open = true;
+            self.draft = Some((selection, String::new()));
+        }
+    }
+
+    /// Loads comments when another note is opened, and keeps their ranges
+    /// on the same text as the note is edited.
+    pub fn sync(&mut self, note: Option<&Path>, text: &str, metadata: &Metadata) {
+        if self.note.as_deref() != note {
+            self.note = note.map(Path::to_path_buf);
+            self.comments = note.map(|n| metadata.get(n).comments).unwrap_or_default();
+            self.text = text.to_owned();
+            self.draft = None;
+            for comment in &mut self.comments {
+                comment.repair(text);
+            }
+            return;
+        }
+        if self.text == text {
+            return;
+        }
+        let (start, old_end, insert) = crate::text::splice(&self.text, text);
+        let new_end = start + insert.len();
+        for comment in &mut self.comments {
+            comment.shift(start, old_end, new_end);
+            comment.repair(text);
+        }
+        if let Some((range, _)) = &mut self.draft {
+            *range = shift_range(range, start, old_end, new_end);
+        }
+        self.text = text.to_owned();
+    }
+
+    /// Stores the comments with the note.
+    pub fn save(&self, metadata: &mut Metadata) {
+        if let Some(note) = &self.note {
+            metadata.update(note, |m| m.comments = self.comments.clone());
+        }
+    }
+
+    /// Ranges of open comments, for marking in the editor.
+    pub fn ranges(&self) -> Vec<Range<usize>> {
+        self.comments.iter().filter(|c| !c.resolved).filter_map(|c| c.range.clone()).collect()
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, metadata: &mut Metadata) -> Option<CommentEvent> {
+        if !self.open {
+            return None;
+        }
+        let mut event = None;
+        let mut changed = false;
+        let mut open = true;
+
+        egui::Window::new(t("💬 Comments")).open(&mut open).default_width(320.0).show(ctx, |ui| {
+            if self.note.is_none() {
+                ui.label(RichText::new(t("Save the note to comment on it")).weak());
+                return;
+            }
+            if let Some((range, body)) = &mut self.draft {
+                let quote = self.text.get(range.clone()).unwrap_or_default();
+                ui.label(RichText::new(format!("“{}”", quote.chars().take(80).collect::<String>())).italics().weak());
+                ui.add(egui::TextEdit::multiline(body).desired_rows(2).hint_text(t("Comment")));
+                let (add, cancel) = ui
+                    .horizontal(|ui| {
+                        let add = ui.add_enabled(!body.trim().is_empty(), egui::Button::new(t("Add comment"))).clicked();
+                        (add, ui.button(t("Cancel")).clicked())
+                    })
+                    .inner;
+                // The text under the draft may have been deleted while it
+                // was being written.
+                if add && self.text.get(range.clone()).is_some_and(|q| !q.is_empty()) {
+                    self.comments.push(Comment::new(&self.text, range.clone(), body.trim().to_owned()));
+                    changed = true;
+                }
+                if add || cancel {
+                    self.draft = None;
+                }
+                ui.separator();
+            } else {
+                ui.label(RichText::new(t("Select text and use “💬 Comment on Selection” (Ctrl+Alt+M)")).small().weak());
+            }
+            ui.checkbox(&mut self.show_resolved, t("Show resolved"));
+            let mut delete = None;
+            egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                let mut order: Vec<usize> = (0..self.comments.len()).filter(|&i| self.show_resolved || !self.comments[i].resolved).collect();
+                order.sort_by_key(|&i| self.comments[i].range.as_ref().map_or(usize::MAX, |r| r.start));
+                for index in order {
+                    let comment = &mut self.comments[index];
+                    egui::Frame::group(ui.style()).show(ui, |ui| {
+                        ui.set_width(ui.available_width());
+                        let quote: String = comment.quote.chars().take(60).collect();
+                        match &comment.range {
+                            Some(range) => {
+                                if ui.link(RichText::new(format!("“{}”", quote)).italics()).clicked() {
+                                    event = Some(CommentEvent::Select(range.clone()));
+                                }
+                            }
+                            None => {
+                                ui.label(RichText::new(tf("“{}” (text removed)", &[&quote])).italics().weak());
+                            }
+                        }
+                        ui.label(&comment.body);
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new(&comment.created).small().weak());
+                            changed |= ui.checkbox(&mut comment.resolved, t("Resolved")).changed();
+                            if ui.small_button("🗑").clicked() {
+                                delete = Some(index);
+                            }
+                        });
+                    });
+                }
+            });
+            if let Some(index) = delete {
+                self.comments.remove(index);
+                changed = true;
+            }
+        });
+
+        if changed {
+            self.save(metadata);
+        }
+        if !open {
+            self.open = false;
+        }
+        event
+    }
+}
@@ -0,0 +1,132 @@
+use crate::config::AutocompleteConfig;
+use eframe::egui;
+use serde_json::{Value, json};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+use tracing::error;
+
+/// How much text before the cursor is sent as the prompt, in chars.
+const CONTEXT_CHARS: usize = 2000;
+
+fn request(config: &AutocompleteConfig, prompt: &str) -> Result<String, String> {
+    // Ollama's /api/generate answers in `response`, llama.cpp's /completion in `content`.
+    let request = crate::http::client().post(&config.endpoint).timeout(Duration::from_secs(20)).json(&json!({
+        "model": config.model,
+        "prompt": prompt,
+        "stream": false,
+        "n_predict": config.max_tokens,
+        "options": {"num_predict": config.max_tokens, "stop": ["\n\n"]},
+    }));
+    let body: Value = crate::http::send(request)
+        .and_then(|r| r.error_for_status())
+        .and_then(|r| r.json())
+        .map_err(|e| e.to_string())?;
+    body.get("response")
+        .or_else(|| body.get("content"))
+        .and_then(Value::as_str)
+        .map(str::to_owned)
+        .ok_or_else(|| "Unexpected completion response".to_owned())
+}
+
+/// A suggested continuation, valid while the text and cursor stay put.
+pub struct Ghost {
+    /// Byte offset the completion would be inserted at.
+    pub at: usize,
+    pub text: String,
+}
+
+#[derive(Default)]
+pub struct Autocomplete {
+    last_edit: Option<Instant>,
+    pending: Option<(usize, Receiver<Result<String, String>>)>,
+    pub ghost: Option<Ghost>,
+}
+
+impl Autocomplete {
+    /// Call when the text changed: drops any stale suggestion and restarts
+    /// the pause timer.
+    pub fn edited(&mut self) {
+        self.moved();
+        self.last_edit = Some(Instant::now());
+    }
+
+    /// Call when only the cursor moved: drops any stale suggestion.
+    pub fn moved(&mut self) {
+        self.ghost = None;
+        self.pending = None;
+        self.last_edit = None;
+    }
+
+    /// Requests a completion once typing has paused at `cursor` (a byte offset).
+    pub fn tick(&mut self, ctx: &egui::Context, config: &AutocompleteConfig, text: &str, cursor: usize) {
+        if !config.enabled {
+            self.ghost = None;
+            return;
+        }
+        if let Some((at, rx)) = &self.pending
+            && let Ok(result) = rx.try_recv()
+        {
+            match result {
+                Ok(completion) if !completion.trim().is_empty() => {
+                    self.ghost = Some(Ghost {
+                        at: *at,
+                        text: completion,
+                    });
+                }
+                Ok(_) => {}
+                Err(err) => error!("Autocomplete failed: {}", err),
+            }
+            self.pending = None;
+        }
+
+        let Some(last_edit) = self.last_edit else {
+            return;
+        };
+        let delay = Duration::from_millis(config.delay_ms);
+        if last_edit.elapsed() < delay {
+            ctx.request_repaint_after(delay - last_edit.elapsed());
+            return;
+        }
+        self.last_edit = None;
+        if text.get(..cursor).is_none_or(|before| before.trim().is_empty()) {
+            return;
+        }
+
+        let before = &text[..cursor];
+        let start = before.char_indices().rev().nth(CONTEXT_CHARS).map_or(0, |(i, _)| i);
+        let prompt = before[start..].to_owned();
+        let (tx, rx) = mpsc::channel();
+        let config = config.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(request(&config, &prompt));
+            ctx.request_repaint();
+        });
+        self.pending = Some((cursor, rx));
+    }
+
+    /// Takes the suggestion if it is still anchored at `cursor`.
+    pub fn accept(&mut self, cursor: usize) -> Option<Ghost> {
+        self.ghost.take_if(|g| g.at == cursor)
+    }
+
+    /// Paints the suggestion in grey just after the cursor.
+    pub fn paint(&self, ui: &egui::Ui, output: &egui::text_edit::TextEditOutput, text: &str) {
+        let (Some(ghost), Some(range)) = (&self.ghost, &output.cursor_range) else {
+            return;
+        };
+        if text.get(..ghost.at).map(|t| t.chars().count()) != Some(range.primary.ccursor.index) {
+            return;
+        }
+        let rect = output.galley.pos_from_cursor(&range.primary);
+        let pos = output.galley_pos + rect.right_top().to_vec2();
+        let shown = ghost.text.lines().next().unwrap_or_default();
+        ui.painter().text(
+            pos,
+            egui::Align2::LEFT_TOP,
+            shown,
+            egui::TextStyle::Monospace.resolve(ui.style()),
+            ui.visuals().weak_text_color(),
+        );
+    }
+}
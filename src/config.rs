@@ -0,0 +1,1154 @@
+use crate::i18n::{self, t};
+use crate::vault::{CONFIG_FILE, THEMES_DIR};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+#[cfg(target_os = "android")]
+use crate::stubs::rfd;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct BackupConfig {
+    pub enabled: bool,
+    pub dir: String,
+    pub interval_minutes: u64,
+    pub keep: usize,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: "backups".to_owned(),
+            interval_minutes: 60,
+            keep: 10,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct NextcloudConfig {
+    pub url: String,
+    pub user: String,
+    pub app_password: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct LanSyncConfig {
+    pub enabled: bool,
+    /// Name other devices see; the host name when empty.
+    pub device_name: String,
+    pub port: u16,
+    /// Minutes between automatic syncs with paired devices; 0 to sync by hand.
+    pub interval_minutes: u64,
+}
+
+impl Default for LanSyncConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            device_name: String::new(),
+            port: 7880,
+            interval_minutes: 10,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub enabled: bool,
+    pub port: u16,
+    pub token: String,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 7878,
+            token: crate::server::generate_token(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaptureTarget {
+    Inbox,
+    DailyNote,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct CaptureConfig {
+    pub enabled: bool,
+    pub hotkey: String,
+    pub target: CaptureTarget,
+    pub inbox_note: String,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hotkey: "Ctrl+Shift+Space".to_owned(),
+            target: CaptureTarget::Inbox,
+            inbox_note: "Inbox.md".to_owned(),
+        }
+    }
+}
+
+/// A shell command the note is piped through on save.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(from = "SaveHookEntry")]
+pub struct SaveHook {
+    pub command: String,
+    /// Only reports problems, e.g. a linter; its output never replaces the note.
+    pub report_only: bool,
+}
+
+/// A save hook as written in the config: older configs list bare commands.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SaveHookEntry {
+    Command(String),
+    Hook {
+        command: String,
+        #[serde(default)]
+        report_only: bool,
+    },
+}
+
+impl From<SaveHookEntry> for SaveHook {
+    fn from(entry: SaveHookEntry) -> Self {
+        match entry {
+            SaveHookEntry::Command(command) => Self { command, report_only: false },
+            SaveHookEntry::Hook { command, report_only } => Self { command, report_only },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CodeRunner {
+    pub language: String,
+    /// Program that reads the code on stdin, or `playground` for the Rust playground.
+    pub command: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct CodeConfig {
+    pub runners: Vec<CodeRunner>,
+    pub confirm_runs: bool,
+}
+
+impl Default for CodeConfig {
+    fn default() -> Self {
+        let runner = |language: &str, command: &str| CodeRunner {
+            language: language.to_owned(),
+            command: command.to_owned(),
+        };
+        Self {
+            runners: vec![
+                runner("sh", "sh"),
+                runner("python", "python3"),
+                runner("rust", "playground"),
+            ],
+            confirm_runs: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AiProvider {
+    OpenAi,
+    Anthropic,
+    Ollama,
+}
+
+impl AiProvider {
+    pub fn label(self) -> String {
+        match self {
+            AiProvider::OpenAi => "OpenAI".to_owned(),
+            AiProvider::Anthropic => "Anthropic".to_owned(),
+            AiProvider::Ollama => t("Ollama (local)"),
+        }
+    }
+
+    fn default_endpoint(self) -> &'static str {
+        match self {
+            AiProvider::OpenAi => "https://api.openai.com/v1/chat/completions",
+            AiProvider::Anthropic => "https://api.anthropic.com/v1/messages",
+            AiProvider::Ollama => "http://localhost:11434/api/chat",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct AiConfig {
+    pub provider: AiProvider,
+    pub endpoint: String,
+    pub model: String,
+    pub api_key: String,
+}
+
+impl Default for AiConfig {
+    fn default() -> Self {
+        Self {
+            provider: AiProvider::Ollama,
+            endpoint: AiProvider::Ollama.default_endpoint().to_owned(),
+            model: "llama3".to_owned(),
+            api_key: String::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct AutocompleteConfig {
+    pub enabled: bool,
+    /// An Ollama `/api/generate` or llama.cpp `/completion` endpoint.
+    pub endpoint: String,
+    pub model: String,
+    pub delay_ms: u64,
+    pub max_tokens: u32,
+}
+
+impl Default for AutocompleteConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: "http://localhost:11434/api/generate".to_owned(),
+            model: "llama3".to_owned(),
+            delay_ms: 800,
+            max_tokens: 32,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TranslateProvider {
+    DeepL,
+    LibreTranslate,
+}
+
+impl TranslateProvider {
+    fn default_endpoint(self) -> &'static str {
+        match self {
+            TranslateProvider::DeepL => "https://api-free.deepl.com/v2/translate",
+            TranslateProvider::LibreTranslate => "https://libretranslate.com/translate",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct TranslateConfig {
+    pub provider: TranslateProvider,
+    pub endpoint: String,
+    pub api_key: String,
+    pub target_lang: String,
+}
+
+impl Default for TranslateConfig {
+    fn default() -> Self {
+        Self {
+            provider: TranslateProvider::LibreTranslate,
+            endpoint: TranslateProvider::LibreTranslate.default_endpoint().to_owned(),
+            api_key: String::new(),
+            target_lang: "EN".to_owned(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DictationConfig {
+    /// OpenAI-compatible transcription endpoint (hosted, or a local whisper.cpp server).
+    pub endpoint: String,
+    pub model: String,
+    pub api_key: String,
+    pub push_to_talk_key: String,
+}
+
+impl Default for DictationConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: "http://localhost:8080/v1/audio/transcriptions".to_owned(),
+            model: "whisper-1".to_owned(),
+            api_key: String::new(),
+            push_to_talk_key: "F9".to_owned(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct EditorConfig {
+    pub format_tables_on_save: bool,
+    /// Larger controls, swipes and a formatting bar for touch screens.
+    pub touch_mode: bool,
+    /// Note in the notes folder listing acronyms and jargon with what they
+    /// stand for.
+    pub glossary_note: String,
+    /// Convert HTML on the clipboard to Markdown when pasting.
+    pub paste_html: bool,
+    /// Look up the page title for a URL pasted with nothing selected.
+    pub fetch_link_titles: bool,
+    /// Save notes opened in another encoding back in it instead of UTF-8.
+    pub keep_encoding: bool,
+    /// Cleanups when saving.
+    pub trim_trailing_whitespace: bool,
+    pub final_newline: bool,
+    pub collapse_blank_lines: bool,
+    /// What the cleanups do has been shown and accepted.
+    pub cleanup_previewed: bool,
+    /// Seconds after the last edit to save the note; 0 to save by hand.
+    pub autosave_seconds: u64,
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        Self {
+            format_tables_on_save: true,
+            touch_mode: cfg!(target_os = "android"),
+            glossary_note: "Glossary.md".to_owned(),
+            paste_html: true,
+            fetch_link_titles: false,
+            keep_encoding: false,
+            trim_trailing_whitespace: false,
+            final_newline: false,
+            collapse_blank_lines: false,
+            cleanup_previewed: false,
+            autosave_seconds: 0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum NoteSort {
+    #[default]
+    Title,
+    Created,
+    Modified,
+    Size,
+}
+
+impl NoteSort {
+    pub const ALL: [NoteSort; 4] = [NoteSort::Title, NoteSort::Created, NoteSort::Modified, NoteSort::Size];
+
+    pub fn label(self) -> String {
+        match self {
+            NoteSort::Title => t("Title"),
+            NoteSort::Created => t("Created"),
+            NoteSort::Modified => t("Modified"),
+            NoteSort::Size => t("Size"),
+        }
+    }
+}
+
+/// Note list order, changed from the sidebar itself.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct SidebarConfig {
+    pub sort: NoteSort,
+    pub descending: bool,
+    pub smart_folders: Vec<crate::search::SmartFolder>,
+}
+
+/// What "Publish" renders and where it goes.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct PublishConfig {
+    /// Top-level folders to publish; `""` stands for notes outside folders.
+    pub folders: Vec<String>,
+    pub output_dir: String,
+    pub site_title: String,
+    /// Remote to force-push the site to; empty to only write the files.
+    pub git_remote: String,
+    pub git_branch: String,
+}
+
+impl Default for PublishConfig {
+    fn default() -> Self {
+        Self {
+            folders: Vec::new(),
+            output_dir: "site".to_owned(),
+            site_title: "My Notes".to_owned(),
+            git_remote: String::new(),
+            git_branch: "gh-pages".to_owned(),
+        }
+    }
+}
+
+/// The daily queue of old notes to look at again.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ReviewConfig {
+    pub queue_size: usize,
+    /// Notes left unchanged this long are picked first.
+    pub stale_days: u64,
+}
+
+impl Default for ReviewConfig {
+    fn default() -> Self {
+        Self {
+            queue_size: 5,
+            stale_days: 30,
+        }
+    }
+}
+
+/// Which grammar and spelling checkers run, and their settings. The
+/// results of every enabled one are merged.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GrammarConfig {
+    pub languagetool: bool,
+    /// The public API, or a server of your own such as
+    /// `http://localhost:8081/v2/check`.
+    pub languagetool_url: String,
+    /// A premium account; both empty for the free API.
+    pub languagetool_username: String,
+    pub languagetool_api_key: String,
+    /// Your first language, e.g. `de-DE`, so LanguageTool warns about
+    /// false friends from it.
+    pub mother_tongue: String,
+    /// Variants auto-detection picks, comma-separated, e.g. `en-GB,de-AT`.
+    pub preferred_variants: String,
+    pub spellcheck: bool,
+    /// Look up each word in the word list as soon as it is typed.
+    pub spell_as_you_type: bool,
+    /// One word per line; Hunspell `.dic` files work too.
+    pub word_list: String,
+    /// A MyThes `.dat` file or an OpenThesaurus text export, for synonyms.
+    pub thesaurus: String,
+    /// A Wiktionary extract in JSON lines from kaikki.org, for definitions.
+    pub dictionary: String,
+}
+
+impl Default for GrammarConfig {
+    fn default() -> Self {
+        Self {
+            languagetool: true,
+            languagetool_url: crate::grammar::ENDPOINT.to_owned(),
+            languagetool_username: String::new(),
+            languagetool_api_key: String::new(),
+            mother_tongue: String::new(),
+            preferred_variants: String::new(),
+            spellcheck: false,
+            spell_as_you_type: true,
+            word_list: if cfg!(unix) { "/usr/share/dict/words".to_owned() } else { String::new() },
+            thesaurus: if cfg!(unix) { "/usr/share/mythes/th_en_US_v2.dat".to_owned() } else { String::new() },
+            dictionary: String::new(),
+        }
+    }
+}
+
+/// Corrections made while typing, off unless turned on. A note can opt
+/// out with `autocorrect: false` in its front matter.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct AutocorrectConfig {
+    pub enabled: bool,
+    /// A word typed and what it becomes once a space or punctuation
+    /// follows it.
+    pub replacements: Vec<(String, String)>,
+    /// Typographic quotes and apostrophes in place of `"` and `'`.
+    pub smart_quotes: bool,
+}
+
+impl Default for AutocorrectConfig {
+    fn default() -> Self {
+        let replacements = [
+            ("teh", "the"),
+            ("adn", "and"),
+            ("taht", "that"),
+            ("recieve", "receive"),
+            ("seperate", "separate"),
+            ("definately", "definitely"),
+            ("occured", "occurred"),
+            ("i", "I"),
+        ];
+        Self {
+            enabled: false,
+            replacements: replacements.iter().map(|(from, to)| (from.to_string(), to.to_string())).collect(),
+            smart_quotes: true,
+        }
+    }
+}
+
+/// Presets for reading comfort, applied through the egui style.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AppearanceConfig {
+    /// White on black with strong outlines and a bright selection.
+    pub high_contrast: bool,
+    /// Show text in OpenDyslexic when it is installed.
+    pub dyslexic_font: bool,
+    /// The OpenDyslexic font file; empty to look in the usual places.
+    pub dyslexic_font_path: String,
+    /// Line height in the editor and preview as a multiple of the font size.
+    pub line_spacing: f32,
+    /// Extra points between letters in the editor and preview.
+    pub letter_spacing: f32,
+    /// Extra points below each blank line between paragraphs in the editor.
+    pub paragraph_spacing: f32,
+    /// Characters per line in the editor, which then sits centered in the
+    /// window; 0 to fill the width.
+    pub max_line_chars: u32,
+    /// Name of a theme file in the themes folder; empty for the default.
+    pub theme: String,
+}
+
+impl Default for AppearanceConfig {
+    fn default() -> Self {
+        Self {
+            high_contrast: false,
+            dyslexic_font: false,
+            dyslexic_font_path: String::new(),
+            line_spacing: 1.0,
+            letter_spacing: 0.0,
+            paragraph_spacing: 0.0,
+            max_line_chars: 0,
+            theme: String::new(),
+        }
+    }
+}
+
+impl AppearanceConfig {
+    /// Whether text is spaced out from egui's own layout.
+    pub fn is_spaced(&self) -> bool {
+        self.line_spacing != 1.0 || self.letter_spacing != 0.0 || self.paragraph_spacing != 0.0
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct NetworkConfig {
+    /// Seconds a request may take before it is given up.
+    pub timeout_secs: u64,
+    /// Extra attempts when the server cannot be reached or fails.
+    pub retries: u32,
+    /// Proxy URL for every request; empty to use `HTTP_PROXY` and
+    /// `HTTPS_PROXY` from the environment.
+    pub proxy: String,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 30,
+            retries: 3,
+            proxy: String::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct CitationConfig {
+    /// BibTeX file to read references from; empty to skip.
+    pub bibtex_file: String,
+    /// Local Zotero URL answering with BibTeX; empty to skip.
+    pub zotero_url: String,
+    /// CSL style passed to pandoc on export; empty for pandoc's default.
+    pub csl_style: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct Config {
+    pub backup: BackupConfig,
+    pub nextcloud: NextcloudConfig,
+    pub lan_sync: LanSyncConfig,
+    pub server: ServerConfig,
+    pub capture: CaptureConfig,
+    pub minimize_to_tray: bool,
+    /// Language code of the UI; empty to follow the system.
+    pub language: String,
+    pub disabled_plugins: Vec<String>,
+    /// Shell commands the note is piped through on save, in order.
+    pub on_save_commands: Vec<SaveHook>,
+    pub code: CodeConfig,
+    pub ai: AiConfig,
+    pub autocomplete: AutocompleteConfig,
+    pub translate: TranslateConfig,
+    pub dictation: DictationConfig,
+    /// Tesseract language codes for OCR, e.g. `eng` or `eng+deu`.
+    pub ocr_language: String,
+    pub citations: CitationConfig,
+    pub editor: EditorConfig,
+    pub sidebar: SidebarConfig,
+    pub review: ReviewConfig,
+    pub publish: PublishConfig,
+    pub network: NetworkConfig,
+    pub grammar: GrammarConfig,
+    pub autocorrect: AutocorrectConfig,
+    pub appearance: AppearanceConfig,
+}
+
+impl Config {
+    pub fn load() -> Self {
+        std::fs::read_to_string(CONFIG_FILE)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => {
+                if let Err(err) = std::fs::write(CONFIG_FILE, json) {
+                    error!("Failed to save config: {}", err);
+                }
+            }
+            Err(err) => error!("Failed to serialize config: {}", err),
+        }
+    }
+
+    /// Settings window; changes are written to disk as they are made.
+    pub fn show(&mut self, ctx: &egui::Context, open: &mut bool) {
+        let mut changed = false;
+        egui::Window::new(t("⚙ Settings")).open(open).show(ctx, |ui| {
+            ui.heading(t("Backups"));
+            changed |= ui.checkbox(&mut self.backup.enabled, t("Automatic backups")).changed();
+            ui.horizontal(|ui| {
+                ui.label(t("Directory"));
+                changed |= ui.text_edit_singleline(&mut self.backup.dir).changed();
+                if ui.button("…").clicked()
+                    && let Some(dir) = rfd::FileDialog::new().pick_folder()
+                {
+                    self.backup.dir = dir.display().to_string();
+                    changed = true;
+                }
+            });
+            changed |= ui
+                .add(egui::Slider::new(&mut self.backup.interval_minutes, 5..=1440).text(t("minutes between backups")))
+                .changed();
+            changed |= ui
+                .add(egui::Slider::new(&mut self.backup.keep, 1..=100).text(t("backups to keep")))
+                .changed();
+
+            ui.separator();
+            ui.heading(t("Nextcloud Notes"));
+            egui::Grid::new("nextcloud").num_columns(2).show(ui, |ui| {
+                ui.label(t("Server URL"));
+                changed |= ui.text_edit_singleline(&mut self.nextcloud.url).changed();
+                ui.end_row();
+                ui.label(t("User"));
+                changed |= ui.text_edit_singleline(&mut self.nextcloud.user).changed();
+                ui.end_row();
+                ui.label(t("App password"));
+                changed |= ui
+                    .add(egui::TextEdit::singleline(&mut self.nextcloud.app_password).password(true))
+                    .changed();
+                ui.end_row();
+            });
+
+            ui.separator();
+            ui.heading(t("LAN sync"));
+            changed |= ui
+                .checkbox(&mut self.lan_sync.enabled, t("Sync with devices on this network (restart to apply)"))
+                .changed();
+            egui::Grid::new("lan_sync").num_columns(2).show(ui, |ui| {
+                ui.label(t("Device name"));
+                changed |= ui
+                    .add(egui::TextEdit::singleline(&mut self.lan_sync.device_name).hint_text(t("host name")))
+                    .changed();
+                ui.end_row();
+                ui.label(t("Port"));
+                changed |= ui.add(egui::DragValue::new(&mut self.lan_sync.port)).changed();
+                ui.end_row();
+                ui.label(t("Sync paired devices"));
+                changed |= ui
+                    .add(egui::DragValue::new(&mut self.lan_sync.interval_minutes).clamp_range(0..=1440).prefix(t("every ")).suffix(t(" min")))
+                    .on_hover_text(t("0 to sync only by hand"))
+                    .changed();
+                ui.end_row();
+            });
+
+            ui.separator();
+            ui.heading(t("Local API server"));
+            changed |= ui
+                .checkbox(&mut self.server.enabled, t("Serve notes on 127.0.0.1 (restart to apply)"))
+                .changed();
+            changed |= ui.add(egui::DragValue::new(&mut self.server.port).prefix(t("port "))).changed();
+            ui.horizontal(|ui| {
+                ui.label(t("Token"));
+                ui.code(&self.server.token);
+                if ui.button("📋").on_hover_text(t("Copy")).clicked() {
+                    ui.output_mut(|o| o.copied_text = self.server.token.clone());
+                }
+                if ui.button(t("Regenerate")).clicked() {
+                    self.server.token = crate::server::generate_token();
+                    changed = true;
+                }
+            });
+
+            ui.separator();
+            ui.heading(t("Quick capture"));
+            changed |= ui
+                .checkbox(&mut self.capture.enabled, t("Global hotkey (restart to apply)"))
+                .changed();
+            ui.horizontal(|ui| {
+                ui.label(t("Hotkey"));
+                changed |= ui.text_edit_singleline(&mut self.capture.hotkey).changed();
+            });
+            ui.horizontal(|ui| {
+                changed |= ui
+                    .radio_value(&mut self.capture.target, CaptureTarget::Inbox, t("Inbox note"))
+                    .changed();
+                changed |= ui
+                    .radio_value(&mut self.capture.target, CaptureTarget::DailyNote, t("Today's daily note"))
+                    .changed();
+            });
+            if self.capture.target == CaptureTarget::Inbox {
+                ui.horizontal(|ui| {
+                    ui.label(t("Inbox"));
+                    changed |= ui.text_edit_singleline(&mut self.capture.inbox_note).changed();
+                });
+            }
+
+            ui.separator();
+            ui.heading(t("Editor"));
+            changed |= ui
+                .checkbox(&mut self.editor.format_tables_on_save, t("Format tables on save"))
+                .changed();
+            changed |= ui
+                .checkbox(&mut self.editor.trim_trailing_whitespace, t("Strip trailing whitespace on save"))
+                .on_hover_text(t("Two spaces that break a line are kept"))
+                .changed();
+            changed |= ui.checkbox(&mut self.editor.final_newline, t("End with a single newline on save")).changed();
+            changed |= ui.checkbox(&mut self.editor.collapse_blank_lines, t("Collapse runs of blank lines to two on save")).changed();
+            changed |= ui
+                .checkbox(&mut self.editor.paste_html, t("Paste from web pages and documents as Markdown"))
+                .on_hover_text(t("Ctrl+Shift+V pastes plain text"))
+                .changed();
+            changed |= ui
+                .checkbox(&mut self.editor.fetch_link_titles, t("Link pasted URLs with the page title"))
+                .on_hover_text(t("Fetches the page in the background. A URL pasted over a selection always links the selection."))
+                .changed();
+            changed |= ui
+                .checkbox(&mut self.editor.keep_encoding, t("Save files in the encoding they were opened in"))
+                .on_hover_text(t("Otherwise files in Windows-1252 or UTF-16 are converted to UTF-8 when saved"))
+                .changed();
+            ui.horizontal(|ui| {
+                ui.label(t("Autosave"));
+                changed |= ui
+                    .add(egui::DragValue::new(&mut self.editor.autosave_seconds).clamp_range(0..=600).suffix(t(" s after the last edit")))
+                    .on_hover_text(t("0 saves only by hand"))
+                    .changed();
+            });
+            ui.horizontal(|ui| {
+                ui.label(t("Glossary note"));
+                changed |= ui
+                    .text_edit_singleline(&mut self.editor.glossary_note)
+                    .on_hover_text(t("Lines like \"API: Application Programming Interface\", or a two-column table"))
+                    .changed();
+            });
+
+            ui.separator();
+            ui.heading(t("Review queue"));
+            ui.horizontal(|ui| {
+                changed |= ui.add(egui::DragValue::new(&mut self.review.queue_size).clamp_range(0..=50).suffix(t(" notes a day"))).changed();
+                changed |= ui.add(egui::DragValue::new(&mut self.review.stale_days).clamp_range(1..=3650).prefix(t("stale after ")).suffix(t(" days"))).changed();
+            });
+
+            ui.separator();
+            ui.heading(t("On-save commands"));
+            ui.weak(t("The note is piped through each command after it is saved; its output replaces the note unless the command only reports."));
+            let mut remove = None;
+            for (index, hook) in self.on_save_commands.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    changed |= ui.text_edit_singleline(&mut hook.command).changed();
+                    changed |= ui
+                        .checkbox(&mut hook.report_only, t("Report only"))
+                        .on_hover_text(t("Show what the command reports without changing the note, e.g. for a linter"))
+                        .changed();
+                    if ui.small_button("🗑").clicked() {
+                        remove = Some(index);
+                    }
+                });
+            }
+            if let Some(index) = remove {
+                self.on_save_commands.remove(index);
+                changed = true;
+            }
+            if ui.button(t("➕ Add command")).clicked() {
+                self.on_save_commands.push(SaveHook::default());
+            }
+
+            ui.separator();
+            ui.heading(t("Code blocks"));
+            changed |= ui
+                .checkbox(&mut self.code.confirm_runs, t("Ask before running a code block"))
+                .changed();
+            egui::Grid::new("runners").num_columns(2).show(ui, |ui| {
+                for runner in &mut self.code.runners {
+                    changed |= ui.text_edit_singleline(&mut runner.language).changed();
+                    changed |= ui.text_edit_singleline(&mut runner.command).changed();
+                    ui.end_row();
+                }
+            });
+            if ui.button(t("➕ Add runner")).clicked() {
+                self.code.runners.push(CodeRunner {
+                    language: String::new(),
+                    command: String::new(),
+                });
+            }
+
+            ui.separator();
+            ui.heading(t("Assistant"));
+            egui::Grid::new("ai").num_columns(2).show(ui, |ui| {
+                ui.label(t("Provider"));
+                egui::ComboBox::from_id_source("ai_provider")
+                    .selected_text(self.ai.provider.label())
+                    .show_ui(ui, |ui| {
+                        for provider in [AiProvider::OpenAi, AiProvider::Anthropic, AiProvider::Ollama] {
+                            if ui
+                                .selectable_value(&mut self.ai.provider, provider, provider.label())
+                                .changed()
+                            {
+                                self.ai.endpoint = provider.default_endpoint().to_owned();
+                                changed = true;
+                            }
+                        }
+                    });
+                ui.end_row();
+                ui.label(t("Endpoint"));
+                changed |= ui.text_edit_singleline(&mut self.ai.endpoint).changed();
+                ui.end_row();
+                ui.label(t("Model"));
+                changed |= ui.text_edit_singleline(&mut self.ai.model).changed();
+                ui.end_row();
+                if self.ai.provider != AiProvider::Ollama {
+                    ui.label(t("API key"));
+                    changed |= ui
+                        .add(egui::TextEdit::singleline(&mut self.ai.api_key).password(true))
+                        .changed();
+                    ui.end_row();
+                }
+            });
+
+            ui.separator();
+            ui.heading(t("Inline completion"));
+            changed |= ui
+                .checkbox(&mut self.autocomplete.enabled, t("Suggest ghost text from a local model (Tab accepts)"))
+                .changed();
+            if self.autocomplete.enabled {
+                egui::Grid::new("autocomplete").num_columns(2).show(ui, |ui| {
+                    ui.label(t("Endpoint"));
+                    changed |= ui.text_edit_singleline(&mut self.autocomplete.endpoint).changed();
+                    ui.end_row();
+                    ui.label(t("Model"));
+                    changed |= ui.text_edit_singleline(&mut self.autocomplete.model).changed();
+                    ui.end_row();
+                });
+                changed |= ui
+                    .add(egui::Slider::new(&mut self.autocomplete.delay_ms, 200..=3000).text(t("ms pause before suggesting")))
+                    .changed();
+                changed |= ui
+                    .add(egui::Slider::new(&mut self.autocomplete.max_tokens, 8..=128).text(t("max tokens")))
+                    .changed();
+            }
+
+            ui.separator();
+            ui.heading(t("Grammar and spelling"));
+            changed |= ui.checkbox(&mut self.grammar.languagetool, t("LanguageTool")).changed();
+            if self.grammar.languagetool {
+                egui::Grid::new("languagetool").num_columns(2).show(ui, |ui| {
+                    ui.label(t("Server"));
+                    ui.horizontal(|ui| {
+                        changed |= ui.text_edit_singleline(&mut self.grammar.languagetool_url).changed();
+                        if ui.small_button(t("Public")).on_hover_text(t("Use the free public API")).clicked() {
+                            self.grammar.languagetool_url = crate::grammar::ENDPOINT.to_owned();
+                            changed = true;
+                        }
+                    });
+                    ui.end_row();
+                    ui.label(t("Premium user"));
+                    changed |= ui
+                        .add(egui::TextEdit::singleline(&mut self.grammar.languagetool_username).hint_text(t("optional")))
+                        .changed();
+                    ui.end_row();
+                    ui.label(t("API key"));
+                    changed |= ui
+                        .add(egui::TextEdit::singleline(&mut self.grammar.languagetool_api_key).password(true))
+                        .changed();
+                    ui.end_row();
+                    ui.label(t("Mother tongue"));
+                    changed |= ui
+                        .add(egui::TextEdit::singleline(&mut self.grammar.mother_tongue).hint_text(t("e.g. de-DE")))
+                        .on_hover_text(t("Warns about false friends from your first language"))
+                        .changed();
+                    ui.end_row();
+                    ui.label(t("Preferred variants"));
+                    changed |= ui
+                        .add(egui::TextEdit::singleline(&mut self.grammar.preferred_variants).hint_text(t("e.g. en-GB,de-AT")))
+                        .on_hover_text(t("Which variant \"Detect automatically\" picks for a language"))
+                        .changed();
+                    ui.end_row();
+                });
+            }
+            changed |= ui.checkbox(&mut self.grammar.spellcheck, t("Spellchecker")).changed();
+            changed |= ui
+                .checkbox(&mut self.grammar.spell_as_you_type, t("Check each word as it is typed"))
+                .changed();
+            if self.grammar.spellcheck || self.grammar.spell_as_you_type {
+                ui.horizontal(|ui| {
+                    ui.label(t("Word list"));
+                    changed |= ui.text_edit_singleline(&mut self.grammar.word_list).changed();
+                    if ui.button("📂").clicked()
+                        && let Some(path) = rfd::FileDialog::new().pick_file()
+                    {
+                        self.grammar.word_list = path.to_string_lossy().into_owned();
+                        changed = true;
+                    }
+                });
+            }
+            ui.horizontal(|ui| {
+                ui.label(t("Thesaurus"));
+                changed |= ui
+                    .text_edit_singleline(&mut self.grammar.thesaurus)
+                    .on_hover_text(t("A MyThes .dat file, as LibreOffice uses, or an OpenThesaurus text export"))
+                    .changed();
+                if ui.button("📂").clicked()
+                    && let Some(path) = rfd::FileDialog::new().pick_file()
+                {
+                    self.grammar.thesaurus = path.to_string_lossy().into_owned();
+                    changed = true;
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label(t("Dictionary"));
+                changed |= ui
+                    .text_edit_singleline(&mut self.grammar.dictionary)
+                    .on_hover_text(t("A Wiktionary extract in JSON lines, as downloaded from kaikki.org"))
+                    .changed();
+                if ui.button("📂").clicked()
+                    && let Some(path) = rfd::FileDialog::new().pick_file()
+                {
+                    self.grammar.dictionary = path.to_string_lossy().into_owned();
+                    changed = true;
+                }
+            });
+
+            ui.separator();
+            ui.heading(t("Autocorrect"));
+            changed |= ui.checkbox(&mut self.autocorrect.enabled, t("Correct as I type")).changed();
+            if self.autocorrect.enabled {
+                ui.weak(t("Ctrl+Z or Backspace right after a correction takes it back. Notes with autocorrect: false in their front matter are left alone."));
+                changed |= ui.checkbox(&mut self.autocorrect.smart_quotes, t("Typographic quotes")).changed();
+                let mut remove = None;
+                egui::Grid::new("autocorrect").num_columns(3).show(ui, |ui| {
+                    for (index, (from, to)) in self.autocorrect.replacements.iter_mut().enumerate() {
+                        changed |= ui.add(egui::TextEdit::singleline(from).desired_width(120.0)).changed();
+                        changed |= ui.add(egui::TextEdit::singleline(to).desired_width(120.0)).changed();
+                        if ui.small_button("🗑").clicked() {
+                            remove = Some(index);
+                        }
+                        ui.end_row();
+                    }
+                });
+                if let Some(index) = remove {
+                    self.autocorrect.replacements.remove(index);
+                    changed = true;
+                }
+                if ui.button(t("➕ Add replacement")).clicked() {
+                    self.autocorrect.replacements.push((String::new(), String::new()));
+                }
+            }
+
+            ui.separator();
+            ui.heading(t("Translation"));
+            ui.horizontal(|ui| {
+                for (provider, label) in [
+                    (TranslateProvider::DeepL, "DeepL"),
+                    (TranslateProvider::LibreTranslate, "LibreTranslate"),
+                ] {
+                    if ui.radio_value(&mut self.translate.provider, provider, label).changed() {
+                        self.translate.endpoint = provider.default_endpoint().to_owned();
+                        changed = true;
+                    }
+                }
+            });
+            egui::Grid::new("translate").num_columns(2).show(ui, |ui| {
+                ui.label(t("Endpoint"));
+                changed |= ui.text_edit_singleline(&mut self.translate.endpoint).changed();
+                ui.end_row();
+                ui.label(t("API key"));
+                changed |= ui
+                    .add(egui::TextEdit::singleline(&mut self.translate.api_key).password(true))
+                    .changed();
+                ui.end_row();
+            });
+
+            ui.separator();
+            ui.heading(t("Dictation"));
+            egui::Grid::new("dictation").num_columns(2).show(ui, |ui| {
+                ui.label(t("Transcription endpoint"));
+                changed |= ui.text_edit_singleline(&mut self.dictation.endpoint).changed();
+                ui.end_row();
+                ui.label(t("Model"));
+                changed |= ui.text_edit_singleline(&mut self.dictation.model).changed();
+                ui.end_row();
+                ui.label(t("API key"));
+                changed |= ui
+                    .add(egui::TextEdit::singleline(&mut self.dictation.api_key).password(true))
+                    .changed();
+                ui.end_row();
+                ui.label(t("Push-to-talk key"));
+                changed |= ui.text_edit_singleline(&mut self.dictation.push_to_talk_key).changed();
+                ui.end_row();
+            });
+
+            ui.separator();
+            ui.heading(t("OCR"));
+            ui.horizontal(|ui| {
+                ui.label(t("Tesseract languages"));
+                changed |= ui.text_edit_singleline(&mut self.ocr_language).changed();
+            });
+
+            ui.separator();
+            ui.heading(t("Citations"));
+            egui::Grid::new("citations").num_columns(2).show(ui, |ui| {
+                ui.label(t("BibTeX file"));
+                ui.horizontal(|ui| {
+                    changed |= ui.text_edit_singleline(&mut self.citations.bibtex_file).changed();
+                    if ui.button("📂").clicked()
+                        && let Some(path) = rfd::FileDialog::new().add_filter("BibTeX", &["bib"]).pick_file()
+                    {
+                        self.citations.bibtex_file = path.to_string_lossy().into_owned();
+                        changed = true;
+                    }
+                });
+                ui.end_row();
+                ui.label(t("Zotero URL"));
+                changed |= ui
+                    .add(
+                        egui::TextEdit::singleline(&mut self.citations.zotero_url)
+                            .hint_text("http://localhost:23119/api/users/0/items/top?format=bibtex"),
+                    )
+                    .changed();
+                ui.end_row();
+                ui.label(t("CSL style"));
+                changed |= ui.text_edit_singleline(&mut self.citations.csl_style).changed();
+                ui.end_row();
+            });
+
+            ui.separator();
+            ui.heading(t("Network"));
+            egui::Grid::new("network").num_columns(2).show(ui, |ui| {
+                ui.label(t("Timeout"));
+                changed |= ui
+                    .add(egui::DragValue::new(&mut self.network.timeout_secs).clamp_range(1..=600).suffix(" s"))
+                    .changed();
+                ui.end_row();
+                ui.label(t("Retries"));
+                changed |= ui.add(egui::DragValue::new(&mut self.network.retries).clamp_range(0..=10)).changed();
+                ui.end_row();
+                ui.label(t("Proxy"));
+                changed |= ui
+                    .add(egui::TextEdit::singleline(&mut self.network.proxy).hint_text(t("from HTTP_PROXY / HTTPS_PROXY")))
+                    .changed();
+                ui.end_row();
+            });
+
+            ui.separator();
+            ui.heading(t("Theme"));
+            ui.horizontal(|ui| {
+                let selected = if self.appearance.theme.is_empty() { t("Default") } else { self.appearance.theme.clone() };
+                egui::ComboBox::from_id_source("theme").selected_text(selected).show_ui(ui, |ui| {
+                    changed |= ui.selectable_value(&mut self.appearance.theme, String::new(), t("Default")).changed();
+                    for name in crate::theme::list() {
+                        changed |= ui.selectable_value(&mut self.appearance.theme, name.clone(), name).changed();
+                    }
+                });
+                if ui.button(t("📂 Themes folder")).clicked() {
+                    let dir = std::fs::create_dir_all(THEMES_DIR).and_then(|_| std::path::Path::new(THEMES_DIR).canonicalize());
+                    match dir {
+                        Ok(dir) => ui.ctx().open_url(egui::OpenUrl::new_tab(format!("file://{}", dir.display()))),
+                        Err(err) => error!("Failed to open {}: {}", THEMES_DIR, err),
+                    }
+                }
+            })
+            .response
+            .on_hover_text(t("TOML or JSON files with colors, font sizes and Markdown colors; changes show as soon as a file is saved"));
+
+            ui.separator();
+            ui.heading(t("Accessibility"));
+            changed |= ui
+                .checkbox(&mut self.appearance.high_contrast, t("High contrast"))
+                .changed();
+            ui.horizontal(|ui| {
+                changed |= ui.checkbox(&mut self.appearance.dyslexic_font, t("OpenDyslexic font")).changed();
+                changed |= ui
+                    .add_enabled(
+                        self.appearance.dyslexic_font,
+                        egui::TextEdit::singleline(&mut self.appearance.dyslexic_font_path).hint_text(t("found automatically")),
+                    )
+                    .on_hover_text(t("The font file, if it is not installed where it is usually found"))
+                    .changed();
+            });
+
+            ui.separator();
+            ui.heading(t("Typography"));
+            changed |= ui
+                .add(egui::Slider::new(&mut self.appearance.line_spacing, 1.0..=2.5).step_by(0.1).text(t("line spacing")))
+                .changed();
+            changed |= ui
+                .add(egui::Slider::new(&mut self.appearance.letter_spacing, 0.0..=4.0).step_by(0.5).text(t("letter spacing")))
+                .changed();
+            changed |= ui
+                .add(egui::Slider::new(&mut self.appearance.paragraph_spacing, 0.0..=24.0).step_by(1.0).text(t("paragraph spacing")))
+                .changed();
+            changed |= ui
+                .add(egui::Slider::new(&mut self.appearance.max_line_chars, 0..=160).text(t("characters per line")))
+                .on_hover_text(t("The editor is centered with margins on either side; 0 fills the window"))
+                .changed();
+
+            ui.separator();
+            ui.heading(t("Window"));
+            ui.horizontal(|ui| {
+                ui.label(t("Language"));
+                let before = self.language.clone();
+                egui::ComboBox::from_id_source("ui_language")
+                    .selected_text(i18n::language_name(&self.language))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.language, String::new(), i18n::language_name(""));
+                        for code in i18n::languages() {
+                            let name = i18n::language_name(&code);
+                            ui.selectable_value(&mut self.language, code, name);
+                        }
+                    });
+                if self.language != before {
+                    i18n::set_language(&self.language);
+                    changed = true;
+                }
+            });
+            changed |= ui
+                .checkbox(&mut self.minimize_to_tray, t("Closing the window minimizes to the tray"))
+                .changed();
+            changed |= ui
+                .checkbox(&mut self.editor.touch_mode, t("Touch layout"))
+                .on_hover_text(t("Larger controls, swipe from the left edge for the note list, and a formatting bar"))
+                .changed();
+
+            ui.separator();
+            ui.heading(t("Links"));
+            if ui.button(t("Register noteapp:// links")).clicked()
+                && let Err(err) = crate::links::register_scheme()
+            {
+                error!("Failed to register URL scheme: {}", err);
+            }
+        });
+        if changed {
+            crate::http::configure(&self.network);
+            self.save();
+        }
+    }
+}
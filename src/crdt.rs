@@ -0,0 +1,247 @@
+use serde::{Deserialize, Serialize};
+
+/// Identifies one inserted character across every copy of a document: a
+/// Lamport counter, then the site that made it to break ties.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Id {
+    pub counter: u64,
+    pub site: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Op {
+    /// `ch` goes right after `after`, or at the start for `None`.
+    Insert { id: Id, after: Option<Id>, ch: char },
+    Delete { id: Id },
+}
+
+/// A character of the document; deleted ones stay as tombstones so later
+/// inserts can still find their place.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Elem {
+    pub id: Id,
+    pub ch: char,
+    pub deleted: bool,
+}
+
+/// Replicated text (an RGA sequence): copies that have applied the same
+/// operations, in any order that keeps each insert after its anchor,
+/// hold the same text.
+pub struct Doc {
+    site: u64,
+    counter: u64,
+    elems: Vec<Elem>,
+}
+
+impl Doc {
+    /// A new document holding `text`, as made by `site`.
+    pub fn new(site: u64, text: &str) -> Self {
+        let mut doc = Self {
+            site,
+            counter: 0,
+            elems: Vec::new(),
+        };
+        doc.edit(text);
+        doc
+    }
+
+    /// A copy of another site's document.
+    pub fn from_elems(site: u64, elems: Vec<Elem>) -> Self {
+        let counter = elems.iter().map(|e| e.id.counter).max().unwrap_or(0);
+        Self { site, counter, elems }
+    }
+
+    pub fn elems(&self) -> &[Elem] {
+        &self.elems
+    }
+
+    pub fn text(&self) -> String {
+        self.elems.iter().filter(|e| !e.deleted).map(|e| e.ch).collect()
+    }
+
+    fn index_of(&self, id: Id) -> Option<usize> {
+        self.elems.iter().position(|e| e.id == id)
+    }
+
+    fn next_id(&mut self) -> Id {
+        self.counter += 1;
+        Id {
+            counter: self.counter,
+            site: self.site,
+        }
+    }
+
+    /// Applies an operation; returns false when it refers to a character
+    /// this copy has not seen.
+    pub fn apply(&mut self, op: &Op) -> bool {
+        match *op {
+            Op::Insert { id, after, ch } => {
+                if self.index_of(id).is_some() {
+                    return true;
+                }
+                let mut at = match after {
+                    Some(after) => match self.index_of(after) {
+                        Some(index) => index + 1,
+                        None => return false,
+                    },
+                    None => 0,
+                };
+                // Concurrent inserts at the same place, and everything typed
+                // after them, have larger ids and stay in front.
+                while self.elems.get(at).is_some_and(|e| e.id > id) {
+                    at += 1;
+                }
+                self.elems.insert(at, Elem { id, ch, deleted: false });
+                self.counter = self.counter.max(id.counter);
+                true
+            }
+            Op::Delete { id } => match self.index_of(id) {
+                Some(index) => {
+                    self.elems[index].deleted = true;
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+
+    /// Turns the document into `text` by deleting and inserting around the
+    /// changed middle. Returns the operations to send to other copies.
+    pub fn edit(&mut self, text: &str) -> Vec<Op> {
+        let visible: Vec<usize> = (0..self.elems.len()).filter(|&i| !self.elems[i].deleted).collect();
+        let new: Vec<char> = text.chars().collect();
+        let old: Vec<char> = visible.iter().map(|&i| self.elems[i].ch).collect();
+        let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+
+        let mut ops = Vec::new();
+        for &index in &visible[prefix..old.len() - suffix] {
+            self.elems[index].deleted = true;
+            ops.push(Op::Delete { id: self.elems[index].id });
+        }
+        let mut after = prefix.checked_sub(1).map(|i| self.elems[visible[i]].id);
+        for &ch in &new[prefix..new.len() - suffix] {
+            let op = Op::Insert {
+                id: self.next_id(),
+                after,
+                ch,
+            };
+            self.apply(&op);
+            if let Op::Insert { id, .. } = op {
+                after = Some(id);
+            }
+            ops.push(op);
+        }
+        ops
+    }
+
+    /// The character before a char position, which a cursor stays after
+    /// while others edit; `None` is the start of the text.
+    pub fn anchor(&self, position: usize) -> Option<Id> {
+        position.checked_sub(1).and_then(|p| self.elems.iter().filter(|e| !e.deleted).nth(p)).map(|e| e.id)
+    }
+
+    /// The char position right after an anchor, even once it is deleted.
+    pub fn position(&self, anchor: Option<Id>) -> usize {
+        let Some(anchor) = anchor else {
+            return 0;
+        };
+        let mut position = 0;
+        for elem in &self.elems {
+            if !elem.deleted {
+                position += 1;
+            }
+            if elem.id == anchor {
+                return position;
+            }
+        }
+        position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Applies `ops` to `doc`, holding back the ones whose anchor has not
+    /// arrived yet, as a peer would until the rest catch up.
+    fn deliver(doc: &mut Doc, ops: &[Op]) {
+        let mut pending: Vec<&Op> = ops.iter().collect();
+        while !pending.is_empty() {
+            let before = pending.len();
+            pending.retain(|op| !doc.apply(op));
+            assert!(pending.len() < before, "operations never became applicable");
+        }
+    }
+
+    #[test]
+    fn concurrent_inserts_at_the_same_place_converge() {
+        let mut a = Doc::new(1, "ac");
+        let mut b = Doc::from_elems(2, a.elems().to_vec());
+        let from_a = a.edit("aXc");
+        let from_b = b.edit("aYYc");
+        deliver(&mut a, &from_b);
+        deliver(&mut b, &from_a);
+        assert_eq!(a.text(), b.text());
+        // Each side's run of characters stays together.
+        assert!(a.text() == "aXYYc" || a.text() == "aYYXc", "{}", a.text());
+    }
+
+    #[test]
+    fn delete_and_insert_at_the_same_place_converge() {
+        let mut a = Doc::new(1, "hello world");
+        let mut b = Doc::from_elems(2, a.elems().to_vec());
+        let from_a = a.edit("hello");
+        let from_b = b.edit("hello big world");
+        deliver(&mut a, &from_b);
+        deliver(&mut b, &from_a);
+        assert_eq!(a.text(), b.text());
+        // The space before "big" went with " world".
+        assert_eq!(a.text(), "hellobig ");
+    }
+
+    #[test]
+    fn delivery_order_does_not_matter() {
+        let base = Doc::new(1, "one two three");
+        let mut a = Doc::from_elems(2, base.elems().to_vec());
+        let mut b = Doc::from_elems(3, base.elems().to_vec());
+        let mut ops = a.edit("one 2 three");
+        ops.extend(a.edit("zero one 2 three"));
+        let from_b = b.edit("one two three four");
+
+        let mut forward = Doc::from_elems(4, base.elems().to_vec());
+        deliver(&mut forward, &ops);
+        deliver(&mut forward, &from_b);
+        let mut backward = Doc::from_elems(5, base.elems().to_vec());
+        let reversed: Vec<Op> = from_b.iter().chain(&ops).rev().cloned().collect();
+        deliver(&mut backward, &reversed);
+
+        deliver(&mut a, &from_b);
+        deliver(&mut b, &ops);
+        assert_eq!(a.text(), "zero one 2 three four");
+        for doc in [&b, &forward, &backward] {
+            assert_eq!(doc.text(), a.text());
+        }
+    }
+
+    #[test]
+    fn applying_an_op_twice_changes_nothing() {
+        let mut a = Doc::new(1, "abc");
+        let mut b = Doc::from_elems(2, a.elems().to_vec());
+        let ops = a.edit("abXc");
+        deliver(&mut b, &ops);
+        deliver(&mut b, &ops);
+        assert_eq!(b.text(), "abXc");
+    }
+
+    #[test]
+    fn cursors_stay_after_their_character() {
+        let mut a = Doc::new(1, "abc");
+        let anchor = a.anchor(2);
+        a.edit("XXabc");
+        assert_eq!(a.position(anchor), 4);
+        a.edit("XXac");
+        assert_eq!(a.position(anchor), 3);
+        assert_eq!(a.position(a.anchor(0)), 0);
+    }
+}
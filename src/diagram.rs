@@ -0,0 +1,131 @@
+use crate::i18n::t;
+use crate::svg::{SvgCache, hex_color};
+use eframe::egui;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tracing::error;
+#[cfg(target_os = "android")]
+use crate::stubs::rfd;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+    Mermaid,
+    Graphviz,
+}
+
+impl Kind {
+    /// The diagram language of a fenced block, by its info string.
+    pub fn from_lang(lang: &str) -> Option<Self> {
+        match lang.to_lowercase().as_str() {
+            "mermaid" => Some(Kind::Mermaid),
+            "dot" | "graphviz" => Some(Kind::Graphviz),
+            _ => None,
+        }
+    }
+}
+
+/// Renders a diagram to `format` (`svg` or `png`) with the installed tool:
+/// `mmdc` from mermaid-cli, or Graphviz's `dot`. With a `foreground` color
+/// (hex RGB) it is drawn for the app's dark background; without one, in
+/// the tool's default light theme for export.
+pub fn render(kind: Kind, source: &str, format: &str, foreground: Option<&str>) -> Result<Vec<u8>, String> {
+    match kind {
+        Kind::Graphviz => {
+            let mut command = Command::new("dot");
+            command.arg(format!("-T{}", format));
+            if let Some(foreground) = foreground {
+                let color = format!("#{}", foreground);
+                command.args(["-Gbgcolor=transparent"]);
+                for attr in ["-Gcolor", "-Gfontcolor", "-Ncolor", "-Nfontcolor", "-Ecolor", "-Efontcolor"] {
+                    command.arg(format!("{}={}", attr, color));
+                }
+            }
+            pipe(&mut command, source)
+        }
+        Kind::Mermaid => {
+            let mut hasher = DefaultHasher::new();
+            (source, format, foreground).hash(&mut hasher);
+            let dir = std::env::temp_dir().join(format!("noteapp-mermaid-{:x}", hasher.finish()));
+            std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+            let input = dir.join("diagram.mmd");
+            let output = dir.join(format!("diagram.{}", format));
+            std::fs::write(&input, source).map_err(|e| e.to_string())?;
+            let mut command = Command::new("mmdc");
+            command.arg("-i").arg(&input).arg("-o").arg(&output);
+            match foreground {
+                Some(_) => command.args(["-t", "dark", "-b", "transparent"]),
+                None => command.args(["-b", "white"]),
+            };
+            let result = pipe(&mut command, "").and_then(|_| std::fs::read(&output).map_err(|e| e.to_string()));
+            let _ = std::fs::remove_dir_all(&dir);
+            result
+        }
+    }
+}
+
+fn pipe(command: &mut Command, input: &str) -> Result<Vec<u8>, String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("{}: {}", program, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(format!("{}: {}", program, String::from_utf8_lossy(&output.stderr).trim()))
+    }
+}
+
+/// Asks where to save a diagram and writes it there as SVG or PNG,
+/// depending on the chosen extension.
+pub fn export(kind: Kind, source: &str) {
+    let Some(dest) = rfd::FileDialog::new()
+        .set_file_name("diagram.svg")
+        .add_filter("SVG", &["svg"])
+        .add_filter("PNG", &["png"])
+        .save_file()
+    else {
+        return;
+    };
+    if let Err(err) = export_to(kind, source, &dest) {
+        error!("Failed to export diagram: {}", err);
+    }
+}
+
+fn export_to(kind: Kind, source: &str, dest: &Path) -> Result<(), String> {
+    let format = match dest.extension().and_then(|e| e.to_str()) {
+        Some("png") => "png",
+        _ => "svg",
+    };
+    let bytes = render(kind, source, format, None)?;
+    std::fs::write(dest.with_extension(format), bytes).map_err(|e| e.to_string())
+}
+
+/// Shows a diagram block, with its source as a fallback and a button to
+/// export it.
+pub fn show(ui: &mut egui::Ui, images: &mut SvgCache, kind: Kind, source: &str) {
+    let foreground = hex_color(ui.visuals().text_color());
+    let key = format!("diagram:{:?}:{}:{}", kind, foreground, source);
+    let owned = source.to_owned();
+    let render_svg = move || {
+        render(kind, &owned, "svg", Some(&foreground)).map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+    };
+    egui::Frame::group(ui.style()).show(ui, |ui| {
+        ui.set_width(ui.available_width());
+        if images.show(ui, key, 1.0, render_svg).is_none() {
+            ui.label(egui::RichText::new(source.trim_end()).monospace().weak());
+        }
+        if ui.small_button(t("💾 Export diagram")).clicked() {
+            export(kind, source);
+        }
+    });
+    ui.add_space(6.0);
+}
@@ -0,0 +1,230 @@
+use crate::config::DictationConfig;
+use crate::i18n::{t, tf};
+use eframe::egui;
+use std::sync::mpsc::{self, Receiver};
+
+/// Captures mono samples from the default input device until dropped.
+#[cfg(feature = "dictation")]
+struct Recorder {
+    _stream: cpal::Stream,
+    samples: std::sync::Arc<std::sync::Mutex<Vec<f32>>>,
+    sample_rate: u32,
+}
+
+#[cfg(feature = "dictation")]
+impl Recorder {
+    fn start() -> Result<Self, String> {
+        use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+        let device = cpal::default_host()
+            .default_input_device()
+            .ok_or("No microphone found")?;
+        let supported = device.default_input_config().map_err(|e| e.to_string())?;
+        let channels = supported.channels() as usize;
+        let sample_rate = supported.sample_rate().0;
+        let samples = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let sink = samples.clone();
+        let push = move |data: &[f32]| {
+            if let Ok(mut samples) = sink.lock() {
+                samples.extend(data.chunks(channels).map(|frame| frame.iter().sum::<f32>() / channels as f32));
+            }
+        };
+        let config = supported.config();
+        let on_error = |err| tracing::error!("Microphone error: {}", err);
+        let stream = match supported.sample_format() {
+            cpal::SampleFormat::F32 => device.build_input_stream(&config, move |d: &[f32], _: &_| push(d), on_error, None),
+            cpal::SampleFormat::I16 => device.build_input_stream(
+                &config,
+                move |d: &[i16], _: &_| push(&d.iter().map(|s| *s as f32 / i16::MAX as f32).collect::<Vec<_>>()),
+                on_error,
+                None,
+            ),
+            format => return Err(format!("Unsupported sample format {:?}", format)),
+        }
+        .map_err(|e| e.to_string())?;
+        stream.play().map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            _stream: stream,
+            samples,
+            sample_rate,
+        })
+    }
+
+    fn finish(self) -> (Vec<f32>, u32) {
+        let samples = self.samples.lock().map(|s| s.clone()).unwrap_or_default();
+        (samples, self.sample_rate)
+    }
+}
+
+/// Stand-in when built without the `dictation` feature.
+#[cfg(not(feature = "dictation"))]
+struct Recorder;
+
+#[cfg(not(feature = "dictation"))]
+impl Recorder {
+    fn start() -> Result<Self, String> {
+        Err("built without the `dictation` feature".to_owned())
+    }
+
+    fn finish(self) -> (Vec<f32>, u32) {
+        (Vec::new(), 16_000)
+    }
+}
+
+/// Encodes mono samples as a 16-bit PCM WAV file.
+fn wav(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut out = Vec::with_capacity(44 + data_len as usize);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    out.extend_from_slice(&1u16.to_le_bytes()); // mono
+    out.extend_from_slice(&sample_rate.to_le_bytes());
+    out.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+    out.extend_from_slice(&2u16.to_le_bytes());
+    out.extend_from_slice(&16u16.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    out
+}
+
+/// Sends audio to an OpenAI-compatible `/audio/transcriptions` endpoint,
+/// which both the hosted Whisper API and a local whisper.cpp server provide.
+fn transcribe(config: &DictationConfig, audio: Vec<u8>) -> Result<String, String> {
+    let part = reqwest::blocking::multipart::Part::bytes(audio)
+        .file_name("dictation.wav")
+        .mime_str("audio/wav")
+        .map_err(|e| e.to_string())?;
+    let form = reqwest::blocking::multipart::Form::new()
+        .text("model", config.model.clone())
+        .text("response_format", "json")
+        .part("file", part);
+    let mut request = crate::http::client().post(&config.endpoint).multipart(form);
+    if !config.api_key.is_empty() {
+        request = request.bearer_auth(&config.api_key);
+    }
+    let body: serde_json::Value = crate::http::send(request)
+        .and_then(|r| r.error_for_status())
+        .and_then(|r| r.json())
+        .map_err(|e| e.to_string())?;
+    body.get("text")
+        .and_then(|t| t.as_str())
+        .map(|t| t.trim().to_owned())
+        .ok_or_else(|| "Unexpected transcription response".to_owned())
+}
+
+#[derive(Default)]
+pub struct Dictation {
+    recorder: Option<Recorder>,
+    pending: Option<Receiver<Result<String, String>>>,
+    error: Option<String>,
+    push_to_talk: bool,
+}
+
+impl Dictation {
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    pub fn is_transcribing(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    pub fn toggle(&mut self, ctx: &egui::Context, config: &DictationConfig) {
+        if self.is_recording() {
+            self.stop(ctx, config);
+        } else {
+            self.start();
+        }
+    }
+
+    fn start(&mut self) {
+        match Recorder::start() {
+            Ok(recorder) => {
+                self.recorder = Some(recorder);
+                self.error = None;
+            }
+            Err(err) => self.error = Some(err),
+        }
+    }
+
+    fn stop(&mut self, ctx: &egui::Context, config: &DictationConfig) {
+        let Some(recorder) = self.recorder.take() else {
+            return;
+        };
+        let (samples, rate) = recorder.finish();
+        if samples.is_empty() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let config = config.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(transcribe(&config, wav(&samples, rate)));
+            ctx.request_repaint();
+        });
+        self.pending = Some(rx);
+    }
+
+    /// Handles push-to-talk and returns finished transcriptions.
+    pub fn update(&mut self, ctx: &egui::Context, config: &DictationConfig) -> Option<String> {
+        if let Some(key) = egui::Key::from_name(&config.push_to_talk_key) {
+            let held = ctx.input(|i| i.key_down(key));
+            if held && !self.is_recording() && !self.push_to_talk {
+                self.push_to_talk = true;
+                self.start();
+            } else if !held && self.push_to_talk {
+                self.push_to_talk = false;
+                self.stop(ctx, config);
+            }
+        }
+        if self.is_recording() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(250));
+        }
+
+        let rx = self.pending.as_ref()?;
+        let result = rx.try_recv().ok()?;
+        self.pending = None;
+        match result {
+            Ok(text) => Some(text),
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+
+    /// Recording badge in the corner of the window; also shows the last
+    /// error until clicked away.
+    pub fn show_indicator(&mut self, ctx: &egui::Context) {
+        let label = if self.is_recording() {
+            egui::RichText::new(t("● Recording")).color(egui::Color32::RED)
+        } else if self.is_transcribing() {
+            egui::RichText::new(t("… Transcribing")).color(egui::Color32::YELLOW)
+        } else if let Some(err) = &self.error {
+            egui::RichText::new(tf("⚠ Dictation: {}", &[&err])).color(egui::Color32::LIGHT_RED)
+        } else {
+            return;
+        };
+        let clicked = egui::Area::new(egui::Id::new("dictation_indicator"))
+            .anchor(egui::Align2::RIGHT_TOP, [-12.0, 40.0])
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style())
+                    .show(ui, |ui| ui.add(egui::Label::new(label.strong()).sense(egui::Sense::click())))
+                    .inner
+            })
+            .inner
+            .clicked();
+        if clicked {
+            self.error = None;
+        }
+    }
+}
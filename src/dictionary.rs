@@ -0,0 +1,221 @@
+use crate::i18n::t;
+use eframe::egui;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::sync::OnceLock;
+use tracing::{error, warn};
+
+/// Meanings shown per part of speech.
+const MAX_SENSES: usize = 5;
+
+/// A word's meanings as one part of speech.
+#[derive(Clone, Debug, Default)]
+pub struct Entry {
+    pub part: String,
+    /// IPA, e.g. `/ˈhæpi/`.
+    pub pronunciation: Option<String>,
+    pub glosses: Vec<String>,
+}
+
+/// One line of a Wiktionary extract from kaikki.org; everything else on
+/// it is ignored.
+#[derive(Deserialize)]
+struct Line {
+    #[serde(default)]
+    pos: String,
+    #[serde(default)]
+    senses: Vec<LineSense>,
+    #[serde(default)]
+    sounds: Vec<Sound>,
+}
+
+#[derive(Deserialize)]
+struct LineSense {
+    #[serde(default)]
+    glosses: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Sound {
+    ipa: Option<String>,
+}
+
+/// Definitions from an offline Wiktionary extract in JSON lines, as
+/// kaikki.org publishes them. These run to gigabytes, so only where each
+/// word's lines start is kept, and they are read when looked up.
+pub struct Dictionary {
+    pub path: String,
+    /// Byte offsets of each word's lines, built once off the UI thread.
+    index: OnceLock<HashMap<String, Vec<u64>>>,
+}
+
+impl Dictionary {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_owned(),
+            index: OnceLock::new(),
+        }
+    }
+
+    fn build_index(&self) -> std::io::Result<HashMap<String, Vec<u64>>> {
+        #[derive(Deserialize)]
+        struct Word {
+            word: String,
+        }
+        let mut index: HashMap<String, Vec<u64>> = HashMap::new();
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        let mut line = String::new();
+        let mut offset = 0;
+        loop {
+            line.clear();
+            let read = reader.read_line(&mut line)?;
+            if read == 0 {
+                break;
+            }
+            if let Ok(entry) = serde_json::from_str::<Word>(&line) {
+                index.entry(entry.word).or_default().push(offset);
+            }
+            offset += read as u64;
+        }
+        Ok(index)
+    }
+
+    /// Indexes the file if it has not been indexed yet.
+    pub fn load(&self) {
+        self.index.get_or_init(|| {
+            self.build_index().unwrap_or_else(|err| {
+                error!("Failed to read the dictionary {}: {}", self.path, err);
+                HashMap::new()
+            })
+        });
+    }
+
+    fn read(&self, offsets: &[u64]) -> std::io::Result<Vec<Entry>> {
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        let mut entries = Vec::new();
+        for &offset in offsets {
+            reader.seek(SeekFrom::Start(offset))?;
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            let Ok(line) = serde_json::from_str::<Line>(&line) else {
+                continue;
+            };
+            let glosses: Vec<String> = line
+                .senses
+                .into_iter()
+                .filter_map(|s| s.glosses.into_iter().last())
+                .take(MAX_SENSES)
+                .collect();
+            if !glosses.is_empty() {
+                entries.push(Entry {
+                    part: line.pos,
+                    pronunciation: line.sounds.into_iter().find_map(|s| s.ipa),
+                    glosses,
+                });
+            }
+        }
+        Ok(entries)
+    }
+
+    /// The entries for `word`, trying it in lowercase too. `None` while the
+    /// file is still being indexed.
+    pub fn lookup(&self, word: &str) -> Option<Vec<Entry>> {
+        let index = self.index.get()?;
+        let offsets = index.get(word).or_else(|| index.get(&word.to_lowercase()));
+        let Some(offsets) = offsets else {
+            return Some(Vec::new());
+        };
+        Some(self.read(offsets).unwrap_or_else(|err| {
+            warn!("Failed to look up {} in {}: {}", word, self.path, err);
+            Vec::new()
+        }))
+    }
+}
+
+fn entries_ui(ui: &mut egui::Ui, entries: Option<&[Entry]>) {
+    match entries {
+        None => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(t("Reading the dictionary…"));
+            });
+            ui.ctx().request_repaint_after(std::time::Duration::from_millis(200));
+        }
+        Some([]) => {
+            ui.weak(t("Not in the dictionary."));
+        }
+        Some(entries) => {
+            for entry in entries {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(&entry.part).italics());
+                    if let Some(ipa) = &entry.pronunciation {
+                        ui.weak(ipa);
+                    }
+                });
+                for (n, gloss) in entry.glosses.iter().enumerate() {
+                    ui.label(format!("{}. {}", n + 1, gloss));
+                }
+            }
+        }
+    }
+}
+
+/// Definitions of a word, in a tooltip while Ctrl is held over it or in a
+/// small window for the word at the cursor.
+#[derive(Default)]
+pub struct DefinitionView {
+    pub open: bool,
+    word: String,
+    pos: Option<egui::Pos2>,
+    /// The last word looked up, so hovering does not read the file every
+    /// frame.
+    cached: Option<(String, Vec<Entry>)>,
+}
+
+impl DefinitionView {
+    pub fn open_for(&mut self, word: &str, pos: Option<egui::Pos2>) {
+        self.word = word.to_owned();
+        self.pos = pos;
+        self.open = true;
+    }
+
+    fn entries(&mut self, dictionary: &Dictionary, word: &str) -> Option<&[Entry]> {
+        if self.cached.as_ref().is_none_or(|(w, _)| w != word) {
+            self.cached = Some((word.to_owned(), dictionary.lookup(word)?));
+        }
+        self.cached.as_ref().map(|(_, entries)| entries.as_slice())
+    }
+
+    pub fn tooltip(&mut self, ctx: &egui::Context, dictionary: &Dictionary, word: &str) {
+        egui::show_tooltip_at_pointer(ctx, egui::Id::new("definition"), |ui| {
+            ui.set_max_width(360.0);
+            ui.strong(word);
+            entries_ui(ui, self.entries(dictionary, word));
+        });
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, dictionary: &Dictionary) {
+        if !self.open {
+            return;
+        }
+        let word = self.word.clone();
+        let mut open = true;
+        let mut window = egui::Window::new(format!("📖 {}", word))
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(360.0);
+        if let Some(pos) = self.pos {
+            window = window.default_pos(pos);
+        }
+        window.show(ctx, |ui| {
+            egui::ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                entries_ui(ui, self.entries(dictionary, &word));
+            });
+        });
+        if !open || ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.open = false;
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use crate::i18n::t;
+use crate::journal::{self, Revision};
+use crate::merge::lcs_pairs;
+use eframe::egui::{self, Color32, RichText};
+use std::path::{Path, PathBuf};
+use tracing::error;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Chunk {
+    Same(String),
+    /// Lines that differ; either side may be empty.
+    Changed { left: String, right: String },
+}
+
+/// Line diff of two texts, as alternating runs of common and changed lines.
+pub fn diff(left: &str, right: &str) -> Vec<Chunk> {
+    let a: Vec<&str> = left.split_inclusive('\n').collect();
+    let b: Vec<&str> = right.split_inclusive('\n').collect();
+    let mut chunks = Vec::new();
+    let mut same = String::new();
+    let (mut i, mut j) = (0, 0);
+    for (pi, pj) in lcs_pairs(&a, &b).into_iter().chain([(a.len(), b.len())]) {
+        if pi > i || pj > j {
+            if !same.is_empty() {
+                chunks.push(Chunk::Same(std::mem::take(&mut same)));
+            }
+            chunks.push(Chunk::Changed {
+                left: a[i..pi].concat(),
+                right: b[j..pj].concat(),
+            });
+        }
+        if pi < a.len() {
+            same.push_str(a[pi]);
+        }
+        (i, j) = (pi + 1, pj + 1);
+    }
+    if !same.is_empty() {
+        chunks.push(Chunk::Same(same));
+    }
+    chunks
+}
+
+/// The texts with one changed chunk taken from the other side.
+fn apply(chunks: &[Chunk], index: usize, to_left: bool) -> String {
+    let mut out = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        match chunk {
+            Chunk::Same(text) => out.push_str(text),
+            Chunk::Changed { left, right } => {
+                let take_right = if i == index { to_left } else { !to_left };
+                out.push_str(if take_right { right } else { left });
+            }
+        }
+    }
+    out
+}
+
+/// Side-by-side comparison of the open note with another note or a
+/// history snapshot.
+pub struct DiffView {
+    pub other: PathBuf,
+    /// Heading for the other side.
+    title: String,
+    other_text: String,
+    /// Snapshots are only ever copied from.
+    read_only: bool,
+    /// The open note's text the chunks were computed from.
+    left: String,
+    chunks: Vec<Chunk>,
+}
+
+impl DiffView {
+    pub fn open(other: &Path) -> std::io::Result<Self> {
+        let history = Path::new(crate::vault::HISTORY_DIR).canonicalize();
+        let read_only = history.is_ok_and(|h| other.canonicalize().is_ok_and(|o| o.starts_with(h)));
+        Ok(Self {
+            other: other.to_path_buf(),
+            title: other.display().to_string(),
+            other_text: std::fs::read_to_string(other)?,
+            read_only,
+            left: String::new(),
+            chunks: Vec::new(),
+        })
+    }
+
+    /// Compares with a revision from the change journal.
+    pub fn revision(revision: &Revision) -> std::io::Result<Self> {
+        Ok(Self {
+            other: PathBuf::from(&revision.note),
+            title: format!("{} at {}", revision.note, revision.time.get(..16).unwrap_or(&revision.time).replace('T', " ")),
+            other_text: journal::content(revision)?,
+            read_only: true,
+            left: String::new(),
+            chunks: Vec::new(),
+        })
+    }
+
+    /// Returns false once closed. Copying a hunk to the left edits `note`,
+    /// unless it is `locked`; copying to the right writes the other file.
+    pub fn show(&mut self, ctx: &egui::Context, note: &mut String, locked: bool) -> bool {
+        if self.chunks.is_empty() || self.left != *note {
+            self.left = note.clone();
+            self.chunks = diff(&self.left, &self.other_text);
+        }
+        let mut open = true;
+        let mut copy = None;
+
+        egui::Window::new(t("⇆ Compare"))
+            .open(&mut open)
+            .default_width(760.0)
+            .show(ctx, |ui| {
+                ui.columns(2, |cols| {
+                    cols[0].label(RichText::new(t("Open note")).strong());
+                    cols[1].label(RichText::new(&self.title).strong());
+                });
+                ui.separator();
+                let changes = self.chunks.iter().filter(|c| matches!(c, Chunk::Changed { .. })).count();
+                if changes == 0 {
+                    ui.label(t("The notes are identical."));
+                }
+                egui::ScrollArea::vertical().max_height(480.0).show(ui, |ui| {
+                    for (index, chunk) in self.chunks.iter().enumerate() {
+                        ui.push_id(index, |ui| match chunk {
+                            Chunk::Same(text) => {
+                                ui.columns(2, |cols| {
+                                    for col in cols.iter_mut() {
+                                        col.label(RichText::new(text.trim_end_matches('\n')).monospace().weak());
+                                    }
+                                });
+                            }
+                            Chunk::Changed { left, right } => {
+                                ui.columns(2, |cols| {
+                                    let sides = [(left, Color32::from_rgb(110, 30, 30)), (right, Color32::from_rgb(30, 90, 40))];
+                                    for (col, (text, color)) in cols.iter_mut().zip(sides) {
+                                        if !text.is_empty() {
+                                            col.label(
+                                                RichText::new(text.trim_end_matches('\n'))
+                                                    .monospace()
+                                                    .background_color(color)
+                                                    .color(Color32::WHITE),
+                                            );
+                                        }
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    if ui.add_enabled(!locked, egui::Button::new(t("← Copy to left")).small()).clicked() {
+                                        copy = Some((index, true));
+                                    }
+                                    if ui.add_enabled(!self.read_only, egui::Button::new(t("Copy to right →")).small()).clicked() {
+                                        copy = Some((index, false));
+                                    }
+                                });
+                            }
+                        });
+                    }
+                });
+            });
+
+        match copy {
+            Some((index, true)) => *note = apply(&self.chunks, index, true),
+            Some((index, false)) => {
+                let text = apply(&self.chunks, index, false);
+                match crate::journal::write(&self.other, &text) {
+                    Ok(()) => {
+                        self.other_text = text;
+                        self.chunks.clear();
+                    }
+                    Err(err) => error!("Failed to save {}: {}", self.other.display(), err),
+                }
+            }
+            None => {}
+        }
+        open
+    }
+}
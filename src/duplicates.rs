@@ -0,0 +1,293 @@
+use crate::i18n::{t, tf};
+use crate::jobs::{self, Job, JobHandle};
+use crate::merge::lcs_pairs;
+use crate::vault;
+use eframe::egui::{self, RichText};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::TryRecvError;
+use tracing::error;
+#[cfg(target_os = "android")]
+use crate::stubs::rfd;
+
+/// Word shingle size for near-duplicate comparison.
+const SHINGLE: usize = 3;
+
+pub struct Pair {
+    pub left: PathBuf,
+    pub right: PathBuf,
+    /// 1.0 for identical content (ignoring case and whitespace).
+    pub similarity: f32,
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace().map(str::to_lowercase).collect()
+}
+
+fn shingles(words: &[String]) -> HashSet<u64> {
+    words
+        .windows(SHINGLE.min(words.len()).max(1))
+        .map(|window| {
+            let mut hasher = DefaultHasher::new();
+            window.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f32 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f32 / union as f32
+}
+
+/// Pairs of notes whose content is identical or at least `threshold`
+/// similar, most similar first.
+pub fn find(threshold: f32, job: &Job) -> io::Result<Vec<Pair>> {
+    job.status("Reading notes");
+    let notes: Vec<(PathBuf, Vec<String>)> = vault::note_files()
+        .into_iter()
+        .filter_map(|path| {
+            let text = std::fs::read_to_string(&path).ok()?;
+            let words = words(&text);
+            (!words.is_empty()).then_some((path, words))
+        })
+        .collect();
+
+    // Identical content, compared by hash of the normalized words.
+    let mut pairs = Vec::new();
+    let mut by_hash: HashMap<u64, Vec<&PathBuf>> = HashMap::new();
+    for (path, words) in &notes {
+        let mut hasher = DefaultHasher::new();
+        words.hash(&mut hasher);
+        by_hash.entry(hasher.finish()).or_default().push(path);
+    }
+    let mut exact = HashSet::new();
+    for group in by_hash.values().filter(|g| g.len() > 1) {
+        for (i, left) in group.iter().enumerate() {
+            for right in &group[i + 1..] {
+                exact.insert(((*left).clone(), (*right).clone()));
+                pairs.push(Pair {
+                    left: (*left).clone(),
+                    right: (*right).clone(),
+                    similarity: 1.0,
+                });
+            }
+        }
+    }
+
+    // Near duplicates, skipping pairs whose lengths alone rule them out.
+    let shingled: Vec<HashSet<u64>> = notes.iter().map(|(_, words)| shingles(words)).collect();
+    for i in 0..notes.len() {
+        job.check()?;
+        job.progress(i, notes.len());
+        for j in i + 1..notes.len() {
+            let (short, long) = {
+                let (a, b) = (notes[i].1.len(), notes[j].1.len());
+                (a.min(b) as f32, a.max(b) as f32)
+            };
+            if short / long < threshold || exact.contains(&(notes[i].0.clone(), notes[j].0.clone())) {
+                continue;
+            }
+            let similarity = jaccard(&shingled[i], &shingled[j]);
+            if similarity >= threshold {
+                pairs.push(Pair {
+                    left: notes[i].0.clone(),
+                    right: notes[j].0.clone(),
+                    similarity,
+                });
+            }
+        }
+    }
+    pairs.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+    Ok(pairs)
+}
+
+/// `keep` with the lines only `other` has slotted in where they appeared.
+pub fn union_lines(keep: &str, other: &str) -> String {
+    let a: Vec<&str> = keep.split_inclusive('\n').collect();
+    let b: Vec<&str> = other.split_inclusive('\n').collect();
+    let mut out = String::new();
+    let (mut i, mut j) = (0, 0);
+    for (pi, pj) in lcs_pairs(&a, &b).into_iter().chain([(a.len(), b.len())]) {
+        for line in &a[i..pi] {
+            out.push_str(line);
+        }
+        for line in &b[j..pj] {
+            if !out.is_empty() && !out.ends_with('\n') {
+                out.push('\n');
+            }
+            out.push_str(line);
+        }
+        if pi < a.len() {
+            if !out.is_empty() && !out.ends_with('\n') {
+                out.push('\n');
+            }
+            out.push_str(a[pi]);
+        }
+        (i, j) = (pi + 1, pj + 1);
+    }
+    out
+}
+
+fn confirm(description: String) -> bool {
+    rfd::MessageDialog::new()
+        .set_title(t("Duplicate notes"))
+        .set_description(description)
+        .set_buttons(rfd::MessageButtons::YesNo)
+        .show()
+        == rfd::MessageDialogResult::Yes
+}
+
+fn delete(path: &Path) {
+    if confirm(tf("Delete {}?", &[&path.display()]))
+        && let Err(err) = crate::journal::remove(path)
+    {
+        error!("Failed to delete note: {}", err);
+    }
+}
+
+/// Merges `from` into `into` and deletes `from`.
+fn merge(into: &Path, from: &Path) {
+    if !confirm(tf("Merge {} into {} and delete it?", &[&from.display(), &into.display()])) {
+        return;
+    }
+    let result = (|| -> std::io::Result<()> {
+        let keep = std::fs::read_to_string(into)?;
+        let other = std::fs::read_to_string(from)?;
+        crate::journal::write(into, &union_lines(&keep, &other))?;
+        crate::journal::remove(from)
+    })();
+    if let Err(err) = result {
+        error!("Failed to merge notes: {}", err);
+    }
+}
+
+/// What the app should do after the user acted in the view.
+pub enum DuplicateEvent {
+    Open(PathBuf),
+    /// Notes were merged or deleted on disk.
+    Changed,
+}
+
+pub struct DuplicatesView {
+    pub pairs: Option<Vec<Pair>>,
+    selected: Option<usize>,
+    /// Contents of the selected pair, read when it is selected.
+    texts: [String; 2],
+    threshold: f32,
+    running: Option<JobHandle<io::Result<Vec<Pair>>>>,
+}
+
+impl Default for DuplicatesView {
+    fn default() -> Self {
+        Self {
+            pairs: None,
+            selected: None,
+            texts: Default::default(),
+            threshold: 0.8,
+            running: None,
+        }
+    }
+}
+
+impl DuplicatesView {
+    /// Starts a search in the background; the view opens when it is done.
+    pub fn run(&mut self) {
+        let threshold = self.threshold;
+        self.selected = None;
+        self.running = Some(jobs::spawn("Finding duplicate notes", move |job| find(threshold, job)));
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<DuplicateEvent> {
+        if let Some(running) = &self.running {
+            match running.try_recv() {
+                Ok(Ok(pairs)) => {
+                    self.pairs = Some(pairs);
+                    self.selected = None;
+                    self.running = None;
+                }
+                Ok(Err(_)) | Err(TryRecvError::Disconnected) => self.running = None,
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+        let pairs = self.pairs.as_ref()?;
+        let mut open = true;
+        let mut event = None;
+        let mut rerun = false;
+
+        egui::Window::new(t("👯 Duplicate Notes"))
+            .open(&mut open)
+            .default_width(760.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(t("Similarity at least"));
+                    rerun |= ui.add(egui::Slider::new(&mut self.threshold, 0.5..=1.0)).drag_stopped();
+                    if let Some(running) = &self.running {
+                        running.show_progress(ui);
+                    }
+                });
+                ui.separator();
+                if pairs.is_empty() {
+                    ui.label(t("No duplicates found."));
+                }
+                egui::ScrollArea::vertical().id_source("pairs").max_height(160.0).show(ui, |ui| {
+                    for (index, pair) in pairs.iter().enumerate() {
+                        let label = format!(
+                            "{:.0}%  {}  ↔  {}",
+                            pair.similarity * 100.0,
+                            pair.left.display(),
+                            pair.right.display()
+                        );
+                        if ui.selectable_label(self.selected == Some(index), label).clicked() {
+                            self.selected = Some(index);
+                            self.texts = [&pair.left, &pair.right].map(|p| std::fs::read_to_string(p).unwrap_or_default());
+                        }
+                    }
+                });
+
+                let Some(pair) = self.selected.and_then(|i| pairs.get(i)) else {
+                    return;
+                };
+                ui.separator();
+                ui.columns(2, |cols| {
+                    for (col, (path, other)) in [(&pair.left, &pair.right), (&pair.right, &pair.left)].into_iter().enumerate() {
+                        let ui = &mut cols[col];
+                        ui.push_id(col, |ui| {
+                            if ui.link(RichText::new(path.display().to_string()).strong()).clicked() {
+                                event = Some(DuplicateEvent::Open(path.clone()));
+                            }
+                            egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                                ui.label(RichText::new(&self.texts[col]).monospace());
+                            });
+                            ui.horizontal(|ui| {
+                                if ui.button(t("Keep, merging the other in")).clicked() {
+                                    merge(path, other);
+                                    event = Some(DuplicateEvent::Changed);
+                                    rerun = true;
+                                }
+                                if ui.button(t("🗑 Delete")).clicked() {
+                                    delete(path);
+                                    event = Some(DuplicateEvent::Changed);
+                                    rerun = true;
+                                }
+                            });
+                        });
+                    }
+                });
+            });
+
+        if !open {
+            self.pairs = None;
+            self.running = None;
+        } else if rerun {
+            self.run();
+        }
+        event
+    }
+}
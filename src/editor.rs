@@ -0,0 +1,525 @@
+use crate::config::AppearanceConfig;
+use crate::theme::Syntax;
+use eframe::egui::{self, text::CCursor, text::CCursorRange, text::LayoutJob, text_edit::TextEditState, Color32, TextFormat};
+use std::ops::Range;
+use std::sync::Arc;
+
+pub fn id() -> egui::Id {
+    egui::Id::new("note_editor")
+}
+
+/// Moves the editor cursor (or selection) to a char range.
+pub fn set_cursor(ctx: &egui::Context, chars: Range<usize>) {
+    let mut state = TextEditState::load(ctx, id()).unwrap_or_default();
+    state.cursor.set_char_range(Some(CCursorRange::two(CCursor::new(chars.start), CCursor::new(chars.end))));
+    state.store(ctx, id());
+}
+
+/// Appends a line of prose, coloring `code` spans and `*emphasis*` when
+/// the theme gives colors for them.
+fn append_spans(job: &mut LayoutJob, line: &str, format: &TextFormat, code: Option<Color32>, emphasis: Option<Color32>) {
+    let (mut in_code, mut in_emphasis) = (false, false);
+    let mut start = 0;
+    let color = |in_code: bool, in_emphasis: bool| match (in_code, in_emphasis) {
+        (true, _) => code,
+        (false, true) => emphasis,
+        _ => None,
+    };
+    let push = |job: &mut LayoutJob, text: &str, color: Option<Color32>| {
+        if !text.is_empty() {
+            let mut format = format.clone();
+            format.color = color.unwrap_or(format.color);
+            job.append(text, 0.0, format);
+        }
+    };
+    for (at, c) in line.char_indices() {
+        let opens = match c {
+            '`' if code.is_some() => true,
+            // `* ` starts a list item rather than emphasis.
+            '*' if emphasis.is_some() && !in_code => in_emphasis || !line[at + 1..].starts_with([' ', '*']),
+            _ => false,
+        };
+        if !opens {
+            continue;
+        }
+        // The markers themselves take the color of what they enclose.
+        let (before, after) = if c == '`' { (in_code, !in_code) } else { (in_emphasis, !in_emphasis) };
+        let end = if before { at + 1 } else { at };
+        push(job, &line[start..end], color(in_code, in_emphasis));
+        start = end;
+        if c == '`' {
+            in_code = after;
+        } else {
+            in_emphasis = after;
+        }
+    }
+    push(job, &line[start..], color(in_code, in_emphasis));
+}
+
+/// The inline Markdown a run of a line is in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Inline {
+    strong: bool,
+    italic: bool,
+    strike: bool,
+    code: bool,
+    link: bool,
+    /// The `**`, `` ` ``, `[` and `](…)` around the rest.
+    markup: bool,
+}
+
+/// Splits a line into runs of the same inline styling. A delimiter only
+/// opens when it is closed later on the line, and `_` not inside a word.
+fn inline_runs(line: &str) -> Vec<(Range<usize>, Inline)> {
+    let mut runs: Vec<(Range<usize>, Inline)> = Vec::new();
+    let push = |runs: &mut Vec<(Range<usize>, Inline)>, range: Range<usize>, style: Inline| {
+        if range.is_empty() {
+            return;
+        }
+        match runs.last_mut() {
+            Some((last, last_style)) if *last_style == style && last.end == range.start => last.end = range.end,
+            _ => runs.push((range, style)),
+        }
+    };
+    let mut style = Inline::default();
+    let (mut at, mut start) = (0, 0);
+    // Where a link's text ends and the markup after it does.
+    let mut link_end: Option<(usize, usize)> = None;
+    while at < line.len() {
+        let rest = &line[at..];
+        let c = rest.chars().next().unwrap_or_default();
+        let prev = line[..at].chars().next_back();
+        let mut markup = |runs: &mut Vec<(Range<usize>, Inline)>, len: usize, style: Inline| {
+            push(runs, start..at, style);
+            push(runs, at..at + len, Inline { markup: true, ..style });
+            start = at + len;
+        };
+        if let Some((text_end, end)) = link_end
+            && at == text_end
+        {
+            markup(&mut runs, end - at, style);
+            style.link = false;
+            link_end = None;
+            at = end;
+            continue;
+        }
+        if c == '`' && (style.code || rest[1..].contains('`')) {
+            markup(&mut runs, 1, style);
+            style.code = !style.code;
+            at += 1;
+            continue;
+        }
+        if style.code {
+            at += c.len_utf8();
+            continue;
+        }
+        if c == '\\' {
+            at += 1 + rest[1..].chars().next().map_or(0, char::len_utf8);
+            continue;
+        }
+        let double = ["**", "__", "~~"].into_iter().find(|d| rest.starts_with(d));
+        if let Some(d) = double {
+            let on = if d == "~~" { style.strike } else { style.strong };
+            let opens = !on && rest[2..].contains(d) && !rest[2..].starts_with(char::is_whitespace) && (d != "__" || !prev.is_some_and(char::is_alphanumeric));
+            if on || opens {
+                markup(&mut runs, 2, style);
+                if d == "~~" {
+                    style.strike = !on;
+                } else {
+                    style.strong = !on;
+                }
+                at += 2;
+                continue;
+            }
+        }
+        if c == '*' || c == '_' {
+            let next = rest[1..].chars().next();
+            let word = |c: Option<char>| c.is_some_and(char::is_alphanumeric) && c != Some('*');
+            let opens = !style.italic && next.is_some_and(|n| !n.is_whitespace()) && rest[1..].contains(c) && (c == '*' || !word(prev));
+            let closes = style.italic && prev.is_some_and(|p| !p.is_whitespace()) && (c == '*' || !word(next));
+            if opens || closes {
+                markup(&mut runs, 1, style);
+                style.italic = opens;
+                at += 1;
+                continue;
+            }
+        }
+        if link_end.is_none() && c == '[' {
+            let wiki = rest.starts_with("[[").then(|| rest.find("]]")).flatten().map(|j| (2, at + j, at + j + 2));
+            let link = rest.find(']').filter(|&j| rest[j + 1..].starts_with('(')).and_then(|j| {
+                let close = rest[j..].find(')')?;
+                Some((1, at + j, at + j + close + 1))
+            });
+            if let Some((open, text_end, end)) = wiki.or(link).filter(|&(open, text_end, _)| text_end > at + open) {
+                markup(&mut runs, open, style);
+                style.link = true;
+                link_end = Some((text_end, end));
+                at += open;
+                continue;
+            }
+        }
+        at += c.len_utf8();
+    }
+    push(&mut runs, start..line.len(), style);
+    runs
+}
+
+/// Colors the live preview styles text with.
+struct Palette {
+    strong: Color32,
+    weak: Color32,
+    link: Color32,
+    code: Color32,
+    code_background: Color32,
+    heading: Color32,
+    quote: Option<Color32>,
+    emphasis: Option<Color32>,
+}
+
+/// Makes markup take up no room, leaving its characters in the galley for
+/// the cursor.
+fn hide(format: &mut TextFormat) {
+    format.color = Color32::TRANSPARENT;
+    format.font_id.size = 1.0;
+    format.extra_letter_spacing = 0.0;
+    format.underline = egui::Stroke::NONE;
+    format.strikethrough = egui::Stroke::NONE;
+    format.background = Color32::TRANSPARENT;
+}
+
+/// Appends a line styled the way it reads: headings larger, bold strong,
+/// italics slanted, links colored and code shaded. The markup is hidden
+/// unless the line is `raw`, where the cursor is, and then only dimmed.
+fn append_live(job: &mut LayoutJob, line: &str, format: &TextFormat, palette: &Palette, raw: bool) {
+    let mut format = format.clone();
+    let body = line.trim_start();
+    let indent = line.len() - body.len();
+    let hashes = body.len() - body.trim_start_matches('#').len();
+    let mut prefix = 0;
+    if (1..=6).contains(&hashes) && body[hashes..].starts_with(' ') {
+        let scale = [1.6, 1.4, 1.25, 1.1, 1.05, 1.0][hashes - 1];
+        format.font_id.size *= scale;
+        format.line_height = format.line_height.map(|h| h * scale);
+        format.color = palette.heading;
+        prefix = line.len() - body[hashes..].trim_start_matches(' ').len();
+    } else if let Some(quoted) = body.strip_prefix('>') {
+        format.italics = true;
+        format.color = palette.quote.unwrap_or(format.color);
+        // The quote's bar stays, dimmed.
+        let mut bar = format.clone();
+        bar.color = palette.weak;
+        let end = indent + 1 + usize::from(quoted.starts_with(' '));
+        job.append(&line[..end], 0.0, bar);
+        return append_runs(job, &line[end..], &format, palette, raw);
+    }
+    if prefix > 0 {
+        let mut marker = format.clone();
+        if raw {
+            marker.color = palette.weak;
+        } else {
+            hide(&mut marker);
+        }
+        job.append(&line[..prefix], 0.0, marker);
+    }
+    append_runs(job, &line[prefix..], &format, palette, raw);
+}
+
+/// Appends the inline runs of the rest of a line for the live preview.
+fn append_runs(job: &mut LayoutJob, line: &str, format: &TextFormat, palette: &Palette, raw: bool) {
+    for (range, style) in inline_runs(line) {
+        let mut format = format.clone();
+        if style.strong || style.italic {
+            format.color = palette.emphasis.unwrap_or(if style.strong { palette.strong } else { format.color });
+        }
+        format.italics |= style.italic;
+        if style.strike {
+            format.strikethrough = egui::Stroke::new(1.0, format.color);
+        }
+        if style.code {
+            format.color = palette.code;
+            format.background = palette.code_background;
+        }
+        if style.link {
+            format.color = palette.link;
+            format.underline = egui::Stroke::new(1.0, palette.link);
+        }
+        if style.markup {
+            if raw {
+                format.color = palette.weak;
+                format.underline = egui::Stroke::NONE;
+            } else {
+                hide(&mut format);
+            }
+        }
+        job.append(&line[range], 0.0, format);
+    }
+}
+
+/// Lays out the note with the configured spacing and the theme's Markdown
+/// colors. Blank lines between paragraphs are made taller, and lines that
+/// start in `hidden` take up no room, for folded sections. With `live`,
+/// the selection, Markdown is shown styled everywhere but on the lines
+/// the selection is on.
+pub fn layout(
+    ui: &egui::Ui,
+    text: &str,
+    wrap_width: f32,
+    appearance: &AppearanceConfig,
+    syntax: &Syntax,
+    hidden: &[Range<usize>],
+    live: Option<Range<usize>>,
+) -> Arc<egui::Galley> {
+    let font = egui::TextStyle::Monospace.resolve(ui.style());
+    let line_height = font.size * appearance.line_spacing;
+    let plain = TextFormat {
+        font_id: font,
+        color: ui.visuals().override_text_color.unwrap_or_else(|| ui.visuals().widgets.inactive.text_color()),
+        line_height: Some(line_height).filter(|_| appearance.is_spaced()),
+        extra_letter_spacing: appearance.letter_spacing,
+        ..Default::default()
+    };
+    let (heading, code, quote, emphasis) = (syntax.heading(), syntax.code(), syntax.quote(), syntax.emphasis());
+    let visuals = ui.visuals();
+    let palette = Palette {
+        strong: visuals.strong_text_color(),
+        weak: visuals.weak_text_color(),
+        link: visuals.hyperlink_color,
+        code: code.unwrap_or(plain.color),
+        code_background: visuals.code_bg_color,
+        heading: heading.unwrap_or_else(|| visuals.strong_text_color()),
+        quote,
+        emphasis,
+    };
+    let mut job = LayoutJob::default();
+    job.wrap.max_width = wrap_width;
+    let mut fenced = false;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let trimmed = line.trim_start();
+        let fence = trimmed.starts_with("```") || trimmed.starts_with("~~~");
+        let mut format = plain.clone();
+        if hidden.iter().any(|r| r.contains(&start)) {
+            fenced ^= fence;
+            format.color = Color32::TRANSPARENT;
+            format.line_height = Some(0.0);
+            job.append(line, 0.0, format);
+            continue;
+        }
+        if line.trim().is_empty() && appearance.paragraph_spacing > 0.0 {
+            format.line_height = Some(line_height + appearance.paragraph_spacing);
+        }
+        let line_color = if fence || fenced {
+            code
+        } else if trimmed.starts_with('#') {
+            heading
+        } else if trimmed.starts_with('>') {
+            quote
+        } else {
+            None
+        };
+        let in_code = fence || fenced;
+        fenced ^= fence;
+        if let Some(selection) = &live
+            && !in_code
+        {
+            let raw = start <= selection.end && selection.start <= start + line.trim_end_matches(['\n', '\r']).len();
+            append_live(&mut job, line, &format, &palette, raw);
+            continue;
+        }
+        if live.is_some() {
+            format.background = palette.code_background;
+        }
+        match line_color {
+            Some(color) => {
+                format.color = color;
+                job.append(line, 0.0, format);
+            }
+            None => append_spans(&mut job, line, &format, code, emphasis),
+        }
+    }
+    ui.fonts(|fonts| fonts.layout_job(job))
+}
+
+/// Marks search matches in the editor with a translucent background.
+pub fn paint_matches(ui: &egui::Ui, output: &egui::text_edit::TextEditOutput, text: &str, terms: &[String]) {
+    let fill = egui::Color32::from_rgba_unmultiplied(255, 200, 0, 60);
+    for range in crate::text::find_terms(text, terms) {
+        let start = output.galley.from_ccursor(CCursor::new(text[..range.start].chars().count()));
+        let end = output.galley.from_ccursor(CCursor::new(text[..range.end].chars().count()));
+        let (start, end) = (output.galley.pos_from_cursor(&start), output.galley.pos_from_cursor(&end));
+        // Matches wrapped onto two rows are left unmarked.
+        if (start.min.y - end.min.y).abs() < 1.0 {
+            let rect = egui::Rect::from_min_max(start.min, end.max).translate(output.galley_pos.to_vec2());
+            ui.painter().rect_filled(rect, 2.0, fill);
+        }
+    }
+}
+
+/// Outlines a bracket and its partner, given as byte offsets.
+pub fn paint_pair(ui: &egui::Ui, output: &egui::text_edit::TextEditOutput, text: &str, brackets: [usize; 2]) {
+    let stroke = egui::Stroke::new(1.0, ui.visuals().weak_text_color());
+    for at in brackets {
+        let Some(c) = text.get(at..).and_then(|rest| rest.chars().next()) else {
+            continue;
+        };
+        let chars = text[..at].chars().count();
+        let start = output.galley.pos_from_cursor(&output.galley.from_ccursor(CCursor::new(chars)));
+        let end = output.galley.pos_from_cursor(&output.galley.from_ccursor(CCursor::new(chars + 1)));
+        if c != '\n' && (start.min.y - end.min.y).abs() < 1.0 {
+            let rect = egui::Rect::from_min_max(start.min, end.max).translate(output.galley_pos.to_vec2());
+            ui.painter().rect_stroke(rect, 1.0, stroke);
+        }
+    }
+}
+
+/// Underlines commented text and puts a marker in the right margin of
+/// each line a comment starts on.
+pub fn paint_comments(ui: &egui::Ui, output: &egui::text_edit::TextEditOutput, text: &str, ranges: &[Range<usize>]) {
+    let color = egui::Color32::from_rgb(120, 160, 230);
+    let font = egui::TextStyle::Small.resolve(ui.style());
+    for range in ranges {
+        let (Some(before), Some(quote)) = (text.get(..range.start), text.get(range.clone())) else {
+            continue;
+        };
+        let start_chars = before.chars().count();
+        let start = output.galley.from_ccursor(CCursor::new(start_chars));
+        let end = output.galley.from_ccursor(CCursor::new(start_chars + quote.chars().count()));
+        let (start, end) = (output.galley.pos_from_cursor(&start), output.galley.pos_from_cursor(&end));
+        let offset = output.galley_pos.to_vec2();
+        // Text wrapped onto several rows is underlined on its first row only.
+        let right = if (start.min.y - end.min.y).abs() < 1.0 { end.max.x } else { output.galley.rect.right() };
+        let y = start.max.y + offset.y;
+        ui.painter().line_segment([egui::pos2(start.min.x + offset.x, y), egui::pos2(right + offset.x, y)], egui::Stroke::new(2.0, color));
+        let margin = egui::pos2(output.response.rect.right() - 4.0, start.center().y + offset.y);
+        ui.painter().text(margin, egui::Align2::RIGHT_CENTER, "💬", font.clone(), color);
+    }
+}
+
+/// Underlines problems from a check and marks their lines in the left
+/// margin, and marks all of them on a strip along the right edge scaled
+/// to the whole note, so clusters in a long note stand out. Returns the problem whose mark on
+/// the strip was clicked.
+pub fn paint_issues(ui: &egui::Ui, output: &egui::text_edit::TextEditOutput, text: &str, issues: &[(Range<usize>, egui::Color32)]) -> Option<Range<usize>> {
+    let offset = output.galley_pos.to_vec2();
+    let height = output.galley.rect.height().max(1.0);
+    let strip = ui.clip_rect().with_min_x(ui.clip_rect().right() - 4.0);
+    let mut marks = Vec::new();
+    for (range, color) in issues {
+        let (Some(before), Some(flagged)) = (text.get(..range.start), text.get(range.clone())) else {
+            continue;
+        };
+        let start_chars = before.chars().count();
+        let cursor = output.galley.from_ccursor(CCursor::new(start_chars));
+        let row = output.galley.pos_from_cursor(&cursor);
+        let end = output.galley.pos_from_cursor(&output.galley.from_ccursor(CCursor::new(start_chars + flagged.chars().count())));
+        // Text wrapped onto several rows is underlined on its first row only.
+        let right = if (row.min.y - end.min.y).abs() < 1.0 { end.max.x } else { output.galley.rect.right() };
+        let y = row.max.y + offset.y;
+        ui.painter().line_segment([egui::pos2(row.min.x + offset.x, y), egui::pos2(right + offset.x, y)], egui::Stroke::new(1.5, *color));
+        let gutter = egui::Rect::from_x_y_ranges(output.response.rect.left()..=output.response.rect.left() + 3.0, row.min.y + offset.y..=row.max.y + offset.y);
+        ui.painter().rect_filled(gutter, 1.0, *color);
+        let y = strip.top() + row.center().y / height * strip.height();
+        ui.painter().rect_filled(egui::Rect::from_x_y_ranges(strip.x_range(), y - 1.5..=y + 1.5), 0.0, *color);
+        marks.push((y, range));
+    }
+    if marks.is_empty() {
+        return None;
+    }
+    let response = ui.interact(strip, ui.id().with("issue_strip"), egui::Sense::click());
+    let pointer = response.interact_pointer_pos().filter(|_| response.clicked())?;
+    marks.into_iter().min_by(|a, b| (a.0 - pointer.y).abs().total_cmp(&(b.0 - pointer.y).abs())).map(|(_, range)| range.clone())
+}
+
+/// Draws another person's cursor at a char position.
+pub fn paint_caret(ui: &egui::Ui, output: &egui::text_edit::TextEditOutput, chars: usize, color: egui::Color32) {
+    let cursor = output.galley.from_ccursor(CCursor::new(chars));
+    let rect = output.galley.pos_from_cursor(&cursor).translate(output.galley_pos.to_vec2());
+    ui.painter().line_segment([rect.center_top(), rect.center_bottom()], egui::Stroke::new(2.0, color));
+    ui.painter().circle_filled(rect.center_top(), 3.0, color);
+}
+
+/// One entry in the completion popup.
+pub struct Candidate {
+    pub label: String,
+    pub detail: String,
+    /// Text that replaces the word being completed.
+    pub insert: String,
+}
+
+/// Popup listing completions for the word at the cursor. Arrow keys pick,
+/// Enter or Tab accepts, Escape hides it until another word is started.
+#[derive(Default)]
+pub struct CompletionPopup {
+    selected: usize,
+    /// Start of the word the popup was dismissed for.
+    dismissed: Option<usize>,
+}
+
+impl CompletionPopup {
+    /// Call when there is nothing to complete.
+    pub fn reset(&mut self) {
+        self.selected = 0;
+        self.dismissed = None;
+    }
+
+    /// Handles the popup's keys before the editor sees them. Returns the
+    /// index of an accepted candidate.
+    pub fn input(&mut self, ctx: &egui::Context, word_start: usize, candidates: &[Candidate]) -> Option<usize> {
+        if self.dismissed.is_some_and(|at| at != word_start) {
+            self.reset();
+        }
+        if self.dismissed.is_some() || candidates.is_empty() {
+            return None;
+        }
+        self.selected = self.selected.min(candidates.len() - 1);
+        ctx.input_mut(|i| {
+            use egui::{Key, Modifiers};
+            if i.consume_key(Modifiers::NONE, Key::Escape) {
+                self.dismissed = Some(word_start);
+                return None;
+            }
+            if i.consume_key(Modifiers::NONE, Key::ArrowDown) {
+                self.selected = (self.selected + 1) % candidates.len();
+            }
+            if i.consume_key(Modifiers::NONE, Key::ArrowUp) {
+                self.selected = (self.selected + candidates.len() - 1) % candidates.len();
+            }
+            let accept = i.consume_key(Modifiers::NONE, Key::Enter) || i.consume_key(Modifiers::NONE, Key::Tab);
+            accept.then_some(self.selected)
+        })
+    }
+
+    /// Shows the popup under the cursor. Returns the index of a clicked candidate.
+    pub fn show(
+        &mut self,
+        ui: &egui::Ui,
+        output: &egui::text_edit::TextEditOutput,
+        candidates: &[Candidate],
+    ) -> Option<usize> {
+        let range = output.cursor_range?;
+        if self.dismissed.is_some() || candidates.is_empty() {
+            return None;
+        }
+        let rect = output.galley.pos_from_cursor(&range.primary);
+        let pos = output.galley_pos + rect.left_bottom().to_vec2();
+        let mut clicked = None;
+        egui::Area::new(id().with("completions"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(pos)
+            .show(ui.ctx(), |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    for (index, candidate) in candidates.iter().enumerate() {
+                        let response = ui
+                            .selectable_label(index == self.selected, &candidate.label)
+                            .on_hover_text(&candidate.detail);
+                        if response.clicked() {
+                            clicked = Some(index);
+                        }
+                    }
+                });
+            });
+        clicked
+    }
+}
@@ -0,0 +1,70 @@
+use crate::editor::Candidate;
+use std::ops::Range;
+
+/// How many completions the popup lists at most.
+const MAX_CANDIDATES: usize = 8;
+/// Characters typed after the colon before suggestions appear.
+const MIN_PREFIX: usize = 2;
+
+fn is_shortcode_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-')
+}
+
+/// `:shortcode` completions for the word ending at `cursor` (a byte
+/// offset). Returns the range to replace and the matching emoji.
+pub fn complete(text: &str, cursor: usize) -> Option<(Range<usize>, Vec<Candidate>)> {
+    let before = text.get(..cursor)?;
+    let word_start = before
+        .char_indices()
+        .rev()
+        .find(|&(_, c)| !is_shortcode_char(c))
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let colon = word_start.checked_sub(1)?;
+    if before.as_bytes().get(colon) != Some(&b':') || before.len() - word_start < MIN_PREFIX {
+        return None;
+    }
+    // Not a time like 10:30 or a URL scheme.
+    if before[..colon].chars().next_back().is_some_and(|c| c.is_alphanumeric()) {
+        return None;
+    }
+    let prefix = before[word_start..].to_lowercase();
+    let mut matches: Vec<(&str, &emojis::Emoji)> = emojis::iter()
+        .flat_map(|emoji| emoji.shortcodes().map(move |code| (code, emoji)))
+        .filter(|(code, _)| code.contains(&prefix))
+        .collect();
+    // Prefix matches first, then shorter codes.
+    matches.sort_by_key(|(code, _)| (!code.starts_with(&prefix), code.len()));
+    let candidates: Vec<Candidate> = matches
+        .into_iter()
+        .take(MAX_CANDIDATES)
+        .map(|(code, emoji)| Candidate {
+            label: format!("{}  :{}:", emoji.as_str(), code),
+            detail: emoji.name().to_owned(),
+            insert: emoji.as_str().to_owned(),
+        })
+        .collect();
+    (!candidates.is_empty()).then_some((colon..cursor, candidates))
+}
+
+/// Replaces known `:shortcode:`s with their emoji.
+pub fn replace_shortcodes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(':') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = after.find(|c: char| !is_shortcode_char(c));
+        if let Some(end) = end
+            && after[end..].starts_with(':')
+            && let Some(emoji) = emojis::get_by_shortcode(&after[..end])
+        {
+            out.push_str(emoji.as_str());
+            rest = &after[end + 1..];
+        } else {
+            out.push(':');
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+    out
+}
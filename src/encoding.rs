@@ -0,0 +1,146 @@
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1252};
+use std::io;
+use std::path::Path;
+
+/// How a note's file was encoded.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FileEncoding {
+    pub encoding: &'static Encoding,
+    /// Started with a byte order mark.
+    pub bom: bool,
+}
+
+impl Default for FileEncoding {
+    fn default() -> Self {
+        Self {
+            encoding: UTF_8,
+            bom: false,
+        }
+    }
+}
+
+impl FileEncoding {
+    /// Plain UTF-8, which needs no mention.
+    pub fn is_utf8(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn name(&self) -> String {
+        if self.bom && self.encoding == UTF_8 {
+            "UTF-8 with BOM".to_owned()
+        } else {
+            self.encoding.name().to_owned()
+        }
+    }
+}
+
+/// UTF-16 without a byte order mark still shows as every other byte being
+/// zero in mostly-Latin text.
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    if bytes.len() < 4 || !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+    let zeros = |start: usize| bytes.iter().skip(start).step_by(2).filter(|&&b| b == 0).count();
+    let half = bytes.len() / 2;
+    match (zeros(0), zeros(1)) {
+        (even, odd) if odd > half * 3 / 4 && even == 0 => Some(UTF_16LE),
+        (even, odd) if even > half * 3 / 4 && odd == 0 => Some(UTF_16BE),
+        _ => None,
+    }
+}
+
+/// The text of a file in whichever of UTF-8, UTF-16 or Windows-1252 it
+/// appears to be in, and which one that was.
+pub fn decode(bytes: &[u8]) -> (String, FileEncoding) {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return (text.into_owned(), FileEncoding { encoding, bom: true });
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return (text.to_owned(), FileEncoding::default());
+    }
+    // Anything else that is not UTF-8 is most likely from an older
+    // Windows tool; every byte means something in Windows-1252.
+    let encoding = sniff_utf16(bytes).unwrap_or(WINDOWS_1252);
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    (text.into_owned(), FileEncoding { encoding, bom: false })
+}
+
+/// `text` in `encoding`, or `None` if it has characters the encoding
+/// cannot hold.
+pub fn encode(text: &str, encoding: FileEncoding) -> Option<Vec<u8>> {
+    let units = || text.encode_utf16();
+    let mut bytes: Vec<u8> = match encoding.encoding {
+        // encoding_rs only decodes UTF-16.
+        e if e == UTF_16LE => units().flat_map(u16::to_le_bytes).collect(),
+        e if e == UTF_16BE => units().flat_map(u16::to_be_bytes).collect(),
+        e => {
+            let (bytes, _, unmappable) = e.encode(text);
+            if unmappable {
+                return None;
+            }
+            bytes.into_owned()
+        }
+    };
+    if encoding.bom {
+        let bom: &[u8] = match encoding.encoding {
+            e if e == UTF_16LE => &[0xFF, 0xFE],
+            e if e == UTF_16BE => &[0xFE, 0xFF],
+            _ => &[0xEF, 0xBB, 0xBF],
+        };
+        bytes.splice(0..0, bom.iter().copied());
+    }
+    Some(bytes)
+}
+
+pub fn read(path: &Path) -> io::Result<(String, FileEncoding)> {
+    std::fs::read(path).map(|bytes| decode(&bytes))
+}
+
+/// How lines end in a note's file. The editor always works with `\n`;
+/// the file's own endings are put back when it is saved.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    /// The ending most lines of `text` use.
+    pub fn detect(text: &str) -> Self {
+        let crlf = text.matches("\r\n").count();
+        if crlf > 0 && crlf >= text.matches('\n').count() - crlf {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    /// `text` with every line ending `\n`.
+    pub fn normalize(text: &str) -> String {
+        text.replace("\r\n", "\n")
+    }
+
+    /// `text`, which ends its lines in `\n`, with this ending.
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            LineEnding::Lf => text.to_owned(),
+            LineEnding::CrLf => text.replace('\n', "\r\n"),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::CrLf => "CRLF",
+        }
+    }
+
+    pub fn other(self) -> Self {
+        match self {
+            LineEnding::Lf => LineEnding::CrLf,
+            LineEnding::CrLf => LineEnding::Lf,
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use crate::i18n::{t, tf};
+use crate::sidebar::NoteInfo;
+use crate::vault::{self, FLASHCARDS_FILE};
+use chrono::{Local, NaiveDate};
+use eframe::egui::{self, RichText};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::error;
+
+/// A question and answer found in a note.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Card {
+    /// Stable while the question is unchanged: note path and front.
+    pub id: String,
+    pub note: PathBuf,
+    pub front: String,
+    pub back: String,
+}
+
+/// Replaces `{{c1::text}}` or `{{text}}` clozes, hiding or revealing them.
+/// Returns `None` when the line has no cloze.
+fn cloze(line: &str, reveal: bool) -> Option<String> {
+    let mut out = String::new();
+    let mut rest = line;
+    let mut found = false;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+        let inner = &rest[start + 2..start + len];
+        let answer = match inner.split_once("::") {
+            Some((label, answer)) if label.starts_with('c') => answer,
+            _ => inner,
+        };
+        out.push_str(&rest[..start]);
+        out.push_str(if reveal { answer } else { "[…]" });
+        rest = &rest[start + len + 2..];
+        found = true;
+    }
+    out.push_str(rest);
+    found.then_some(out)
+}
+
+/// Cards written as `question :: answer` on one line, a `Q::` line
+/// followed by an `A::` line, or a line with `{{cloze}}` deletions.
+pub fn parse(note: &Path, text: &str) -> Vec<Card> {
+    let fences = crate::markdown::fenced_blocks(text);
+    let key = vault::note_key(note);
+    let mut cards = Vec::new();
+    let mut question: Option<String> = None;
+    let mut offset = 0;
+    for line in text.lines() {
+        let start = offset;
+        offset += line.len() + 1;
+        if fences.iter().any(|f| f.range.contains(&start)) {
+            continue;
+        }
+        let line = line.trim().trim_start_matches(['-', '*']).trim();
+        let (front, back) = if let Some(q) = line.strip_prefix("Q::") {
+            question = Some(q.trim().to_owned());
+            continue;
+        } else if let Some(a) = line.strip_prefix("A::") {
+            match question.take() {
+                Some(q) => (q, a.trim().to_owned()),
+                None => continue,
+            }
+        } else if let (Some(front), Some(back)) = (cloze(line, false), cloze(line, true)) {
+            (front, back)
+        } else if let Some((q, a)) = line.split_once(" :: ") {
+            (q.trim().to_owned(), a.trim().to_owned())
+        } else {
+            continue;
+        };
+        if front.is_empty() || back.is_empty() {
+            continue;
+        }
+        cards.push(Card {
+            id: format!("{}#{}", key, front),
+            note: note.to_path_buf(),
+            front,
+            back,
+        });
+    }
+    cards
+}
+
+/// Every card in the vault, outside the archive.
+pub fn deck(notes: &[NoteInfo]) -> Vec<Card> {
+    notes
+        .iter()
+        .filter(|n| !vault::is_archived(&n.path))
+        .flat_map(|n| parse(&n.path, &n.text))
+        .collect()
+}
+
+/// SM-2 scheduling state of one card.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Schedule {
+    pub ease: f32,
+    pub interval_days: u32,
+    pub repetitions: u32,
+    /// `YYYY-MM-DD`; cards are due on or after it.
+    pub due: String,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self {
+            ease: 2.5,
+            interval_days: 0,
+            repetitions: 0,
+            due: String::new(),
+        }
+    }
+}
+
+impl Schedule {
+    pub fn is_due(&self, today: NaiveDate) -> bool {
+        NaiveDate::parse_from_str(&self.due, "%Y-%m-%d").is_ok_and(|due| due <= today) || self.due.is_empty()
+    }
+
+    /// Applies a review graded 0 (forgotten) to 5 (perfect recall).
+    pub fn review(&mut self, grade: u8, today: NaiveDate) {
+        let grade = grade.min(5);
+        if grade < 3 {
+            self.repetitions = 0;
+            self.interval_days = 1;
+        } else {
+            self.repetitions += 1;
+            self.interval_days = match self.repetitions {
+                1 => 1,
+                2 => 6,
+                _ => (self.interval_days as f32 * self.ease).round() as u32,
+            };
+        }
+        let miss = (5 - grade) as f32;
+        self.ease = (self.ease + 0.1 - miss * (0.08 + miss * 0.02)).max(1.3);
+        self.due = (today + chrono::Days::new(self.interval_days as u64)).format("%Y-%m-%d").to_string();
+    }
+}
+
+/// Review state of every card, kept in one file in the vault.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ReviewState {
+    cards: BTreeMap<String, Schedule>,
+}
+
+impl ReviewState {
+    pub fn load() -> Self {
+        std::fs::read_to_string(FLASHCARDS_FILE)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => {
+                if let Err(err) = std::fs::write(FLASHCARDS_FILE, json) {
+                    error!("Failed to save review state: {}", err);
+                }
+            }
+            Err(err) => error!("Failed to serialize review state: {}", err),
+        }
+    }
+
+    pub fn schedule(&self, card: &Card) -> Schedule {
+        self.cards.get(&card.id).cloned().unwrap_or_default()
+    }
+
+    pub fn review(&mut self, card: &Card, grade: u8) {
+        let mut schedule = self.schedule(card);
+        schedule.review(grade, Local::now().date_naive());
+        self.cards.insert(card.id.clone(), schedule);
+        self.save();
+    }
+}
+
+/// Shows due cards one at a time and grades them.
+#[derive(Default)]
+pub struct ReviewView {
+    /// Cards left in this session; `None` while closed.
+    queue: Option<Vec<Card>>,
+    total: usize,
+    revealed: bool,
+    state: Option<ReviewState>,
+}
+
+impl ReviewView {
+    pub fn start(&mut self, notes: &[NoteInfo]) {
+        let state = ReviewState::load();
+        let today = Local::now().date_naive();
+        let due: Vec<Card> = deck(notes).into_iter().filter(|c| state.schedule(c).is_due(today)).collect();
+        self.total = due.len();
+        self.queue = Some(due);
+        self.revealed = false;
+        self.state = Some(state);
+    }
+
+    /// Returns a note to open when the user asks for a card's source.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<PathBuf> {
+        let (Some(queue), Some(state)) = (self.queue.as_mut(), self.state.as_mut()) else {
+            return None;
+        };
+        let mut open = true;
+        let mut source = None;
+
+        egui::Window::new(t("🃏 Review")).open(&mut open).default_width(420.0).show(ctx, |ui| {
+            let Some(card) = queue.first().cloned() else {
+                ui.label(tf("Done! {} cards reviewed.", &[&self.total]));
+                return;
+            };
+            ui.label(RichText::new(tf("{} of {}", &[&(self.total - queue.len() + 1), &self.total])).small().weak());
+            ui.separator();
+            ui.label(RichText::new(&card.front).heading());
+            ui.add_space(8.0);
+            if self.revealed {
+                ui.separator();
+                ui.label(RichText::new(&card.back).size(18.0));
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    let grades = [("Again", 1), ("Hard", 3), ("Good", 4), ("Easy", 5)];
+                    for (label, grade) in grades {
+                        if ui.button(label).clicked() {
+                            state.review(&card, grade);
+                            let card = queue.remove(0);
+                            if grade < 3 {
+                                // Forgotten cards come back at the end of the session.
+                                queue.push(card);
+                            }
+                            self.revealed = false;
+                        }
+                    }
+                });
+            } else if ui.button(t("Show answer")).clicked() {
+                self.revealed = true;
+            }
+            ui.separator();
+            if ui.link(RichText::new(vault::note_key(&card.note)).small()).clicked() {
+                source = Some(card.note.clone());
+            }
+        });
+
+        if !open {
+            self.queue = None;
+            self.state = None;
+        }
+        source
+    }
+}
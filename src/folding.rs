@@ -0,0 +1,109 @@
+use crate::i18n::{t, tf};
+use crate::markdown;
+use eframe::egui::{self, text::CCursor, text_edit::TextEditOutput, Sense};
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// Room left of the text for the fold chevrons.
+pub const GUTTER: f32 = 16.0;
+
+/// A heading's section or a code block, which can be folded away under
+/// its first line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fold {
+    /// The first line and which of the lines with that text it is, which
+    /// stays the same as the note is edited elsewhere.
+    pub key: String,
+    /// Byte offset of the first line.
+    pub start: usize,
+    /// What folding hides: the rest of the section or block.
+    pub body: Range<usize>,
+}
+
+/// Byte offset just past the line `start` is on.
+fn line_end(text: &str, start: usize) -> usize {
+    text[start..].find('\n').map_or(text.len(), |n| start + n + 1)
+}
+
+/// The sections under headings, each up to the next heading of the same
+/// or a higher level, and the fenced code blocks, in the order they start.
+pub fn folds(text: &str) -> Vec<Fold> {
+    let headings = markdown::headings(text);
+    let mut spans: Vec<(usize, Range<usize>)> = Vec::new();
+    for (index, heading) in headings.iter().enumerate() {
+        let end = headings[index + 1..].iter().find(|h| h.level <= heading.level).map_or(text.len(), |h| h.offset);
+        spans.push((heading.offset, line_end(text, heading.offset)..end));
+    }
+    for block in markdown::fenced_blocks(text) {
+        spans.push((block.range.start, line_end(text, block.range.start)..block.range.end));
+    }
+    spans.retain(|(_, body)| body.start < body.end);
+    spans.sort_by_key(|(start, _)| *start);
+
+    let mut seen: Vec<&str> = Vec::new();
+    spans
+        .into_iter()
+        .map(|(start, body)| {
+            let line = text[start..body.start].trim_end();
+            let nth = seen.iter().filter(|l| **l == line).count();
+            seen.push(line);
+            Fold { key: format!("{}:{}", nth, line), start, body }
+        })
+        .collect()
+}
+
+/// The byte ranges hidden by the folds in `folded`.
+pub fn hidden(text: &str, folded: &HashSet<String>) -> Vec<Range<usize>> {
+    if folded.is_empty() {
+        return Vec::new();
+    }
+    folds(text).into_iter().filter(|f| folded.contains(&f.key)).map(|f| f.body).collect()
+}
+
+/// Unfolds the sections the cursor has moved into, and forgets folds
+/// whose first line is gone.
+pub fn reveal(text: &str, cursor: usize, folded: &mut HashSet<String>) {
+    if folded.is_empty() {
+        return;
+    }
+    let folds = folds(text);
+    folded.retain(|key| folds.iter().find(|f| f.key == *key).is_some_and(|f| !f.body.contains(&cursor)));
+}
+
+/// Draws a chevron left of each line that starts a fold, and a marker
+/// after the ones folded, toggling a fold when its chevron is clicked.
+pub fn show_gutter(ui: &egui::Ui, output: &TextEditOutput, text: &str, folded: &mut HashSet<String>) {
+    let folds = folds(text);
+    let hidden: Vec<Range<usize>> = folds.iter().filter(|f| folded.contains(&f.key)).map(|f| f.body.clone()).collect();
+    let font = egui::TextStyle::Small.resolve(ui.style());
+    let color = ui.visuals().weak_text_color();
+    for fold in &folds {
+        if hidden.iter().any(|r| r.contains(&fold.start)) {
+            continue;
+        }
+        let chars = text[..fold.start].chars().count();
+        let row = output.galley.pos_from_cursor(&output.galley.from_ccursor(CCursor::new(chars)));
+        let row = row.translate(output.galley_pos.to_vec2());
+        let rect = egui::Rect::from_min_size(egui::pos2(row.left() - GUTTER, row.top()), egui::vec2(GUTTER, row.height()));
+        let response = ui.interact(rect, ui.id().with(("fold", &fold.key)), Sense::click());
+        let is_folded = folded.contains(&fold.key);
+        // Open folds show their chevron while the pointer is on the line.
+        let line = egui::Rect::from_x_y_ranges(rect.left()..=output.galley_pos.x + output.galley.rect.right(), row.y_range());
+        if is_folded || ui.rect_contains_pointer(line) {
+            let chevron = if is_folded { "▶" } else { "▼" };
+            let color = if response.hovered() { ui.visuals().strong_text_color() } else { color };
+            ui.painter().text(rect.center(), egui::Align2::CENTER_CENTER, chevron, font.clone(), color);
+        }
+        if is_folded {
+            let end = text[..fold.start].chars().count() + text[fold.start..fold.body.start].trim_end().chars().count();
+            let after = output.galley.pos_from_cursor(&output.galley.from_ccursor(CCursor::new(end)));
+            let lines = text[fold.body.clone()].lines().count();
+            let at = after.right_center() + output.galley_pos.to_vec2() + egui::vec2(8.0, 0.0);
+            let label = tf("⋯ {} lines", &[&lines]);
+            ui.painter().text(at, egui::Align2::LEFT_CENTER, label, font.clone(), color);
+        }
+        if response.on_hover_text(if is_folded { t("Unfold") } else { t("Fold") }).clicked() && !folded.remove(&fold.key) {
+            folded.insert(fold.key.clone());
+        }
+    }
+}
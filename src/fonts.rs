@@ -0,0 +1,146 @@
+use eframe::egui::{FontData, FontDefinitions, FontFamily};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// System fonts to fall back on for scripts the bundled fonts lack, by
+/// script, most likely first. The first one found for each is used.
+#[cfg(target_os = "windows")]
+const FALLBACKS: &[(&str, &[&str])] = &[
+    ("CJK", &["C:\\Windows\\Fonts\\msyh.ttc", "C:\\Windows\\Fonts\\YuGothR.ttc", "C:\\Windows\\Fonts\\meiryo.ttc", "C:\\Windows\\Fonts\\simsun.ttc"]),
+    ("Korean", &["C:\\Windows\\Fonts\\malgun.ttf"]),
+    ("Arabic and Hebrew", &["C:\\Windows\\Fonts\\segoeui.ttf", "C:\\Windows\\Fonts\\arial.ttf"]),
+    ("Indic", &["C:\\Windows\\Fonts\\Nirmala.ttf", "C:\\Windows\\Fonts\\mangal.ttf"]),
+    ("Thai", &["C:\\Windows\\Fonts\\LeelawUI.ttf", "C:\\Windows\\Fonts\\tahoma.ttf"]),
+    ("Symbols and emoji", &["C:\\Windows\\Fonts\\seguisym.ttf", "C:\\Windows\\Fonts\\seguiemj.ttf"]),
+];
+
+#[cfg(target_os = "macos")]
+const FALLBACKS: &[(&str, &[&str])] = &[
+    ("CJK", &["/System/Library/Fonts/PingFang.ttc", "/System/Library/Fonts/Hiragino Sans GB.ttc", "/Library/Fonts/Arial Unicode.ttf"]),
+    ("Korean", &["/System/Library/Fonts/AppleSDGothicNeo.ttc"]),
+    ("Arabic", &["/System/Library/Fonts/GeezaPro.ttc", "/System/Library/Fonts/Supplemental/GeezaPro.ttc"]),
+    ("Hebrew", &["/System/Library/Fonts/ArialHB.ttc", "/System/Library/Fonts/Supplemental/Arial Unicode.ttf"]),
+    ("Indic", &["/System/Library/Fonts/Kohinoor.ttc", "/System/Library/Fonts/Supplemental/DevanagariMT.ttc"]),
+    ("Thai", &["/System/Library/Fonts/Thonburi.ttc"]),
+    ("Symbols", &["/System/Library/Fonts/Apple Symbols.ttf"]),
+];
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const FALLBACKS: &[(&str, &[&str])] = &[
+    (
+        "CJK",
+        &[
+            "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+            "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+            "/system/fonts/NotoSansCJK-Regular.ttc",
+        ],
+    ),
+    (
+        "Arabic",
+        &[
+            "/usr/share/fonts/truetype/noto/NotoSansArabic-Regular.ttf",
+            "/usr/share/fonts/noto/NotoSansArabic-Regular.ttf",
+            "/usr/share/fonts/google-noto/NotoSansArabic-Regular.ttf",
+            "/system/fonts/NotoNaskhArabic-Regular.ttf",
+        ],
+    ),
+    (
+        "Hebrew",
+        &[
+            "/usr/share/fonts/truetype/noto/NotoSansHebrew-Regular.ttf",
+            "/usr/share/fonts/noto/NotoSansHebrew-Regular.ttf",
+            "/usr/share/fonts/google-noto/NotoSansHebrew-Regular.ttf",
+            "/system/fonts/NotoSansHebrew-Regular.ttf",
+        ],
+    ),
+    (
+        "Indic",
+        &[
+            "/usr/share/fonts/truetype/noto/NotoSansDevanagari-Regular.ttf",
+            "/usr/share/fonts/noto/NotoSansDevanagari-Regular.ttf",
+            "/system/fonts/NotoSansDevanagari-VF.ttf",
+        ],
+    ),
+    ("Thai", &["/usr/share/fonts/truetype/noto/NotoSansThai-Regular.ttf", "/usr/share/fonts/noto/NotoSansThai-Regular.ttf"]),
+    // Wide coverage of Greek, Cyrillic, Hebrew and symbols.
+    ("Symbols", &["/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf", "/usr/share/fonts/TTF/DejaVuSans.ttf", "/usr/share/fonts/dejavu/DejaVuSans.ttf"]),
+];
+
+/// Adds the system fonts found for other scripts after the ones already
+/// set, so characters the first fonts lack are drawn from them instead of
+/// as boxes. egui's own fonts already cover emoji in black and white.
+pub fn add_fallbacks(fonts: &mut FontDefinitions) {
+    for (script, paths) in FALLBACKS {
+        let Some((path, data)) = paths.iter().find_map(|p| std::fs::read(p).ok().map(|data| (p, data))) else {
+            continue;
+        };
+        info!("Using {} for {} text", path, script);
+        let name = format!("fallback-{}", script);
+        fonts.font_data.insert(name.clone(), FontData::from_owned(data));
+        for family in [FontFamily::Proportional, FontFamily::Monospace] {
+            fonts.families.entry(family).or_default().push(name.clone());
+        }
+    }
+}
+
+/// Folders OpenDyslexic is usually installed in, by package managers or
+/// for the user alone.
+fn dyslexic_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = [
+        "/usr/share/fonts/opentype/opendyslexic",
+        "/usr/share/fonts/truetype/opendyslexic",
+        "/usr/share/fonts/OTF",
+        "/usr/local/share/fonts",
+        "/Library/Fonts",
+        "C:\\Windows\\Fonts",
+    ]
+    .iter()
+    .map(PathBuf::from)
+    .collect();
+    if let Ok(home) = std::env::var("HOME") {
+        dirs.push(Path::new(&home).join(".local/share/fonts"));
+        dirs.push(Path::new(&home).join(".fonts"));
+        dirs.push(Path::new(&home).join("Library/Fonts"));
+    }
+    if let Ok(local) = std::env::var("LOCALAPPDATA") {
+        dirs.push(Path::new(&local).join("Microsoft\\Windows\\Fonts"));
+    }
+    dirs
+}
+
+/// Puts OpenDyslexic before the other fonts, from `path` or wherever it is
+/// installed, with its monospace cut for code when that is next to it.
+/// Returns whether it was found.
+pub fn add_dyslexic(fonts: &mut FontDefinitions, path: &str) -> bool {
+    let regular = if path.is_empty() {
+        dyslexic_dirs().into_iter().map(|dir| dir.join("OpenDyslexic-Regular.otf")).find(|p| p.exists())
+    } else {
+        Some(PathBuf::from(path))
+    };
+    let Some(regular) = regular else {
+        warn!("OpenDyslexic is not installed; install it or set where its font file is");
+        return false;
+    };
+    let data = match std::fs::read(&regular) {
+        Ok(data) => data,
+        Err(err) => {
+            warn!("Failed to read {}: {}", regular.display(), err);
+            return false;
+        }
+    };
+    fonts.font_data.insert("OpenDyslexic".to_owned(), FontData::from_owned(data));
+    fonts.families.entry(FontFamily::Proportional).or_default().insert(0, "OpenDyslexic".to_owned());
+    let mono = regular.with_file_name("OpenDyslexicMono-Regular.otf");
+    let mono_name = match std::fs::read(&mono) {
+        Ok(data) => {
+            fonts.font_data.insert("OpenDyslexicMono".to_owned(), FontData::from_owned(data));
+            "OpenDyslexicMono"
+        }
+        Err(_) => "OpenDyslexic",
+    };
+    fonts.families.entry(FontFamily::Monospace).or_default().insert(0, mono_name.to_owned());
+    true
+}
@@ -0,0 +1,70 @@
+use std::ops::Range;
+
+/// A `[^label]` reference, or the `[^label]:` that starts a definition.
+#[derive(Debug, Clone)]
+pub struct Marker {
+    pub label: String,
+    /// Byte range of the bracketed label, colon excluded.
+    pub range: Range<usize>,
+    pub definition: bool,
+}
+
+/// Footnote markers in `text`, in order, skipping fenced code blocks.
+pub fn markers(text: &str) -> Vec<Marker> {
+    let fences = crate::markdown::fenced_blocks(text);
+    let mut markers = Vec::new();
+    for (start, _) in text.match_indices("[^") {
+        if fences.iter().any(|f| f.range.contains(&start)) {
+            continue;
+        }
+        let rest = &text[start + 2..];
+        let Some(close) = rest.find(']') else {
+            continue;
+        };
+        let label = &rest[..close];
+        if label.is_empty() || label.contains(char::is_whitespace) || label.contains('[') {
+            continue;
+        }
+        let end = start + 2 + close + 1;
+        let at_line_start = start == 0 || text[..start].ends_with('\n');
+        markers.push(Marker {
+            label: label.to_owned(),
+            range: start..end,
+            definition: at_line_start && text[end..].starts_with(':'),
+        });
+    }
+    markers
+}
+
+/// Where to jump from the marker under `cursor`: from a reference to its
+/// definition, from a definition to the first reference. A byte offset.
+pub fn jump_target(text: &str, cursor: usize) -> Option<usize> {
+    let markers = markers(text);
+    let here = markers.iter().find(|m| m.range.start <= cursor && cursor <= m.range.end)?;
+    markers
+        .iter()
+        .find(|m| m.label == here.label && m.definition != here.definition)
+        .map(|m| if m.definition { m.range.end + 1 } else { m.range.start })
+}
+
+/// The next free numeric label.
+pub fn next_number(text: &str) -> usize {
+    markers(text).iter().filter_map(|m| m.label.parse::<usize>().ok()).max().unwrap_or(0) + 1
+}
+
+/// Inserts a numbered reference at `cursor` and an empty definition at the
+/// end of the note. Returns the new text and the offset where the
+/// definition's text goes.
+pub fn insert(text: &str, cursor: usize) -> (String, usize) {
+    let number = next_number(text);
+    let mut out = text.to_owned();
+    out.insert_str(cursor, &format!("[^{}]", number));
+    let trimmed = out.trim_end_matches('\n').len();
+    out.truncate(trimmed);
+    // Keep a run of definitions together.
+    let last_line = out.rsplit('\n').next().unwrap_or_default();
+    let separator = if last_line.starts_with("[^") { "\n" } else { "\n\n" };
+    out.push_str(&format!("{}[^{}]: ", separator, number));
+    let at = out.len();
+    (out, at)
+}
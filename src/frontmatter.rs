@@ -0,0 +1,255 @@
+use chrono::NaiveDate;
+use std::ops::Range;
+
+/// Byte range of the YAML front matter block at the start of a note,
+/// including both `---` lines.
+pub fn range(text: &str) -> Option<Range<usize>> {
+    let rest = text.strip_prefix("---\n").or_else(|| text.strip_prefix("---\r\n"))?;
+    let start = text.len() - rest.len();
+    let mut offset = start;
+    for line in rest.split_inclusive('\n') {
+        offset += line.len();
+        if line.trim_end() == "---" {
+            return Some(0..offset);
+        }
+    }
+    None
+}
+
+/// The `key: value` lines of the front matter, in order. Nested YAML is
+/// not interpreted; only top-level scalar fields are returned.
+pub fn fields(text: &str) -> Vec<(String, String)> {
+    let Some(range) = range(text) else {
+        return Vec::new();
+    };
+    text[range]
+        .lines()
+        .filter(|line| !line.starts_with([' ', '\t', '-', '#']))
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            Some((key.trim().to_owned(), value.trim().trim_matches(['"', '\'']).to_owned()))
+        })
+        .collect()
+}
+
+pub fn get(text: &str, key: &str) -> Option<String> {
+    fields(text).into_iter().find(|(k, _)| k == key).map(|(_, v)| v)
+}
+
+/// Sets a top-level field, or removes it with `None`. The block is
+/// created when needed and dropped once it holds nothing.
+pub fn set(text: &str, key: &str, value: Option<&str>) -> String {
+    let (mut lines, body) = match range(text) {
+        Some(range) => {
+            let block = &text[range.clone()];
+            let inner: Vec<String> = block.lines().skip(1).take(block.lines().count().saturating_sub(2)).map(String::from).collect();
+            (inner, &text[range.end..])
+        }
+        None => (Vec::new(), text),
+    };
+
+    let prefix = format!("{}:", key);
+    let existing = lines.iter().position(|line| line.starts_with(&prefix));
+    match (existing, value) {
+        (Some(index), value) => {
+            // Drop the indented or list lines that belonged to the field.
+            while lines.get(index + 1).is_some_and(|l| l.starts_with([' ', '\t', '-'])) {
+                lines.remove(index + 1);
+            }
+            match value {
+                Some(value) => lines[index] = format!("{} {}", prefix, value).trim_end().to_owned(),
+                None => {
+                    lines.remove(index);
+                }
+            }
+        }
+        (None, Some(value)) => lines.push(format!("{} {}", prefix, value).trim_end().to_owned()),
+        (None, None) => {}
+    }
+
+    if lines.is_empty() {
+        body.to_owned()
+    } else {
+        format!("---\n{}\n---\n{}", lines.join("\n"), body)
+    }
+}
+
+/// Whether the note asks to be protected from edits with `locked: true`.
+pub fn is_locked(text: &str) -> bool {
+    get(text, "locked").is_some_and(|v| v == "true")
+}
+
+/// What kind of value a property holds, which decides how it is edited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Text,
+    List,
+    Date,
+    Checkbox,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 4] = [Kind::Text, Kind::List, Kind::Date, Kind::Checkbox];
+}
+
+/// A front matter field's value, read by the shape of its YAML.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Text(String),
+    List(Vec<String>),
+    Date(NaiveDate),
+    Checkbox(bool),
+    /// A nested map or anything else not edited as a property.
+    Other,
+}
+
+impl Value {
+    pub fn kind(&self) -> Option<Kind> {
+        match self {
+            Value::Text(_) => Some(Kind::Text),
+            Value::List(_) => Some(Kind::List),
+            Value::Date(_) => Some(Kind::Date),
+            Value::Checkbox(_) => Some(Kind::Checkbox),
+            Value::Other => None,
+        }
+    }
+
+    /// The value as another kind, keeping what carries over: list items
+    /// joined as text, text split into items, a date from text that is
+    /// one.
+    pub fn convert(&self, kind: Kind) -> Value {
+        let text = self.to_string();
+        match kind {
+            Kind::Text => Value::Text(text),
+            Kind::List => match self {
+                Value::List(items) => Value::List(items.clone()),
+                _ => Value::List(text.split(',').map(|i| i.trim().to_owned()).filter(|i| !i.is_empty()).collect()),
+            },
+            Kind::Date => Value::Date(parse_date(&text).unwrap_or_else(|| chrono::Local::now().date_naive())),
+            Kind::Checkbox => Value::Checkbox(matches!(self, Value::Checkbox(true)) || text == "true"),
+        }
+    }
+
+    /// The value written as YAML after the key. Text that would read back
+    /// as another kind is quoted.
+    pub fn to_yaml(&self) -> String {
+        match self {
+            Value::Text(text) => quoted(text, false),
+            Value::List(items) => format!("[{}]", items.iter().map(|i| quoted(i, true)).collect::<Vec<_>>().join(", ")),
+            Value::Date(date) => date.format("%Y-%m-%d").to_string(),
+            Value::Checkbox(checked) => checked.to_string(),
+            Value::Other => String::new(),
+        }
+    }
+}
+
+/// Plain text, as shown in the properties strip and compared in searches.
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Value::Text(text) => f.write_str(text),
+            Value::List(items) => f.write_str(&items.join(", ")),
+            Value::Date(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+            Value::Checkbox(checked) => write!(f, "{}", checked),
+            Value::Other => Ok(()),
+        }
+    }
+}
+
+pub fn parse_date(text: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d").ok()
+}
+
+fn unquoted(text: &str) -> &str {
+    let text = text.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = text.strip_prefix(quote).and_then(|t| t.strip_suffix(quote)) {
+            return inner;
+        }
+    }
+    text
+}
+
+/// `text` in double quotes when YAML would otherwise read it as
+/// something else, or not as written. Commas end an item `in_list`.
+fn quoted(text: &str, in_list: bool) -> String {
+    let special = text.starts_with(['[', '{', '-', '#', '&', '*', '!', '|', '>', '\'', '"', '%', '@', '`', ' '])
+        || text.ends_with(' ')
+        || text.contains(": ")
+        || text.contains(" #")
+        || (in_list && text.contains([',', ']']))
+        || matches!(text, "true" | "false" | "null" | "~")
+        || parse_date(text).is_some();
+    if special && !text.is_empty() {
+        format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        text.to_owned()
+    }
+}
+
+/// The items of a `[a, "b, c"]` list, split at commas outside quotes.
+fn flow_items(list: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let (mut start, mut quote) = (0, None);
+    for (at, c) in list.char_indices().chain([(list.len(), ',')]) {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, ',') => {
+                items.push(unquoted(&list[start..at]).to_owned());
+                start = at + 1;
+            }
+            _ => {}
+        }
+    }
+    if quote.is_some() {
+        items.push(list[start..].trim().to_owned());
+    }
+    items.retain(|i| !i.is_empty());
+    items
+}
+
+/// The front matter's top-level fields with their values read by shape:
+/// `[a, b]` and `- item` lines are lists, `true` and `false` checkboxes,
+/// `YYYY-MM-DD` dates and anything else text. Quoted values are text.
+pub fn properties(text: &str) -> Vec<(String, Value)> {
+    let Some(range) = range(text) else {
+        return Vec::new();
+    };
+    let lines: Vec<&str> = text[range].lines().collect();
+    let inner = &lines[1..lines.len() - 1];
+    let mut properties = Vec::new();
+    for (index, line) in inner.iter().enumerate() {
+        if line.starts_with([' ', '\t', '-', '#']) {
+            continue;
+        }
+        let Some((key, raw)) = line.split_once(':') else {
+            continue;
+        };
+        let raw = raw.trim();
+        let nested: Vec<&str> = inner[index + 1..].iter().take_while(|l| l.starts_with([' ', '\t', '-'])).map(|l| l.trim()).collect();
+        let value = if raw.is_empty() && !nested.is_empty() {
+            match nested.iter().map(|l| l.strip_prefix('-')).collect::<Option<Vec<_>>>() {
+                Some(items) => Value::List(items.iter().map(|i| unquoted(i).to_owned()).collect()),
+                None => Value::Other,
+            }
+        } else if let Some(items) = raw.strip_prefix('[').and_then(|r| r.strip_suffix(']')) {
+            Value::List(flow_items(items))
+        } else if raw.starts_with(['"', '\'']) {
+            Value::Text(unquoted(raw).replace("\\\"", "\"").replace("\\\\", "\\"))
+        } else if let Ok(checked) = raw.parse::<bool>() {
+            Value::Checkbox(checked)
+        } else if let Some(date) = parse_date(raw) {
+            Value::Date(date)
+        } else {
+            Value::Text(raw.to_owned())
+        };
+        properties.push((key.trim().to_owned(), value));
+    }
+    properties
+}
+
+/// Sets a property to a typed value, or removes it with `None`.
+pub fn set_value(text: &str, key: &str, value: Option<&Value>) -> String {
+    set(text, key, value.map(Value::to_yaml).as_deref())
+}
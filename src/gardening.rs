@@ -0,0 +1,125 @@
+use crate::i18n::{t, tf};
+use crate::{links, vault};
+use eframe::egui;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// Roughly a month, for the staleness threshold.
+const MONTH: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+pub struct StaleNote {
+    pub path: PathBuf,
+    pub modified: SystemTime,
+}
+
+/// Notes with no links in or out, and notes untouched for a while.
+pub struct Report {
+    pub orphans: Vec<PathBuf>,
+    pub stale: Vec<StaleNote>,
+}
+
+pub fn build(stale_months: u32) -> Report {
+    let notes = vault::note_files();
+    let mut linked = HashSet::new();
+    for note in &notes {
+        let Ok(text) = std::fs::read_to_string(note) else {
+            continue;
+        };
+        let outgoing = links::outgoing(note, &text, &notes);
+        if outgoing.iter().any(|target| target != note) {
+            linked.insert(note.clone());
+        }
+        linked.extend(outgoing.into_iter().filter(|target| target != note));
+    }
+
+    let cutoff = SystemTime::now() - MONTH * stale_months;
+    let mut stale: Vec<StaleNote> = notes
+        .iter()
+        .filter_map(|path| {
+            let modified = path.metadata().and_then(|m| m.modified()).ok()?;
+            (modified < cutoff).then(|| StaleNote {
+                path: path.clone(),
+                modified,
+            })
+        })
+        .collect();
+    stale.sort_by_key(|note| note.modified);
+
+    Report {
+        orphans: notes.into_iter().filter(|n| !linked.contains(n)).collect(),
+        stale,
+    }
+}
+
+pub struct GardeningView {
+    pub report: Option<Report>,
+    stale_months: u32,
+}
+
+impl Default for GardeningView {
+    fn default() -> Self {
+        Self {
+            report: None,
+            stale_months: 6,
+        }
+    }
+}
+
+impl GardeningView {
+    pub fn run(&mut self) {
+        self.report = Some(build(self.stale_months));
+    }
+
+    /// Returns a note the user clicked.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<PathBuf> {
+        let report = self.report.as_ref()?;
+        let mut open = true;
+        let mut clicked = None;
+        let mut rerun = false;
+
+        egui::Window::new(t("🌱 Vault Gardening"))
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().max_height(480.0).show(ui, |ui| {
+                    egui::CollapsingHeader::new(tf("Orphans ({})", &[&report.orphans.len()]))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            ui.label(egui::RichText::new(t("Notes with no links in or out")).small().weak());
+                            for path in &report.orphans {
+                                if ui.link(path.display().to_string()).clicked() {
+                                    clicked = Some(path.clone());
+                                }
+                            }
+                        });
+                    egui::CollapsingHeader::new(tf("Stale ({})", &[&report.stale.len()]))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label(t("Not modified in"));
+                                rerun |= ui
+                                    .add(egui::DragValue::new(&mut self.stale_months).clamp_range(1..=120).suffix(t(" months")))
+                                    .changed();
+                            });
+                            for note in &report.stale {
+                                let modified: chrono::DateTime<chrono::Local> = note.modified.into();
+                                ui.horizontal(|ui| {
+                                    if ui.link(note.path.display().to_string()).clicked() {
+                                        clicked = Some(note.path.clone());
+                                    }
+                                    ui.label(egui::RichText::new(modified.format("%Y-%m-%d").to_string()).weak());
+                                });
+                            }
+                        });
+                });
+            });
+
+        if !open {
+            self.report = None;
+        } else if rerun {
+            self.run();
+        }
+        clicked
+    }
+}
@@ -0,0 +1,101 @@
+use crate::vault::NOTES_DIR;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::warn;
+
+/// How often the glossary note is checked for changes.
+const RECHECK: Duration = Duration::from_secs(2);
+
+/// Where a term ends and its expansion starts on a line.
+const SEPARATORS: [&str; 5] = [": ", " — ", " – ", " - ", " = "];
+
+/// A table cell made of dashes and colons only, under a header row.
+fn is_rule(cell: &str) -> bool {
+    !cell.is_empty() && cell.chars().all(|c| matches!(c, '-' | ':'))
+}
+
+/// The term and expansion on one line of a glossary note: a table row,
+/// or `TERM: expansion` with an optional list marker and emphasis.
+fn entry(line: &str) -> Option<(String, String)> {
+    let line = line.trim();
+    if line.starts_with('|') {
+        let mut cells = line.trim_matches('|').split('|').map(str::trim);
+        let (term, expansion) = (cells.next()?, cells.next()?);
+        return Some((term.to_owned(), expansion.to_owned()));
+    }
+    let line = line.strip_prefix(['-', '*', '+']).map_or(line, str::trim_start);
+    let (term, expansion) = SEPARATORS.iter().filter_map(|s| line.split_once(s)).min_by_key(|(term, _)| term.len())?;
+    let term = term.trim().trim_matches(['*', '_', '`']).trim();
+    Some((term.to_owned(), expansion.trim().to_owned()))
+}
+
+/// Terms and what they stand for, from a note of `TERM: expansion` lines
+/// or a two-column table. Headings, code and the front matter are skipped.
+pub fn parse(text: &str) -> Vec<(String, String)> {
+    let body = crate::frontmatter::range(text).map_or(text, |r| &text[r.end..]);
+    let mut terms: Vec<(String, String)> = Vec::new();
+    let mut fenced = false;
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fenced = !fenced;
+            continue;
+        }
+        if fenced || trimmed.starts_with('#') {
+            continue;
+        }
+        let Some((term, expansion)) = entry(line) else {
+            continue;
+        };
+        if is_rule(&term) {
+            // The row above was the table's header.
+            terms.pop();
+        } else if !term.is_empty() && !expansion.is_empty() && term.chars().count() <= 40 {
+            terms.push((term, expansion));
+        }
+    }
+    terms
+}
+
+/// The glossary note's terms, read again when the note changes on disk.
+#[derive(Default)]
+pub struct Glossary {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    checked: Option<Instant>,
+    terms: Vec<(String, String)>,
+}
+
+impl Glossary {
+    /// Picks up changes to `note`, a path in the notes folder, every few
+    /// seconds.
+    pub fn refresh(&mut self, note: &str) {
+        let path = Path::new(NOTES_DIR).join(note);
+        if path == self.path && self.checked.is_some_and(|at| at.elapsed() < RECHECK) {
+            return;
+        }
+        self.checked = Some(Instant::now());
+        let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        if path == self.path && modified == self.modified {
+            return;
+        }
+        self.terms = match modified.map(|_| std::fs::read_to_string(&path)) {
+            Some(Ok(text)) => parse(&text),
+            Some(Err(err)) => {
+                warn!("Failed to read the glossary {}: {}", path.display(), err);
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+        self.path = path;
+        self.modified = modified;
+    }
+
+    /// What `term` stands for. Acronyms are matched exactly, other terms
+    /// in any case.
+    pub fn get(&self, term: &str) -> Option<&str> {
+        let exact = self.terms.iter().find(|(t, _)| t == term);
+        let loose = || self.terms.iter().find(|(t, _)| t.chars().any(char::is_lowercase) && t.eq_ignore_ascii_case(term));
+        exact.or_else(loose).map(|(_, expansion)| expansion.as_str())
+    }
+}
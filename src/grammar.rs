@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug)]
+pub struct LTResponse {
+    pub matches: Vec<LTMatch>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LTMatch {
+    pub message: String,
+    pub offset: usize,
+    pub length: usize,
+    pub replacements: Vec<LTSuggestion>,
+    /// The provider that found it, when results from several are merged.
+    #[serde(default)]
+    pub provider: String,
+    #[serde(default)]
+    pub rule: LTRule,
+}
+
+impl LTMatch {
+    pub fn category(&self) -> &str {
+        if self.rule.category.name.is_empty() { "Other" } else { &self.rule.category.name }
+    }
+
+    pub fn is_spelling(&self) -> bool {
+        self.rule.category.id == SPELLING_CATEGORY
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LTSuggestion {
+    pub value: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct LTRule {
+    pub id: String,
+    pub category: LTCategory,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct LTCategory {
+    pub id: String,
+    pub name: String,
+}
+
+/// LanguageTool's category for misspelled words, which other providers
+/// use for theirs too.
+pub const SPELLING_CATEGORY: &str = "TYPOS";
+
+impl LTRule {
+    pub fn new(id: &str, category_id: &str, category: &str) -> Self {
+        Self {
+            id: id.to_owned(),
+            category: LTCategory {
+                id: category_id.to_owned(),
+                name: category.to_owned(),
+            },
+        }
+    }
+}
+
+pub const ENDPOINT: &str = "https://api.languagetoolplus.com/v2/check";
+
+pub const DEFAULT_LANGUAGE: &str = "en-US";
+
+/// Languages offered for checking, as LanguageTool codes and names.
+pub const LANGUAGES: &[(&str, &str)] = &[
+    ("auto", "Detect automatically"),
+    ("en-US", "English (US)"),
+    ("en-GB", "English (UK)"),
+    ("de-DE", "German"),
+    ("fr", "French"),
+    ("es", "Spanish"),
+    ("it", "Italian"),
+    ("nl", "Dutch"),
+    ("pt-PT", "Portuguese"),
+];
+
+pub fn language_name(code: &str) -> &str {
+    LANGUAGES.iter().find(|(c, _)| *c == code).map_or(code, |(_, name)| name)
+}
+
+/// The public API's limits: 20 requests and 75 KB of text a minute.
+#[cfg(not(target_arch = "wasm32"))]
+static LIMITER: std::sync::Mutex<crate::ratelimit::RateLimiter> = std::sync::Mutex::new(crate::ratelimit::RateLimiter::new(20, 75_000));
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub enum CheckError {
+    /// LanguageTool answered 429; checks wait this long before going out again.
+    CoolingDown(std::time::Duration),
+    Http(reqwest::Error),
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl std::fmt::Display for CheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckError::CoolingDown(wait) => write!(f, "too many checks, cooling down for {}s", wait.as_secs()),
+            CheckError::Http(err) => err.fmt(f),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<reqwest::Error> for CheckError {
+    fn from(err: reqwest::Error) -> Self {
+        CheckError::Http(err)
+    }
+}
+
+/// Checks `text` with the public API.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn check(text: &str, language: &str) -> Result<Vec<LTMatch>, CheckError> {
+    check_at(ENDPOINT, &[], text, language)
+}
+
+/// Checks `text` with the LanguageTool server at `endpoint`, sending
+/// `params` besides the text and language: `username` and `apiKey` for a
+/// premium account, `motherTongue` and the like. Checks with the free
+/// public API first wait in line if they have been going out faster than
+/// it allows.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn check_at(endpoint: &str, params: &[(&str, &str)], text: &str, language: &str) -> Result<Vec<LTMatch>, CheckError> {
+    if endpoint == ENDPOINT && !params.iter().any(|(key, _)| *key == "apiKey") {
+        loop {
+            // Not holding the lock while waiting, so the status bar can read it.
+            let wait = LIMITER.lock().unwrap_or_else(|e| e.into_inner()).reserve(text.len());
+            let Some(wait) = wait else {
+                break;
+            };
+            std::thread::sleep(wait.min(std::time::Duration::from_secs(1)));
+        }
+    }
+    let mut form = vec![("text", text), ("language", language)];
+    form.extend_from_slice(params);
+    let resp = crate::http::send(crate::http::client().post(endpoint).form(&form))?;
+    if let Some(wait) = LIMITER.lock().unwrap_or_else(|e| e.into_inner()).observe(&resp) {
+        return Err(CheckError::CoolingDown(wait));
+    }
+    Ok(resp.error_for_status()?.json::<LTResponse>()?.matches)
+}
+
+/// "Cooling down" after LanguageTool said to slow down, or the checks left
+/// when it reports a quota.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn status() -> Option<String> {
+    let limiter = LIMITER.lock().unwrap_or_else(|e| e.into_inner());
+    match (limiter.cooling_down(), limiter.remaining()) {
+        (Some(wait), _) => Some(crate::i18n::tf("Grammar: cooling down, {}s", &[&(wait.as_secs() + 1)])),
+        (None, Some(remaining)) => Some(crate::i18n::tf("Grammar: {} checks left", &[&remaining])),
+        (None, None) => None,
+    }
+}
+
+/// The same check in the browser, where requests go through `fetch` and
+/// cannot block.
+#[cfg(target_arch = "wasm32")]
+pub async fn check_async(text: &str, language: &str) -> reqwest::Result<Vec<LTMatch>> {
+    let resp = reqwest::Client::new()
+        .post(ENDPOINT)
+        .form(&[("text", text), ("language", language)])
+        .send()
+        .await?;
+    Ok(resp.json::<LTResponse>().await?.matches)
+}
@@ -0,0 +1,197 @@
+use crate::i18n::t;
+use crate::sidebar::{NoteInfo, NoteList};
+use crate::vault;
+use chrono::{Local, NaiveDate};
+use eframe::egui::{self, RichText};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::path::PathBuf;
+use tracing::error;
+
+/// Marks a task list item in a daily note as a habit: `- [x] Exercise #habit`.
+const TAG: &str = "#habit";
+/// Days shown in the heatmap, ending today.
+const DAYS: u64 = 12 * 7;
+
+/// Each day's habits and whether they were done.
+pub type Days = BTreeMap<NaiveDate, BTreeMap<String, bool>>;
+
+/// A habit line's name, whether it is checked, and its marker offset.
+fn habit(line: &str) -> Option<(String, bool, usize)> {
+    let at = crate::kanban::marker_offset(line)?;
+    let mark = line[at..].chars().next()?;
+    let rest = &line[at + mark.len_utf8() + 1..];
+    if !rest.split_whitespace().any(|w| w == TAG) {
+        return None;
+    }
+    let name = rest.split_whitespace().filter(|w| *w != TAG).collect::<Vec<_>>().join(" ");
+    let done = matches!(mark, 'x' | 'X');
+    (!name.is_empty()).then_some((name, done, at))
+}
+
+/// Habits checked or listed in each daily note, by day.
+pub fn days(notes: &[NoteInfo]) -> Days {
+    let mut days = BTreeMap::new();
+    for note in notes {
+        if !vault::note_key(&note.path).starts_with("daily/") {
+            continue;
+        }
+        let Some(date) = note
+            .path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
+        else {
+            continue;
+        };
+        let habits: BTreeMap<String, bool> = note.text.lines().filter_map(habit).map(|(name, done, _)| (name, done)).collect();
+        if !habits.is_empty() {
+            days.insert(date, habits);
+        }
+    }
+    days
+}
+
+/// Days in a row the habit was done, ending today, or yesterday while
+/// today is still open.
+pub fn streak(days: &Days, name: &str, today: NaiveDate) -> usize {
+    let done = |date: NaiveDate| days.get(&date).and_then(|h| h.get(name)).copied().unwrap_or(false);
+    let mut date = if done(today) { today } else { today - chrono::Days::new(1) };
+    let mut count = 0;
+    while done(date) {
+        count += 1;
+        date = date - chrono::Days::new(1);
+    }
+    count
+}
+
+/// Checks or unchecks a habit in that day's note, adding the line (and
+/// the note) when it is missing. Returns the daily note's path.
+pub fn toggle(date: NaiveDate, name: &str) -> io::Result<PathBuf> {
+    let path = vault::daily_note_path(date);
+    let text = std::fs::read_to_string(&path).unwrap_or_default();
+    let mut lines: Vec<String> = text.split('\n').map(String::from).collect();
+    for line in lines.iter_mut() {
+        if let Some((habit, done, at)) = habit(line)
+            && habit == name
+        {
+            let marker = line[at..].chars().next().map_or(1, char::len_utf8);
+            line.replace_range(at..at + marker, if done { " " } else { "x" });
+            crate::journal::write(&path, &lines.join("\n"))?;
+            return Ok(path);
+        }
+    }
+    crate::capture::append(&path, &format!("- [x] {} {}", name, TAG))?;
+    Ok(path)
+}
+
+/// A grid of every habit's last weeks, built from the daily notes.
+#[derive(Default)]
+pub struct HabitsView {
+    pub open: bool,
+    new_habit: String,
+    days: Option<(u64, Days)>,
+}
+
+impl HabitsView {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// Returns the daily note a click changed on disk.
+    pub fn show(&mut self, ctx: &egui::Context, list: &mut NoteList) -> Option<PathBuf> {
+        if !self.open {
+            return None;
+        }
+        let generation = list.generation;
+        if self.days.as_ref().is_none_or(|(g, _)| *g != generation) {
+            self.days = Some((generation, days(list.notes())));
+        }
+        let Some((_, days)) = &self.days else {
+            return None;
+        };
+        let names: BTreeSet<&String> = days.values().flat_map(|h| h.keys()).collect();
+        let today = Local::now().date_naive();
+        let start = today - chrono::Days::new(DAYS - 1);
+        let mut clicked = None;
+        let mut open = true;
+
+        egui::Window::new(t("✅ Habits")).open(&mut open).default_width(640.0).show(ctx, |ui| {
+            ui.label(RichText::new(t("Habits are task items tagged #habit in daily notes. Click a day to check it off.")).small().weak());
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.new_habit).hint_text(t("New habit")));
+                let name = self.new_habit.trim().to_owned();
+                if ui.add_enabled(!name.is_empty(), egui::Button::new(t("➕ Done today"))).clicked() {
+                    clicked = Some((today, name));
+                    self.new_habit.clear();
+                }
+            });
+            ui.separator();
+            if names.is_empty() {
+                ui.label(RichText::new(t("No habits yet")).weak());
+            }
+            egui::ScrollArea::both().show(ui, |ui| {
+                egui::Grid::new("habits").num_columns(3).spacing([8.0, 6.0]).show(ui, |ui| {
+                    for name in &names {
+                        ui.label(name.as_str());
+                        ui.horizontal(|ui| {
+                            ui.spacing_mut().item_spacing = egui::vec2(2.0, 2.0);
+                            for offset in 0..DAYS {
+                                let date = start + chrono::Days::new(offset);
+                                let state = days.get(&date).and_then(|h| h.get(*name)).copied();
+                                let (rect, response) = ui.allocate_exact_size(egui::vec2(10.0, 10.0), egui::Sense::click());
+                                let color = match state {
+                                    Some(true) => egui::Color32::from_rgb(64, 160, 80),
+                                    Some(false) => ui.visuals().faint_bg_color.gamma_multiply(2.0),
+                                    None => ui.visuals().extreme_bg_color,
+                                };
+                                ui.painter().rect_filled(rect, 2.0, color);
+                                if date == today {
+                                    ui.painter().rect_stroke(rect, 2.0, ui.visuals().selection.stroke);
+                                }
+                                if response.on_hover_text(date.format("%Y-%m-%d").to_string()).clicked() {
+                                    clicked = Some((date, (*name).clone()));
+                                }
+                            }
+                        });
+                        ui.label(format!("🔥 {}", streak(days, name, today)));
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+
+        if !open {
+            self.open = false;
+        }
+        let (date, name) = clicked?;
+        match toggle(date, &name) {
+            Ok(path) => {
+                list.invalidate();
+                Some(path)
+            }
+            Err(err) => {
+                error!("Failed to update habit: {}", err);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn habit_reads_checked_and_open_items() {
+        assert_eq!(habit("- [x] Exercise #habit"), Some(("Exercise".to_owned(), true, 3)));
+        assert_eq!(habit("- [ ] Read #habit daily"), Some(("Read daily".to_owned(), false, 3)));
+        assert_eq!(habit("- [x] Exercise"), None);
+    }
+
+    #[test]
+    fn habit_takes_multibyte_marks() {
+        assert_eq!(habit("- [✓] Stretch #habit"), Some(("Stretch".to_owned(), false, 3)));
+        assert_eq!(habit("- [✓]"), None);
+    }
+}
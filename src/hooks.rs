@@ -0,0 +1,68 @@
+use crate::config::SaveHook;
+use crate::jobs::Job;
+use std::process::Command;
+use std::time::Duration;
+
+/// How long one command may take before it is killed.
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Debug, Clone)]
+pub struct HookError {
+    pub command: String,
+    pub message: String,
+}
+
+fn shell(command: &str) -> Command {
+    if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    }
+}
+
+/// Pipes `text` through one external command and returns its stdout.
+fn run(command: &str, text: &str, job: &Job) -> Result<String, String> {
+    let output = crate::runner::pipe(&mut shell(command), text, TIMEOUT, job)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+        return Err(match (stderr.is_empty(), stdout.is_empty()) {
+            (false, _) => stderr,
+            (true, false) => stdout,
+            (true, true) => output.status.to_string(),
+        });
+    }
+    String::from_utf8(output.stdout).map_err(|_| "output was not valid UTF-8".to_owned())
+}
+
+/// Runs each configured command in order, feeding each the previous output.
+/// Report-only commands see the note but leave it alone. A failing
+/// command, or one that prints nothing, is skipped and reported; the rest
+/// still run.
+pub fn run_formatters(hooks: &[SaveHook], text: &str, job: &Job) -> (String, Vec<HookError>) {
+    let mut current = text.to_owned();
+    let mut errors = Vec::new();
+    for hook in hooks.iter().filter(|h| !h.command.trim().is_empty()) {
+        job.status(&hook.command);
+        let result = match run(&hook.command, &current, job) {
+            Ok(_) if hook.report_only => Ok(()),
+            Ok(formatted) if formatted.trim().is_empty() => Err("printed nothing; the note was left as it was".to_owned()),
+            Ok(formatted) => {
+                current = formatted;
+                Ok(())
+            }
+            Err(message) => Err(message),
+        };
+        if let Err(message) = result {
+            errors.push(HookError {
+                command: hook.command.clone(),
+                message,
+            });
+        }
+    }
+    (current, errors)
+}
@@ -0,0 +1,121 @@
+use crate::config::NetworkConfig;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use tracing::{error, warn};
+
+/// How long to wait for a connection, whatever the request timeout.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Wait before the first retry; it doubles with each one after.
+const FIRST_RETRY: Duration = Duration::from_millis(500);
+/// Attempts in a row that could not reach a server before the app shows
+/// itself as offline.
+const OFFLINE_AFTER: u32 = 3;
+
+/// The client every request goes through, so connections are reused, with
+/// the settings it was built from.
+struct Shared {
+    config: NetworkConfig,
+    client: Client,
+}
+
+static SHARED: Mutex<Option<Shared>> = Mutex::new(None);
+static FAILURES: AtomicU32 = AtomicU32::new(0);
+static LAST_ERROR: Mutex<String> = Mutex::new(String::new());
+
+fn build(config: &NetworkConfig) -> Client {
+    // Without a proxy set here, reqwest uses HTTP_PROXY, HTTPS_PROXY and
+    // NO_PROXY from the environment.
+    let mut builder = Client::builder().connect_timeout(CONNECT_TIMEOUT).timeout(Duration::from_secs(config.timeout_secs));
+    let proxy = config.proxy.trim();
+    if !proxy.is_empty() {
+        match reqwest::Proxy::all(proxy) {
+            Ok(proxy) => builder = builder.proxy(proxy),
+            Err(err) => warn!("Ignoring proxy {}: {}", proxy, err),
+        }
+    }
+    builder.build().unwrap_or_else(|err| {
+        error!("Failed to set up the HTTP client: {}", err);
+        Client::new()
+    })
+}
+
+/// Rebuilds the shared client when the network settings changed.
+pub fn configure(config: &NetworkConfig) {
+    let mut shared = SHARED.lock().unwrap_or_else(|e| e.into_inner());
+    if shared.as_ref().is_none_or(|s| s.config != *config) {
+        *shared = Some(Shared {
+            config: config.clone(),
+            client: build(config),
+        });
+    }
+}
+
+fn shared() -> (NetworkConfig, Client) {
+    let mut shared = SHARED.lock().unwrap_or_else(|e| e.into_inner());
+    let shared = shared.get_or_insert_with(|| {
+        let config = NetworkConfig::default();
+        Shared {
+            client: build(&config),
+            config,
+        }
+    });
+    (shared.config.clone(), shared.client.clone())
+}
+
+/// The shared client, with the proxy and timeout from the settings.
+pub fn client() -> Client {
+    shared().1
+}
+
+fn record(result: &reqwest::Result<Response>) {
+    match result {
+        Err(err) if err.is_connect() || err.is_timeout() => {
+            FAILURES.fetch_add(1, Ordering::Relaxed);
+            *LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner()) = err.to_string();
+        }
+        Err(_) => {}
+        Ok(_) => FAILURES.store(0, Ordering::Relaxed),
+    }
+}
+
+/// Whether sending `request` twice does no more than sending it once, so
+/// a POST that may have reached the server is never sent again.
+fn idempotent(request: &RequestBuilder) -> bool {
+    request.try_clone().and_then(|r| r.build().ok()).is_some_and(|r| r.method().is_idempotent())
+}
+
+/// Sends a request, keeping track of whether the network is reachable.
+/// Idempotent requests that cannot reach the server, time out or get a
+/// server error are retried, waiting twice as long each time.
+pub fn send(mut request: RequestBuilder) -> reqwest::Result<Response> {
+    let (config, _) = shared();
+    let retries = if idempotent(&request) { config.retries } else { 0 };
+    let mut delay = FIRST_RETRY;
+    let mut attempt = 0;
+    loop {
+        // Requests with a streamed body cannot be sent twice.
+        let retry = if attempt < retries { request.try_clone() } else { None };
+        let result = request.send();
+        record(&result);
+        let failed = match &result {
+            Ok(response) => response.status().is_server_error(),
+            Err(err) => err.is_connect() || err.is_timeout(),
+        };
+        match retry {
+            Some(next) if failed => {
+                std::thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+                request = next;
+            }
+            _ => return result,
+        }
+    }
+}
+
+/// Why requests keep failing, while they do.
+pub fn offline() -> Option<String> {
+    (FAILURES.load(Ordering::Relaxed) >= OFFLINE_AFTER).then(|| LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner()).clone())
+}
@@ -0,0 +1,184 @@
+use crate::vault::LOCALES_DIR;
+use std::collections::HashMap;
+use std::fmt::{Display, Write as _};
+use std::path::Path;
+use std::sync::RwLock;
+use tracing::{info, warn};
+
+/// Catalogs that ship with the app, by language code. More can be put in
+/// the locales folder as `<code>.po`, and those there win over these.
+const BUILT_IN: [(&str, &str); 1] = [("de", include_str!("../locales/de.po"))];
+
+/// Translations of the English strings shown in the UI; empty for English.
+static CATALOG: RwLock<Option<HashMap<String, String>>> = RwLock::new(None);
+
+/// The contents of a quoted `.po` string, escapes undone.
+fn unquote(text: &str) -> String {
+    let inner = text.trim().strip_prefix('"').and_then(|t| t.strip_suffix('"')).unwrap_or("");
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+/// The translations in a gettext `.po` file. Untranslated and fuzzy
+/// entries are left out so the English shows instead.
+pub fn parse_po(text: &str) -> HashMap<String, String> {
+    // Each entry as its id, translation and whether it is marked fuzzy.
+    let mut entries: Vec<(String, String, bool)> = Vec::new();
+    let mut fuzzy = false;
+    let mut in_translation = false;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with("#,") && line.contains("fuzzy") {
+            fuzzy = true;
+        } else if let Some(rest) = line.strip_prefix("msgid ") {
+            entries.push((unquote(rest), String::new(), fuzzy));
+            fuzzy = false;
+            in_translation = false;
+        } else if let Some(rest) = line.strip_prefix("msgstr ")
+            && let Some(entry) = entries.last_mut()
+        {
+            entry.1 = unquote(rest);
+            in_translation = true;
+        } else if line.starts_with('"')
+            && let Some(entry) = entries.last_mut()
+        {
+            // A string continued on the next line.
+            let part = if in_translation { &mut entry.1 } else { &mut entry.0 };
+            part.push_str(&unquote(line));
+        }
+    }
+    entries
+        .into_iter()
+        .filter(|(id, translation, fuzzy)| !id.is_empty() && !translation.is_empty() && !fuzzy)
+        .map(|(id, translation, _)| (id, translation))
+        .collect()
+}
+
+/// The language the system is set to, from the usual variables.
+fn system_language() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .map(|value| value.split(['_', '.', '-']).next().unwrap_or_default().to_ascii_lowercase())
+        .filter(|code| code != "c" && code != "posix")
+}
+
+/// Codes of the languages there are catalogs for, English first.
+pub fn languages() -> Vec<String> {
+    let mut codes: Vec<String> = BUILT_IN.iter().map(|(code, _)| code.to_string()).collect();
+    if let Ok(entries) = std::fs::read_dir(LOCALES_DIR) {
+        for path in entries.flatten().map(|e| e.path()) {
+            if path.extension().is_some_and(|e| e == "po")
+                && let Some(code) = path.file_stem().and_then(|s| s.to_str())
+                && !codes.iter().any(|c| c == code)
+            {
+                codes.push(code.to_owned());
+            }
+        }
+    }
+    codes.sort();
+    codes.insert(0, "en".to_owned());
+    codes
+}
+
+/// Switches the UI to `code`, or to the system's language when empty.
+/// Strings without a translation stay in English.
+pub fn set_language(code: &str) {
+    let code = if code.is_empty() { system_language().unwrap_or_default() } else { code.to_owned() };
+    let file = Path::new(LOCALES_DIR).join(format!("{}.po", code));
+    let text = match std::fs::read_to_string(&file) {
+        Ok(text) => Some(text),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            BUILT_IN.iter().find(|(c, _)| *c == code).map(|(_, text)| text.to_string())
+        }
+        Err(err) => {
+            warn!("Failed to read {}: {}", file.display(), err);
+            None
+        }
+    };
+    let catalog = text.map(|text| parse_po(&text));
+    if let Some(catalog) = &catalog {
+        info!("UI language {} with {} translated strings", code, catalog.len());
+    }
+    *CATALOG.write().unwrap_or_else(|e| e.into_inner()) = catalog;
+}
+
+/// `text` in the UI language.
+pub fn t(text: &str) -> String {
+    let catalog = CATALOG.read().unwrap_or_else(|e| e.into_inner());
+    catalog.as_ref().and_then(|c| c.get(text)).cloned().unwrap_or_else(|| text.to_owned())
+}
+
+/// `template` in the UI language with each `{}` filled in from `args`, in
+/// order; translations may move them around with `{0}`, `{1}` and so on.
+/// The text is read once, so braces in an argument are left as they are.
+pub fn tf(template: &str, args: &[&dyn Display]) -> String {
+    let text = t(template);
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text.as_str();
+    let mut next = 0;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let placeholder = after.find('}').map(|close| (close, &after[..close]));
+        let index = match placeholder {
+            Some((_, "")) => {
+                next += 1;
+                Some(next - 1)
+            }
+            Some((_, number)) => number.parse().ok(),
+            None => None,
+        };
+        match (placeholder, index.and_then(|i| args.get(i))) {
+            (Some((close, _)), Some(arg)) => {
+                let _ = write!(out, "{}", arg);
+                rest = &after[close + 1..];
+            }
+            _ => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Languages by their own names, for the picker.
+const NAMES: [(&str, &str); 15] = [
+    ("ar", "العربية"),
+    ("de", "Deutsch"),
+    ("en", "English"),
+    ("es", "Español"),
+    ("fr", "Français"),
+    ("he", "עברית"),
+    ("it", "Italiano"),
+    ("ja", "日本語"),
+    ("ko", "한국어"),
+    ("nl", "Nederlands"),
+    ("pl", "Polski"),
+    ("pt", "Português"),
+    ("ru", "Русский"),
+    ("uk", "Українська"),
+    ("zh", "中文"),
+];
+
+pub fn language_name(code: &str) -> String {
+    if code.is_empty() {
+        return t("System default");
+    }
+    NAMES.iter().find(|(c, _)| *c == code).map_or(code, |(_, name)| name).to_owned()
+}
@@ -0,0 +1,157 @@
+use std::ops::Range;
+
+/// Byte offset of the start of the line `at` is on.
+fn line_start(text: &str, at: usize) -> usize {
+    text[..at].rfind('\n').map_or(0, |n| n + 1)
+}
+
+/// Byte offset just past the line `at` is on, newline included.
+pub fn line_end(text: &str, at: usize) -> usize {
+    text[at..].find('\n').map_or(text.len(), |n| at + n + 1)
+}
+
+/// The whole lines `selection` touches. A selection ending at the start
+/// of a line leaves that line out.
+fn lines_of(text: &str, selection: &Range<usize>) -> Range<usize> {
+    let last = if selection.end > selection.start && text[..selection.end].ends_with('\n') { selection.end - 1 } else { selection.end };
+    line_start(text, selection.start)..line_end(text, last)
+}
+
+/// Width of a line's indentation, a tab counting as four spaces.
+pub fn indent_of(line: &str) -> usize {
+    line.chars().take_while(|c| *c == ' ' || *c == '\t').map(|c| if c == '\t' { 4 } else { 1 }).sum()
+}
+
+/// The column a list item's text starts at, past its `-`, `*`, `+`, `1.`
+/// or `1)` marker, or `None` when the line is not a list item.
+pub fn content_column(line: &str) -> Option<usize> {
+    let rest = line.trim_start_matches([' ', '\t']);
+    let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let marker = match rest.chars().next()? {
+        '-' | '*' | '+' => 1,
+        _ if (1..=9).contains(&digits) && rest[digits..].starts_with(['.', ')']) => digits + 1,
+        _ => return None,
+    };
+    let after = &rest[marker..];
+    let spaces = after.len() - after.trim_start_matches(' ').len();
+    (spaces > 0 || after.trim().is_empty()).then(|| indent_of(line) + marker + spaces.max(1))
+}
+
+/// Replaces each line in `lines` that `change` returns a new version of,
+/// and moves the selection along with the text around it. `None` when
+/// nothing changed.
+fn rewrite(text: &str, lines: Range<usize>, selection: &Range<usize>, change: impl Fn(&str) -> Option<String>) -> Option<(String, Range<usize>)> {
+    let mut region = String::with_capacity(lines.len() + 16);
+    let (mut start, mut end) = (None, None);
+    let mut offset = lines.start;
+    for line in text[lines.clone()].split_inclusive('\n') {
+        let new = change(line).unwrap_or_else(|| line.to_owned());
+        // Lines only change at their start, so a position on one keeps its
+        // distance from the line's end.
+        let map = |at: usize| {
+            (offset..offset + line.len())
+                .contains(&at)
+                .then(|| lines.start + region.len() + new.len().saturating_sub(offset + line.len() - at))
+        };
+        start = start.or_else(|| map(selection.start));
+        end = end.or_else(|| map(selection.end));
+        region.push_str(&new);
+        offset += line.len();
+    }
+    if region == text[lines.clone()] {
+        return None;
+    }
+    // Positions past the lines move with the text after them.
+    let moved = |at: usize| if at >= lines.end { at - lines.end + lines.start + region.len() } else { at };
+    let selection = start.unwrap_or_else(|| moved(selection.start))..end.unwrap_or_else(|| moved(selection.end));
+    Some((format!("{}{}{}", &text[..lines.start], region, &text[lines.end..]), selection))
+}
+
+/// Makes the headings in the selected lines one level smaller with
+/// `deeper`, or one level bigger, between `#` and `######`. Returns the
+/// new text and selection, or `None` when no line is a heading that can
+/// change.
+pub fn shift_headings(text: &str, selection: Range<usize>, deeper: bool) -> Option<(String, Range<usize>)> {
+    let lines = lines_of(text, &selection);
+    rewrite(text, lines, &selection, |line| {
+        let hashes = line.len() - line.trim_start_matches('#').len();
+        let rest = &line[hashes..];
+        if !(1..=6).contains(&hashes) || !(rest.starts_with(' ') || rest.trim().is_empty()) {
+            return None;
+        }
+        match (deeper, hashes) {
+            (true, 6) | (false, 1) => None,
+            (true, _) => Some(format!("#{}", line)),
+            (false, _) => Some(line[1..].to_owned()),
+        }
+    })
+}
+
+/// Indents the list items in the selection under the item before them,
+/// or with `outdent` out to the level of their parent, taking the lines
+/// below each item that belong to it along. Returns the new text and
+/// selection, or `None` when the selection is not on a list item or it
+/// cannot move that way.
+pub fn shift_list(text: &str, selection: Range<usize>, outdent: bool) -> Option<(String, Range<usize>)> {
+    let selected = lines_of(text, &selection);
+    let first = &text[selected.start..line_end(text, selected.start)];
+    content_column(first)?;
+    let indent = indent_of(first);
+
+    let end = lines_under(text, selected.end, indent);
+
+    let before = &text[..selected.start];
+    let mut earlier = before.split_inclusive('\n').rev().filter(|l| !l.trim().is_empty());
+    let target = if outdent {
+        // The parent is the nearest item above indented less.
+        earlier.find(|l| indent_of(l) < indent && content_column(l).is_some()).map(indent_of)?
+    } else {
+        // The sibling above, skipping its own children; the item goes
+        // under its text.
+        let sibling = earlier.find(|l| indent_of(l) <= indent)?;
+        if indent_of(sibling) != indent {
+            return None;
+        }
+        content_column(sibling)?
+    };
+    rewrite(text, selected.start..end, &selection, |line| {
+        if line.trim().is_empty() {
+            return None;
+        }
+        let width = (indent_of(line) + target).saturating_sub(indent);
+        Some(format!("{}{}", " ".repeat(width), line.trim_start_matches([' ', '\t'])))
+    })
+}
+
+/// Byte offset just past the last line belonging to the list item on the
+/// line `at` is on: the lines after it indented more, with the blank
+/// lines between them.
+pub fn subtree_end(text: &str, at: usize) -> usize {
+    let start = line_start(text, at);
+    let end = line_end(text, start);
+    lines_under(text, end, indent_of(&text[start..end]))
+}
+
+/// Byte offset just past the lines from `from` on that are indented more
+/// than `indent`, blank lines between them included.
+fn lines_under(text: &str, from: usize, indent: usize) -> usize {
+    let (mut end, mut at) = (from, from);
+    while at < text.len() {
+        let next = line_end(text, at);
+        let line = &text[at..next];
+        if !line.trim().is_empty() {
+            if indent_of(line) <= indent {
+                break;
+            }
+            end = next;
+        }
+        at = next;
+    }
+    end
+}
+
+/// Whether the cursor's line is a list item, for Tab to indent it.
+pub fn in_list(text: &str, at: usize) -> bool {
+    let start = line_start(text, at);
+    content_column(&text[start..line_end(text, start)]).is_some()
+}
@@ -0,0 +1,45 @@
+use eframe::egui;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver};
+
+const IPC_ADDR: &str = "127.0.0.1:47291";
+
+pub enum Instance {
+    Primary(TcpListener),
+    /// Another instance is running and has been handed our message.
+    Forwarded,
+    /// The port is taken by something else; run without single-instance support.
+    Standalone,
+}
+
+/// Becomes the primary instance by binding the IPC port, or forwards
+/// `message` to the instance that already holds it.
+pub fn acquire(message: Option<&str>) -> Instance {
+    match TcpListener::bind(IPC_ADDR) {
+        Ok(listener) => Instance::Primary(listener),
+        Err(_) => match TcpStream::connect(IPC_ADDR) {
+            Ok(mut stream) => {
+                let _ = writeln!(stream, "{}", message.unwrap_or(""));
+                Instance::Forwarded
+            }
+            Err(_) => Instance::Standalone,
+        },
+    }
+}
+
+/// Relays lines sent by other instances to the UI thread.
+pub fn listen(listener: TcpListener, ctx: egui::Context) -> Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            for line in BufReader::new(stream).lines().map_while(Result::ok) {
+                if tx.send(line).is_err() {
+                    return;
+                }
+                ctx.request_repaint();
+            }
+        }
+    });
+    rx
+}
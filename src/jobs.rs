@@ -0,0 +1,286 @@
+use crate::i18n::{t, tf};
+use eframe::egui::{self, RichText};
+use std::collections::VecDeque;
+use std::io;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::time::Instant;
+use tracing::error;
+
+/// Worker threads; jobs beyond this many wait in the queue.
+const MAX_WORKERS: usize = 4;
+
+#[derive(Clone, Debug, Default)]
+pub struct Progress {
+    pub done: usize,
+    /// Unknown until the job has counted its work.
+    pub total: Option<usize>,
+    /// What the job is doing right now, e.g. the file being read.
+    pub status: String,
+}
+
+/// A job as seen from inside: it reports progress here and checks whether
+/// it should stop.
+pub struct Job {
+    id: u64,
+    name: String,
+    started: Mutex<Option<Instant>>,
+    progress: Mutex<Progress>,
+    cancelled: AtomicBool,
+}
+
+impl Job {
+    pub fn progress(&self, done: usize, total: usize) {
+        let mut progress = self.progress.lock().unwrap_or_else(|e| e.into_inner());
+        progress.done = done;
+        progress.total = Some(total);
+        repaint();
+    }
+
+    pub fn status(&self, status: impl Into<String>) {
+        self.progress.lock().unwrap_or_else(|e| e.into_inner()).status = status.into();
+        repaint();
+    }
+
+    /// True once Cancel was pressed; long jobs check this between items
+    /// and stop early.
+    pub fn cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// An `Interrupted` error once Cancel was pressed, for jobs doing I/O
+    /// to stop with `?`.
+    pub fn check(&self) -> io::Result<()> {
+        if self.cancelled() {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+        }
+        Ok(())
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        repaint();
+    }
+
+    fn snapshot(&self) -> Progress {
+        self.progress.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// The caller's end of a job, receiving its result.
+pub struct JobHandle<T> {
+    job: Arc<Job>,
+    rx: Receiver<T>,
+}
+
+impl<T> JobHandle<T> {
+    /// The result once the job is done. `Disconnected` means it was
+    /// cancelled before it produced one.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.rx.try_recv()
+    }
+
+    /// Shows the job's progress with a Cancel button, for windows that
+    /// started it.
+    pub fn show_progress(&self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            progress_bar(ui, &self.job, 240.0);
+            cancel_button(ui, &self.job);
+        });
+    }
+}
+
+/// A bar with the item counts, or a spinner until the job knows its total.
+fn progress_bar(ui: &mut egui::Ui, job: &Job, width: f32) {
+    let progress = job.snapshot();
+    match progress.total {
+        Some(total) if total > 0 => {
+            let bar = egui::ProgressBar::new(progress.done as f32 / total as f32)
+                .desired_width(width)
+                .text(format!("{} / {}", progress.done, total));
+            let response = ui.add(bar);
+            if !progress.status.is_empty() {
+                response.on_hover_text(&progress.status);
+            }
+        }
+        _ if job.started.lock().unwrap_or_else(|e| e.into_inner()).is_some() => {
+            ui.spinner();
+        }
+        _ => {
+            ui.weak(t("Queued"));
+        }
+    }
+}
+
+fn cancel_button(ui: &mut egui::Ui, job: &Job) {
+    if job.cancelled() {
+        ui.weak(t("Cancelling…"));
+    } else if ui.small_button(t("Cancel")).clicked() {
+        job.cancel();
+    }
+}
+
+type Task = Box<dyn FnOnce() + Send>;
+
+/// The pool: queued tasks, jobs queued or running for the Jobs panel, and
+/// the context to repaint when one of them moves on.
+struct Scheduler {
+    queue: Mutex<VecDeque<Task>>,
+    wake: Condvar,
+    workers: Mutex<usize>,
+    jobs: Mutex<Vec<Arc<Job>>>,
+    ctx: OnceLock<egui::Context>,
+}
+
+fn scheduler() -> &'static Scheduler {
+    static SCHEDULER: OnceLock<Scheduler> = OnceLock::new();
+    SCHEDULER.get_or_init(|| Scheduler {
+        queue: Mutex::new(VecDeque::new()),
+        wake: Condvar::new(),
+        workers: Mutex::new(0),
+        jobs: Mutex::new(Vec::new()),
+        ctx: OnceLock::new(),
+    })
+}
+
+fn repaint() {
+    if let Some(ctx) = scheduler().ctx.get() {
+        ctx.request_repaint();
+    }
+}
+
+/// Lets finished jobs wake the UI.
+pub fn init(ctx: &egui::Context) {
+    let _ = scheduler().ctx.set(ctx.clone());
+}
+
+fn worker() {
+    let scheduler = scheduler();
+    loop {
+        let task = {
+            let mut queue = scheduler.queue.lock().unwrap_or_else(|e| e.into_inner());
+            loop {
+                match queue.pop_front() {
+                    Some(task) => break task,
+                    None => queue = scheduler.wake.wait(queue).unwrap_or_else(|e| e.into_inner()),
+                }
+            }
+        };
+        task();
+    }
+}
+
+/// Runs `work` on the pool. Its result arrives through the handle, and
+/// the UI is repainted when it does.
+pub fn spawn<T: Send + 'static>(name: impl Into<String>, work: impl FnOnce(&Job) -> T + Send + 'static) -> JobHandle<T> {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    let scheduler = scheduler();
+    let job = Arc::new(Job {
+        id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        name: name.into(),
+        started: Mutex::new(None),
+        progress: Mutex::new(Progress::default()),
+        cancelled: AtomicBool::new(false),
+    });
+    let (tx, rx) = mpsc::channel();
+    let running = job.clone();
+    let handle = JobHandle { job: job.clone(), rx };
+    let task: Task = Box::new(move || {
+        if !running.cancelled() {
+            *running.started.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+            // A job that panics must not take its worker down with it.
+            match std::panic::catch_unwind(AssertUnwindSafe(|| work(&running))) {
+                Ok(result) => {
+                    let _ = tx.send(result);
+                }
+                Err(_) => error!("Job failed: {}", running.name),
+            }
+        }
+        scheduler.jobs.lock().unwrap_or_else(|e| e.into_inner()).retain(|j| j.id != running.id);
+        repaint();
+    });
+
+    scheduler.jobs.lock().unwrap_or_else(|e| e.into_inner()).push(job);
+    scheduler.queue.lock().unwrap_or_else(|e| e.into_inner()).push_back(task);
+    let mut workers = scheduler.workers.lock().unwrap_or_else(|e| e.into_inner());
+    if *workers < MAX_WORKERS.min(std::thread::available_parallelism().map_or(2, |n| n.get())) {
+        *workers += 1;
+        std::thread::spawn(worker);
+    }
+    scheduler.wake.notify_one();
+    handle
+}
+
+/// Jobs that are queued or running.
+pub fn running() -> usize {
+    scheduler().jobs.lock().unwrap_or_else(|e| e.into_inner()).len()
+}
+
+/// The status bar entry: the oldest running job with its progress and a
+/// Cancel button, and how many more there are. Returns true when it was
+/// clicked to show them all.
+pub fn status_ui(ui: &mut egui::Ui) -> bool {
+    let jobs: Vec<Arc<Job>> = scheduler().jobs.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let Some(job) = jobs.first() else {
+        return false;
+    };
+    ui.separator();
+    let mut clicked = ui.small_button(format!("⏳ {}", job.name)).on_hover_text(t("Show jobs")).clicked();
+    progress_bar(ui, job, 160.0);
+    cancel_button(ui, job);
+    if jobs.len() > 1 {
+        clicked |= ui.small_button(tf("+{} more", &[&(jobs.len() - 1)])).clicked();
+    }
+    clicked
+}
+
+/// Lists queued and running jobs with their progress.
+#[derive(Default)]
+pub struct JobsView {
+    pub open: bool,
+}
+
+impl JobsView {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if !self.open {
+            return;
+        }
+        let jobs: Vec<Arc<Job>> = scheduler().jobs.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        egui::Window::new(t("⏳ Jobs")).open(&mut self.open).default_width(360.0).show(ctx, |ui| {
+            if jobs.is_empty() {
+                ui.weak(t("Nothing running."));
+            }
+            for job in jobs {
+                let progress = job.snapshot();
+                let started = *job.started.lock().unwrap_or_else(|e| e.into_inner());
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(&job.name).strong());
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        cancel_button(ui, &job);
+                        match started {
+                            Some(at) => ui.weak(format!("{}s", at.elapsed().as_secs())),
+                            None => ui.weak(t("Queued")),
+                        };
+                    });
+                });
+                if started.is_some() {
+                    progress_bar(ui, &job, ui.available_width());
+                }
+                if !progress.status.is_empty() {
+                    ui.label(RichText::new(&progress.status).small().weak());
+                }
+                ui.separator();
+            }
+        });
+        if running() > 0 {
+            // Keep elapsed times ticking.
+            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        }
+    }
+}
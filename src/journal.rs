@@ -0,0 +1,219 @@
+use crate::vault::{self, JOURNAL_DIR};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// One change to a note, as recorded in the journal.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Revision {
+    /// The revision id: its place in the journal, so later changes to any
+    /// note have larger ids.
+    pub seq: u64,
+    /// The note's vault key.
+    pub note: String,
+    /// SHA-1 of the content, which is stored under this name; empty when
+    /// the note was deleted or renamed away.
+    pub hash: String,
+    /// Hash of the content this revision replaced.
+    #[serde(default)]
+    pub parent: Option<String>,
+    pub time: String,
+    /// The key the note had before a rename.
+    #[serde(default)]
+    pub renamed_from: Option<String>,
+}
+
+/// The newest revision of each note and the next id, read from the log
+/// once and kept up to date as revisions are added.
+struct Heads {
+    next: u64,
+    latest: HashMap<String, Revision>,
+}
+
+static HEADS: Mutex<Option<Heads>> = Mutex::new(None);
+
+fn log_path() -> PathBuf {
+    Path::new(JOURNAL_DIR).join("log.jsonl")
+}
+
+fn object_path(hash: &str) -> PathBuf {
+    Path::new(JOURNAL_DIR).join("objects").join(hash)
+}
+
+pub fn hash(content: &str) -> String {
+    Sha1::digest(content).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Every revision in the journal, oldest first.
+fn read_log() -> Vec<Revision> {
+    let Ok(file) = std::fs::File::open(log_path()) else {
+        return Vec::new();
+    };
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect()
+}
+
+fn load_heads() -> Heads {
+    let mut heads = Heads {
+        next: 1,
+        latest: HashMap::new(),
+    };
+    for revision in read_log() {
+        heads.next = heads.next.max(revision.seq + 1);
+        heads.latest.insert(revision.note.clone(), revision);
+    }
+    heads
+}
+
+/// Appends a revision for `note` unless its content is unchanged.
+fn add(note: &str, content: Option<&str>, renamed_from: Option<String>) -> io::Result<()> {
+    let mut guard = HEADS.lock().unwrap_or_else(|e| e.into_inner());
+    let heads = guard.get_or_insert_with(load_heads);
+    let hash = content.map(hash).unwrap_or_default();
+    let parent = heads.latest.get(note).map(|r| r.hash.clone()).filter(|h| !h.is_empty());
+    if renamed_from.is_none() && parent.as_deref().unwrap_or_default() == hash {
+        return Ok(());
+    }
+    if let Some(content) = content {
+        let object = object_path(&hash);
+        if !object.exists() {
+            std::fs::create_dir_all(object.parent().unwrap_or(Path::new(JOURNAL_DIR)))?;
+            std::fs::write(object, content)?;
+        }
+    }
+    let revision = Revision {
+        seq: heads.next,
+        note: note.to_owned(),
+        hash,
+        parent,
+        time: chrono::Local::now().to_rfc3339(),
+        renamed_from,
+    };
+    std::fs::create_dir_all(JOURNAL_DIR)?;
+    let mut log = std::fs::OpenOptions::new().create(true).append(true).open(log_path())?;
+    writeln!(log, "{}", serde_json::to_string(&revision)?)?;
+    heads.next += 1;
+    heads.latest.insert(revision.note.clone(), revision);
+    Ok(())
+}
+
+/// Forgets the heads read from the log, so they are read again after the
+/// log was replaced, e.g. by importing a vault.
+pub fn reload() {
+    *HEADS.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// The vault key of `path` when it is a note the journal tracks.
+fn tracked(path: &Path) -> Option<String> {
+    let key = vault::note_key(path);
+    crate::server::note_path(&key).filter(|p| vault::is_note(p)).map(|_| key)
+}
+
+/// Writes a note and records the change. Every write to a note goes
+/// through here (or [`record`]), so the journal sees all of them.
+pub fn write(path: &Path, content: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)?;
+    record(path)
+}
+
+/// Writes a new note, failing with `AlreadyExists` rather than replacing
+/// a file that is already there.
+pub fn create(path: &Path, content: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new().write(true).create_new(true).open(path)?;
+    file.write_all(content.as_bytes())?;
+    record(path)
+}
+
+/// Writes a note as `bytes` in an encoding other than UTF-8, recording
+/// `content`, the text they hold.
+pub fn write_encoded(path: &Path, bytes: &[u8], content: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, bytes)?;
+    match tracked(path) {
+        Some(key) => add(&key, Some(content), None),
+        None => Ok(()),
+    }
+}
+
+/// Records the current content of a note written some other way, such
+/// as by appending.
+pub fn record(path: &Path) -> io::Result<()> {
+    let Some(key) = tracked(path) else {
+        return Ok(());
+    };
+    let content = std::fs::read_to_string(path)?;
+    add(&key, Some(&content), None)
+}
+
+/// Deletes a note and records it.
+pub fn remove(path: &Path) -> io::Result<()> {
+    let key = tracked(path);
+    std::fs::remove_file(path)?;
+    match key {
+        Some(key) => add(&key, None, None),
+        None => Ok(()),
+    }
+}
+
+/// Moves a note and records the rename as a revision under the new key.
+pub fn rename(from: &Path, to: &Path) -> io::Result<()> {
+    let old = tracked(from);
+    std::fs::rename(from, to)?;
+    let Some(key) = tracked(to) else {
+        return Ok(());
+    };
+    let content = std::fs::read_to_string(to)?;
+    if let Some(old) = &old {
+        add(old, None, None)?;
+    }
+    add(&key, Some(&content), old)
+}
+
+/// The id of the newest revision; 0 for an empty journal.
+pub fn head() -> u64 {
+    let mut guard = HEADS.lock().unwrap_or_else(|e| e.into_inner());
+    guard.get_or_insert_with(load_heads).next - 1
+}
+
+/// Revisions newer than `seq`, oldest first: what changed since then.
+pub fn since(seq: u64) -> Vec<Revision> {
+    read_log().into_iter().filter(|r| r.seq > seq).collect()
+}
+
+/// A note's revisions, oldest first, following it back through renames.
+pub fn revisions(note: &Path) -> Vec<Revision> {
+    // Keys the note had, each with the revision it was renamed away at.
+    let mut keys = vec![(vault::note_key(note), u64::MAX)];
+    let mut found: Vec<Revision> = Vec::new();
+    for revision in read_log().into_iter().rev() {
+        let ours = keys.iter().any(|(key, until)| *key == revision.note && revision.seq < *until);
+        if !ours || revision.hash.is_empty() {
+            continue;
+        }
+        if let Some(from) = &revision.renamed_from {
+            keys.push((from.clone(), revision.seq));
+        }
+        found.push(revision);
+    }
+    found.reverse();
+    found
+}
+
+/// The content a revision recorded.
+pub fn content(revision: &Revision) -> io::Result<String> {
+    std::fs::read_to_string(object_path(&revision.hash))
+}
@@ -0,0 +1,283 @@
+use crate::i18n::t;
+use crate::sidebar::{NoteInfo, NoteList, title};
+use crate::{frontmatter, vault};
+use eframe::egui::{self, RichText};
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing::error;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Column {
+    Todo,
+    Doing,
+    Done,
+}
+
+impl Column {
+    pub const ALL: [Column; 3] = [Column::Todo, Column::Doing, Column::Done];
+
+    pub fn label(self) -> String {
+        match self {
+            Column::Todo => t("Todo"),
+            Column::Doing => t("Doing"),
+            Column::Done => t("Done"),
+        }
+    }
+
+    /// Checkbox marker for tasks: `[ ]`, `[/]` or `[x]`.
+    fn marker(self) -> char {
+        match self {
+            Column::Todo => ' ',
+            Column::Doing => '/',
+            Column::Done => 'x',
+        }
+    }
+
+    fn from_marker(marker: char) -> Option<Self> {
+        match marker {
+            ' ' => Some(Column::Todo),
+            '/' | '-' => Some(Column::Doing),
+            'x' | 'X' => Some(Column::Done),
+            _ => None,
+        }
+    }
+
+    /// Value of a note's `status:` front matter field.
+    fn status(self) -> &'static str {
+        match self {
+            Column::Todo => "todo",
+            Column::Doing => "doing",
+            Column::Done => "done",
+        }
+    }
+
+    fn from_status(status: &str) -> Option<Self> {
+        match status.to_lowercase().as_str() {
+            "todo" | "to do" | "open" => Some(Column::Todo),
+            "doing" | "in progress" | "in-progress" | "wip" => Some(Column::Doing),
+            "done" | "closed" => Some(Column::Done),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Source {
+    /// A `- [ ] task` on a line of the note.
+    Task { line: usize },
+    /// The note itself, by its `status:` field.
+    Note,
+}
+
+#[derive(Clone, Debug)]
+pub struct Card {
+    pub note: PathBuf,
+    pub source: Source,
+    pub text: String,
+    pub column: Column,
+}
+
+/// Byte offset of the checkbox marker if `line` is a task list item.
+pub fn marker_offset(line: &str) -> Option<usize> {
+    let indent = line.len() - line.trim_start().len();
+    let rest = &line[indent..];
+    let bullet = if rest.starts_with(['-', '*', '+']) {
+        1
+    } else {
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits == 0 || !rest[digits..].starts_with(['.', ')']) {
+            return None;
+        }
+        digits + 1
+    };
+    let after = &rest[bullet..];
+    let box_start = indent + bullet + (after.len() - after.trim_start().len());
+    let boxed = line.get(box_start..)?;
+    let mut chars = boxed.chars();
+    let (open, mark, close) = (chars.next(), chars.next(), chars.next());
+    (open == Some('[') && mark.is_some() && close == Some(']') && chars.as_str().starts_with(' ')).then_some(box_start + 1)
+}
+
+/// Task cards from a note's checkbox lists, outside code blocks.
+pub fn task_cards(note: &Path, text: &str) -> Vec<Card> {
+    let fences = crate::markdown::fenced_blocks(text);
+    let mut cards = Vec::new();
+    let mut offset = 0;
+    for (index, line) in text.lines().enumerate() {
+        let start = offset;
+        offset += line.len() + 1;
+        if fences.iter().any(|f| f.range.contains(&start)) {
+            continue;
+        }
+        let Some(at) = marker_offset(line) else {
+            continue;
+        };
+        let mark = line[at..].chars().next().unwrap_or(' ');
+        let Some(column) = Column::from_marker(mark) else {
+            continue;
+        };
+        cards.push(Card {
+            note: note.to_path_buf(),
+            source: Source::Task { line: index },
+            text: line[at + mark.len_utf8() + 1..].trim().to_owned(),
+            column,
+        });
+    }
+    cards
+}
+
+/// Every card in the vault, outside the archive.
+pub fn cards(notes: &[NoteInfo]) -> Vec<Card> {
+    let mut cards = Vec::new();
+    for note in notes.iter().filter(|n| !vault::is_archived(&n.path)) {
+        if let Some(column) = frontmatter::get(&note.text, "status").and_then(|s| Column::from_status(&s)) {
+            cards.push(Card {
+                note: note.path.clone(),
+                source: Source::Note,
+                text: title(&note.path),
+                column,
+            });
+        }
+        cards.extend(task_cards(&note.path, &note.text));
+    }
+    cards
+}
+
+/// Writes a card's new column back to its note.
+pub fn move_card(card: &Card, to: Column) -> io::Result<()> {
+    let text = std::fs::read_to_string(&card.note)?;
+    let updated = match card.source {
+        Source::Note => frontmatter::set(&text, "status", Some(to.status())),
+        Source::Task { line } => {
+            let still_there = task_cards(&card.note, &text)
+                .iter()
+                .any(|c| matches!(c.source, Source::Task { line: l } if l == line) && c.text == card.text);
+            if !still_there {
+                return Err(io::Error::other("the note changed since the board was loaded"));
+            }
+            let mut lines: Vec<String> = text.split('\n').map(String::from).collect();
+            let at = marker_offset(&lines[line]).expect("task line");
+            let marker = lines[line][at..].chars().next().map_or(1, char::len_utf8);
+            lines[line].replace_range(at..at + marker, &to.marker().to_string());
+            lines.join("\n")
+        }
+    };
+    crate::journal::write(&card.note, &updated)
+}
+
+/// What the app should do after the user acted on the board.
+pub enum BoardEvent {
+    Open(PathBuf),
+    /// A card was moved, changing this note on disk.
+    Changed(PathBuf),
+}
+
+/// Todo/Doing/Done columns of every task and every note with a status.
+#[derive(Default)]
+pub struct Board {
+    pub open: bool,
+    /// Cards with the note list generation they were read from.
+    cards: Option<(u64, Vec<Card>)>,
+}
+
+impl Board {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, list: &mut NoteList) -> Option<BoardEvent> {
+        if !self.open {
+            return None;
+        }
+        let notes = list.notes().to_vec();
+        if self.cards.as_ref().is_none_or(|(generation, _)| *generation != list.generation) {
+            self.cards = Some((list.generation, cards(&notes)));
+        }
+        let cards = self.cards.as_ref().map(|(_, c)| c.as_slice()).unwrap_or_default();
+        let mut event = None;
+        let mut dropped = None;
+        let mut open = true;
+
+        egui::Window::new(t("📋 Board"))
+            .open(&mut open)
+            .default_width(780.0)
+            .show(ctx, |ui| {
+                ui.label(RichText::new(t("Drag cards between columns. Tasks use - [ ], - [/] and - [x]; notes use status: in their front matter.")).small().weak());
+                ui.columns(3, |cols| {
+                    for (col, column) in cols.iter_mut().zip(Column::ALL) {
+                        let count = cards.iter().filter(|c| c.column == column).count();
+                        col.label(RichText::new(format!("{} ({})", column.label(), count)).strong());
+                        let frame = egui::Frame::group(col.style()).inner_margin(4.0);
+                        let (_, payload) = col.dnd_drop_zone::<usize, _>(frame, |ui| {
+                            ui.set_min_size(egui::vec2(ui.available_width(), 120.0));
+                            egui::ScrollArea::vertical().id_source(column.label()).max_height(480.0).show(ui, |ui| {
+                                for (index, card) in cards.iter().enumerate().filter(|(_, c)| c.column == column) {
+                                    let id = egui::Id::new(("card", index));
+                                    ui.dnd_drag_source(id, index, |ui| {
+                                        egui::Frame::group(ui.style()).show(ui, |ui| {
+                                            ui.set_width(ui.available_width());
+                                            let text = match card.source {
+                                                Source::Note => RichText::new(&card.text).strong(),
+                                                Source::Task { .. } => RichText::new(&card.text),
+                                            };
+                                            ui.add(egui::Label::new(text).wrap(true));
+                                            if ui.link(RichText::new(title(&card.note)).small()).clicked() {
+                                                event = Some(BoardEvent::Open(card.note.clone()));
+                                            }
+                                        });
+                                    });
+                                }
+                            });
+                        });
+                        if let Some(index) = payload {
+                            dropped = Some((*index, column));
+                        }
+                    }
+                });
+            });
+
+        if let Some((index, column)) = dropped
+            && let Some(card) = cards.get(index)
+            && card.column != column
+        {
+            match move_card(card, column) {
+                Ok(()) => {
+                    event = Some(BoardEvent::Changed(card.note.clone()));
+                    list.invalidate();
+                }
+                Err(err) => error!("Failed to move card: {}", err),
+            }
+        }
+        if !open {
+            self.open = false;
+        }
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marker_offset_finds_the_mark() {
+        assert_eq!(marker_offset("- [ ] todo"), Some(3));
+        assert_eq!(marker_offset("  1. [x] done"), Some(6));
+        assert_eq!(marker_offset("- [x]done"), None);
+        assert_eq!(marker_offset("- not a task"), None);
+    }
+
+    #[test]
+    fn marker_offset_takes_multibyte_marks() {
+        assert_eq!(marker_offset("- [✓] done"), Some(3));
+        assert_eq!(marker_offset("- [✓]"), None);
+        assert_eq!(marker_offset("- [é"), None);
+    }
+
+    #[test]
+    fn task_cards_skip_unknown_multibyte_marks() {
+        let cards = task_cards(Path::new("notes/a.md"), "- [✓] done\n- [ ] todo\n");
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].text, "todo");
+    }
+}
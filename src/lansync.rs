@@ -0,0 +1,913 @@
+use crate::config::LanSyncConfig;
+use crate::i18n::{t, tf};
+use crate::journal;
+use crate::vault::{self, LAN_SYNC_FILE};
+use eframe::egui::{self, RichText};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError};
+use std::time::{Duration, Instant};
+use tracing::error;
+
+/// Multicast group and port instances announce themselves on.
+const GROUP: Ipv4Addr = Ipv4Addr::new(239, 255, 78, 65);
+const DISCOVERY_PORT: u16 = 7881;
+const ANNOUNCE_EVERY: Duration = Duration::from_secs(3);
+/// Devices not heard from for this long drop off the list.
+const FORGET_AFTER: Duration = Duration::from_secs(15);
+/// How long a pairing code shown on this device can be used.
+const PAIRING_VALID: Duration = Duration::from_secs(300);
+/// Status lines kept in the window.
+const LOG_LINES: usize = 8;
+
+/// How many edits each device has made to a note, by device id.
+type Clock = BTreeMap<String, u64>;
+
+#[derive(Debug, PartialEq)]
+enum Order {
+    Same,
+    Before,
+    After,
+    /// Each side has edits the other has not seen.
+    Concurrent,
+}
+
+fn compare(a: &Clock, b: &Clock) -> Order {
+    let (mut less, mut more) = (false, false);
+    for device in a.keys().chain(b.keys()) {
+        let (x, y) = (a.get(device).copied().unwrap_or(0), b.get(device).copied().unwrap_or(0));
+        less |= x < y;
+        more |= x > y;
+    }
+    match (less, more) {
+        (false, false) => Order::Same,
+        (true, false) => Order::Before,
+        (false, true) => Order::After,
+        (true, true) => Order::Concurrent,
+    }
+}
+
+fn merge(into: &mut Clock, other: &Clock) {
+    for (device, count) in other {
+        let entry = into.entry(device.clone()).or_default();
+        *entry = (*entry).max(*count);
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// HMAC-SHA1 over both sides' nonces, so each side proves it knows the
+/// shared secret without sending it.
+fn proof(secret: &str, role: &str, nonces: (&str, &str)) -> String {
+    let mut key = [0u8; 64];
+    if secret.len() > key.len() {
+        key[..20].copy_from_slice(&Sha1::digest(secret));
+    } else {
+        key[..secret.len()].copy_from_slice(secret.as_bytes());
+    }
+    let pad = |byte: u8| key.iter().map(|k| k ^ byte).collect::<Vec<u8>>();
+    let mut inner = Sha1::new();
+    inner.update(pad(0x36));
+    inner.update(format!("{}\n{}\n{}", role, nonces.0, nonces.1));
+    let mut outer = Sha1::new();
+    outer.update(pad(0x5c));
+    outer.update(inner.finalize());
+    hex(&outer.finalize())
+}
+
+/// Compares without stopping at the first difference.
+fn same(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Letters and digits a pairing code is made of, leaving out the ones that
+/// are easily mistaken for each other.
+const CODE_ALPHABET: &[u8] = b"23456789ABCDEFGHJKLMNPQRSTUVWXYZ";
+/// Characters in a pairing code: 80 bits. Anyone who overhears a pairing
+/// can check guesses at the code against it offline, so it has to be far
+/// too long to guess, unlike a PIN.
+const CODE_LEN: usize = 16;
+
+/// A new pairing code, in groups of four for typing.
+fn pairing_code() -> String {
+    let bits = u128::from_str_radix(&crate::server::generate_token(), 16).unwrap_or(0);
+    let chars: Vec<char> = (0..CODE_LEN).map(|i| CODE_ALPHABET[(bits >> (i * 5)) as usize & 31] as char).collect();
+    chars.chunks(4).map(|group| group.iter().collect::<String>()).collect::<Vec<_>>().join("-")
+}
+
+/// A code as it is compared: without dashes or spaces, in upper case.
+fn normalize_code(code: &str) -> String {
+    code.chars().filter(char::is_ascii_alphanumeric).map(|c| c.to_ascii_uppercase()).collect()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+struct NoteState {
+    clock: Clock,
+    /// Content hash when the clock was last moved.
+    hash: String,
+    deleted: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct Peer {
+    name: String,
+    /// Secret agreed on while pairing.
+    key: String,
+    /// The change journal's head at the last sync.
+    #[serde(default)]
+    synced: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+struct SyncState {
+    device_id: String,
+    peers: BTreeMap<String, Peer>,
+    notes: BTreeMap<String, NoteState>,
+}
+
+impl SyncState {
+    fn load() -> Self {
+        let mut state: Self = std::fs::read_to_string(LAN_SYNC_FILE)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        if state.device_id.is_empty() {
+            state.device_id = crate::server::generate_token();
+        }
+        state
+    }
+
+    fn save(&self) -> io::Result<()> {
+        std::fs::write(LAN_SYNC_FILE, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Counts an edit by this device for every note changed, added or
+    /// deleted since the last look.
+    fn scan(&mut self) {
+        let mut seen = HashSet::new();
+        for path in vault::note_files() {
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let key = vault::note_key(&path);
+            let hash = journal::hash(&content);
+            let note = self.notes.entry(key.clone()).or_default();
+            if note.deleted || note.hash != hash {
+                *note.clock.entry(self.device_id.clone()).or_default() += 1;
+                note.hash = hash;
+                note.deleted = false;
+            }
+            seen.insert(key);
+        }
+        for (key, note) in &mut self.notes {
+            if !note.deleted && !seen.contains(key) {
+                *note.clock.entry(self.device_id.clone()).or_default() += 1;
+                note.hash.clear();
+                note.deleted = true;
+            }
+        }
+    }
+}
+
+/// What devices send each other, one JSON object per line.
+#[derive(Serialize, Deserialize)]
+enum Message {
+    Hello { device_id: String, name: String, nonce: String },
+    Proof(String),
+    Rejected(String),
+    Index(BTreeMap<String, Clock>),
+    /// A note's text, or `None` when it was deleted.
+    Note { key: String, clock: Clock, content: Option<String> },
+    Done,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Beacon {
+    device_id: String,
+    name: String,
+    port: u16,
+}
+
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_read_timeout(Some(Duration::from_secs(30)))?;
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        })
+    }
+
+    fn send(&mut self, message: &Message) -> io::Result<()> {
+        writeln!(self.writer, "{}", serde_json::to_string(message)?)
+    }
+
+    fn receive(&mut self) -> io::Result<Message> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the other device hung up"));
+        }
+        Ok(serde_json::from_str(&line)?)
+    }
+}
+
+fn unexpected() -> io::Error {
+    io::Error::other("unexpected message from the other device")
+}
+
+/// The outcome of one sync with another device.
+pub struct SyncReport {
+    pub peer: String,
+    /// Notes written or deleted here.
+    pub received: Vec<PathBuf>,
+    pub sent: usize,
+    /// Conflict copies saved next to notes changed on both devices.
+    pub conflicts: Vec<PathBuf>,
+}
+
+impl SyncReport {
+    pub fn summary(&self) -> String {
+        if self.conflicts.is_empty() {
+            tf("LAN sync with {}: {} received, {} sent", &[&self.peer, &self.received.len(), &self.sent])
+        } else {
+            tf(
+                "LAN sync with {}: {} received, {} sent, {} conflict copies",
+                &[&self.peer, &self.received.len(), &self.sent, &self.conflicts.len()],
+            )
+        }
+    }
+}
+
+/// Who the other side of a session is.
+struct Other {
+    id: String,
+    name: String,
+}
+
+/// Applies a note the other device sent. When both changed it, both keep
+/// the version of the device with the larger id and save the other as a
+/// conflict copy, so they end up with the same notes.
+fn receive_note(state: &mut SyncState, own_name: &str, other: &Other, key: &str, clock: Clock, content: Option<String>, report: &mut SyncReport) -> io::Result<()> {
+    let path = crate::server::note_path(key)
+        .filter(|p| vault::is_note(p))
+        .ok_or_else(|| io::Error::other(format!("refusing to write {}", key)))?;
+    let local = state.notes.entry(key.to_owned()).or_default();
+    let remote_wins = match compare(&clock, &local.clock) {
+        Order::Same | Order::Before => return Ok(()),
+        Order::After => {
+            local.clock = clock;
+            true
+        }
+        Order::Concurrent => {
+            merge(&mut local.clock, &clock);
+            let local_content = if local.deleted { None } else { std::fs::read_to_string(&path).ok() };
+            if local_content == content {
+                return Ok(());
+            }
+            let remote_wins = other.id > state.device_id;
+            let (loser, loser_name) = if remote_wins { (local_content, other.name.as_str()) } else { (content.clone(), own_name) };
+            if let Some(loser) = loser {
+                report.conflicts.push(save_conflict(&path, loser_name, &loser)?);
+            }
+            remote_wins
+        }
+    };
+    if remote_wins {
+        match &content {
+            Some(content) => journal::write(&path, content)?,
+            None if path.exists() => journal::remove(&path)?,
+            None => {}
+        }
+        let local = state.notes.entry(key.to_owned()).or_default();
+        local.hash = content.as_deref().map(journal::hash).unwrap_or_default();
+        local.deleted = content.is_none();
+        report.received.push(path);
+    }
+    Ok(())
+}
+
+/// Writes `content` next to `path` as `<name> (conflict <device> <date>)`.
+fn save_conflict(path: &Path, device: &str, content: &str) -> io::Result<PathBuf> {
+    let dir = path.parent().unwrap_or(Path::new(vault::NOTES_DIR));
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("Note");
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("md");
+    let name = format!("{} (conflict {} {})", stem, vault::safe_file_name(device), chrono::Local::now().format("%Y-%m-%d"));
+    let plain = dir.join(format!("{}.{}", name, ext));
+    // The other device may have sent this copy already.
+    if std::fs::read_to_string(&plain).is_ok_and(|c| c == content) {
+        return Ok(plain);
+    }
+    let copy = vault::unique_path(dir, &name, ext);
+    journal::write(&copy, content)?;
+    Ok(copy)
+}
+
+/// Everything the threads of a sync share.
+struct Shared {
+    name: String,
+    state: Mutex<SyncState>,
+    /// Code shown here for another device to pair with, until it expires.
+    pairing: Mutex<Option<(String, Instant)>>,
+    devices: Mutex<HashMap<String, Device>>,
+}
+
+#[derive(Clone)]
+struct Device {
+    name: String,
+    address: SocketAddr,
+    seen: Instant,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The lock, unless a session holds it.
+fn try_lock<T>(mutex: &Mutex<T>) -> Option<MutexGuard<'_, T>> {
+    match mutex.try_lock() {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(err)) => Some(err.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
+impl Shared {
+    /// Runs one session; `code` is set when pairing from this side. The
+    /// state is only locked once the other side has proved who it is, and
+    /// is saved even when the session fails partway.
+    fn session(&self, stream: TcpStream, initiator: bool, code: Option<String>) -> io::Result<SyncReport> {
+        let mut conn = Connection::new(stream)?;
+        let (other, key) = self.handshake(&mut conn, initiator, code)?;
+        let mut state = lock(&self.state);
+        let result = self.exchange(&mut state, conn, initiator, other, key);
+        state.save()?;
+        result
+    }
+
+    /// Both sides prove they know a shared secret, the key kept from an
+    /// earlier pairing or the code shown for pairing now, without sending
+    /// it. Returns the other device and the key to keep for it.
+    fn handshake(&self, conn: &mut Connection, initiator: bool, code: Option<String>) -> io::Result<(Other, String)> {
+        let nonce = crate::server::generate_token();
+        let hello = Message::Hello {
+            device_id: lock(&self.state).device_id.clone(),
+            name: self.name.clone(),
+            nonce: nonce.clone(),
+        };
+        if initiator {
+            conn.send(&hello)?;
+        }
+        let Message::Hello { device_id, name, nonce: other_nonce } = conn.receive()? else {
+            return Err(unexpected());
+        };
+        if !initiator {
+            conn.send(&hello)?;
+        }
+        let other = Other { id: device_id, name };
+        let nonces = if initiator { (nonce.as_str(), other_nonce.as_str()) } else { (other_nonce.as_str(), nonce.as_str()) };
+        let paired = lock(&self.state).peers.get(&other.id).map(|p| p.key.clone());
+
+        let secret = if initiator {
+            let secret = code.or(paired.clone()).ok_or_else(|| io::Error::other(format!("not paired with {}", other.name)))?;
+            conn.send(&Message::Proof(proof(&secret, "initiator", nonces)))?;
+            match conn.receive()? {
+                Message::Proof(p) if same(&p, &proof(&secret, "responder", nonces)) => {}
+                Message::Rejected(reason) => return Err(io::Error::other(reason)),
+                _ => return Err(io::Error::other(format!("{} could not prove it is paired", other.name))),
+            }
+            secret
+        } else {
+            let Message::Proof(p) = conn.receive()? else {
+                return Err(unexpected());
+            };
+            // A pairing code is good for one attempt, right or wrong.
+            let code = lock(&self.pairing).take().filter(|(_, shown)| shown.elapsed() < PAIRING_VALID).map(|(code, _)| normalize_code(&code));
+            let Some(secret) = paired.clone().into_iter().chain(code).find(|s| same(&p, &proof(s, "initiator", nonces))) else {
+                conn.send(&Message::Rejected(format!("{} does not recognise this device or code", self.name)))?;
+                return Err(io::Error::other(format!("rejected {}: wrong pairing code", other.name)));
+            };
+            conn.send(&Message::Proof(proof(&secret, "responder", nonces)))?;
+            secret
+        };
+        let key = if paired.as_ref() == Some(&secret) { secret } else { proof(&secret, "key", nonces) };
+        Ok((other, key))
+    }
+
+    /// Swaps the notes either side has not seen, once the handshake is done.
+    fn exchange(&self, state: &mut SyncState, mut conn: Connection, initiator: bool, other: Other, key: String) -> io::Result<SyncReport> {
+        let synced = state.peers.get(&other.id).map_or(0, |p| p.synced);
+        state.peers.insert(other.id.clone(), Peer { name: other.name.clone(), key, synced });
+
+        state.scan();
+        let index: BTreeMap<String, Clock> = state.notes.iter().map(|(k, n)| (k.clone(), n.clock.clone())).collect();
+        let other_index = if initiator {
+            conn.send(&Message::Index(index))?;
+            let Message::Index(other_index) = conn.receive()? else {
+                return Err(unexpected());
+            };
+            other_index
+        } else {
+            let Message::Index(other_index) = conn.receive()? else {
+                return Err(unexpected());
+            };
+            conn.send(&Message::Index(index))?;
+            other_index
+        };
+        // Notes with edits the other device has not seen, worked out
+        // before taking any of its notes.
+        let outgoing: Vec<String> = state
+            .notes
+            .iter()
+            .filter(|(key, note)| match other_index.get(*key) {
+                Some(clock) => matches!(compare(&note.clock, clock), Order::After | Order::Concurrent),
+                None => !note.deleted,
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        let mut report = SyncReport {
+            peer: other.name.clone(),
+            received: Vec::new(),
+            sent: 0,
+            conflicts: Vec::new(),
+        };
+        // The responder sends first, so the two never both wait on a full
+        // socket buffer.
+        if !initiator {
+            report.sent = self.send_notes(state, &mut conn, &outgoing)?;
+        }
+        loop {
+            match conn.receive()? {
+                Message::Note { key, clock, content } => receive_note(state, &self.name, &other, &key, clock, content, &mut report)?,
+                Message::Done => break,
+                _ => return Err(unexpected()),
+            }
+        }
+        if initiator {
+            report.sent = self.send_notes(state, &mut conn, &outgoing)?;
+        }
+        if let Some(peer) = state.peers.get_mut(&other.id) {
+            peer.synced = journal::head();
+        }
+        Ok(report)
+    }
+
+    fn send_notes(&self, state: &SyncState, conn: &mut Connection, keys: &[String]) -> io::Result<usize> {
+        let mut sent = 0;
+        for key in keys {
+            let note = &state.notes[key];
+            let content = if note.deleted {
+                None
+            } else {
+                match std::fs::read_to_string(Path::new(vault::NOTES_DIR).join(key)) {
+                    Ok(content) => Some(content),
+                    Err(_) => continue,
+                }
+            };
+            conn.send(&Message::Note {
+                key: key.clone(),
+                clock: note.clock.clone(),
+                content,
+            })?;
+            sent += 1;
+        }
+        conn.send(&Message::Done)?;
+        Ok(sent)
+    }
+}
+
+/// Announces this device on the multicast group and collects the
+/// announcements of others.
+fn discover(shared: Arc<Shared>, port: u16, ctx: egui::Context) -> io::Result<()> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, DISCOVERY_PORT))?;
+    socket.join_multicast_v4(&GROUP, &Ipv4Addr::UNSPECIFIED)?;
+    socket.set_read_timeout(Some(ANNOUNCE_EVERY))?;
+    let device_id = lock(&shared.state).device_id.clone();
+    let beacon = serde_json::to_vec(&Beacon {
+        device_id: device_id.clone(),
+        name: shared.name.clone(),
+        port,
+    })?;
+    std::thread::spawn(move || {
+        let mut announced: Option<Instant> = None;
+        let mut buf = [0u8; 1024];
+        loop {
+            if announced.is_none_or(|at| at.elapsed() >= ANNOUNCE_EVERY) {
+                if let Err(err) = socket.send_to(&beacon, (GROUP, DISCOVERY_PORT)) {
+                    error!("Failed to announce for LAN sync: {}", err);
+                }
+                announced = Some(Instant::now());
+            }
+            if let Ok((len, from)) = socket.recv_from(&mut buf)
+                && let Ok(beacon) = serde_json::from_slice::<Beacon>(&buf[..len])
+                && beacon.device_id != device_id
+            {
+                let device = Device {
+                    name: beacon.name,
+                    address: SocketAddr::new(from.ip(), beacon.port),
+                    seen: Instant::now(),
+                };
+                if lock(&shared.devices).insert(beacon.device_id, device).is_none() {
+                    ctx.request_repaint();
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+fn name_of(config: &LanSyncConfig) -> String {
+    let name = config.device_name.trim();
+    if !name.is_empty() {
+        return name.to_owned();
+    }
+    std::env::var("HOSTNAME").or_else(|_| std::env::var("COMPUTERNAME")).unwrap_or_else(|_| "Notes".to_owned())
+}
+
+/// Serverless sync with other instances on the local network: finds them,
+/// pairs with a code, and swaps changed notes.
+///
+/// Pairing proves that each side knows the code shown on the other, and
+/// later the key derived from it, without sending either. Nothing else is
+/// protected: notes travel unencrypted and unsigned, so anyone on the
+/// network can read them and someone in the middle could change them. It
+/// is meant for networks you trust.
+pub struct LanSync {
+    pub open: bool,
+    enabled: bool,
+    shared: Arc<Shared>,
+    reports: Receiver<Result<SyncReport, String>>,
+    tx: Sender<Result<SyncReport, String>>,
+    ctx: egui::Context,
+    /// Pairing codes being typed, by device id.
+    codes: HashMap<String, String>,
+    log: Vec<String>,
+    last_auto: Instant,
+    /// Notes changed here since each paired device last synced, as of a
+    /// journal head.
+    pending: Option<(u64, HashMap<String, usize>)>,
+    /// Syncs started from here that have not finished.
+    syncing: usize,
+}
+
+impl LanSync {
+    pub fn new(config: &LanSyncConfig, ctx: &egui::Context) -> Self {
+        let (tx, reports) = mpsc::channel();
+        let shared = Arc::new(Shared {
+            name: name_of(config),
+            state: Mutex::new(SyncState::load()),
+            pairing: Mutex::new(None),
+            devices: Mutex::new(HashMap::new()),
+        });
+        let mut sync = Self {
+            open: false,
+            enabled: config.enabled,
+            shared,
+            reports,
+            tx,
+            ctx: ctx.clone(),
+            codes: HashMap::new(),
+            log: Vec::new(),
+            last_auto: Instant::now(),
+            pending: None,
+            syncing: 0,
+        };
+        if config.enabled
+            && let Err(err) = sync.start(config.port)
+        {
+            error!("Failed to start LAN sync: {}", err);
+            sync.log(format!("Failed to start: {}", err));
+        }
+        sync
+    }
+
+    fn start(&self, port: u16) -> io::Result<()> {
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, port))?;
+        let (shared, tx, ctx) = (self.shared.clone(), self.tx.clone(), self.ctx.clone());
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (shared, tx, ctx) = (shared.clone(), tx.clone(), ctx.clone());
+                std::thread::spawn(move || {
+                    let _ = tx.send(shared.session(stream, false, None).map_err(|e| e.to_string()));
+                    ctx.request_repaint();
+                });
+            }
+        });
+        discover(self.shared.clone(), port, self.ctx.clone())
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    fn log(&mut self, line: String) {
+        self.log.push(format!("{} {}", chrono::Local::now().format("%H:%M"), line));
+        let excess = self.log.len().saturating_sub(LOG_LINES);
+        self.log.drain(..excess);
+    }
+
+    /// Syncs with a device on a background thread.
+    fn sync_with(&mut self, address: SocketAddr, code: Option<String>) {
+        self.syncing += 1;
+        let (shared, tx, ctx) = (self.shared.clone(), self.tx.clone(), self.ctx.clone());
+        std::thread::spawn(move || {
+            let result = TcpStream::connect_timeout(&address, Duration::from_secs(5)).and_then(|stream| shared.session(stream, true, code));
+            let _ = tx.send(result.map_err(|e| e.to_string()));
+            ctx.request_repaint();
+        });
+    }
+
+    /// Devices currently announcing themselves, by id.
+    fn devices(&self) -> Vec<(String, Device)> {
+        let mut devices = lock(&self.shared.devices);
+        devices.retain(|_, d| d.seen.elapsed() < FORGET_AFTER);
+        let mut devices: Vec<(String, Device)> = devices.iter().map(|(id, d)| (id.clone(), d.clone())).collect();
+        devices.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+        devices
+    }
+
+    /// Starts automatic syncs that are due and collects finished ones.
+    pub fn poll(&mut self, config: &LanSyncConfig) -> Vec<SyncReport> {
+        // A session holding the state puts the round off to a later frame.
+        if self.enabled
+            && config.interval_minutes > 0
+            && self.last_auto.elapsed() >= Duration::from_secs(config.interval_minutes * 60)
+            && let Some(peers) = try_lock(&self.shared.state).map(|state| state.peers.keys().cloned().collect::<HashSet<String>>())
+        {
+            self.last_auto = Instant::now();
+            for (id, device) in self.devices() {
+                if peers.contains(&id) {
+                    self.sync_with(device.address, None);
+                }
+            }
+        }
+        let mut finished = Vec::new();
+        while let Ok(result) = self.reports.try_recv() {
+            self.pending = None;
+            // Syncs other devices start come in here too.
+            self.syncing = self.syncing.saturating_sub(1);
+            match result {
+                Ok(report) => {
+                    self.log(report.summary());
+                    finished.push(report);
+                }
+                Err(err) => {
+                    error!("LAN sync failed: {}", err);
+                    self.log(format!("Failed: {}", err));
+                }
+            }
+        }
+        finished
+    }
+
+    pub fn is_syncing(&self) -> bool {
+        self.syncing > 0
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if !self.open {
+            return;
+        }
+        let mut open = true;
+
+        egui::Window::new(t("📡 LAN Sync")).open(&mut open).default_width(380.0).show(ctx, |ui| {
+            if !self.enabled {
+                ui.label(RichText::new(t("Turn on LAN sync in Settings and restart to find other devices.")).weak());
+                return;
+            }
+            ui.label(tf("This device: {}", &[&self.shared.name]));
+            ui.label(RichText::new(t("Notes are sent unencrypted; only sync on networks you trust.")).small().weak());
+            // Sessions hold the state for their whole run.
+            let Some(state) = try_lock(&self.shared.state) else {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(t("Syncing…"));
+                });
+                ctx.request_repaint_after(Duration::from_millis(200));
+                return;
+            };
+            let peers = state.peers.clone();
+            drop(state);
+            let head = journal::head();
+            if self.pending.as_ref().is_none_or(|(h, _)| *h != head) {
+                let oldest = peers.values().map(|p| p.synced).min().unwrap_or(head);
+                let changes = journal::since(oldest);
+                let counts = peers
+                    .iter()
+                    .map(|(id, peer)| {
+                        let notes: HashSet<&str> = changes.iter().filter(|r| r.seq > peer.synced).map(|r| r.note.as_str()).collect();
+                        (id.clone(), notes.len())
+                    })
+                    .collect();
+                self.pending = Some((head, counts));
+            }
+            let pending = self.pending.as_ref().map(|(_, counts)| counts.clone()).unwrap_or_default();
+
+            ui.horizontal(|ui| {
+                let code = lock(&self.shared.pairing).clone().filter(|(_, shown)| shown.elapsed() < PAIRING_VALID);
+                match code {
+                    Some((code, _)) => {
+                        ui.label(t("Pairing code:"));
+                        ui.label(RichText::new(code).monospace().strong().size(18.0));
+                        ui.label(RichText::new(t("enter it on the other device")).small().weak());
+                    }
+                    None => {
+                        if ui.button(t("Pair a device…")).on_hover_text(t("Show a code to enter on the other device")).clicked() {
+                            *lock(&self.shared.pairing) = Some((pairing_code(), Instant::now()));
+                        }
+                    }
+                }
+            });
+            ui.separator();
+
+            let devices = self.devices();
+            ui.label(RichText::new(t("On this network")).strong());
+            if devices.is_empty() {
+                ui.label(RichText::new(t("No other devices found yet")).weak());
+            }
+            let mut start = None;
+            egui::Grid::new("lan_devices").num_columns(2).show(ui, |ui| {
+                for (id, device) in &devices {
+                    ui.label(&device.name).on_hover_text(device.address.to_string());
+                    if peers.contains_key(id) {
+                        ui.horizontal(|ui| {
+                            if ui.button(t("Sync now")).clicked() {
+                                start = Some((device.address, None));
+                            }
+                            if let Some(count) = pending.get(id).filter(|c| **c > 0) {
+                                ui.label(RichText::new(tf("{} notes changed since last sync", &[&count])).small().weak());
+                            }
+                        });
+                    } else {
+                        ui.horizontal(|ui| {
+                            let code = self.codes.entry(id.clone()).or_default();
+                            ui.add(egui::TextEdit::singleline(code).hint_text(t("code")).desired_width(150.0));
+                            let typed = normalize_code(code);
+                            if ui.add_enabled(typed.len() == CODE_LEN, egui::Button::new(t("Pair"))).clicked() {
+                                start = Some((device.address, Some(typed)));
+                                code.clear();
+                            }
+                        });
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some((address, code)) = start {
+                self.sync_with(address, code);
+            }
+
+            let away: Vec<(&String, &Peer)> = peers.iter().filter(|(id, _)| !devices.iter().any(|(d, _)| d == *id)).collect();
+            if !away.is_empty() {
+                ui.separator();
+                ui.label(RichText::new(t("Paired, not seen")).strong());
+                for (id, peer) in away {
+                    ui.horizontal(|ui| {
+                        ui.label(&peer.name);
+                        if let Some(count) = pending.get(id).filter(|c| **c > 0) {
+                            ui.label(RichText::new(tf("{} changed", &[&count])).small().weak());
+                        }
+                        if ui.small_button(t("Forget")).clicked()
+                            && let Some(mut state) = try_lock(&self.shared.state)
+                        {
+                            state.peers.remove(id);
+                            if let Err(err) = state.save() {
+                                error!("Failed to save LAN sync state: {}", err);
+                            }
+                        }
+                    });
+                }
+            }
+
+            if !self.log.is_empty() {
+                ui.separator();
+                for line in &self.log {
+                    ui.label(RichText::new(line).small().weak());
+                }
+            }
+        });
+
+        if !open {
+            self.open = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clock(counts: &[(&str, u64)]) -> Clock {
+        counts.iter().map(|(device, count)| (device.to_string(), *count)).collect()
+    }
+
+    fn report() -> SyncReport {
+        SyncReport {
+            peer: "B".to_owned(),
+            received: Vec::new(),
+            sent: 0,
+            conflicts: Vec::new(),
+        }
+    }
+
+    /// Runs `test` in an empty vault. Vault paths are relative, so this
+    /// moves the working directory, and the tests that do take turns; no
+    /// other tests touch the file system. The journal caches what it read
+    /// from the last vault, so that is dropped too.
+    fn in_vault(test: impl FnOnce(&mut SyncState)) {
+        static VAULT: Mutex<()> = Mutex::new(());
+        let _turn = lock(&VAULT);
+        let dir = std::env::temp_dir().join(format!("note-app-lansync-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join(vault::NOTES_DIR)).unwrap();
+        std::env::set_current_dir(&dir).unwrap();
+        journal::reload();
+        let mut state = SyncState {
+            device_id: "a".to_owned(),
+            ..Default::default()
+        };
+        test(&mut state);
+    }
+
+    #[test]
+    fn compare_orders_clocks() {
+        let a = clock(&[("a", 2), ("b", 1)]);
+        assert_eq!(compare(&a, &a), Order::Same);
+        assert_eq!(compare(&clock(&[("a", 1)]), &a), Order::Before);
+        assert_eq!(compare(&a, &clock(&[("a", 1)])), Order::After);
+        assert_eq!(compare(&clock(&[("a", 3)]), &a), Order::Concurrent);
+        // A device missing from a clock counts as no edits.
+        assert_eq!(compare(&clock(&[("a", 0)]), &Clock::new()), Order::Same);
+    }
+
+    #[test]
+    fn merge_keeps_the_larger_counts() {
+        let mut into = clock(&[("a", 3), ("b", 1)]);
+        merge(&mut into, &clock(&[("b", 4), ("c", 2)]));
+        assert_eq!(into, clock(&[("a", 3), ("b", 4), ("c", 2)]));
+    }
+
+    #[test]
+    fn pairing_codes_are_long_and_typeable() {
+        let code = pairing_code();
+        assert_eq!(normalize_code(&code).len(), CODE_LEN);
+        assert_eq!(normalize_code(&code.to_lowercase().replace('-', " ")), normalize_code(&code));
+    }
+
+    #[test]
+    fn receive_note_takes_newer_notes() {
+        in_vault(|state| {
+            let other = Other { id: "b".to_owned(), name: "B".to_owned() };
+            let mut report = report();
+            receive_note(state, "A", &other, "n.md", clock(&[("b", 1)]), Some("from b".to_owned()), &mut report).unwrap();
+            assert_eq!(std::fs::read_to_string("notes/n.md").unwrap(), "from b");
+            assert_eq!(report.received.len(), 1);
+
+            // An older version is ignored.
+            receive_note(state, "A", &other, "n.md", Clock::new(), Some("stale".to_owned()), &mut report).unwrap();
+            assert_eq!(std::fs::read_to_string("notes/n.md").unwrap(), "from b");
+
+            receive_note(state, "A", &other, "n.md", clock(&[("b", 2)]), None, &mut report).unwrap();
+            assert!(!Path::new("notes/n.md").exists());
+            assert!(state.notes["n.md"].deleted);
+        });
+    }
+
+    #[test]
+    fn receive_note_keeps_a_conflict_copy() {
+        in_vault(|state| {
+            std::fs::write("notes/n.md", "from a").unwrap();
+            state.notes.insert("n.md".to_owned(), NoteState { clock: clock(&[("a", 1)]), ..Default::default() });
+            // "b" sorts after "a", so its version wins on both devices.
+            let other = Other { id: "b".to_owned(), name: "B".to_owned() };
+            let mut report = report();
+            receive_note(state, "A", &other, "n.md", clock(&[("b", 1)]), Some("from b".to_owned()), &mut report).unwrap();
+            assert_eq!(std::fs::read_to_string("notes/n.md").unwrap(), "from b");
+            assert_eq!(report.conflicts.len(), 1);
+            assert_eq!(std::fs::read_to_string(&report.conflicts[0]).unwrap(), "from a");
+            assert_eq!(state.notes["n.md"].clock, clock(&[("a", 1), ("b", 1)]));
+        });
+    }
+
+    #[test]
+    fn receive_note_stays_in_the_vault() {
+        in_vault(|state| {
+            let other = Other { id: "b".to_owned(), name: "B".to_owned() };
+            let sent = receive_note(state, "A", &other, "../n.md", clock(&[("b", 1)]), Some("x".to_owned()), &mut report());
+            assert!(sent.is_err());
+            assert!(!Path::new("n.md").exists());
+        });
+    }
+}
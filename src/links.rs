@@ -0,0 +1,196 @@
+use crate::vault::NOTES_DIR;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+pub const SCHEME: &str = "noteapp";
+
+/// An action requested through a `noteapp://` link.
+#[derive(Debug, PartialEq, Eq)]
+pub enum LinkAction {
+    Open(String),
+}
+
+/// Parses links like `noteapp://open?note=Projects/Ideas`.
+pub fn parse(url: &str) -> Option<LinkAction> {
+    let rest = url.strip_prefix(SCHEME)?.strip_prefix("://")?;
+    let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let param = |key: &str| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| percent_decode(&v.replace('+', " ")))
+    };
+    match action.trim_end_matches('/') {
+        "open" => param("note").map(LinkAction::Open),
+        _ => None,
+    }
+}
+
+/// Resolves a note name from a link to a file in the vault, trying the
+/// usual extensions when none is given.
+pub fn resolve_note(name: &str) -> Option<PathBuf> {
+    // Only plain names: no `..`, root or drive can lead out of the vault.
+    let base = crate::server::note_path(name.trim_matches('/'))?;
+    // The name may have dots of its own, as in `v1.2`.
+    let with_ext = |ext: &str| {
+        let mut path = base.clone().into_os_string();
+        path.push(ext);
+        PathBuf::from(path)
+    };
+    [base.clone(), with_ext(".md"), with_ext(".txt")].into_iter().find(|p| p.is_file())
+}
+
+/// A `[[note]]`, `[[note|alias]]` or `[[note#heading]]` link.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WikiLink {
+    /// Byte range of the link, brackets included.
+    pub range: Range<usize>,
+    pub target: String,
+    pub alias: Option<String>,
+}
+
+impl WikiLink {
+    /// What the link reads as in running text.
+    pub fn label(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.target)
+    }
+}
+
+/// The wiki links in `text`, skipping fenced code blocks.
+pub fn wiki_links(text: &str) -> Vec<WikiLink> {
+    let fences = crate::markdown::fenced_blocks(text);
+    let mut found = Vec::new();
+    for (start, _) in text.match_indices("[[") {
+        if fences.iter().any(|f| f.range.contains(&start)) || found.iter().any(|l: &WikiLink| l.range.contains(&start)) {
+            continue;
+        }
+        let Some(len) = text[start + 2..].find("]]") else {
+            continue;
+        };
+        let inner = &text[start + 2..start + 2 + len];
+        if inner.is_empty() || inner.contains('\n') {
+            continue;
+        }
+        let (target, alias) = match inner.split_once('|') {
+            Some((target, alias)) => (target, Some(alias.trim().to_owned())),
+            None => (inner, None),
+        };
+        let target = target.split('#').next().unwrap_or_default().trim();
+        found.push(WikiLink {
+            range: start..start + 2 + len + 2,
+            target: target.to_owned(),
+            alias,
+        });
+    }
+    found
+}
+
+/// Finds the note a wiki link points at: a path relative to the notes
+/// folder, or else any note with that name, compared case-insensitively.
+pub fn resolve_wiki(target: &str, notes: &[PathBuf]) -> Option<PathBuf> {
+    resolve_note(target).or_else(|| {
+        let wanted = target.rsplit('/').next().unwrap_or(target).to_lowercase();
+        notes
+            .iter()
+            .find(|p| p.file_stem().and_then(|s| s.to_str()).is_some_and(|s| s.to_lowercase() == wanted))
+            .cloned()
+    })
+}
+
+/// Resolves a link written in `note` to a local path, or `None` for web
+/// links and in-page anchors.
+pub fn local_target(note: &Path, target: &str) -> Option<PathBuf> {
+    if target.is_empty() || target.starts_with('#') || target.contains("://") || target.starts_with("mailto:") {
+        return None;
+    }
+    let path = percent_decode(target.split('#').next().unwrap_or_default());
+    let dir = note.parent().unwrap_or(Path::new(NOTES_DIR));
+    Some(crate::vault::clean_path(&dir.join(path)))
+}
+
+/// The notes `note` links to, through wiki links or Markdown links.
+pub fn outgoing(note: &Path, text: &str, notes: &[PathBuf]) -> Vec<PathBuf> {
+    let wiki = wiki_links(text).into_iter().filter_map(|l| resolve_wiki(&l.target, notes));
+    let markdown = crate::urls::links(text)
+        .into_iter()
+        .filter_map(|l| local_target(note, &l.target))
+        .filter(|p| crate::vault::is_note(p) && p.is_file());
+    let mut targets: Vec<PathBuf> = wiki.chain(markdown).collect();
+    targets.sort();
+    targets.dedup();
+    targets
+}
+
+/// Escapes everything in a link path but unreserved characters and `/`,
+/// so spaces, parentheses and the like cannot end or break the link.
+pub fn percent_encode(path: &str) -> String {
+    path.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+pub fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Registers this executable as the handler for `noteapp://` links.
+#[cfg(target_os = "linux")]
+pub fn register_scheme() -> std::io::Result<()> {
+    let exe = std::env::current_exe()?;
+    let dir = std::env::var("HOME")
+        .map(|home| Path::new(&home).join(".local/share/applications"))
+        .map_err(std::io::Error::other)?;
+    std::fs::create_dir_all(&dir)?;
+    let desktop = format!(
+        "[Desktop Entry]\nType=Application\nName=Rust Note App\nExec=\"{}\" %u\nPath={}\nNoDisplay=true\nMimeType=x-scheme-handler/{};\n",
+        exe.display(),
+        std::env::current_dir()?.display(),
+        SCHEME
+    );
+    std::fs::write(dir.join("noteapp-url.desktop"), desktop)?;
+    std::process::Command::new("xdg-mime")
+        .args(["default", "noteapp-url.desktop", &format!("x-scheme-handler/{}", SCHEME)])
+        .status()?;
+    Ok(())
+}
+
+/// Registers this executable as the handler for `noteapp://` links.
+#[cfg(target_os = "windows")]
+pub fn register_scheme() -> std::io::Result<()> {
+    let exe = std::env::current_exe()?;
+    let key = format!(r"HKCU\Software\Classes\{}", SCHEME);
+    let command = format!("\"{}\" \"%1\"", exe.display());
+    let run = |args: &[&str]| std::process::Command::new("reg").args(args).status();
+    run(&["add", &key, "/ve", "/d", "URL:Rust Note App", "/f"])?;
+    run(&["add", &key, "/v", "URL Protocol", "/d", "", "/f"])?;
+    run(&["add", &format!(r"{}\shell\open\command", key), "/ve", "/d", &command, "/f"])?;
+    Ok(())
+}
+
+/// macOS only routes URL schemes to bundled apps declaring them in Info.plist.
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+pub fn register_scheme() -> std::io::Result<()> {
+    Err(std::io::Error::other(
+        "declare CFBundleURLTypes in the app bundle's Info.plist to register noteapp:// links",
+    ))
+}
@@ -0,0 +1,249 @@
+use crate::i18n::{t, tf};
+use crate::jobs::{self, Job, JobHandle};
+use crate::links;
+use crate::urls;
+use crate::vault::{self, ATTACHMENTS_DIR, NOTES_DIR};
+use eframe::egui::{self, RichText};
+use std::collections::HashSet;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::TryRecvError;
+use tracing::error;
+#[cfg(target_os = "android")]
+use crate::stubs::rfd;
+
+#[derive(Debug, Clone)]
+pub enum Problem {
+    /// A `[[link]]` to a note that does not exist.
+    MissingNote {
+        note: PathBuf,
+        range: Range<usize>,
+        /// The link as written, to check it is still there before a fix.
+        link: String,
+        target: String,
+        label: String,
+    },
+    /// A Markdown link or image pointing at a local file that is gone.
+    MissingFile {
+        note: PathBuf,
+        range: Range<usize>,
+        link: String,
+        target: String,
+        label: String,
+    },
+    /// An attachment no note links to.
+    UnusedAttachment(PathBuf),
+}
+
+/// Text a Markdown link shows, e.g. `text` for `[text](url)`.
+fn markdown_label(source: &str) -> String {
+    let source = source.trim_start_matches('!');
+    match (source.strip_prefix('['), source.find("](")) {
+        (Some(_), Some(close)) => source[1..close].to_owned(),
+        _ => String::new(),
+    }
+}
+
+/// Normalized form for comparing paths that may be spelled differently.
+fn normalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Checks every note's links and every attachment.
+pub fn check_vault(job: &Job) -> io::Result<Vec<Problem>> {
+    let notes = vault::note_files();
+    let mut problems = Vec::new();
+    let mut linked = HashSet::new();
+
+    for (index, note) in notes.iter().enumerate() {
+        job.check()?;
+        job.progress(index, notes.len());
+        let Ok(text) = std::fs::read_to_string(note) else {
+            continue;
+        };
+        for link in links::wiki_links(&text) {
+            match links::resolve_wiki(&link.target, &notes) {
+                Some(path) => {
+                    linked.insert(normalize(&path));
+                }
+                None => problems.push(Problem::MissingNote {
+                    note: note.clone(),
+                    range: link.range.clone(),
+                    link: text[link.range.clone()].to_owned(),
+                    target: link.target.clone(),
+                    label: link.label().to_owned(),
+                }),
+            }
+        }
+        for link in urls::links(&text) {
+            let Some(path) = links::local_target(note, &link.target) else {
+                continue;
+            };
+            if path.exists() {
+                linked.insert(normalize(&path));
+            } else {
+                problems.push(Problem::MissingFile {
+                    note: note.clone(),
+                    range: link.range.clone(),
+                    link: text[link.range.clone()].to_owned(),
+                    target: link.target.clone(),
+                    label: markdown_label(&text[link.range.clone()]),
+                });
+            }
+        }
+    }
+
+    for attachment in vault::walk_files(Path::new(ATTACHMENTS_DIR)) {
+        if !linked.contains(&normalize(&attachment)) {
+            problems.push(Problem::UnusedAttachment(attachment));
+        }
+    }
+    Ok(problems)
+}
+
+/// Replaces a link in a note file with its label, if the file still has
+/// that link where it was found.
+fn unlink(note: &Path, range: &Range<usize>, link: &str, label: &str) -> std::io::Result<()> {
+    let mut text = std::fs::read_to_string(note)?;
+    if text.get(range.clone()) != Some(link) {
+        return Err(std::io::Error::other("the note changed since the check; run it again"));
+    }
+    text.replace_range(range.clone(), label);
+    crate::journal::write(note, &text)
+}
+
+/// Creates the note a `[[target]]` link points at. A note already there,
+/// e.g. one the link does not resolve to, is left alone.
+fn create_note(target: &str) -> std::io::Result<PathBuf> {
+    let mut parts: Vec<String> = target.split('/').map(vault::safe_file_name).collect();
+    let title = parts.pop().unwrap_or_default();
+    let path = Path::new(NOTES_DIR).join(parts.iter().collect::<PathBuf>()).join(format!("{}.md", title));
+    crate::journal::create(&path, &format!("# {}\n", title))?;
+    Ok(path)
+}
+
+/// What the app should do after the user acted in the panel.
+pub enum LintEvent {
+    Open(PathBuf),
+    /// A note file was modified on disk by a quick fix.
+    Changed(PathBuf),
+}
+
+#[derive(Default)]
+pub struct LintPanel {
+    /// Results of the last check; `None` while the panel is closed.
+    pub problems: Option<Vec<Problem>>,
+    running: Option<JobHandle<io::Result<Vec<Problem>>>>,
+}
+
+impl LintPanel {
+    /// Starts a check in the background; the panel opens when it is done.
+    pub fn run(&mut self) {
+        self.running = Some(jobs::spawn("Checking the vault", check_vault));
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<LintEvent> {
+        if let Some(running) = &self.running {
+            match running.try_recv() {
+                Ok(Ok(problems)) => {
+                    self.problems = Some(problems);
+                    self.running = None;
+                }
+                Ok(Err(_)) | Err(TryRecvError::Disconnected) => self.running = None,
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+        let problems = self.problems.as_ref()?;
+        let mut open = true;
+        let mut event = None;
+        let mut rerun = false;
+
+        egui::Window::new(t("🩺 Vault Check"))
+            .open(&mut open)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tf("{} problems", &[&problems.len()]));
+                    match &self.running {
+                        Some(running) => running.show_progress(ui),
+                        None => {
+                            if ui.button(t("🔄 Check again")).clicked() {
+                                rerun = true;
+                            }
+                        }
+                    }
+                });
+                ui.separator();
+                if problems.is_empty() {
+                    ui.label(t("No broken links or unused attachments."));
+                }
+                egui::ScrollArea::vertical().max_height(420.0).show(ui, |ui| {
+                    for (index, problem) in problems.iter().enumerate() {
+                        ui.push_id(index, |ui| {
+                            ui.horizontal_wrapped(|ui| match problem {
+                                Problem::MissingNote { note, range, link, target, label } => {
+                                    if ui.link(note.display().to_string()).clicked() {
+                                        event = Some(LintEvent::Open(note.clone()));
+                                    }
+                                    ui.label(RichText::new(tf("[[{}]] has no note", &[&target])).monospace());
+                                    if ui.button(t("Create note")).clicked() {
+                                        match create_note(target) {
+                                            Ok(path) => event = Some(LintEvent::Changed(path)),
+                                            Err(err) => error!("Failed to create note: {}", err),
+                                        }
+                                        rerun = true;
+                                    }
+                                    if ui.button(t("Remove link")).clicked() {
+                                        match unlink(note, range, link, label) {
+                                            Ok(()) => event = Some(LintEvent::Changed(note.clone())),
+                                            Err(err) => error!("Failed to remove link: {}", err),
+                                        }
+                                        rerun = true;
+                                    }
+                                }
+                                Problem::MissingFile { note, range, link, target, label } => {
+                                    if ui.link(note.display().to_string()).clicked() {
+                                        event = Some(LintEvent::Open(note.clone()));
+                                    }
+                                    ui.label(RichText::new(tf("{} is missing", &[&target])).monospace());
+                                    if ui.button(t("Remove link")).clicked() {
+                                        match unlink(note, range, link, label) {
+                                            Ok(()) => event = Some(LintEvent::Changed(note.clone())),
+                                            Err(err) => error!("Failed to remove link: {}", err),
+                                        }
+                                        rerun = true;
+                                    }
+                                }
+                                Problem::UnusedAttachment(path) => {
+                                    ui.label(RichText::new(path.display().to_string()).monospace());
+                                    ui.label(t("is not linked from any note"));
+                                    if ui.button(t("🗑 Delete")).clicked() {
+                                        let confirmed = rfd::MessageDialog::new()
+                                            .set_title(t("Delete attachment"))
+                                            .set_description(tf("Delete {}?", &[&path.display()]))
+                                            .set_buttons(rfd::MessageButtons::YesNo)
+                                            .show();
+                                        if confirmed == rfd::MessageDialogResult::Yes {
+                                            if let Err(err) = std::fs::remove_file(path) {
+                                                error!("Failed to delete attachment: {}", err);
+                                            }
+                                            rerun = true;
+                                        }
+                                    }
+                                }
+                            });
+                        });
+                    }
+                });
+            });
+
+        if !open {
+            self.problems = None;
+            self.running = None;
+        } else if rerun {
+            self.run();
+        }
+        event
+    }
+}
@@ -0,0 +1,230 @@
+use crate::i18n::t;
+use crate::vault::LOG_DIR;
+use eframe::egui::{self, Color32, RichText};
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+/// The log file is rotated once it grows past this many bytes.
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+/// Rotated files kept next to the current one, as `note_app.1.log` and up.
+const KEEP_FILES: usize = 3;
+/// Entries kept in memory for the log window.
+const KEEP_ENTRIES: usize = 1000;
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub time: String,
+    pub level: Level,
+    /// The module that logged it.
+    pub target: String,
+    pub message: String,
+}
+
+impl Entry {
+    fn line(&self) -> String {
+        format!("{} {:5} {}: {}", self.time, self.level, self.target, self.message)
+    }
+}
+
+static RECENT: Mutex<VecDeque<Entry>> = Mutex::new(VecDeque::new());
+
+fn log_path(index: usize) -> PathBuf {
+    match index {
+        0 => Path::new(LOG_DIR).join("note_app.log"),
+        n => Path::new(LOG_DIR).join(format!("note_app.{}.log", n)),
+    }
+}
+
+/// Shifts `note_app.log` to `note_app.1.log` and so on, dropping the oldest.
+fn rotate() -> std::io::Result<File> {
+    for index in (0..KEEP_FILES).rev() {
+        let from = log_path(index);
+        if from.exists() {
+            std::fs::rename(&from, log_path(index + 1))?;
+        }
+    }
+    let _ = std::fs::remove_file(log_path(KEEP_FILES + 1));
+    File::create(log_path(0))
+}
+
+fn open_file() -> std::io::Result<File> {
+    std::fs::create_dir_all(LOG_DIR)?;
+    std::fs::OpenOptions::new().create(true).append(true).open(log_path(0))
+}
+
+/// Collects an event's message and any other fields as `key=value`.
+#[derive(Default)]
+struct Message(String);
+
+impl Visit for Message {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.0.insert_str(0, value);
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.0.insert_str(0, &format!("{:?}", value));
+        } else {
+            let _ = write!(self.0, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// Writes events to stderr, the log file and the in-memory list the log
+/// window shows. Spans are not tracked.
+struct LogSubscriber {
+    file: Mutex<Option<File>>,
+    next_span: AtomicU64,
+}
+
+impl LogSubscriber {
+    fn write_file(&self, line: &str) {
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if file.as_ref().and_then(|f| f.metadata().ok()).is_some_and(|m| m.len() > MAX_FILE_SIZE) {
+            *file = rotate().ok();
+        }
+        if let Some(file) = file.as_mut() {
+            let _ = writeln!(file, "{}", line);
+        }
+    }
+}
+
+impl Subscriber for LogSubscriber {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        *metadata.level() <= Level::INFO
+    }
+
+    fn new_span(&self, _: &Attributes<'_>) -> Id {
+        Id::from_u64(self.next_span.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut message = Message::default();
+        event.record(&mut message);
+        let metadata = event.metadata();
+        let entry = Entry {
+            time: chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+            level: *metadata.level(),
+            target: metadata.target().to_owned(),
+            message: message.0,
+        };
+        let line = entry.line();
+        eprintln!("{}", line);
+        self.write_file(&line);
+        let mut recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+        if recent.len() == KEEP_ENTRIES {
+            recent.pop_front();
+        }
+        recent.push_back(entry);
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+/// Sends everything logged through `tracing` to stderr, `logs/note_app.log`
+/// and the log window.
+pub fn init() {
+    let file = match open_file() {
+        Ok(file) => Some(file),
+        Err(err) => {
+            eprintln!("Failed to open the log file: {}", err);
+            None
+        }
+    };
+    let subscriber = LogSubscriber {
+        file: Mutex::new(file),
+        next_span: AtomicU64::new(1),
+    };
+    if let Err(err) = tracing::subscriber::set_global_default(subscriber) {
+        eprintln!("Failed to set up logging: {}", err);
+    }
+}
+
+fn level_color(level: Level) -> Color32 {
+    match level {
+        Level::ERROR => Color32::LIGHT_RED,
+        Level::WARN => Color32::YELLOW,
+        Level::INFO => Color32::LIGHT_BLUE,
+        _ => Color32::GRAY,
+    }
+}
+
+/// The "Show logs" window: recent entries, newest last, for finding out
+/// why a save or a request failed.
+#[derive(Default)]
+pub struct LogView {
+    pub open: bool,
+    errors_only: bool,
+    filter: String,
+}
+
+impl LogView {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if !self.open {
+            return;
+        }
+        let entries: Vec<Entry> = RECENT.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect();
+        let filter = self.filter.to_lowercase();
+        let shown: Vec<&Entry> = entries
+            .iter()
+            .filter(|e| !self.errors_only || e.level <= Level::WARN)
+            .filter(|e| filter.is_empty() || e.line().to_lowercase().contains(&filter))
+            .collect();
+
+        egui::Window::new(t("🪵 Logs"))
+            .open(&mut self.open)
+            .default_width(640.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.filter).hint_text(t("Filter")).desired_width(180.0));
+                    ui.checkbox(&mut self.errors_only, t("Warnings and errors only"));
+                    if ui.button(t("📋 Copy")).on_hover_text(t("Copy the shown entries, e.g. for a bug report")).clicked() {
+                        let text: Vec<String> = shown.iter().map(|e| e.line()).collect();
+                        ctx.copy_text(text.join("\n"));
+                    }
+                    if ui.button(t("📂 Log folder")).clicked() {
+                        match Path::new(LOG_DIR).canonicalize() {
+                            Ok(dir) => ctx.open_url(egui::OpenUrl::new_tab(format!("file://{}", dir.display()))),
+                            Err(err) => tracing::error!("Failed to open {}: {}", LOG_DIR, err),
+                        }
+                    }
+                });
+                ui.separator();
+                if shown.is_empty() {
+                    ui.weak(t("Nothing logged yet."));
+                }
+                egui::ScrollArea::vertical().max_height(420.0).stick_to_bottom(true).show(ui, |ui| {
+                    for entry in shown {
+                        ui.horizontal_wrapped(|ui| {
+                            ui.label(RichText::new(&entry.time[11..19]).monospace().weak());
+                            ui.label(RichText::new(entry.level.as_str()).monospace().color(level_color(entry.level)));
+                            ui.label(RichText::new(&entry.target).small().weak());
+                            ui.label(&entry.message);
+                        });
+                    }
+                });
+            });
+    }
+}
@@ -1,163 +1,2701 @@
-use eframe::{egui::{self, FontData, FontDefinitions, FontFamily, FontId, Visuals, Style, TextEdit}, App, CreationContext, NativeOptions};
+use eframe::{egui::{self, FontData, FontDefinitions, FontFamily, FontId, Style, TextEdit}, App, CreationContext, NativeOptions};
 use egui::Context;
-use serde::Deserialize;
+use backup::BackupScheduler;
+use config::Config;
+use grammar::LTMatch;
+use i18n::{t, tf};
+use merge::{MergeOutcome, MergeView};
+use std::collections::HashSet;
+use std::io;
+use std::net::TcpListener;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::Duration;
+use tracing::{error, info, warn};
+// rfd and arboard have no Android backend.
+#[cfg(target_os = "android")]
+use stubs::{arboard, rfd};
 
-#[derive(Deserialize, Debug)]
-pub struct LTResponse {
-    matches: Vec<LTMatch>,
+mod a11y;
+mod anki;
+mod archive;
+mod archived;
+mod assistant;
+mod autocorrect;
+mod backup;
+mod bidi;
+mod capture;
+mod citations;
+mod cleanup;
+mod collab;
+mod comments;
+mod clipper;
+mod completion;
+mod config;
+mod crdt;
+mod dictation;
+mod dictionary;
+mod diagram;
+mod diff;
+mod flashcards;
+mod folding;
+mod duplicates;
+mod editor;
+mod emoji;
+mod encoding;
+mod fonts;
+mod footnotes;
+mod frontmatter;
+mod gardening;
+mod glossary;
+mod grammar;
+mod habits;
+mod hooks;
+mod http;
+mod i18n;
+mod indent;
+mod ipc;
+mod jobs;
+mod journal;
+mod kanban;
+mod lansync;
+mod links;
+mod lint;
+mod logs;
+mod markdown;
+mod math;
+mod merge;
+mod metadata;
+mod minimap;
+mod nextcloud;
+mod noteinfo;
+mod ocr;
+mod outline;
+mod outliner;
+mod pairs;
+mod paste;
+mod plugins;
+mod preview;
+mod properties;
+mod proofread;
+mod publish;
+mod query;
+mod ratelimit;
+mod recovery;
+mod restructure;
+mod roulette;
+mod runner;
+mod scratchpad;
+mod search;
+mod server;
+mod session;
+mod sidebar;
+mod speech;
+mod start;
+mod stats;
+mod status;
+#[cfg(target_os = "android")]
+mod stubs;
+mod suggestions;
+mod svg;
+mod switcher;
+mod tables;
+mod tags;
+mod text;
+mod theme;
+mod thesaurus;
+mod touch;
+mod translate;
+mod tray;
+mod urls;
+mod vault;
+
+/// Results of background jobs the app acts on once they finish.
+enum JobDone {
+    Checked {
+        note: Option<PathBuf>,
+        text: String,
+        result: Result<Vec<LTMatch>, grammar::CheckError>,
+    },
+    Nextcloud(Result<nextcloud::SyncReport, String>),
+    Imported(PathBuf, io::Result<usize>),
+    Ocr(Result<ocr::OcrReport, String>),
+    Clipped(String, Result<PathBuf, String>),
+    /// The title for a URL pasted as `<url>` at an offset of a note.
+    LinkTitle {
+        note: Option<PathBuf>,
+        at: usize,
+        url: String,
+        title: Result<String, String>,
+    },
+    /// A saved note as the on-save commands left it.
+    Formatted {
+        path: PathBuf,
+        text: String,
+        formatted: String,
+        errors: Vec<hooks::HookError>,
+    },
+    /// The output of a code block run from a note.
+    Ran {
+        note: Option<PathBuf>,
+        block: markdown::CodeBlock,
+        output: String,
+    },
+}
+
+pub struct NoteApp {
+    note_content: String,
+    selected_file: Option<String>,
+    suggestions: Vec<LTMatch>,
+    /// The text the suggestions' offsets are into.
+    suggested_text: String,
+    suggestions_view: suggestions::SuggestionsView,
+    show_menu: bool,
+    base_content: String,
+    /// How the open note's file is encoded.
+    encoding: encoding::FileEncoding,
+    line_ending: encoding::LineEnding,
+    merge: Option<MergeView>,
+    /// Nextcloud notes changed on both sides, merged one at a time.
+    nextcloud_conflicts: Vec<nextcloud::Conflict>,
+    /// The Nextcloud conflict the merge view is showing, if any.
+    nextcloud_merge: Option<nextcloud::Conflict>,
+    export_history: bool,
+    /// Also turn headings and the paragraph under them into Anki cards.
+    anki_headings: bool,
+    config: Config,
+    show_settings: bool,
+    backups: BackupScheduler,
+    sync_status: Option<String>,
+    check_state: status::CheckState,
+    autosave: status::Autosave,
+    messages: Option<Receiver<String>>,
+    quick_capture: capture::QuickCapture,
+    tray: Option<tray::Tray>,
+    last_note: Option<PathBuf>,
+    quitting: bool,
+    /// Byte range selected in the editor, if any.
+    selection: Option<Range<usize>>,
+    plugins: plugins::PluginHost,
+    show_plugins: bool,
+    hook_errors: Vec<hooks::HookError>,
+    show_code_blocks: bool,
+    /// URL being entered in the web clipper window, when it is open.
+    clip_url: Option<String>,
+    assistant: assistant::Assistant,
+    autocomplete: completion::Autocomplete,
+    autocorrect: autocorrect::Autocorrect,
+    translate: translate::TranslateView,
+    synonyms: thesaurus::SynonymsView,
+    /// Read on first use and again when its file changes in the settings.
+    thesaurus: Option<Arc<thesaurus::Thesaurus>>,
+    definitions: dictionary::DefinitionView,
+    glossary: glossary::Glossary,
+    dictionary: Option<Arc<dictionary::Dictionary>>,
+    read_aloud: speech::ReadAloud,
+    dictation: dictation::Dictation,
+    ocr_report: Option<ocr::OcrReport>,
+    citations: citations::Library,
+    completion_popup: editor::CompletionPopup,
+    show_preview: bool,
+    show_outline: bool,
+    show_minimap: bool,
+    outliner: outliner::Outliner,
+    /// Markdown shown styled in the editor.
+    live_preview: bool,
+    properties: properties::PropertiesStrip,
+    /// Where the cursor and the view were in recently open notes.
+    positions: session::Positions,
+    /// How the editor was scrolled on the last frame.
+    editor_view: minimap::Viewport,
+    /// The place to put the cursor and the view at in a note just opened.
+    restore: Option<session::Position>,
+    /// Where to scroll the editor to, picked on the minimap.
+    scroll_to: Option<f32>,
+    /// Keys of the sections folded in the open note.
+    folded: HashSet<String>,
+    preview: preview::Preview,
+    lint: lint::LintPanel,
+    gardening: gardening::GardeningView,
+    duplicates: duplicates::DuplicatesView,
+    merge_notes: restructure::MergeNotesView,
+    compare: Option<diff::DiffView>,
+    cleanup_preview: Option<cleanup::CleanupPreview>,
+    show_sidebar: bool,
+    note_list: sidebar::NoteList,
+    metadata: metadata::Metadata,
+    switcher: switcher::QuickSwitcher,
+    archived: archived::ArchivedView,
+    info: noteinfo::InfoPanel,
+    search: search::SearchView,
+    /// Search terms marked in the editor after opening a search result.
+    highlight: Vec<String>,
+    board: kanban::Board,
+    review: flashcards::ReviewView,
+    habits: habits::HabitsView,
+    scratchpad: scratchpad::Scratchpad,
+    clipboard_watcher: capture::ClipboardWatcher,
+    random_note: roulette::RandomNote,
+    /// Shows the start screen until a note is opened or started.
+    show_start: bool,
+    start: start::StartScreen,
+    dashboard: stats::Dashboard,
+    publish: publish::PublishView,
+    collab: collab::CollabView,
+    comments: comments::CommentsView,
+    lan_sync: lansync::LanSync,
+    swipes: touch::SwipeDetector,
+    /// The appearance the fonts and theme were last set up for.
+    appearance: config::AppearanceConfig,
+    theme: theme::ThemeWatcher,
+    /// LanguageTool code the note is checked in.
+    grammar_language: String,
+    grammar_providers: proofread::Providers,
+    logs: logs::LogView,
+    recovery: recovery::RecoveryJournal,
+    recovered: recovery::RecoveryView,
+    jobs: Vec<jobs::JobHandle<JobDone>>,
+    jobs_view: jobs::JobsView,
 }
 
-#[derive(Deserialize, Debug)]
-pub struct LTMatch {
-    message: String,
-    offset: usize,
-    length: usize,
-    replacements: Vec<LTSuggestion>,
+impl NoteApp {
+    pub fn new(cc: &CreationContext<'_>, listener: Option<TcpListener>, initial: Option<String>) -> Self {
+        jobs::init(&cc.egui_ctx);
+        egui_extras::install_image_loaders(&cc.egui_ctx);
+        let config = Config::load();
+        let mut theme = theme::ThemeWatcher::default();
+        theme.refresh(&config.appearance.theme);
+        apply_fonts(&cc.egui_ctx, &config.appearance);
+        apply_style(&cc.egui_ctx, &config.appearance, &theme.theme);
+        http::configure(&config.network);
+        i18n::set_language(&config.language);
+        let session = session::Session::load(cc.storage);
+        let (recovery, recovered) = recovery::RecoveryJournal::start();
+        if config.server.enabled
+            && let Err(err) = server::start(&config.server)
+        {
+            error!("Failed to start API server: {}", err);
+        }
+        let mut app = Self {
+            note_content: String::new(),
+            selected_file: None,
+            suggestions: Vec::new(),
+            suggested_text: String::new(),
+            suggestions_view: suggestions::SuggestionsView::default(),
+            show_menu: false,
+            base_content: String::new(),
+            encoding: encoding::FileEncoding::default(),
+            line_ending: encoding::LineEnding::default(),
+            merge: None,
+            nextcloud_conflicts: Vec::new(),
+            nextcloud_merge: None,
+            export_history: false,
+            anki_headings: false,
+            backups: BackupScheduler::new(&config.backup),
+            quick_capture: capture::QuickCapture::new(&config.capture, &cc.egui_ctx),
+            show_settings: false,
+            sync_status: None,
+            check_state: status::CheckState::default(),
+            autosave: status::Autosave::default(),
+            messages: listener.map(|l| ipc::listen(l, cc.egui_ctx.clone())),
+            tray: tray::start(&cc.egui_ctx),
+            last_note: None,
+            quitting: false,
+            selection: None,
+            plugins: plugins::PluginHost::load(&config.disabled_plugins),
+            show_plugins: false,
+            hook_errors: Vec::new(),
+            show_code_blocks: false,
+            clip_url: None,
+            assistant: assistant::Assistant::default(),
+            autocomplete: completion::Autocomplete::default(),
+            autocorrect: autocorrect::Autocorrect::default(),
+            translate: translate::TranslateView::default(),
+            synonyms: thesaurus::SynonymsView::default(),
+            thesaurus: None,
+            definitions: dictionary::DefinitionView::default(),
+            glossary: glossary::Glossary::default(),
+            dictionary: None,
+            read_aloud: speech::ReadAloud::default(),
+            dictation: dictation::Dictation::default(),
+            ocr_report: None,
+            citations: citations::Library::default(),
+            completion_popup: editor::CompletionPopup::default(),
+            show_preview: session.show_preview,
+            show_outline: session.show_outline,
+            show_minimap: session.show_minimap,
+            outliner: outliner::Outliner::new(session.show_outliner),
+            live_preview: session.live_preview,
+            properties: properties::PropertiesStrip::new(session.show_properties),
+            positions: session.positions,
+            editor_view: minimap::Viewport::default(),
+            restore: None,
+            scroll_to: None,
+            folded: HashSet::new(),
+            preview: preview::Preview::default(),
+            lint: lint::LintPanel::default(),
+            gardening: gardening::GardeningView::default(),
+            duplicates: duplicates::DuplicatesView::default(),
+            merge_notes: restructure::MergeNotesView::default(),
+            compare: None,
+            cleanup_preview: None,
+            show_sidebar: session.show_sidebar,
+            note_list: sidebar::NoteList::default(),
+            metadata: metadata::Metadata::load(),
+            switcher: switcher::QuickSwitcher::default(),
+            archived: archived::ArchivedView::default(),
+            info: noteinfo::InfoPanel::default(),
+            search: search::SearchView::default(),
+            highlight: Vec::new(),
+            board: kanban::Board::default(),
+            review: flashcards::ReviewView::default(),
+            habits: habits::HabitsView::default(),
+            scratchpad: scratchpad::Scratchpad::default(),
+            clipboard_watcher: capture::ClipboardWatcher::default(),
+            random_note: roulette::RandomNote::default(),
+            show_start: true,
+            start: start::StartScreen::default(),
+            dashboard: stats::Dashboard::default(),
+            publish: publish::PublishView::default(),
+            collab: collab::CollabView::default(),
+            comments: comments::CommentsView::default(),
+            lan_sync: lansync::LanSync::new(&config.lan_sync, &cc.egui_ctx),
+            swipes: touch::SwipeDetector::default(),
+            appearance: config.appearance.clone(),
+            theme,
+            grammar_language: session.grammar_language,
+            grammar_providers: proofread::Providers::default(),
+            logs: logs::LogView::default(),
+            recovery,
+            recovered: recovery::RecoveryView::new(recovered),
+            jobs: Vec::new(),
+            jobs_view: jobs::JobsView::default(),
+            config,
+        };
+        app.suggestions_view.open = session.show_suggestions;
+        app.suggestions_view.docked = session.dock_suggestions;
+        app.assistant.open = session.show_assistant;
+        app.comments.open = session.show_comments;
+        app.info.open = session.show_info;
+        if let Some(message) = initial {
+            app.handle_message(&message);
+        } else if let Some(path) = session.open_note.filter(|p| p.is_file()) {
+            app.open_path(&path);
+        }
+        app
+    }
+
+    /// Starts an empty, untitled note once any unsaved edits to the open
+    /// note are settled.
+    pub fn new_note(&mut self) {
+        if self.settle_unsaved() {
+            self.clear_note();
+        }
+    }
+
+    /// Empties the editor for a new note, dropping any unsaved edits.
+    fn clear_note(&mut self) {
+        self.remember_position();
+        self.note_content.clear();
+        self.base_content.clear();
+        self.selected_file = None;
+        self.suggestions.clear();
+        self.check_state = status::CheckState::Idle;
+        self.autosave.reset();
+        self.restore = Some(session::Position::default());
+    }
+
+    fn comment_on_selection(&mut self) {
+        if let Some(selection) = self.selection.clone() {
+            self.comments.comment_on(selection);
+        }
+    }
+
+    /// Opens today's daily note, creating it with a date heading.
+    fn open_daily_note(&mut self) {
+        let path = vault::daily_note_path(chrono::Local::now().date_naive());
+        if !path.exists() {
+            let heading = format!("# {}\n\n", chrono::Local::now().format("%Y-%m-%d"));
+            if let Err(err) = journal::write(&path, &heading) {
+                error!("Failed to create daily note: {}", err);
+                return;
+            }
+            self.note_list.invalidate();
+        }
+        self.open_path(&path);
+    }
+
+    pub fn load_file(&mut self) {
+        if let Some(path) = rfd::FileDialog::new().pick_file() {
+            self.open_path(&path);
+        }
+    }
+
+    /// Asks whether to save the open note's unsaved edits, throw them away
+    /// or keep the note open, before something replaces it. Returns whether
+    /// to go on.
+    fn settle_unsaved(&mut self) -> bool {
+        if self.note_content == self.base_content {
+            return true;
+        }
+        let name = self.selected_file.clone().unwrap_or_else(|| t("Untitled"));
+        let (save, discard) = (t("Save"), t("Discard"));
+        let choice = rfd::MessageDialog::new()
+            .set_title(t("Unsaved changes"))
+            .set_description(tf("Save the changes to {}?", &[&name]))
+            .set_buttons(rfd::MessageButtons::YesNoCancelCustom(save.clone(), discard.clone(), t("Cancel")))
+            .show();
+        match choice {
+            rfd::MessageDialogResult::Yes => self.save_file(),
+            rfd::MessageDialogResult::Custom(button) if button == save => self.save_file(),
+            rfd::MessageDialogResult::No => return true,
+            rfd::MessageDialogResult::Custom(button) if button == discard => return true,
+            _ => return false,
+        }
+        // Saving can stop short: a merge to resolve, a locked note, a
+        // cancelled dialog or a failed write.
+        self.note_content == self.base_content
+    }
+
+    /// Opens the note at `path` in the editor, once any unsaved edits to the
+    /// open note are settled. Opening the note that is already open keeps
+    /// its unsaved edits.
+    pub fn open_path(&mut self, path: &Path) {
+        if self.last_note.as_deref() == Some(path) && self.note_content != self.base_content {
+            return;
+        }
+        if !self.settle_unsaved() {
+            return;
+        }
+        match encoding::read(path) {
+            Ok((content, encoding)) => {
+                if !encoding.is_utf8() {
+                    let saved = if self.config.editor.keep_encoding { "kept" } else { "converted to UTF-8" };
+                    warn!("{} is in {}; it is {} when saved", path.display(), encoding.name(), saved);
+                }
+                self.remember_position();
+                self.encoding = encoding;
+                self.line_ending = encoding::LineEnding::detect(&content);
+                let content = encoding::LineEnding::normalize(&content);
+                self.base_content = content.clone();
+                self.note_content = content;
+                self.highlight.clear();
+                self.check_state = status::CheckState::Idle;
+                self.autosave.reset();
+                self.set_note_path(path);
+                self.restore = Some(self.positions.get(&vault::note_key(path)).unwrap_or_default());
+                if self.config.editor.touch_mode {
+                    self.show_sidebar = false;
+                }
+            }
+            Err(err) => error!("Failed to open {}: {}", path.display(), err),
+        }
+    }
+
+    /// Notes where the cursor and the view are in the open note, to go back
+    /// there when it is opened again.
+    fn remember_position(&mut self) {
+        if let Some(path) = &self.last_note {
+            let cursor = self.selection.as_ref().map_or(0, |r| r.end);
+            let folds = self.folded.iter().cloned().collect();
+            self.positions.remember(vault::note_key(path), session::Position { cursor, scroll: self.editor_view.offset, folds });
+        }
+    }
+
+    /// Points the editor at `path`, e.g. after the open note was renamed.
+    fn set_note_path(&mut self, path: &Path) {
+        self.show_start = false;
+        self.last_note = Some(path.to_path_buf());
+        self.selected_file = match path.strip_prefix(vault::NOTES_DIR) {
+            Ok(relative) => Some(relative.to_string_lossy().into_owned()),
+            Err(_) => path.file_name().and_then(|s| s.to_str()).map(String::from),
+        };
+    }
+
+    /// Handles a `noteapp://` link or file path passed on the command line
+    /// or forwarded from another instance.
+    fn handle_message(&mut self, message: &str) {
+        let message = message.trim();
+        if let Some(links::LinkAction::Open(name)) = links::parse(message) {
+            match links::resolve_note(&name) {
+                Some(path) => self.open_path(&path),
+                None => warn!("No note named {}", name),
+            }
+        } else if Path::new(message).is_file() {
+            self.open_path(Path::new(message));
+        }
+    }
+
+    /// Follows a link target: web links open in the browser, `noteapp://`
+    /// links and relative paths to notes open in the editor.
+    fn follow_link(&mut self, ctx: &Context, target: &str) {
+        if target.starts_with(links::SCHEME) {
+            self.handle_message(target);
+        } else if urls::is_url(target) {
+            ctx.open_url(egui::OpenUrl::new_tab(target));
+        } else {
+            let relative = links::percent_decode(target.split('#').next().unwrap_or_default());
+            let dir = self.last_note.as_ref().and_then(|p| p.parent()).unwrap_or(Path::new(vault::NOTES_DIR));
+            let path = dir.join(relative);
+            if path.is_file() {
+                self.open_path(&path);
+            } else {
+                warn!("Link target not found: {}", path.display());
+            }
+        }
+    }
+
+    /// Opens a text file dropped on the window in place of the open note,
+    /// asking about unsaved edits first, and offers to attach anything else
+    /// to the open note, linked at the cursor. There is one editor, so of
+    /// several text files only the first is opened.
+    fn handle_dropped_files(&mut self, ctx: &Context) {
+        let dropped = ctx.input(|i| i.raw.dropped_files.clone());
+        let mut opened = false;
+        for path in dropped.into_iter().filter_map(|f| f.path) {
+            let text = path.extension().is_some_and(|e| ["md", "markdown", "txt"].iter().any(|x| e.eq_ignore_ascii_case(x)));
+            if text {
+                if opened {
+                    warn!("Not opening {}: only one dropped note is opened at a time", path.display());
+                } else {
+                    self.open_path(&path);
+                    opened = true;
+                }
+                continue;
+            }
+            if !self.editable() {
+                continue;
+            }
+            let note = self.selected_file.as_deref().map_or_else(|| t("Untitled"), |f| Path::new(f).file_stem().and_then(|s| s.to_str()).unwrap_or(f).to_owned());
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let confirmed = rfd::MessageDialog::new()
+                .set_title(t("Attach file"))
+                .set_description(tf("Attach {} to {}?", &[&name, &note]))
+                .set_buttons(rfd::MessageButtons::YesNo)
+                .show();
+            if confirmed != rfd::MessageDialogResult::Yes {
+                continue;
+            }
+            match vault::attach(&path, self.last_note.as_deref()) {
+                Ok(link) => {
+                    let len = self.note_content.len();
+                    let at = self.selection.as_ref().map(|r| r.end).filter(|&at| self.note_content.is_char_boundary(at)).unwrap_or(len);
+                    self.note_content.insert_str(at, &link);
+                    self.move_cursor(ctx, at + link.len());
+                }
+                Err(err) => error!("Failed to attach {}: {}", path.display(), err),
+            }
+        }
+    }
+
+    /// What to paste in place of the clipboard's `text`: a link for a URL,
+    /// Markdown for HTML, or `None` to paste it as it is.
+    fn paste_replacement(&mut self, text: &str) -> Option<String> {
+        if !urls::is_url(text) {
+            return self.config.editor.paste_html.then(paste::clipboard_markdown).flatten();
+        }
+        let url = text.trim().to_owned();
+        let selection = self.selection.clone().filter(|r| self.note_content.get(r.clone()).is_some())?;
+        let selected = &self.note_content[selection.clone()];
+        if !selected.is_empty() {
+            return (!selected.contains('\n')).then(|| urls::make_link(selected, &url));
+        }
+        if !self.config.editor.fetch_link_titles {
+            return None;
+        }
+        let (note, at, fetched) = (self.last_note.clone(), selection.start, url.clone());
+        self.jobs.push(jobs::spawn("Fetching the page title", move |_| JobDone::LinkTitle {
+            title: clipper::fetch_title(&fetched),
+            note,
+            at,
+            url: fetched,
+        }));
+        Some(urls::make_link("", &url))
+    }
+
+    /// Pastes the clipboard's text without converting any HTML.
+    fn paste_plain(&mut self, ctx: &Context) {
+        if !self.editable() {
+            return;
+        }
+        let Some(text) = paste::clipboard_text() else {
+            return;
+        };
+        let len = self.note_content.len();
+        let range = self.selection.clone().filter(|r| self.note_content.get(r.clone()).is_some()).unwrap_or(len..len);
+        let cursor = range.start + text.len();
+        self.note_content.replace_range(range, &text);
+        self.move_cursor(ctx, cursor);
+    }
+
+    fn paste_as_link(&mut self) {
+        if !self.editable() {
+            return;
+        }
+        let url = match arboard::Clipboard::new().and_then(|mut c| c.get_text()) {
+            Ok(text) => text.trim().to_owned(),
+            Err(err) => {
+                error!("Failed to read the clipboard: {}", err);
+                return;
+            }
+        };
+        if !urls::is_url(&url) {
+            warn!("The clipboard does not hold a URL");
+            return;
+        }
+        let len = self.note_content.len();
+        let range = self.selection.clone().filter(|r| self.note_content.get(r.clone()).is_some()).unwrap_or(len..len);
+        let link = urls::make_link(&self.note_content[range.clone()], &url);
+        self.note_content.replace_range(range, &link);
+    }
+
+    /// Picks up changes made to the open note on disk, unless it has
+    /// unsaved edits (those get merged on the next save).
+    fn reload_if_clean(&mut self) {
+        if let Some(path) = self.last_note.clone()
+            && path.is_file()
+            && self.note_content == self.base_content
+        {
+            self.open_path(&path);
+        }
+    }
+
+    /// Moves each H1/H2 section of the open note into a note of its own and
+    /// leaves links to them behind.
+    fn split_note(&mut self) {
+        let Some(path) = self.last_note.clone() else {
+            warn!("Save the note before splitting it");
+            return;
+        };
+        if !self.editable() {
+            return;
+        }
+        let confirmed = rfd::MessageDialog::new()
+            .set_title(t("Split note"))
+            .set_description(tf("Split {} into one note per heading?", &[&path.display()]))
+            .set_buttons(rfd::MessageButtons::YesNo)
+            .show();
+        if confirmed != rfd::MessageDialogResult::Yes {
+            return;
+        }
+        match restructure::split_note(&path, &self.note_content) {
+            Ok(index) => {
+                self.note_content = index;
+                self.write_note(&path);
+            }
+            Err(err) => error!("Failed to split note: {}", err),
+        }
+    }
+
+    /// Picks a note, or a snapshot when starting in the history folder, to
+    /// compare the open note with.
+    fn compare_with(&mut self, dir: &str) {
+        if let Some(path) = rfd::FileDialog::new().set_directory(dir).pick_file() {
+            match diff::DiffView::open(&path) {
+                Ok(view) => self.compare = Some(view),
+                Err(err) => error!("Failed to open {}: {}", path.display(), err),
+            }
+        }
+    }
+
+    /// Toggles `locked` in the note's front matter and saves that change
+    /// straight away, since saving is blocked while locked. Edits from
+    /// before locking are saved the usual way first, so only the flag is
+    /// written here, without the on-save formatters.
+    fn toggle_lock(&mut self) {
+        let locked = frontmatter::is_locked(&self.note_content);
+        if !locked && self.note_content != self.base_content {
+            self.save_file();
+            if self.note_content != self.base_content {
+                warn!("The note was not saved; it stays unlocked");
+                return;
+            }
+        }
+        self.note_content = frontmatter::set(&self.note_content, "locked", (!locked).then_some("true"));
+        let Some(path) = self.last_note.clone() else {
+            self.save_file();
+            return;
+        };
+        match self.changed_on_disk(&path) {
+            Some(disk) => self.begin_merge(path, disk),
+            None => self.store_note(&path),
+        }
+    }
+
+    /// Whether the open note may be changed. A locked note is read-only
+    /// until it is unlocked; every edit made outside the text box asks here.
+    fn editable(&self) -> bool {
+        let locked = frontmatter::is_locked(&self.note_content);
+        if locked {
+            warn!("The note is locked; unlock it to change it");
+        }
+        !locked
+    }
+
+    /// Cycles the note's `dir` field between right to left, left to right
+    /// and unset, where the first letter decides.
+    fn toggle_direction(&mut self) {
+        if !self.editable() {
+            return;
+        }
+        let next = match bidi::note_override(&self.note_content) {
+            None => Some("rtl"),
+            Some(bidi::Direction::Rtl) => Some("ltr"),
+            Some(bidi::Direction::Ltr) => None,
+        };
+        self.note_content = frontmatter::set(&self.note_content, "dir", next);
+    }
+
+    /// Saves and moves the open note into the archive, then closes it.
+    fn archive_note(&mut self) {
+        let Some(path) = self.last_note.clone() else {
+            return;
+        };
+        if self.note_content != self.base_content {
+            self.save_file();
+            if self.note_content != self.base_content {
+                warn!("The note was not saved; it stays open and unarchived");
+                return;
+            }
+        }
+        match archived::archive(&path, &mut self.metadata) {
+            Ok(_) => {
+                self.new_note();
+                self.last_note = None;
+                self.show_start = true;
+                self.note_list.invalidate();
+            }
+            Err(err) => error!("Failed to archive note: {}", err),
+        }
+    }
+
+    pub fn save_file(&mut self) {
+        if frontmatter::is_locked(&self.note_content) && self.last_note.is_some() {
+            warn!("The note is locked; unlock it to save changes");
+            return;
+        }
+        if let Some(filename) = &self.selected_file {
+            let path = PathBuf::from(format!("notes/{}", filename));
+            if let Some(disk) = self.changed_on_disk(&path) {
+                self.begin_merge(path, disk);
+                return;
+            }
+            self.write_note(&path);
+        } else if let Some(path) = rfd::FileDialog::new().save_file() {
+            self.write_note(&path);
+        }
+    }
+
+    /// Switches the open note between LF and CRLF and saves it that way.
+    fn convert_line_endings(&mut self) {
+        self.line_ending = self.line_ending.other();
+        if self.selected_file.is_some() {
+            self.save_file();
+        }
+    }
+
+    /// The note as it is on disk, when someone else changed it since it was
+    /// loaded and saving would overwrite that.
+    fn changed_on_disk(&self, path: &Path) -> Option<String> {
+        let disk = encoding::read(path).map(|(disk, _)| encoding::LineEnding::normalize(&disk)).ok()?;
+        (disk != self.base_content && disk != self.note_content).then_some(disk)
+    }
+
+    fn write_note(&mut self, path: &Path) {
+        let hooked = self.plugins.on_save(&self.note_content);
+        let hooked = if self.config.editor.format_tables_on_save { tables::format_all(&hooked) } else { hooked };
+        if hooked != self.note_content {
+            self.note_content = hooked;
+        }
+        if cleanup::enabled(&self.config.editor) {
+            let cleaned = cleanup::clean(&self.note_content, &self.config.editor);
+            if cleaned != self.note_content {
+                if self.config.editor.cleanup_previewed {
+                    self.note_content = cleaned;
+                } else if self.cleanup_preview.is_none() {
+                    // Saved as it is this time; the preview asks about next time.
+                    self.cleanup_preview = Some(cleanup::CleanupPreview::new(path, &self.note_content, &cleaned));
+                }
+            }
+        }
+        self.store_note(path);
+        if self.note_content == self.base_content {
+            self.run_save_hooks(path);
+        }
+    }
+
+    /// Pipes the saved note through the on-save commands in the background;
+    /// their result is saved too if the note was not edited meanwhile.
+    fn run_save_hooks(&mut self, path: &Path) {
+        if self.config.on_save_commands.iter().all(|h| h.command.trim().is_empty()) {
+            return;
+        }
+        let (hooks, path, text) = (self.config.on_save_commands.clone(), path.to_path_buf(), self.note_content.clone());
+        self.jobs.push(jobs::spawn("Running save hooks", move |job| {
+            let (formatted, errors) = hooks::run_formatters(&hooks, &text, job);
+            JobDone::Formatted { path, text, formatted, errors }
+        }));
+    }
+
+    /// Writes the note as it is, in its encoding and line endings.
+    fn store_note(&mut self, path: &Path) {
+        let on_disk = self.line_ending.apply(&self.note_content);
+        let encoded = match self.encoding {
+            encoding if encoding.is_utf8() || !self.config.editor.keep_encoding => None,
+            encoding => {
+                let bytes = encoding::encode(&on_disk, encoding);
+                if bytes.is_none() {
+                    warn!("The note has characters {} cannot hold; saving it as UTF-8", encoding.name());
+                }
+                bytes
+            }
+        };
+        let written = match &encoded {
+            Some(bytes) => journal::write_encoded(path, bytes, &on_disk),
+            None => journal::write(path, &on_disk),
+        };
+        match written {
+            Ok(()) => {
+                if encoded.is_none() {
+                    self.encoding = encoding::FileEncoding::default();
+                }
+                self.base_content = self.note_content.clone();
+                self.autosave.reset();
+                self.note_list.invalidate();
+                self.comments.sync(Some(path), &self.note_content, &self.metadata);
+                self.comments.save(&mut self.metadata);
+            }
+            Err(err) => error!("Failed to save file: {}", err),
+        }
+    }
+
+    /// The file changed on disk since it was loaded: merge instead of overwriting.
+    fn begin_merge(&mut self, path: PathBuf, remote: String) {
+        let base = self.base_content.clone();
+        self.merge_with(path, &base, &remote);
+    }
+
+    /// Merges `remote` into the open note, asking only if both changed the
+    /// same lines. Returns whether the merge is done.
+    fn merge_with(&mut self, path: PathBuf, base: &str, remote: &str) -> bool {
+        let view = MergeView::new(path.clone(), base, &self.note_content, remote);
+        if !view.has_conflicts()
+            && let Some(merged) = view.result()
+        {
+            self.note_content = merged;
+            self.write_note(&path);
+            return true;
+        }
+        self.merge = Some(view);
+        false
+    }
+
+    /// Opens the next note Nextcloud reported as changed on both sides and
+    /// merges the server's version into it.
+    fn merge_next_nextcloud_conflict(&mut self) {
+        let Some(conflict) = self.nextcloud_conflicts.pop() else {
+            return;
+        };
+        self.open_path(&conflict.path);
+        if self.last_note.as_ref() != Some(&conflict.path) {
+            return;
+        }
+        if self.merge_with(conflict.path.clone(), &conflict.base, &conflict.remote) {
+            self.nextcloud_resolved(&conflict);
+        } else {
+            self.nextcloud_merge = Some(conflict);
+        }
+    }
+
+    fn nextcloud_resolved(&self, conflict: &nextcloud::Conflict) {
+        if let Err(err) = conflict.resolved() {
+            error!("Failed to record the Nextcloud merge of {}: {}", conflict.path.display(), err);
+        }
+    }
+
+    pub fn export_vault(&self) {
+        let Some(dest) = rfd::FileDialog::new()
+            .add_filter(t("Zip archive"), &["zip"])
+            .set_file_name("vault.zip")
+            .save_file()
+        else {
+            return;
+        };
+        let history = self.export_history;
+        jobs::spawn("Exporting the vault", move |job| match archive::export_vault(&dest, history, job) {
+            Ok(count) => info!("Exported {} files to {}", count, dest.display()),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => info!("Vault export cancelled"),
+            Err(err) => error!("Failed to export vault: {}", err),
+        });
+    }
+
+    fn export_anki(&mut self) {
+        let Some(dest) = rfd::FileDialog::new()
+            .add_filter(t("Anki text file"), &["txt", "csv"])
+            .set_file_name("flashcards.txt")
+            .save_file()
+        else {
+            return;
+        };
+        let (notes, headings) = (self.note_list.notes().to_vec(), self.anki_headings);
+        jobs::spawn("Exporting flashcards", move |job| match anki::export(&notes, &dest, headings, job) {
+            Ok(count) => info!("Exported {} cards to {}", count, dest.display()),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => info!("Flashcard export cancelled"),
+            Err(err) => error!("Failed to export flashcards: {}", err),
+        });
+    }
+
+    pub fn import_vault(&mut self) {
+        let Some(src) = rfd::FileDialog::new().add_filter(t("Zip archive"), &["zip"]).pick_file() else {
+            return;
+        };
+        let confirmed = rfd::MessageDialog::new()
+            .set_title(t("Import vault"))
+            .set_description(t("Notes with the same name will be overwritten. Continue?"))
+            .set_buttons(rfd::MessageButtons::YesNo)
+            .show();
+        if confirmed != rfd::MessageDialogResult::Yes {
+            return;
+        }
+        self.jobs.push(jobs::spawn("Importing a vault", move |job| JobDone::Imported(src.clone(), archive::import_vault(&src, job))));
+    }
+
+    pub fn sync_nextcloud(&mut self) {
+        let client = nextcloud::NextcloudClient::new(&self.config.nextcloud);
+        self.sync_status = Some(t("Nextcloud: syncing…"));
+        self.jobs.push(jobs::spawn("Syncing with Nextcloud", move |_| JobDone::Nextcloud(client.sync())));
+    }
+
+    fn nextcloud_synced(&mut self, result: Result<nextcloud::SyncReport, String>) {
+        let status = match &result {
+            Ok(report) if report.conflicts.is_empty() => tf("Nextcloud: {} pulled, {} pushed", &[&report.pulled, &report.pushed]),
+            Ok(report) => tf(
+                "Nextcloud: {} pulled, {} pushed, {} changed on both sides",
+                &[&report.pulled, &report.pushed, &report.conflicts.len()],
+            ),
+            Err(err) => {
+                error!("Nextcloud sync failed: {}", err);
+                tf("Nextcloud sync failed: {}", &[err])
+            }
+        };
+        self.sync_status = Some(status);
+        if let Ok(report) = result {
+            self.nextcloud_conflicts = report.conflicts;
+        }
+    }
+
+    /// The thesaurus from the settings, reading it in the background the
+    /// first time.
+    fn thesaurus(&mut self) -> Arc<thesaurus::Thesaurus> {
+        let path = &self.config.grammar.thesaurus;
+        match &self.thesaurus {
+            Some(thesaurus) if thesaurus.path == *path => thesaurus.clone(),
+            _ => {
+                let thesaurus = Arc::new(thesaurus::Thesaurus::new(path));
+                let loading = thesaurus.clone();
+                jobs::spawn("Reading the thesaurus", move |_| loading.load());
+                self.thesaurus = Some(thesaurus.clone());
+                thesaurus
+            }
+        }
+    }
+
+    /// Looks up the word at the cursor, showing the synonyms at `pos`.
+    fn show_synonyms(&mut self, pos: Option<egui::Pos2>) {
+        if let Some(cursor) = self.selection.as_ref().map(|r| r.start) {
+            self.thesaurus();
+            self.synonyms.open_for(&self.note_content, cursor, pos);
+        }
+    }
+
+    /// The dictionary from the settings, if one is set, indexing it in the
+    /// background the first time.
+    fn dictionary(&mut self) -> Option<Arc<dictionary::Dictionary>> {
+        let path = &self.config.grammar.dictionary;
+        if path.trim().is_empty() {
+            return None;
+        }
+        match &self.dictionary {
+            Some(dictionary) if dictionary.path == *path => Some(dictionary.clone()),
+            _ => {
+                let dictionary = Arc::new(dictionary::Dictionary::new(path));
+                let loading = dictionary.clone();
+                jobs::spawn("Indexing the dictionary", move |_| loading.load());
+                self.dictionary = Some(dictionary.clone());
+                Some(dictionary)
+            }
+        }
+    }
+
+    /// Defines the word at the cursor in a window at `pos`.
+    fn show_definition(&mut self, pos: Option<egui::Pos2>) {
+        if self.config.grammar.dictionary.trim().is_empty() {
+            self.show_settings = true;
+            return;
+        }
+        if let Some(range) = self.selection.as_ref().and_then(|r| text::word_at(&self.note_content, r.start)) {
+            self.dictionary();
+            self.definitions.open_for(&self.note_content[range], pos);
+        }
+    }
+
+    /// Writes out the glossary term at the cursor, keeping it after in
+    /// brackets: "API" becomes "Application Programming Interface (API)".
+    fn expand_term(&mut self, ctx: &Context) {
+        if !self.editable() {
+            return;
+        }
+        let Some(range) = self.selection.as_ref().and_then(|r| text::word_at(&self.note_content, r.start)) else {
+            return;
+        };
+        let term = &self.note_content[range.clone()];
+        let Some(expansion) = self.glossary.get(term) else {
+            warn!("{} is not in the glossary", term);
+            return;
+        };
+        let expanded = format!("{} ({})", expansion, term);
+        let cursor = range.start + expanded.len();
+        self.note_content.replace_range(range, &expanded);
+        self.move_cursor(ctx, cursor);
+    }
+
+    /// The language the open note is checked in: its `lang:` field, or the
+    /// one picked in the menu.
+    fn check_language(&self) -> String {
+        frontmatter::get(&self.note_content, "lang").filter(|l| !l.is_empty()).unwrap_or_else(|| self.grammar_language.clone())
+    }
+
+    pub fn check_suggestions(&mut self) {
+        let (text, language) = (self.note_content.clone(), self.check_language());
+        let providers = self.grammar_providers.get(&self.config.grammar);
+        let note = self.last_note.clone();
+        self.check_state = status::CheckState::Running;
+        self.suggestions_view.open = true;
+        self.jobs.push(jobs::spawn("Checking grammar", move |_| {
+            let result = proofread::check(&providers, &text, &language);
+            JobDone::Checked { note, text, result }
+        }));
+    }
+
+    /// Acts on background jobs that have finished.
+    fn poll_jobs(&mut self) {
+        let mut finished = Vec::new();
+        self.jobs.retain(|job| match job.try_recv() {
+            Ok(done) => {
+                finished.push(done);
+                false
+            }
+            Err(TryRecvError::Empty) => true,
+            Err(TryRecvError::Disconnected) => false,
+        });
+        for done in finished {
+            match done {
+                // Results for a note that is no longer open are dropped.
+                JobDone::Checked { note, .. } if note != self.last_note => {}
+                JobDone::Checked { text, result, .. } => match result {
+                    Ok(mut matches) => {
+                        // The offsets are into the text that was checked;
+                        // carry them over to what was typed since.
+                        suggestions::remap(&mut matches, &text, &self.note_content);
+                        self.suggestions = matches;
+                        self.suggested_text = self.note_content.clone();
+                        self.check_state = status::CheckState::Done;
+                    }
+                    Err(err) => {
+                        match &err {
+                            // The status bar counts down until checks go out again.
+                            grammar::CheckError::CoolingDown(wait) => info!("LanguageTool asked to slow down for {}s", wait.as_secs()),
+                            err => error!("Suggestion error: {}", err),
+                        }
+                        self.check_state = status::CheckState::Failed(err.to_string());
+                    }
+                },
+                JobDone::Nextcloud(result) => self.nextcloud_synced(result),
+                JobDone::Imported(src, result) => {
+                    // Files restored before a cancel or an error are kept.
+                    self.note_list.invalidate();
+                    self.reload_if_clean();
+                    // Take up the settings the archive may have brought, or the
+                    // next save would write the old ones back over them.
+                    self.config = Config::load();
+                    i18n::set_language(&self.config.language);
+                    match result {
+                        Ok(count) => info!("Restored {} files from {}; settings marked \"restart to apply\" need a restart", count, src.display()),
+                        Err(err) if err.kind() == io::ErrorKind::Interrupted => info!("Import from {} cancelled", src.display()),
+                        Err(err) => error!("Failed to import vault: {}", err),
+                    }
+                }
+                JobDone::Ocr(result) => match result {
+                    Ok(report) => {
+                        self.note_list.invalidate();
+                        self.open_path(&report.note);
+                        self.ocr_report = Some(report);
+                    }
+                    Err(err) => error!("OCR import failed: {}", err),
+                },
+                JobDone::LinkTitle { note, at, url, title } => match title {
+                    Ok(title) if note == self.last_note && self.editable() => {
+                        let placeholder = format!("<{}>", url);
+                        // Look where it was pasted first; edits before it may have moved it.
+                        let found = if self.note_content.get(at..at + placeholder.len()) == Some(placeholder.as_str()) {
+                            Some(at)
+                        } else {
+                            self.note_content.find(&placeholder)
+                        };
+                        if let Some(start) = found {
+                            let link = urls::make_link(&title.replace('[', "\\[").replace(']', "\\]"), &url);
+                            self.note_content.replace_range(start..start + placeholder.len(), &link);
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(err) => warn!("Failed to fetch the title of {}: {}", url, err),
+                },
+                JobDone::Ran { note, block, output } if note == self.last_note && self.editable() => {
+                    // Edits while it ran may have moved the block; it gets no output if it changed.
+                    let current = markdown::fenced_blocks(&self.note_content)
+                        .into_iter()
+                        .filter(|b| b.lang == block.lang && b.code == block.code)
+                        .min_by_key(|b| b.range.start.abs_diff(block.range.start));
+                    if let Some(current) = current {
+                        runner::insert_output(&mut self.note_content, &current, &output);
+                    }
+                }
+                JobDone::Ran { .. } => {}
+                JobDone::Formatted { path, text, formatted, errors } => {
+                    self.hook_errors = errors;
+                    let unchanged = self.note_content == text && self.base_content == text;
+                    if unchanged && formatted != text && self.last_note.as_ref().is_none_or(|note| *note == path) {
+                        self.note_content = formatted;
+                        self.store_note(&path);
+                    }
+                }
+                JobDone::Clipped(url, result) => match result {
+                    Ok(path) => {
+                        self.note_list.invalidate();
+                        self.open_path(&path);
+                    }
+                    Err(err) => error!("Failed to clip {}: {}", url, err),
+                },
+            }
+        }
+    }
 }
 
-#[derive(Deserialize, Debug)]
-pub struct LTSuggestion {
-    value: String,
+impl NoteApp {
+    fn run_code_block(&mut self, block: &markdown::CodeBlock) {
+        if !self.editable() {
+            return;
+        }
+        if self.config.code.confirm_runs {
+            let confirmed = rfd::MessageDialog::new()
+                .set_title(t("Run code block"))
+                .set_description(tf("Run this {} code on your machine?", &[&block.lang]))
+                .set_buttons(rfd::MessageButtons::YesNo)
+                .show();
+            if confirmed != rfd::MessageDialogResult::Yes {
+                return;
+            }
+        }
+        let (note, block, runners) = (self.last_note.clone(), block.clone(), self.config.code.runners.clone());
+        self.jobs.push(jobs::spawn(format!("Running a {} block", block.lang), move |job| JobDone::Ran {
+            output: runner::run(&block, &runners, job).unwrap_or_else(|err| err),
+            note,
+            block,
+        }));
+    }
+
+    fn show_code_blocks(&mut self, ctx: &Context) {
+        let mut run = None;
+        egui::Window::new(t("▶ Code Blocks"))
+            .open(&mut self.show_code_blocks)
+            .show(ctx, |ui| {
+                let blocks = markdown::fenced_blocks(&self.note_content);
+                let runnable: Vec<_> = blocks.iter().filter(|b| b.lang != "output").collect();
+                if runnable.is_empty() {
+                    ui.weak(t("No fenced code blocks in this note."));
+                }
+                for block in runnable {
+                    ui.horizontal(|ui| {
+                        let known = self.config.code.runners.iter().any(|r| r.language == block.lang);
+                        if ui.add_enabled(known, egui::Button::new("▶")).clicked() {
+                            run = Some(block.clone());
+                        }
+                        let first = block.code.lines().next().unwrap_or_default();
+                        ui.label(egui::RichText::new(&block.lang).strong());
+                        ui.label(egui::RichText::new(first).monospace().weak());
+                    });
+                }
+            });
+        if let Some(block) = run {
+            self.run_code_block(&block);
+        }
+    }
+
+    fn import_ocr(&mut self) {
+        let Some(source) = rfd::FileDialog::new()
+            .add_filter(t("Images and PDFs"), &["png", "jpg", "jpeg", "tif", "tiff", "bmp", "pdf"])
+            .pick_file()
+        else {
+            return;
+        };
+        let language = if self.config.ocr_language.is_empty() { "eng".to_owned() } else { self.config.ocr_language.clone() };
+        self.jobs.push(jobs::spawn("Reading text from an image", move |_| JobDone::Ocr(ocr::import(&source, &language))));
+    }
+
+    fn export_pandoc(&mut self) {
+        let stem = self
+            .selected_file
+            .as_deref()
+            .and_then(|f| Path::new(f).file_stem())
+            .and_then(|s| s.to_str())
+            .unwrap_or("note");
+        let Some(dest) = rfd::FileDialog::new()
+            .set_file_name(format!("{}.docx", stem))
+            .add_filter(t("Documents"), &["docx", "odt", "pdf", "html", "tex", "epub"])
+            .save_file()
+        else {
+            return;
+        };
+        let (text, bibtex, config) = (self.note_content.clone(), self.citations.bibtex.clone(), self.config.citations.clone());
+        jobs::spawn("Exporting with pandoc", move |_| {
+            if let Err(err) = citations::export(&text, &bibtex, &config, &dest) {
+                error!("Pandoc export failed: {}", err);
+            }
+        });
+    }
+
+    /// Applies a table command at the cursor.
+    fn edit_table(&mut self, ctx: &Context, edit: tables::TableEdit) {
+        let Some(cursor) = self.selection.as_ref().map(|r| r.end) else {
+            return;
+        };
+        if let Some((text, cursor)) = tables::apply(&self.note_content, cursor, edit) {
+            self.set_text_and_cursor(ctx, text, cursor);
+        }
+    }
+
+    /// Replaces the whole note and moves the cursor to a byte offset in it.
+    fn set_text_and_cursor(&mut self, ctx: &Context, text: String, cursor: usize) {
+        if !self.editable() {
+            return;
+        }
+        self.note_content = text;
+        self.move_cursor(ctx, cursor);
+    }
+
+    /// Closes brackets and quotes as they are typed and puts them around
+    /// the selection, taking those keys from the editor.
+    fn type_pairs(&mut self, ctx: &Context, selection: Range<usize>) {
+        let typed = ctx.input(|i| {
+            let mut texts = i.events.iter().filter_map(|e| match e {
+                egui::Event::Text(text) => Some(text.clone()),
+                _ => None,
+            });
+            // Several at once, e.g. from an input method, go to the editor.
+            match (texts.next(), texts.next()) {
+                (Some(text), None) => Some(text),
+                _ => None,
+            }
+        });
+        let edit = match typed.and_then(|typed| pairs::typed(&self.note_content, selection.clone(), &typed)) {
+            Some(edit) => {
+                ctx.input_mut(|i| i.events.retain(|e| !matches!(e, egui::Event::Text(_))));
+                Some(edit)
+            }
+            None if selection.is_empty() && ctx.input(|i| i.key_pressed(egui::Key::Backspace) && i.modifiers.is_none()) => {
+                pairs::backspace(&self.note_content, selection.start).map(|(text, cursor)| {
+                    ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Backspace));
+                    (text, cursor..cursor)
+                })
+            }
+            None => None,
+        };
+        if let Some((text, selection)) = edit {
+            self.note_content = text;
+            self.autocomplete.edited();
+            self.autosave.edited();
+            self.select_range(ctx, selection);
+        }
+    }
+
+    /// Changes heading levels or list indentation in the selection with
+    /// one of the `indent` edits.
+    fn restructure(&mut self, ctx: &Context, edit: impl FnOnce(&str, Range<usize>) -> Option<(String, Range<usize>)>) {
+        let selection = self.selection.clone().unwrap_or(0..0);
+        if self.note_content.get(selection.clone()).is_none() || !self.editable() {
+            return;
+        }
+        if let Some((text, selection)) = edit(&self.note_content, selection) {
+            self.note_content = text;
+            self.autocomplete.edited();
+            self.autosave.edited();
+            self.select_range(ctx, selection);
+        }
+    }
+
+    /// Moves the editor cursor to a byte offset and focuses the editor.
+    fn move_cursor(&mut self, ctx: &Context, cursor: usize) {
+        let chars = self.note_content[..cursor].chars().count();
+        editor::set_cursor(ctx, chars..chars);
+        ctx.memory_mut(|m| m.request_focus(editor::id()));
+        self.selection = Some(cursor..cursor);
+    }
+
+    /// Selects a byte range of the note in the editor.
+    fn select_range(&mut self, ctx: &Context, range: Range<usize>) {
+        if self.note_content.get(range.clone()).is_none() {
+            return;
+        }
+        let start = self.note_content[..range.start].chars().count();
+        let end = self.note_content[..range.end].chars().count();
+        editor::set_cursor(ctx, start..end);
+        ctx.memory_mut(|m| m.request_focus(editor::id()));
+        self.selection = Some(range);
+    }
+
+    /// Applies a button from the touch formatting bar to the selection.
+    fn format_selection(&mut self, ctx: &Context, format: touch::Format) {
+        if !self.editable() {
+            return;
+        }
+        let len = self.note_content.len();
+        let selection = self.selection.clone().filter(|r| self.note_content.get(r.clone()).is_some()).unwrap_or(len..len);
+        let (text, selection) = touch::apply(&self.note_content, selection, format);
+        self.note_content = text;
+        let start = self.note_content[..selection.start].chars().count();
+        let end = self.note_content[..selection.end].chars().count();
+        editor::set_cursor(ctx, start..end);
+        ctx.memory_mut(|m| m.request_focus(editor::id()));
+        self.selection = Some(selection);
+    }
+
+    /// Replaces `range` with a completion and puts the cursor after it.
+    fn accept_completion(&mut self, ctx: &Context, range: Range<usize>, insert: &str) {
+        if self.note_content.get(range.clone()).is_none() || !self.editable() {
+            return;
+        }
+        self.note_content.replace_range(range.clone(), insert);
+        let end = self.note_content[..range.start + insert.len()].chars().count();
+        editor::set_cursor(ctx, end..end);
+        ctx.memory_mut(|m| m.request_focus(editor::id()));
+        self.completion_popup.reset();
+    }
+
+    fn show_ocr_report(&mut self, ctx: &Context) {
+        let Some(report) = &self.ocr_report else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new(t("🖼 OCR Result")).open(&mut open).show(ctx, |ui| {
+            ui.label(tf("Mean confidence: {}%", &[&format!("{:.0}", report.mean_confidence)]));
+            if !report.failed_pages.is_empty() {
+                let pages: Vec<String> = report.failed_pages.iter().map(usize::to_string).collect();
+                ui.colored_label(egui::Color32::LIGHT_RED, tf("Failed pages: {}", &[&pages.join(", ")]));
+            }
+            if !report.weak_lines.is_empty() {
+                ui.separator();
+                ui.label(t("Lines worth checking:"));
+                egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                    for line in &report.weak_lines {
+                        ui.horizontal(|ui| {
+                            ui.weak(format!("p.{} {:.0}%", line.page, line.confidence));
+                            ui.label(&line.text);
+                        });
+                    }
+                });
+            }
+        });
+        if !open {
+            self.ocr_report = None;
+        }
+    }
+
+    fn show_clipper(&mut self, ctx: &Context) {
+        let Some(url) = &mut self.clip_url else {
+            return;
+        };
+        let mut open = true;
+        let mut submit = false;
+        egui::Window::new(t("🌐 New Note from URL")).open(&mut open).show(ctx, |ui| {
+            let edit = ui.add(egui::TextEdit::singleline(url).hint_text("https://…").desired_width(360.0));
+            submit = edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            submit |= ui.button(t("Clip")).clicked();
+        });
+        if submit {
+            let url = url.clone();
+            self.jobs.push(jobs::spawn(format!("Clipping {}", url), move |_| {
+                let result = clipper::clip(&url);
+                JobDone::Clipped(url, result)
+            }));
+            self.clip_url = None;
+        } else if !open {
+            self.clip_url = None;
+        }
+    }
+
+    fn handle_tray_action(&mut self, ctx: &Context, action: tray::TrayAction) {
+        let show = |ctx: &Context| {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        };
+        match action {
+            tray::TrayAction::NewNote => {
+                self.new_note();
+                show(ctx);
+            }
+            tray::TrayAction::QuickCapture => self.quick_capture.open(),
+            tray::TrayAction::OpenLastNote => {
+                if let Some(path) = self.last_note.clone() {
+                    self.open_path(&path);
+                }
+                show(ctx);
+            }
+            tray::TrayAction::Quit => {
+                self.quitting = true;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+        }
+    }
 }
 
-pub struct NoteApp {
-    note_content: String,
-    selected_file: Option<String>,
-    suggestions: Vec<LTMatch>,
-    show_menu: bool,
-}
+impl App for NoteApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.remember_position();
+        session::Session {
+            open_note: self.last_note.clone(),
+            show_sidebar: self.show_sidebar,
+            show_preview: self.show_preview,
+            show_outline: self.show_outline,
+            show_minimap: self.show_minimap,
+            show_outliner: self.outliner.open,
+            live_preview: self.live_preview,
+            show_properties: self.properties.open,
+            show_suggestions: self.suggestions_view.open,
+            dock_suggestions: self.suggestions_view.docked,
+            show_assistant: self.assistant.open,
+            show_comments: self.comments.open,
+            show_info: self.info.open,
+            grammar_language: self.grammar_language.clone(),
+            positions: self.positions.clone(),
+        }
+        .store(storage);
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.recovery.close();
+    }
+
+    fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
+        self.poll_jobs();
+        if a11y::handle_keys(ctx, editor::id()) {
+            self.show_menu = true;
+        }
+        let messages: Vec<String> = self.messages.iter().flat_map(|rx| rx.try_iter()).collect();
+        for message in messages {
+            self.handle_message(&message);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        }
+
+        let actions: Vec<tray::TrayAction> = self.tray.iter().flat_map(|t| t.actions.try_iter()).collect();
+        for action in actions {
+            self.handle_tray_action(ctx, action);
+        }
+        if ctx.input(|i| i.viewport().close_requested())
+            && self.tray.is_some()
+            && self.config.minimize_to_tray
+            && !self.quitting
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Visible(false));
+        }
+
+        touch::apply_style(ctx, self.config.editor.touch_mode);
+        let appearance = &self.config.appearance;
+        let fonts_changed = (appearance.dyslexic_font, &appearance.dyslexic_font_path)
+            != (self.appearance.dyslexic_font, &self.appearance.dyslexic_font_path);
+        if fonts_changed {
+            apply_fonts(ctx, appearance);
+        }
+        let theme_changed = self.theme.refresh(&appearance.theme);
+        if fonts_changed || theme_changed || appearance.high_contrast != self.appearance.high_contrast {
+            apply_style(ctx, appearance, &self.theme.theme);
+        }
+        self.appearance = self.config.appearance.clone();
+        self.preview.spacing = Some((self.appearance.line_spacing, self.appearance.letter_spacing)).filter(|_| self.appearance.is_spaced());
+        self.preview.syntax = self.theme.theme.syntax.clone();
+        if self.config.editor.touch_mode {
+            match self.swipes.detect(ctx) {
+                Some(touch::Swipe::Right) => self.show_sidebar = true,
+                Some(touch::Swipe::Left) => self.show_sidebar = false,
+                None => {}
+            }
+        }
+
+        // Dropdown Menu
+        egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                let menu = ui.button(t("☰ Menu")).on_hover_text("F10");
+                a11y::region(&menu, a11y::Region::Menu);
+                if menu.clicked() {
+                    self.show_menu = !self.show_menu;
+                }
+                ui.toggle_value(&mut self.show_sidebar, t("🗂 Notes"));
+                ui.menu_button(t("View"), |ui| {
+                    ui.checkbox(&mut self.show_sidebar, t("Notes"));
+                    ui.checkbox(&mut self.show_outline, t("Outline"));
+                    ui.checkbox(&mut self.show_preview, t("Preview"));
+                    ui.checkbox(&mut self.show_minimap, t("Minimap"));
+                    ui.checkbox(&mut self.live_preview, t("Live preview"))
+                        .on_hover_text(t("Show Markdown styled in the editor, except on the line being edited"));
+                    ui.checkbox(&mut self.outliner.open, t("Edit as outline"));
+                    ui.checkbox(&mut self.properties.open, t("Front matter as properties"));
+                    ui.separator();
+                    if ui.button(t("Fold All")).clicked() {
+                        self.folded = folding::folds(&self.note_content).into_iter().map(|f| f.key).collect();
+                        ui.close_menu();
+                    }
+                    if ui.button(t("Unfold All")).clicked() {
+                        self.folded.clear();
+                        ui.close_menu();
+                    }
+                    ui.checkbox(&mut self.suggestions_view.open, t("Suggestions"));
+                    ui.checkbox(&mut self.suggestions_view.docked, t("Dock suggestions beside the note"));
+                    ui.separator();
+                    ui.checkbox(&mut self.assistant.open, t("Assistant"));
+                    ui.checkbox(&mut self.comments.open, t("Comments"));
+                    ui.checkbox(&mut self.info.open, t("Note info"));
+                });
+                if self.collab.is_active() && ui.small_button(t("👥 Live")).on_hover_text(t("Live session")).clicked() {
+                    self.collab.open = true;
+                }
+                if self.clipboard_watcher.is_active() {
+                    ui.label(egui::RichText::new(t("📎 Capturing clipboard")).small().weak());
+                }
+                if self.show_menu {
+                    if ui.button(t("📝 New Note")).clicked() {
+                        self.new_note();
+                        self.show_start = false;
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("🌐 New Note from URL")).clicked() {
+                        self.clip_url = Some(String::new());
+                        self.show_menu = false;
+                    }
+                    let watch = if self.clipboard_watcher.is_active() { t("📎 Stop Capturing Clipboard") } else { t("📎 Capture Clipboard") };
+                    if ui.button(watch).clicked() {
+                        self.clipboard_watcher.toggle(ctx);
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("🖼 Import Text from Image/PDF")).clicked() {
+                        self.import_ocr();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("📂 Open File")).clicked() {
+                        self.load_file();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("💾 Save File")).clicked() {
+                        self.save_file();
+                        self.show_menu = false;
+                    }
+                    if ui.button(tf("↵ Convert to {}", &[&self.line_ending.other().label()])).clicked() {
+                        self.convert_line_endings();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("⇄ Switch Text Direction")).clicked() {
+                        self.toggle_direction();
+                        self.show_menu = false;
+                    }
+                    let lock = if frontmatter::is_locked(&self.note_content) { t("🔓 Unlock Note") } else { t("🔒 Lock Note") };
+                    if ui.button(lock).clicked() {
+                        self.toggle_lock();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("🏷 Add Property")).clicked() {
+                        self.properties.add();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("🔍 Check Grammar")).clicked() {
+                        self.check_suggestions();
+                        self.show_menu = false;
+                    }
+                    egui::ComboBox::from_id_source("grammar_language")
+                        .selected_text(grammar::language_name(&self.grammar_language))
+                        .show_ui(ui, |ui| {
+                            for (code, name) in grammar::LANGUAGES {
+                                ui.selectable_value(&mut self.grammar_language, code.to_string(), *name);
+                            }
+                        });
+                    if ui.button(t("📦 Export Vault")).clicked() {
+                        self.export_vault();
+                        self.show_menu = false;
+                    }
+                    ui.checkbox(&mut self.export_history, t("with history"));
+                    if ui.button(t("🌍 Publish Site")).clicked() {
+                        self.publish.toggle();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("🃏 Export to Anki")).clicked() {
+                        self.export_anki();
+                        self.show_menu = false;
+                    }
+                    ui.checkbox(&mut self.anki_headings, t("with headings"));
+                    if ui.button(t("📄 Export with Pandoc")).clicked() {
+                        self.export_pandoc();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("📥 Import Vault")).clicked() {
+                        self.import_vault();
+                        self.show_menu = false;
+                    }
+                    let nextcloud = !self.config.nextcloud.url.is_empty();
+                    if ui.add_enabled(nextcloud, egui::Button::new(t("☁ Sync Nextcloud"))).clicked() {
+                        self.sync_nextcloud();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("📡 LAN Sync")).clicked() {
+                        self.lan_sync.toggle();
+                        self.show_menu = false;
+                    }
+                    let cursor = self.selection.as_ref().map(|r| r.end);
+                    if ui.button(t("📋 Paste as Plain Text")).on_hover_text("Ctrl+Shift+V").clicked() {
+                        self.paste_plain(ctx);
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("🔗 Paste as Link")).clicked() {
+                        self.paste_as_link();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("🔗 Fix Bare URLs")).clicked() && self.editable() {
+                        self.note_content = urls::fix_bare_urls(&self.note_content);
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("¹ Insert Footnote")).clicked() {
+                        let at = cursor.filter(|&c| self.note_content.is_char_boundary(c)).unwrap_or(self.note_content.len());
+                        let (text, cursor) = footnotes::insert(&self.note_content, at);
+                        self.set_text_and_cursor(ctx, text, cursor);
+                        self.show_menu = false;
+                    }
+                    let jump = cursor.and_then(|c| footnotes::jump_target(&self.note_content, c));
+                    if ui.add_enabled(jump.is_some(), egui::Button::new(t("↕ Go to Footnote"))).clicked()
+                        && let Some(target) = jump
+                    {
+                        self.move_cursor(ctx, target);
+                        self.show_menu = false;
+                    }
+                    let in_table = cursor.is_some_and(|c| tables::in_table(&self.note_content, c));
+                    ui.menu_button(t("▦ Table"), |ui| {
+                        if ui.button(t("Insert table")).clicked() && self.editable() {
+                            let at = cursor.filter(|&c| self.note_content.is_char_boundary(c)).unwrap_or(self.note_content.len());
+                            let prefix = if at == 0 || self.note_content[..at].ends_with('\n') { "" } else { "\n" };
+                            self.note_content.insert_str(at, &format!("{}{}", prefix, tables::TEMPLATE));
+                            ui.close_menu();
+                        }
+                        ui.separator();
+                        for (label, edit) in [
+                            (t("Format table"), tables::TableEdit::Format),
+                            (t("Add row below"), tables::TableEdit::InsertRowBelow),
+                            (t("Delete row"), tables::TableEdit::DeleteRow),
+                            (t("Add column right"), tables::TableEdit::InsertColumnRight),
+                            (t("Delete column"), tables::TableEdit::DeleteColumn),
+                            (t("Align column left"), tables::TableEdit::Align(tables::Align::Left)),
+                            (t("Align column center"), tables::TableEdit::Align(tables::Align::Center)),
+                            (t("Align column right"), tables::TableEdit::Align(tables::Align::Right)),
+                        ] {
+                            if ui.add_enabled(in_table, egui::Button::new(label)).clicked() {
+                                self.edit_table(ctx, edit);
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                    ui.menu_button(t("☰ Structure"), |ui| {
+                        if ui.button(t("Promote heading")).on_hover_text("Alt+Shift+←").clicked() {
+                            self.restructure(ctx, |text, selection| indent::shift_headings(text, selection, false));
+                            ui.close_menu();
+                        }
+                        if ui.button(t("Demote heading")).on_hover_text("Alt+Shift+→").clicked() {
+                            self.restructure(ctx, |text, selection| indent::shift_headings(text, selection, true));
+                            ui.close_menu();
+                        }
+                        ui.separator();
+                        if ui.button(t("Indent list item")).on_hover_text(t("Tab or Alt+Shift+→")).clicked() {
+                            self.restructure(ctx, |text, selection| indent::shift_list(text, selection, false));
+                            ui.close_menu();
+                        }
+                        if ui.button(t("Outdent list item")).on_hover_text(t("Shift+Tab or Alt+Shift+←")).clicked() {
+                            self.restructure(ctx, |text, selection| indent::shift_list(text, selection, true));
+                            ui.close_menu();
+                        }
+                    });
+                    if ui.button(t("👁 Preview")).clicked() {
+                        self.show_preview = !self.show_preview;
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("✨ Assistant")).clicked() {
+                        self.assistant.open = !self.assistant.open;
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("🔤 Translate")).clicked() {
+                        self.translate.open_for(&self.note_content, self.selection.clone());
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("📚 Synonyms")).on_hover_text(t("For the word at the cursor (Shift+F7)")).clicked() {
+                        self.show_synonyms(None);
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("📖 Define")).on_hover_text(t("The word at the cursor (Ctrl+Shift+D), or hold Ctrl over a word")).clicked() {
+                        self.show_definition(None);
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("🔠 Expand Acronym")).on_hover_text(t("Write out the glossary term at the cursor (Ctrl+Shift+E)")).clicked() {
+                        self.expand_term(ctx);
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("🔊 Read Aloud")).clicked() {
+                        self.read_aloud.open = true;
+                        self.show_menu = false;
+                    }
+                    let dictate = if self.dictation.is_recording() { t("⏹ Stop Dictation") } else { t("🎤 Dictate") };
+                    if ui.button(dictate).clicked() {
+                        self.dictation.toggle(ctx, &self.config.dictation);
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("🩺 Check Vault")).clicked() {
+                        self.lint.run();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("🌱 Orphans & Stale Notes")).clicked() {
+                        self.gardening.run();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("👯 Find Duplicates")).clicked() {
+                        self.duplicates.run();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("⇆ Compare with...")).clicked() {
+                        self.compare_with(vault::NOTES_DIR);
+                        self.show_menu = false;
+                    }
+                    if Path::new(vault::HISTORY_DIR).is_dir() && ui.button(t("⇆ Compare with Snapshot...")).clicked() {
+                        self.compare_with(vault::HISTORY_DIR);
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("🔎 Search")).clicked() {
+                        self.search.toggle();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("📋 Board")).clicked() {
+                        self.board.toggle();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("💬 Comments")).clicked() {
+                        self.comments.toggle();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("💬 Comment on Selection")).clicked() {
+                        self.comment_on_selection();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("👥 Live Session")).clicked() {
+                        self.collab.toggle();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("📊 Statistics")).clicked() {
+                        self.dashboard.toggle();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("🎲 Random Note")).clicked() {
+                        self.random_note.toggle();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("📝 Scratchpad")).clicked() {
+                        self.scratchpad.toggle();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("✅ Habits")).clicked() {
+                        self.habits.toggle();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("🃏 Review Flashcards")).clicked() {
+                        self.review.start(self.note_list.notes());
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("ℹ Note Info")).clicked() {
+                        self.info.toggle();
+                        self.show_menu = false;
+                    }
+                    let archivable = self.last_note.as_deref().is_some_and(|p| !vault::is_archived(p));
+                    if ui.add_enabled(archivable, egui::Button::new(t("🗄 Archive Note"))).clicked() {
+                        self.archive_note();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("🗄 Archived Notes")).clicked() {
+                        self.archived.run();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("🧷 Merge Notes")).clicked() {
+                        self.merge_notes.open();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("✂ Split by Headings")).clicked() {
+                        self.split_note();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("▶ Code Blocks")).clicked() {
+                        self.show_code_blocks = true;
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("🧩 Plugins")).clicked() {
+                        self.show_plugins = true;
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("⚙ Settings")).clicked() {
+                        self.show_settings = true;
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("⏳ Jobs")).clicked() {
+                        self.jobs_view.toggle();
+                        self.show_menu = false;
+                    }
+                    if ui.button(t("🪵 Show Logs")).clicked() {
+                        self.logs.toggle();
+                        self.show_menu = false;
+                    }
+                }
+            });
+        });
+
+        // Status bar
+        self.backups.tick(&self.config.backup);
+        ctx.request_repaint_after(Duration::from_secs(30));
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                // Separators go between items, and the backup status may be hidden.
+                let mut shown = false;
+                if let Some(status) = self.backups.status(&self.config.backup) {
+                    ui.label(status);
+                    shown = true;
+                }
+                let sync = if self.lan_sync.is_syncing() {
+                    Some(t("🔄 Syncing…"))
+                } else if let Some(status) = &self.sync_status {
+                    Some(format!("☁ {}", status))
+                } else if !self.config.nextcloud.url.is_empty() || self.config.lan_sync.enabled {
+                    Some(t("☁ Not synced yet"))
+                } else {
+                    None
+                };
+                if let Some(sync) = sync {
+                    if shown {
+                        ui.separator();
+                    }
+                    ui.label(sync);
+                    shown = true;
+                }
+                if self.selected_file.is_some() || !self.note_content.is_empty() {
+                    if shown {
+                        ui.separator();
+                    }
+                    shown = true;
+                    let unsaved = self.note_content != self.base_content;
+                    match self.autosave.remaining(self.config.editor.autosave_seconds) {
+                        Some(left) if unsaved => {
+                            ui.label(tf("💾 Autosave in {}s", &[&left.as_secs_f32().ceil()]));
+                            ctx.request_repaint_after(Duration::from_millis(250));
+                        }
+                        _ if unsaved => {
+                            ui.label(t("● Unsaved")).on_hover_text(t("Save from the menu, or turn on autosave in Settings"));
+                        }
+                        _ => {
+                            ui.label(t("✔ Saved"));
+                        }
+                    }
+                }
+                if !self.encoding.is_utf8() {
+                    if shown {
+                        ui.separator();
+                    }
+                    shown = true;
+                    let saved = if self.config.editor.keep_encoding { t("Saved in the same encoding") } else { t("Converted to UTF-8 when saved") };
+                    ui.colored_label(egui::Color32::YELLOW, self.encoding.name()).on_hover_text(saved);
+                }
+                if shown {
+                    ui.separator();
+                }
+                let other = self.line_ending.other();
+                if ui
+                    .add(egui::Label::new(self.line_ending.label()).sense(egui::Sense::click()))
+                    .on_hover_text(tf("Line endings; click to save with {} instead", &[&other.label()]))
+                    .clicked()
+                {
+                    self.convert_line_endings();
+                }
+                ui.separator();
+                let direction = match (bidi::note_override(&self.note_content), bidi::note_direction(&self.note_content)) {
+                    (Some(_), bidi::Direction::Rtl) => t("RTL"),
+                    (Some(_), bidi::Direction::Ltr) => t("LTR"),
+                    (None, bidi::Direction::Rtl) => t("RTL (auto)"),
+                    (None, bidi::Direction::Ltr) => t("LTR (auto)"),
+                };
+                if ui
+                    .add(egui::Label::new(direction).sense(egui::Sense::click()))
+                    .on_hover_text(t("Text direction; click to switch (Ctrl+Shift+R)"))
+                    .clicked()
+                {
+                    self.toggle_direction();
+                }
+                let language = self.check_language();
+                ui.separator();
+                let from_note = language != self.grammar_language;
+                ui.label(format!("🔤 {}", grammar::language_name(&language))).on_hover_text(if from_note {
+                    t("Check language, from this note's lang field")
+                } else {
+                    t("Check language")
+                });
+                ui.separator();
+                let (check, hover) = self.check_state.label(self.suggestions.len());
+                if ui.add(egui::Label::new(check).sense(egui::Sense::click())).on_hover_text(hover).clicked()
+                    && self.check_state != status::CheckState::Running
+                {
+                    self.check_suggestions();
+                }
+                if let Some(status) = grammar::status() {
+                    ui.separator();
+                    ui.label(status);
+                    // Keep a cool-down counting.
+                    ctx.request_repaint_after(Duration::from_secs(1));
+                }
+                if let Some(err) = http::offline() {
+                    ui.separator();
+                    ui.colored_label(egui::Color32::YELLOW, t("📴 Offline")).on_hover_text(tf("Requests keep failing: {}", &[&err]));
+                }
+                if jobs::status_ui(ui) {
+                    self.jobs_view.toggle();
+                }
+            });
+        });
 
-impl NoteApp {
-    pub fn new(cc: &CreationContext<'_>) -> Self {
-        apply_custom_style(&cc.egui_ctx);
-        Self {
-            note_content: String::new(),
-            selected_file: None,
-            suggestions: Vec::new(),
-            show_menu: false,
+        // Dictation
+        if let Some(text) = self.dictation.update(ctx, &self.config.dictation)
+            && self.editable()
+        {
+            let at = self
+                .selection
+                .as_ref()
+                .map(|r| r.end)
+                .filter(|&at| self.note_content.is_char_boundary(at))
+                .unwrap_or(self.note_content.len());
+            self.note_content.insert_str(at, &text);
         }
-    }
+        self.dictation.show_indicator(ctx);
 
-    pub fn load_file(&mut self) {
-        if let Some(path) = rfd::FileDialog::new().pick_file() {
-            if let Ok(content) = std::fs::read_to_string(&path) {
-                self.note_content = content;
-                self.selected_file = path.file_name().and_then(|s| s.to_str()).map(String::from);
+        // Assistant panel
+        let selection = self.selection.clone().filter(|r| self.note_content.get(r.clone()).is_some());
+        match self.assistant.show(ctx, &self.config.ai, &self.note_content, selection.clone()) {
+            Some(_) if !self.editable() => {}
+            Some(assistant::AssistantEdit::Insert(text)) => {
+                let at = selection.map_or(self.note_content.len(), |r| r.end);
+                self.note_content.insert_str(at, &text);
+            }
+            Some(assistant::AssistantEdit::Replace(text)) => {
+                if let Some(range) = selection {
+                    self.note_content.replace_range(range, &text);
+                }
             }
+            None => {}
         }
-    }
 
-    pub fn save_file(&self) {
-        if let Some(filename) = &self.selected_file {
-            let path = format!("notes/{}", filename);
-            if let Err(err) = std::fs::write(&path, &self.note_content) {
-                eprintln!("Failed to save file: {}", err);
+        // Note list and quick switcher
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::P)) {
+            self.switcher.toggle();
+        }
+        if let Some(path) = self.switcher.show(ctx, self.note_list.notes(), &self.metadata) {
+            self.open_path(&path);
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::F)) {
+            self.search.toggle();
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Backtick)) {
+            self.scratchpad.toggle();
+        }
+        self.scratchpad.show(ctx);
+        let sidebar = self.config.sidebar.clone();
+        match self.search.show(ctx, &mut self.note_list, &mut self.config.sidebar) {
+            Some(search::SearchEvent::Open(path)) => {
+                self.open_path(&path);
+                if self.last_note.as_ref() == Some(&path) {
+                    self.highlight = self.search.terms();
+                    if let Some(first) = text::find_terms(&self.note_content, &self.highlight).first() {
+                        self.move_cursor(ctx, first.start);
+                    }
+                }
             }
-        } else if let Some(path) = rfd::FileDialog::new().save_file() {
-            if let Err(err) = std::fs::write(path, &self.note_content) {
-                eprintln!("Failed to save file: {}", err);
+            Some(search::SearchEvent::Replaced) => self.reload_if_clean(),
+            None => {}
+        }
+        if self.show_sidebar {
+            match sidebar::show(ctx, &mut self.note_list, &mut self.config.sidebar, &mut self.metadata, self.last_note.as_deref()) {
+                Some(sidebar::SidebarEvent::Open(path)) => self.open_path(&path),
+                Some(sidebar::SidebarEvent::EditFolder(folder)) => self.search.edit(&folder),
+                None => {}
             }
         }
-    }
+        if self.config.sidebar != sidebar {
+            self.config.save();
+        }
 
-    pub fn check_suggestions(&mut self) {
-        let client = reqwest::blocking::Client::new();
-        let res = client
-            .post("https://api.languagetoolplus.com/v2/check")
-            .form(&[
-                ("text", self.note_content.as_str()),
-                ("language", "en-US"),
-            ])
-            .send();
+        // Outline
+        if self.show_outline
+            && let Some(offset) = outline::show(ctx, &self.note_content, self.selection.as_ref().map(|r| r.start))
+        {
+            self.move_cursor(ctx, offset);
+        }
+
+        // Preview
+        self.citations.update(ctx, &self.config.citations);
+        if self.show_preview {
+            egui::SidePanel::right("preview")
+                .resizable(true)
+                .default_width(400.0)
+                .show(ctx, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        self.preview.show(ui, &citations::render(&self.note_content, &self.citations));
+                    });
+                });
+        }
 
-        match res {
-            Ok(resp) => {
-                if let Ok(parsed) = resp.json::<LTResponse>() {
-                    self.suggestions = parsed.matches;
+        // Live session
+        self.collab.show(ctx, &self.note_content, self.selected_file.as_deref());
+        match self.collab.sync(&self.note_content, self.selection.as_ref().map(|r| r.end)) {
+            Some(collab::SyncEvent::Joined { note, text }) => {
+                if self.settle_unsaved() {
+                    self.clear_note();
+                    self.note_content = text;
+                    self.selected_file = Some(note);
+                    self.last_note = None;
+                    self.show_start = false;
+                } else {
+                    warn!("Left the live session to keep the open note's edits");
+                    self.collab.leave();
                 }
             }
-            Err(err) => {
-                eprintln!("Suggestion error: {}", err);
+            Some(collab::SyncEvent::Remote { text, cursor }) => {
+                self.note_content = text;
+                if let Some(cursor) = cursor {
+                    let chars = self.note_content[..cursor].chars().count();
+                    editor::set_cursor(ctx, chars..chars);
+                    self.selection = Some(cursor..cursor);
+                }
             }
+            None => {}
         }
-    }
-}
 
-impl App for NoteApp {
-    fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
-        // Dropdown Menu
-        egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
-            ui.horizontal_wrapped(|ui| {
-                if ui.button("☰ Menu").clicked() {
-                    self.show_menu = !self.show_menu;
+        // Comments
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::ALT, egui::Key::M)) {
+            self.comment_on_selection();
+        }
+        let note = self.selected_file.as_ref().map(|f| Path::new(vault::NOTES_DIR).join(f));
+        self.comments.sync(note.as_deref(), &self.note_content, &self.metadata);
+        if let Some(comments::CommentEvent::Select(range)) = self.comments.show(ctx, &mut self.metadata) {
+            self.select_range(ctx, range);
+        }
+
+        // Formatting bar
+        if self.config.editor.touch_mode
+            && !(self.show_start && self.note_content.is_empty())
+            && !frontmatter::is_locked(&self.note_content)
+            && let Some(format) = touch::show_bar(ctx)
+        {
+            self.format_selection(ctx, format);
+        }
+
+        // Suggestions panel
+        if self.suggested_text != self.note_content {
+            suggestions::remap(&mut self.suggestions, &self.suggested_text, &self.note_content);
+            if self.config.grammar.spell_as_you_type
+                && let Some(typo) = self.grammar_providers.speller(&self.config.grammar).check_typed(&self.suggested_text, &self.note_content)
+                && !self.suggestions.iter().any(|s| s.offset == typo.offset && s.length == typo.length)
+            {
+                let at = self.suggestions.partition_point(|s| s.offset < typo.offset);
+                self.suggestions.insert(at, typo);
+            }
+            self.suggested_text = self.note_content.clone();
+        }
+        match self.suggestions_view.show(ctx, &self.note_content, &self.suggestions) {
+            Some(suggestions::SuggestionEvent::Select(range)) => self.select_range(ctx, range),
+            Some(suggestions::SuggestionEvent::Apply(fixes)) if self.editable() => {
+                for (range, inserted) in suggestions::apply(&mut self.note_content, fixes) {
+                    suggestions::shift(&mut self.suggestions, &range, inserted);
                 }
-                if self.show_menu {
-                    if ui.button("📂 Open File").clicked() {
-                        self.load_file();
-                        self.show_menu = false;
-                    }
-                    if ui.button("💾 Save File").clicked() {
-                        self.save_file();
-                        self.show_menu = false;
-                    }
-                    if ui.button("🔍 Check Grammar").clicked() {
-                        self.check_suggestions();
-                        self.show_menu = false;
+                self.suggested_text = self.note_content.clone();
+                self.check_suggestions();
+            }
+            Some(suggestions::SuggestionEvent::Apply(_)) | None => {}
+        }
+
+        // Minimap
+        if self.show_minimap
+            && !(self.show_start && self.note_content.is_empty())
+            && let Some(offset) = minimap::show(ctx, &self.note_content, self.editor_view, &self.theme.theme.syntax)
+        {
+            self.scroll_to = Some(offset);
+        }
+
+        // Files dragged onto the window
+        if ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+            let screen = ctx.screen_rect();
+            let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("drop_target")));
+            painter.rect_filled(screen, 0.0, egui::Color32::from_black_alpha(160));
+            painter.text(
+                screen.center(),
+                egui::Align2::CENTER_CENTER,
+                t("Drop a note to open it, or any other file to attach it"),
+                egui::TextStyle::Heading.resolve(&ctx.style()),
+                egui::Color32::WHITE,
+            );
+        }
+        self.handle_dropped_files(ctx);
+
+        // Main text editor
+        self.glossary.refresh(&self.config.editor.glossary_note);
+        let mut start_event = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if self.show_start && self.note_content.is_empty() {
+                start_event = self.start.show(ui, &mut self.note_list, &self.metadata, &self.config.review);
+                return;
+            }
+            let locked = frontmatter::is_locked(&self.note_content);
+            if locked {
+                ui.label(egui::RichText::new(t("🔒 This note is locked")).weak());
+            }
+            match self.properties.show(ui, &mut self.note_content, locked) {
+                Some(properties::PropertyEvent::Changed) => self.autosave.edited(),
+                Some(properties::PropertyEvent::Search(query)) => self.search.search_for(query),
+                None => {}
+            }
+            if self.outliner.open {
+                if self.outliner.show(ui, &mut self.note_content, locked) {
+                    self.autocomplete.edited();
+                    self.autosave.edited();
+                }
+                return;
+            }
+            let cursor = self.selection.as_ref().map(|r| r.end).filter(|_| !locked);
+            let completions = self
+                .selection
+                .as_ref()
+                .filter(|r| r.is_empty() && !locked)
+                .and_then(|r| {
+                    citations::complete(&self.note_content, r.end, &self.citations)
+                        .or_else(|| emoji::complete(&self.note_content, r.end))
+                });
+            match &completions {
+                Some((range, candidates)) => {
+                    if let Some(index) = self.completion_popup.input(ctx, range.start, candidates) {
+                        self.accept_completion(ctx, range.clone(), &candidates[index].insert);
                     }
                 }
+                None => self.completion_popup.reset(),
+            }
+            if !locked && ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::T)) {
+                self.edit_table(ctx, tables::TableEdit::Format);
+            }
+            if let Some(cursor) = cursor
+                && completions.is_none()
+                && tables::in_table(&self.note_content, cursor)
+            {
+                let backwards = ctx.input_mut(|i| i.consume_key(egui::Modifiers::SHIFT, egui::Key::Tab));
+                if (backwards || ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Tab)))
+                    && let Some((text, cursor)) = tables::next_cell(&self.note_content, cursor, backwards)
+                {
+                    self.set_text_and_cursor(ctx, text, cursor);
+                }
+            }
+            if let Some(cursor) = cursor
+                && self.autocomplete.ghost.as_ref().is_some_and(|g| g.at == cursor)
+                && ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Tab))
+                && let Some(ghost) = self.autocomplete.accept(cursor)
+            {
+                self.note_content.insert_str(cursor, &ghost.text);
+                let end = self.note_content[..cursor + ghost.text.len()].chars().count();
+                editor::set_cursor(ctx, end..end);
+            }
+            // Tab and Shift+Tab move a list item and the items under it in
+            // and out; Alt+Shift+Right and Left do the same, or change the
+            // level of a heading.
+            if let Some(selection) = self.selection.clone()
+                && !locked
+                && completions.is_none()
+                && ctx.memory(|m| m.has_focus(editor::id()))
+                && indent::in_list(&self.note_content, selection.start.min(self.note_content.len()))
+            {
+                let outdent = ctx.input_mut(|i| i.consume_key(egui::Modifiers::SHIFT, egui::Key::Tab));
+                if outdent || ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Tab)) {
+                    self.restructure(ctx, |text, selection| indent::shift_list(text, selection, outdent));
+                }
+            }
+            for (key, deeper) in [(egui::Key::ArrowRight, true), (egui::Key::ArrowLeft, false)] {
+                if !locked
+                    && ctx.memory(|m| m.has_focus(editor::id()))
+                    && ctx.input_mut(|i| i.consume_key(egui::Modifiers::ALT | egui::Modifiers::SHIFT, key))
+                {
+                    self.restructure(ctx, |text, selection| {
+                        indent::shift_headings(text, selection.clone(), deeper).or_else(|| indent::shift_list(text, selection, !deeper))
+                    });
+                }
+            }
+            // Ctrl+Shift+V keeps the plain text.
+            let pasted = ctx.input(|i| {
+                let pasted = i.events.iter().find_map(|e| match e {
+                    egui::Event::Paste(text) => Some(text.clone()),
+                    _ => None,
+                });
+                pasted.filter(|_| !i.modifiers.shift)
             });
-        });
+            if let Some(pasted) = pasted
+                && !locked
+                && ctx.memory(|m| m.has_focus(editor::id()))
+                && let Some(replacement) = self.paste_replacement(&pasted)
+            {
+                ctx.input_mut(|i| {
+                    for event in &mut i.events {
+                        if let egui::Event::Paste(text) = event {
+                            *text = replacement.clone();
+                        }
+                    }
+                });
+            }
+            if self.autocorrect.can_undo(&self.note_content)
+                && ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z))
+                && let Some((text, cursor)) = self.autocorrect.undo(&self.note_content)
+            {
+                self.set_text_and_cursor(ctx, text, cursor);
+            }
 
-        // Main text editor
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.add_sized(
-                ui.available_size(),
-                TextEdit::multiline(&mut self.note_content)
+            if !locked && ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::R)) {
+                self.toggle_direction();
+            }
+            if !locked
+                && ctx.memory(|m| m.has_focus(editor::id()))
+                && let Some(selection) = self.selection.clone()
+            {
+                self.type_pairs(ctx, selection);
+            }
+            if let Some(cursor) = self.selection.as_ref().filter(|r| r.is_empty()).map(|r| r.end)
+                && let Some((_, partner)) = pairs::matching(&self.note_content, cursor)
+                && ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::M))
+            {
+                self.move_cursor(ctx, partner);
+            }
+            let align = match bidi::note_direction(&self.note_content) {
+                bidi::Direction::Ltr => egui::Align::LEFT,
+                bidi::Direction::Rtl => egui::Align::RIGHT,
+            };
+            let appearance = self.appearance.clone();
+            let syntax = self.theme.theme.syntax.clone();
+            let folded = self.folded.clone();
+            // Without a cursor every line is styled.
+            let live = self.live_preview.then(|| self.selection.clone().unwrap_or(usize::MAX..usize::MAX));
+            let hide_yaml = self.properties.hides_yaml(&self.note_content);
+            let mut styled = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+                let mut hidden = folding::hidden(text, &folded);
+                hidden.extend(frontmatter::range(text).filter(|_| hide_yaml));
+                editor::layout(ui, text, wrap_width, &appearance, &syntax, &hidden, live.clone())
+            };
+            let mut scroll = egui::ScrollArea::vertical().id_source("editor_scroll").auto_shrink([false; 2]);
+            if let Some(position) = self.restore.take() {
+                let mut cursor = position.cursor.min(self.note_content.len());
+                while !self.note_content.is_char_boundary(cursor) {
+                    cursor -= 1;
+                }
+                let chars = self.note_content[..cursor].chars().count();
+                editor::set_cursor(ctx, chars..chars);
+                self.selection = Some(cursor..cursor);
+                scroll = scroll.vertical_scroll_offset(position.scroll);
+                self.folded = position.folds.into_iter().collect();
+            }
+            if let Some(offset) = self.scroll_to.take() {
+                scroll = scroll.vertical_scroll_offset(offset);
+            }
+            let styled_layout = self.appearance.is_spaced() || self.theme.theme.syntax.is_set() || !self.folded.is_empty() || self.live_preview || hide_yaml;
+            let scrolled = scroll.show(ui, |ui| {
+                let mut column = a11y::text_column(ui, ui.available_rect_before_wrap(), &self.appearance);
+                column.min.x += folding::GUTTER;
+                let mut column_ui = ui.child_ui(column, *ui.layout());
+                let mut edit = TextEdit::multiline(&mut self.note_content)
+                    .id(editor::id())
                     .font(egui::TextStyle::Monospace)
                     .code_editor()
                     .lock_focus(true)
-                    .desired_width(f32::INFINITY),
-            );
+                    .interactive(!locked)
+                    .desired_width(f32::INFINITY)
+                    .min_size(column.size())
+                    .horizontal_align(align);
+                if styled_layout {
+                    edit = edit.layouter(&mut styled);
+                }
+                let output = edit.show(&mut column_ui);
+                ui.allocate_rect(column_ui.min_rect(), egui::Sense::hover());
+                folding::show_gutter(ui, &output, &self.note_content, &mut self.folded);
+                output
+            });
+            self.editor_view = minimap::Viewport {
+                offset: scrolled.state.offset.y,
+                height: scrolled.inner_rect.height(),
+                content: scrolled.content_size.y,
+            };
+            let output = scrolled.inner;
+            a11y::region(&output.response, a11y::Region::Editor);
+            let name = match &self.last_note {
+                Some(path) => tf("Note {}", &[&sidebar::title(path)]),
+                None => t("Unsaved note"),
+            };
+            a11y::label(ctx, editor::id(), name);
+            if let Some(range) = output.cursor_range {
+                let chars = range.as_sorted_char_range();
+                let selection = text::char_to_byte(&self.note_content, chars.start)
+                    ..text::char_to_byte(&self.note_content, chars.end);
+                if output.response.changed() {
+                    self.autocomplete.edited();
+                    self.autosave.edited();
+                    if let Some((text, cursor)) = self.autocorrect.update(&self.config.autocorrect, &self.note_content) {
+                        // The layout is of the text before; draw again.
+                        self.set_text_and_cursor(ctx, text, cursor);
+                        ctx.request_repaint();
+                        return;
+                    }
+                } else if self.selection.as_ref() != Some(&selection) {
+                    self.autocomplete.moved();
+                }
+                folding::reveal(&self.note_content, selection.end, &mut self.folded);
+                // The cursor is kept out of front matter hidden behind the
+                // properties strip.
+                let selection = match frontmatter::range(&self.note_content).filter(|_| hide_yaml) {
+                    Some(front) if selection.is_empty() && selection.start < front.end => {
+                        let chars = self.note_content[..front.end].chars().count();
+                        editor::set_cursor(ctx, chars..chars);
+                        front.end..front.end
+                    }
+                    _ => selection,
+                };
+                if self.live_preview && self.selection.as_ref() != Some(&selection) {
+                    // The lines shown raw follow the cursor.
+                    ctx.request_repaint();
+                }
+                self.selection = Some(selection);
+            }
+            if !locked
+                && let Some(range) = output.cursor_range
+                && ctx.input_mut(|i| i.consume_key(egui::Modifiers::SHIFT, egui::Key::F7))
+            {
+                let caret = output.galley.pos_from_cursor(&range.primary);
+                self.show_synonyms(Some(output.galley_pos + caret.right_bottom().to_vec2()));
+            }
+            if !locked && ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::E)) {
+                self.expand_term(ctx);
+            }
+            if let Some(range) = output.cursor_range
+                && ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::D))
+            {
+                let caret = output.galley.pos_from_cursor(&range.primary);
+                self.show_definition(Some(output.galley_pos + caret.right_bottom().to_vec2()));
+            }
+            if let Some(cursor) = self.selection.as_ref().map(|r| r.end) {
+                self.autocomplete.tick(ctx, &self.config.autocomplete, &self.note_content, cursor);
+            }
+            self.autocomplete.paint(ui, &output, &self.note_content);
+            if output.response.changed() || ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+                self.highlight.clear();
+            }
+            editor::paint_matches(ui, &output, &self.note_content, &self.highlight);
+            if let Some(cursor) = self.selection.as_ref().filter(|r| r.is_empty()).map(|r| r.end)
+                && let Some((at, partner)) = pairs::matching(&self.note_content, cursor)
+            {
+                editor::paint_pair(ui, &output, &self.note_content, [at, partner]);
+            }
+            editor::paint_comments(ui, &output, &self.note_content, &self.comments.ranges());
+            let issues: Vec<(Range<usize>, egui::Color32)> =
+                self.suggestions.iter().map(|s| (s.offset..s.offset + s.length, suggestions::color(s))).collect();
+            if let Some(range) = editor::paint_issues(ui, &output, &self.note_content, &issues) {
+                self.select_range(ctx, range);
+            }
+            if let Some(chars) = self.collab.remote_cursor() {
+                editor::paint_caret(ui, &output, chars, egui::Color32::from_rgb(230, 120, 40));
+            }
+            let hover_at = output
+                .response
+                .hover_pos()
+                .map(|pos| output.galley.cursor_from_pos(pos - output.galley_pos).ccursor.index)
+                .map(|chars| text::char_to_byte(&self.note_content, chars));
+            let hovered = hover_at.and_then(|at| urls::link_at(&self.note_content, at));
+            if let Some(link) = &hovered {
+                egui::show_tooltip_at_pointer(ctx, editor::id().with("link"), |ui| {
+                    ui.label(&link.target);
+                    ui.label(egui::RichText::new(t("Ctrl+Click to open")).small().weak());
+                });
+            } else if let Some(range) = hover_at.and_then(|at| text::word_at(&self.note_content, at)) {
+                if ctx.input(|i| i.modifiers.command) {
+                    if let Some(dictionary) = self.dictionary() {
+                        self.definitions.tooltip(ctx, &dictionary, &self.note_content[range]);
+                    }
+                } else if let word = &self.note_content[range]
+                    && let Some(expansion) = self.glossary.get(word)
+                {
+                    egui::show_tooltip_at_pointer(ctx, editor::id().with("glossary"), |ui| {
+                        ui.label(egui::RichText::new(word).strong());
+                        ui.label(expansion);
+                        ui.label(egui::RichText::new(t("Ctrl+Shift+E to write it out")).small().weak());
+                    });
+                }
+            }
+            if output.response.clicked()
+                && ctx.input(|i| i.modifiers.command)
+                && let Some(cursor) = self.selection.as_ref().map(|r| r.end)
+            {
+                if let Some(target) = footnotes::jump_target(&self.note_content, cursor) {
+                    self.move_cursor(ctx, target);
+                } else if let Some(link) = hovered {
+                    self.follow_link(ctx, &link.target);
+                }
+            }
+            if let Some((range, candidates)) = completions
+                && !output.response.changed()
+                && let Some(index) = self.completion_popup.show(ui, &output, &candidates)
+            {
+                self.accept_completion(ctx, range, &candidates[index].insert);
+            }
         });
+        match start_event {
+            Some(start::StartEvent::Open(path)) => self.open_path(&path),
+            Some(start::StartEvent::NewNote) => self.show_start = false,
+            Some(start::StartEvent::DailyNote) => self.open_daily_note(),
+            Some(start::StartEvent::RandomNote) => self.random_note.open = true,
+            Some(start::StartEvent::Search) => self.search.toggle(),
+            Some(start::StartEvent::Settings) => self.show_settings = true,
+            None => {}
+        }
 
-        // Suggestions panel
-        if !self.suggestions.is_empty() {
-            egui::Window::new("💡 Suggestions")
-                .default_width(300.0)
-                .collapsible(false)
-                .show(ctx, |ui| {
-                    for suggestion in &self.suggestions {
-                        let snippet = &self.note_content
-                            [suggestion.offset..suggestion.offset + suggestion.length];
-                        let replacement = suggestion
-                            .replacements
-                            .get(0)
-                            .map(|r| r.value.as_str())
-                            .unwrap_or("❌");
-                        let suggestion_text = format!("{} → {}", snippet, replacement);
-
-                        if ui.button(suggestion_text).clicked() {
-                            self.note_content.replace_range(
-                                suggestion.offset..suggestion.offset + suggestion.length,
-                                replacement,
-                            );
-                            self.check_suggestions();
-                            break;
-                        }
+        self.config.show(ctx, &mut self.show_settings);
+        self.quick_capture.show(ctx, &self.config.capture);
+        if let Some(path) = self.clipboard_watcher.poll(ctx, &self.config.capture) {
+            self.note_list.invalidate();
+            if self.last_note.as_ref().is_some_and(|p| vault::note_key(p) == vault::note_key(&path)) {
+                self.reload_if_clean();
+            }
+        }
+        let locked = frontmatter::is_locked(&self.note_content);
+        if self.plugins.show_manager(ctx, &mut self.show_plugins, &mut self.note_content, self.selection.clone(), locked) {
+            self.config.disabled_plugins = self.plugins.disabled.iter().cloned().collect();
+            self.config.save();
+        }
+        self.plugins.show_panels(ctx, &self.note_content);
+        self.show_code_blocks(ctx);
+        self.show_clipper(ctx);
+        self.show_ocr_report(ctx);
+        match self.duplicates.show(ctx) {
+            Some(duplicates::DuplicateEvent::Open(path)) => self.open_path(&path),
+            Some(duplicates::DuplicateEvent::Changed) => self.reload_if_clean(),
+            None => {}
+        }
+        if let Some(view) = &mut self.compare
+            && !view.show(ctx, &mut self.note_content, locked)
+        {
+            self.compare = None;
+        }
+        if let Some(preview) = &mut self.cleanup_preview {
+            let (choice, open) = preview.show(ctx);
+            if !open {
+                self.cleanup_preview = None;
+            }
+            match choice {
+                Some(cleanup::CleanupChoice::Apply(path)) => {
+                    self.config.editor.cleanup_previewed = true;
+                    self.config.save();
+                    if self.last_note.as_ref() == Some(&path) && self.editable() {
+                        self.write_note(&path);
                     }
-                });
+                }
+                Some(cleanup::CleanupChoice::TurnOff) => {
+                    self.config.editor.trim_trailing_whitespace = false;
+                    self.config.editor.final_newline = false;
+                    self.config.editor.collapse_blank_lines = false;
+                    self.config.save();
+                }
+                None => {}
+            }
+        }
+        if let Some(path) = self.info.show(ctx, self.last_note.as_deref(), &mut self.note_content, &mut self.metadata, locked) {
+            self.set_note_path(&path);
+            self.note_list.invalidate();
+        }
+        if let Some(revision) = self.info.take_compare() {
+            match diff::DiffView::revision(&revision) {
+                Ok(view) => self.compare = Some(view),
+                Err(err) => error!("Failed to read revision {}: {}", revision.seq, err),
+            }
+        }
+        match self.board.show(ctx, &mut self.note_list) {
+            Some(kanban::BoardEvent::Open(path)) => self.open_path(&path),
+            Some(kanban::BoardEvent::Changed(path)) if self.last_note.as_ref().is_some_and(|p| vault::note_key(p) == vault::note_key(&path)) => {
+                self.reload_if_clean()
+            }
+            Some(kanban::BoardEvent::Changed(_)) | None => {}
+        }
+        self.lan_sync.show(ctx);
+        self.logs.show(ctx);
+        self.jobs_view.show(ctx);
+        for report in self.lan_sync.poll(&self.config.lan_sync) {
+            self.sync_status = Some(report.summary());
+            if !report.received.is_empty() || !report.conflicts.is_empty() {
+                self.note_list.invalidate();
+            }
+            if self.last_note.as_ref().is_some_and(|p| report.received.iter().any(|r| vault::note_key(r) == vault::note_key(p))) {
+                self.reload_if_clean();
+            }
+        }
+        if let Some(path) = self.habits.show(ctx, &mut self.note_list)
+            && self.last_note.as_ref().is_some_and(|p| vault::note_key(p) == vault::note_key(&path))
+        {
+            self.reload_if_clean();
+        }
+        let publish = self.config.publish.clone();
+        self.publish.show(ctx, &mut self.note_list, &mut self.config.publish);
+        if self.config.publish != publish {
+            self.config.save();
+        }
+        if let Some(path) = self.dashboard.show(ctx, &mut self.note_list) {
+            self.open_path(&path);
+        }
+        if let Some(path) = self.random_note.show(ctx, self.note_list.notes(), &self.config.review) {
+            self.open_path(&path);
+        }
+        if let Some(path) = self.review.show(ctx) {
+            self.open_path(&path);
+        }
+        match self.archived.show(ctx, &mut self.metadata) {
+            Some(archived::ArchivedEvent::Open(path)) => self.open_path(&path),
+            Some(archived::ArchivedEvent::Restored(path)) => {
+                self.note_list.invalidate();
+                self.open_path(&path);
+            }
+            None => {}
+        }
+        if let Some(path) = self.merge_notes.show(ctx) {
+            self.open_path(&path);
+        }
+        if let Some(path) = self.gardening.show(ctx) {
+            self.open_path(&path);
+        }
+        match self.lint.show(ctx) {
+            Some(lint::LintEvent::Open(path)) => self.open_path(&path),
+            Some(lint::LintEvent::Changed(path)) if self.last_note.as_ref() == Some(&path) => self.reload_if_clean(),
+            Some(lint::LintEvent::Changed(_)) | None => {}
+        }
+        self.read_aloud.show(ctx, &self.note_content);
+        let target = self.config.translate.target_lang.clone();
+        match self.translate.show(ctx, &mut self.config.translate, &self.note_content) {
+            Some(_) if !self.editable() => {}
+            Some(translate::TranslateEdit::Replace(range, text)) => self.note_content.replace_range(range, &text),
+            Some(translate::TranslateEdit::InsertAfter(at, text)) => self.note_content.insert_str(at, &text),
+            None => {}
+        }
+        if self.config.translate.target_lang != target {
+            self.config.save();
+        }
+        if self.definitions.open
+            && let Some(dictionary) = self.dictionary()
+        {
+            self.definitions.show(ctx, &dictionary);
+        }
+        if self.synonyms.open {
+            let thesaurus = self.thesaurus();
+            if let Some((range, synonym)) = self.synonyms.show(ctx, &self.note_content, &thesaurus)
+                && self.editable()
+            {
+                let cursor = range.start + synonym.len();
+                self.note_content.replace_range(range, &synonym);
+                self.move_cursor(ctx, cursor);
+            }
+        }
+
+        // Autosave
+        if self.autosave.due(self.config.editor.autosave_seconds)
+            && self.selected_file.is_some()
+            && self.note_content != self.base_content
+            && self.merge.is_none()
+            && !frontmatter::is_locked(&self.note_content)
+        {
+            self.save_file();
+        }
+
+        // Crash recovery
+        let unsaved = self.note_content != self.base_content;
+        if let Some(wait) = self.recovery.track(self.last_note.as_deref(), &self.note_content, unsaved) {
+            ctx.request_repaint_after(wait);
+        }
+        if let Some(item) = self.recovered.show(ctx, unsaved) {
+            let opened = match item.note.filter(|p| p.is_file()) {
+                Some(path) => {
+                    self.open_path(&path);
+                    self.last_note.as_ref() == Some(&path)
+                }
+                None => {
+                    self.new_note();
+                    true
+                }
+            };
+            if opened {
+                self.note_content = item.text;
+                self.show_start = false;
+            }
+        }
+
+        // Save hook errors
+        if !self.hook_errors.is_empty() {
+            let mut open = true;
+            egui::Window::new(t("⚠ Save hooks")).open(&mut open).show(ctx, |ui| {
+                for error in &self.hook_errors {
+                    ui.label(egui::RichText::new(&error.command).monospace().strong());
+                    ui.colored_label(egui::Color32::LIGHT_RED, &error.message);
+                    ui.separator();
+                }
+            });
+            if !open {
+                self.hook_errors.clear();
+            }
+        }
+
+        // Merge view
+        if let Some(view) = &mut self.merge {
+            match view.show(ctx) {
+                MergeOutcome::Pending => {}
+                MergeOutcome::Cancelled => {
+                    self.merge = None;
+                    self.nextcloud_merge = None;
+                }
+                MergeOutcome::Merged(text) => {
+                    let path = view.path.clone();
+                    self.note_content = text;
+                    self.merge = None;
+                    self.write_note(&path);
+                    if let Some(conflict) = self.nextcloud_merge.take() {
+                        self.nextcloud_resolved(&conflict);
+                    }
+                }
+            }
+        } else if !self.nextcloud_conflicts.is_empty() {
+            self.merge_next_nextcloud_conflict();
         }
     }
 }
 
-fn apply_custom_style(ctx: &Context) {
+fn apply_fonts(ctx: &Context, appearance: &config::AppearanceConfig) {
     let mut fonts = FontDefinitions::default();
     fonts.font_data.insert(
         "Minigap".to_owned(),
-        FontData::from_owned(
-            std::fs::read("fonts/Minigap-Regular.ttf").expect("Font file not found"),
-        ),
+        FontData::from_static(include_bytes!("../fonts/Minigap-Regular.ttf")),
     );
     fonts
         .families
@@ -169,11 +2707,18 @@ fn apply_custom_style(ctx: &Context) {
         .entry(FontFamily::Monospace)
         .or_default()
         .insert(0, "Minigap".to_owned());
+    fonts::add_fallbacks(&mut fonts);
+    if appearance.dyslexic_font {
+        fonts::add_dyslexic(&mut fonts, &appearance.dyslexic_font_path);
+    }
 
     ctx.set_fonts(fonts);
+}
 
+/// The chosen theme's colors and font sizes, or high contrast over it.
+fn apply_style(ctx: &Context, appearance: &config::AppearanceConfig, theme: &theme::Theme) {
     let mut style: Style = (*ctx.style()).clone();
-    style.visuals = Visuals::dark();
+    style.visuals = if appearance.high_contrast { a11y::visuals(true) } else { theme.visuals() };
     style.text_styles = [
         (egui::TextStyle::Heading, FontId::new(20.0, FontFamily::Proportional)),
         (egui::TextStyle::Body, FontId::new(16.0, FontFamily::Proportional)),
@@ -182,17 +2727,26 @@ fn apply_custom_style(ctx: &Context) {
         (egui::TextStyle::Small, FontId::new(12.0, FontFamily::Proportional)),
     ]
         .into();
+    theme.apply_fonts(&mut style);
 
     ctx.set_style(style);
 }
 
 fn main() -> eframe::Result<()> {
+    logs::init();
+    let initial = std::env::args().nth(1);
+    let listener = match ipc::acquire(initial.as_deref()) {
+        ipc::Instance::Primary(listener) => Some(listener),
+        ipc::Instance::Forwarded => return Ok(()),
+        ipc::Instance::Standalone => None,
+    };
+
     let options = NativeOptions {
         ..Default::default()
     };
     eframe::run_native(
         "Rust Note App",
         options,
-        Box::new(|cc| Box::new(NoteApp::new(cc))),
+        Box::new(|cc| Box::new(NoteApp::new(cc, listener, initial))),
     )
 }
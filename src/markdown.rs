@@ -0,0 +1,89 @@
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeBlock {
+    pub lang: String,
+    pub code: String,
+    /// Byte range of the whole block, fences included.
+    pub range: Range<usize>,
+}
+
+/// The run of three or more backticks or tildes a line starts with.
+fn fence(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    let marker = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+    let len = trimmed.len() - trimmed.trim_start_matches(marker).len();
+    (len >= 3).then(|| &trimmed[..len])
+}
+
+/// Finds ``` and ~~~ fenced code blocks. A block is closed by a fence of
+/// the same kind at least as long; an unclosed fence runs to the end.
+pub fn fenced_blocks(text: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    let mut open: Option<(&str, String, usize, usize)> = None;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        match (&open, fence(line)) {
+            (None, Some(fence)) => {
+                let lang = trimmed[fence.len()..].trim().to_owned();
+                open = Some((fence, lang, offset, offset + line.len()));
+            }
+            (Some((marker, _, _, _)), Some(fence))
+                if fence.starts_with(&marker[..1]) && fence.len() >= marker.len() && trimmed.trim() == fence =>
+            {
+                let (_, lang, start, body) = open.take().expect("open fence");
+                blocks.push(CodeBlock {
+                    lang,
+                    code: text[body..offset].to_owned(),
+                    range: start..offset + line.len(),
+                });
+            }
+            _ => {}
+        }
+        offset += line.len();
+    }
+
+    if let Some((_, lang, start, body)) = open {
+        blocks.push(CodeBlock {
+            lang,
+            code: text[body..].to_owned(),
+            range: start..text.len(),
+        });
+    }
+    blocks
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    pub level: u8,
+    pub title: String,
+    /// Byte offset of the start of its line.
+    pub offset: usize,
+}
+
+/// The `#` headings of a note, leaving out code blocks and front matter.
+pub fn headings(text: &str) -> Vec<Heading> {
+    let fences = fenced_blocks(text);
+    let skip = crate::frontmatter::range(text).map_or(0, |r| r.end);
+    let mut headings = Vec::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        if start < skip || fences.iter().any(|f| f.range.contains(&start)) {
+            continue;
+        }
+        let hashes = line.len() - line.trim_start_matches('#').len();
+        let rest = &line[hashes..];
+        if (1..=6).contains(&hashes) && (rest.starts_with([' ', '\t']) || rest.trim().is_empty()) {
+            headings.push(Heading {
+                level: hashes as u8,
+                title: rest.trim().trim_end_matches('#').trim_end().to_owned(),
+                offset: start,
+            });
+        }
+    }
+    headings
+}
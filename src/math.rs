@@ -0,0 +1,77 @@
+use crate::svg::{SvgCache, hex_color};
+use eframe::egui;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::process::{Command, Stdio};
+
+/// Renders TeX to SVG with whatever is installed: MathJax's `tex2svg`, or
+/// `latex` followed by `dvisvgm`. `color` is a hex RGB triple like `dddddd`.
+pub fn render_svg(tex: &str, display: bool, color: &str) -> Result<String, String> {
+    match tex2svg(tex, display) {
+        Ok(svg) => Ok(svg.replace("currentColor", &format!("#{}", color))),
+        Err(first) => latex_svg(tex, display, color).map_err(|second| format!("{}; {}", first, second)),
+    }
+}
+
+fn tex2svg(tex: &str, display: bool) -> Result<String, String> {
+    let mut command = Command::new("tex2svg");
+    if !display {
+        command.arg("--inline");
+    }
+    let output = command.arg(tex).output().map_err(|e| format!("tex2svg: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("tex2svg: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn latex_svg(tex: &str, display: bool, color: &str) -> Result<String, String> {
+    let mut hasher = DefaultHasher::new();
+    (tex, display, color).hash(&mut hasher);
+    let dir = std::env::temp_dir().join(format!("noteapp-math-{:x}", hasher.finish()));
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let body = if display { format!("$\\displaystyle {}$", tex) } else { format!("${}$", tex) };
+    let document = format!(
+        "\\documentclass[preview,border=1pt]{{standalone}}\n\\usepackage{{amsmath,amssymb,xcolor}}\n\\begin{{document}}\\color[HTML]{{{}}}{}\\end{{document}}\n",
+        color.to_uppercase(),
+        body
+    );
+    std::fs::write(dir.join("math.tex"), document).map_err(|e| e.to_string())?;
+
+    let run = |program: &str, args: &[&str]| -> Result<(), String> {
+        let output = Command::new(program)
+            .args(args)
+            .current_dir(&dir)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| format!("{}: {}", program, e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            let log = String::from_utf8_lossy(&output.stdout);
+            let error = log.lines().find(|l| l.starts_with('!')).unwrap_or("failed");
+            Err(format!("{}: {}", program, error))
+        }
+    };
+    run("latex", &["-interaction=nonstopmode", "-halt-on-error", "math.tex"])?;
+    run("dvisvgm", &["--no-fonts", "--exact-bbox", "-o", "math.svg", "math.dvi"])?;
+    let svg = std::fs::read_to_string(dir.join("math.svg")).map_err(|e| e.to_string());
+    let _ = std::fs::remove_dir_all(&dir);
+    svg
+}
+
+/// Shows a formula, falling back to its source until it has been rendered.
+pub fn show(ui: &mut egui::Ui, images: &mut SvgCache, tex: &str, display: bool) {
+    let tex = tex.trim();
+    let color = hex_color(ui.visuals().text_color());
+    let key = format!("math:{}:{}:{}", display, color, tex);
+    let source = tex.to_owned();
+    let scale = if display { 1.6 } else { 1.3 };
+    match images.show(ui, key, scale, move || render_svg(&source, display, &color)) {
+        Some(response) => {
+            response.on_hover_text(tex);
+        }
+        None => {
+            ui.label(egui::RichText::new(tex).monospace().italics());
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use crate::i18n::{t, tf};
+use eframe::egui::{self, Color32, RichText};
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Choice {
+    Local,
+    Remote,
+    Both,
+}
+
+#[derive(Debug)]
+pub enum Hunk {
+    Resolved(String),
+    Conflict {
+        base: String,
+        local: String,
+        remote: String,
+        choice: Option<Choice>,
+    },
+}
+
+impl Hunk {
+    fn text(&self) -> Option<String> {
+        match self {
+            Hunk::Resolved(text) => Some(text.clone()),
+            Hunk::Conflict { local, remote, choice, .. } => match choice {
+                Some(Choice::Local) => Some(local.clone()),
+                Some(Choice::Remote) => Some(remote.clone()),
+                Some(Choice::Both) => Some(format!("{}{}", local, remote)),
+                None => None,
+            },
+        }
+    }
+}
+
+/// Longest common subsequence of two line slices, as matched index pairs.
+pub fn lcs_pairs(a: &[&str], b: &[&str]) -> Vec<(usize, usize)> {
+    let mut table = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            table[i][j] = if a[i] == b[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+fn base_map(base: &[&str], other: &[&str]) -> Vec<Option<usize>> {
+    let mut map = vec![None; base.len()];
+    for (i, j) in lcs_pairs(base, other) {
+        map[i] = Some(j);
+    }
+    map
+}
+
+/// Line-based three-way merge. Changes made on only one side are taken
+/// automatically; overlapping changes become conflict hunks.
+pub fn merge3(base: &str, local: &str, remote: &str) -> Vec<Hunk> {
+    let b: Vec<&str> = base.split_inclusive('\n').collect();
+    let l: Vec<&str> = local.split_inclusive('\n').collect();
+    let r: Vec<&str> = remote.split_inclusive('\n').collect();
+    let to_local = base_map(&b, &l);
+    let to_remote = base_map(&b, &r);
+
+    let mut hunks = Vec::new();
+    let mut stable = String::new();
+    let (mut i, mut j, mut k) = (0, 0, 0);
+
+    loop {
+        let sync = (i..b.len()).find_map(|n| match (to_local[n], to_remote[n]) {
+            (Some(lj), Some(rk)) => Some((n, lj, rk)),
+            _ => None,
+        });
+        let (ni, nj, nk) = sync.unwrap_or((b.len(), l.len(), r.len()));
+
+        if sync.is_some() && (ni, nj, nk) == (i, j, k) {
+            stable.push_str(b[i]);
+            i += 1;
+            j += 1;
+            k += 1;
+            continue;
+        }
+
+        let chunk_base = b[i..ni].concat();
+        let chunk_local = l[j..nj].concat();
+        let chunk_remote = r[k..nk].concat();
+
+        if chunk_local == chunk_base {
+            stable.push_str(&chunk_remote);
+        } else if chunk_remote == chunk_base || chunk_local == chunk_remote {
+            stable.push_str(&chunk_local);
+        } else {
+            if !stable.is_empty() {
+                hunks.push(Hunk::Resolved(std::mem::take(&mut stable)));
+            }
+            hunks.push(Hunk::Conflict {
+                base: chunk_base,
+                local: chunk_local,
+                remote: chunk_remote,
+                choice: None,
+            });
+        }
+
+        if sync.is_none() {
+            break;
+        }
+        (i, j, k) = (ni, nj, nk);
+    }
+
+    if !stable.is_empty() {
+        hunks.push(Hunk::Resolved(stable));
+    }
+    hunks
+}
+
+pub enum MergeOutcome {
+    Pending,
+    Cancelled,
+    Merged(String),
+}
+
+pub struct MergeView {
+    pub path: PathBuf,
+    pub local: String,
+    pub remote: String,
+    hunks: Vec<Hunk>,
+}
+
+impl MergeView {
+    pub fn new(path: PathBuf, base: &str, local: &str, remote: &str) -> Self {
+        Self {
+            path,
+            local: local.to_owned(),
+            remote: remote.to_owned(),
+            hunks: merge3(base, local, remote),
+        }
+    }
+
+    pub fn has_conflicts(&self) -> bool {
+        self.hunks.iter().any(|h| matches!(h, Hunk::Conflict { .. }))
+    }
+
+    /// The merged text, if every conflict has been resolved.
+    pub fn result(&self) -> Option<String> {
+        self.hunks.iter().map(Hunk::text).collect()
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> MergeOutcome {
+        let mut outcome = MergeOutcome::Pending;
+        let mut open = true;
+
+        egui::Window::new(t("🔀 Resolve Conflicts"))
+            .open(&mut open)
+            .default_width(720.0)
+            .show(ctx, |ui| {
+                ui.label(tf("{} was changed on disk while you were editing it.", &[&self.path.display()]));
+                ui.separator();
+
+                egui::ScrollArea::vertical().max_height(480.0).show(ui, |ui| {
+                    for (index, hunk) in self.hunks.iter_mut().enumerate() {
+                        match hunk {
+                            Hunk::Resolved(text) => {
+                                ui.label(RichText::new(text.trim_end()).monospace().weak());
+                            }
+                            Hunk::Conflict { base, local, remote, choice } => {
+                                ui.push_id(index, |ui| {
+                                    conflict_ui(ui, base, local, remote, choice);
+                                });
+                            }
+                        }
+                    }
+                });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let merged = self.result();
+                    let apply = egui::Button::new(t("✔ Apply merge"));
+                    if ui.add_enabled(merged.is_some(), apply).clicked()
+                        && let Some(text) = merged
+                    {
+                        outcome = MergeOutcome::Merged(text);
+                    }
+                    if ui.button(t("Keep mine")).clicked() {
+                        outcome = MergeOutcome::Merged(self.local.clone());
+                    }
+                    if ui.button(t("Take theirs")).clicked() {
+                        outcome = MergeOutcome::Merged(self.remote.clone());
+                    }
+                    if ui.button(t("Cancel")).clicked() {
+                        outcome = MergeOutcome::Cancelled;
+                    }
+                });
+            });
+
+        if !open {
+            outcome = MergeOutcome::Cancelled;
+        }
+        outcome
+    }
+}
+
+fn conflict_ui(
+    ui: &mut egui::Ui,
+    base: &str,
+    local: &str,
+    remote: &str,
+    choice: &mut Option<Choice>,
+) {
+    egui::Frame::group(ui.style()).show(ui, |ui| {
+        ui.columns(3, |cols| {
+            cols[0].label(RichText::new(t("Base")).strong());
+            cols[0].label(RichText::new(base.trim_end()).monospace().weak());
+            cols[1].label(RichText::new(t("Local")).strong().color(Color32::LIGHT_GREEN));
+            cols[1].label(RichText::new(local.trim_end()).monospace());
+            cols[2].label(RichText::new(t("Remote")).strong().color(Color32::LIGHT_BLUE));
+            cols[2].label(RichText::new(remote.trim_end()).monospace());
+        });
+        ui.horizontal(|ui| {
+            ui.selectable_value(choice, Some(Choice::Local), t("Accept local"));
+            ui.selectable_value(choice, Some(Choice::Remote), t("Accept remote"));
+            ui.selectable_value(choice, Some(Choice::Both), t("Accept both"));
+        });
+    });
+}
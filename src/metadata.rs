@@ -0,0 +1,65 @@
+use crate::vault::{self, METADATA_FILE};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::error;
+
+/// Per-note state that is not part of the note's text.
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct NoteMeta {
+    pub pinned: bool,
+    pub favorite: bool,
+    pub comments: Vec<crate::comments::Comment>,
+}
+
+/// Note state kept in one file in the vault, keyed by the note's path
+/// relative to the notes folder.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Metadata {
+    notes: BTreeMap<String, NoteMeta>,
+}
+
+impl Metadata {
+    pub fn load() -> Self {
+        std::fs::read_to_string(METADATA_FILE)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) {
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => {
+                if let Err(err) = std::fs::write(METADATA_FILE, json) {
+                    error!("Failed to save note metadata: {}", err);
+                }
+            }
+            Err(err) => error!("Failed to serialize note metadata: {}", err),
+        }
+    }
+
+    pub fn get(&self, note: &Path) -> NoteMeta {
+        self.notes.get(&vault::note_key(note)).cloned().unwrap_or_default()
+    }
+
+    /// Changes a note's state and writes the file.
+    pub fn update(&mut self, note: &Path, change: impl FnOnce(&mut NoteMeta)) {
+        let key = vault::note_key(note);
+        let mut meta = self.notes.remove(&key).unwrap_or_default();
+        change(&mut meta);
+        if meta != NoteMeta::default() {
+            self.notes.insert(key, meta);
+        }
+        self.save();
+    }
+
+    /// Keeps a note's state when it is moved.
+    pub fn rename(&mut self, from: &Path, to: &Path) {
+        if let Some(meta) = self.notes.remove(&vault::note_key(from)) {
+            self.notes.insert(vault::note_key(to), meta);
+            self.save();
+        }
+    }
+}
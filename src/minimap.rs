@@ -0,0 +1,91 @@
+use crate::theme::Syntax;
+use eframe::egui::{self, Color32, Rect, Sense};
+
+/// Width of the strip, in points.
+const WIDTH: f32 = 90.0;
+/// Tallest a line is drawn; long notes are squeezed to fit the strip.
+const LINE_HEIGHT: f32 = 3.0;
+/// How much of a character's width a column takes up.
+const CHAR_WIDTH: f32 = 1.0;
+
+/// How the editor was scrolled on the last frame, in points.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Viewport {
+    pub offset: f32,
+    /// Height of the part of the note on screen.
+    pub height: f32,
+    /// Height of the whole note.
+    pub content: f32,
+}
+
+/// A zoomed-out picture of the note beside the editor, each line drawn as
+/// bars where its words are, with the part on screen shaded. Clicking or
+/// dragging on it returns where to scroll the editor to.
+pub fn show(ctx: &egui::Context, text: &str, viewport: Viewport, syntax: &Syntax) -> Option<f32> {
+    let mut scroll = None;
+    egui::SidePanel::right("minimap").exact_width(WIDTH).resizable(false).show(ctx, |ui| {
+        let (rect, response) = ui.allocate_exact_size(ui.available_size(), Sense::click_and_drag());
+        let lines: Vec<&str> = text.lines().collect();
+        let line_height = (rect.height() / lines.len().max(1) as f32).min(LINE_HEIGHT);
+        let map_height = line_height * lines.len() as f32;
+        let painter = ui.painter_at(rect);
+
+        let visuals = ui.visuals();
+        let plain = visuals.weak_text_color();
+        let heading = syntax.heading().unwrap_or_else(|| visuals.strong_text_color());
+        let code = syntax.code().unwrap_or_else(|| plain.gamma_multiply(0.6));
+        let mut fenced = false;
+        for (index, line) in lines.iter().enumerate() {
+            let trimmed = line.trim_start();
+            let fence = trimmed.starts_with("```") || trimmed.starts_with("~~~");
+            let color = if fence || fenced {
+                code
+            } else if trimmed.starts_with('#') {
+                heading
+            } else {
+                plain
+            };
+            fenced ^= fence;
+            let y = rect.top() + index as f32 * line_height;
+            paint_line(&painter, rect, y, line_height, line, color);
+        }
+
+        if viewport.content > viewport.height && map_height > 0.0 {
+            let top = rect.top() + viewport.offset / viewport.content * map_height;
+            let bottom = rect.top() + (viewport.offset + viewport.height) / viewport.content * map_height;
+            let shown = Rect::from_x_y_ranges(rect.x_range(), top..=bottom.max(top + 4.0));
+            painter.rect_filled(shown, 2.0, Color32::from_white_alpha(if response.hovered() { 28 } else { 16 }));
+        }
+        if (response.clicked() || response.dragged())
+            && map_height > 0.0
+            && let Some(pointer) = response.interact_pointer_pos()
+        {
+            // Centers the view on the line under the pointer.
+            let fraction = ((pointer.y - rect.top()) / map_height).clamp(0.0, 1.0);
+            let target = fraction * viewport.content - viewport.height / 2.0;
+            scroll = Some(target.clamp(0.0, (viewport.content - viewport.height).max(0.0)));
+        }
+    });
+    scroll
+}
+
+/// Draws a bar for each run of non-blank characters in `line`.
+fn paint_line(painter: &egui::Painter, rect: Rect, y: f32, height: f32, line: &str, color: Color32) {
+    let bar = (height * 0.7).max(0.5);
+    let mut start = None;
+    let columns = line.chars().map(Some).chain([None]).enumerate();
+    for (column, c) in columns {
+        match (start, c.is_some_and(|c| !c.is_whitespace())) {
+            (None, true) => start = Some(column),
+            (Some(from), false) => {
+                let left = rect.left() + 4.0 + from as f32 * CHAR_WIDTH;
+                let right = (rect.left() + 4.0 + column as f32 * CHAR_WIDTH).min(rect.right() - 2.0);
+                if left < right {
+                    painter.rect_filled(Rect::from_min_max(egui::pos2(left, y), egui::pos2(right, y + bar)), 0.0, color);
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+}
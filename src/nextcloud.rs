@@ -0,0 +1,281 @@
+use crate::config::NextcloudConfig;
+use crate::vault::NOTES_DIR;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const STATE_FILE: &str = "notes/.nextcloud.json";
+
+#[derive(Deserialize, Debug)]
+pub struct RemoteNote {
+    id: u64,
+    etag: String,
+    title: String,
+    category: String,
+    content: String,
+}
+
+#[derive(Serialize, Debug)]
+struct NoteBody<'a> {
+    title: &'a str,
+    category: &'a str,
+    content: &'a str,
+}
+
+/// What we last exchanged with the server for one remote note, and the
+/// local file it lives in.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Synced {
+    path: PathBuf,
+    etag: String,
+    /// Journal hash of the content both sides agreed on.
+    #[serde(default)]
+    hash: String,
+    synced_at: SystemTime,
+}
+
+/// Keyed by remote note id, so a note comes back to the file it was pushed
+/// from whatever its extension or title.
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct SyncState {
+    notes: HashMap<u64, Synced>,
+}
+
+/// State files from before notes were keyed by id.
+#[derive(Deserialize)]
+struct LegacyState {
+    notes: HashMap<PathBuf, LegacySynced>,
+}
+
+#[derive(Deserialize)]
+struct LegacySynced {
+    id: u64,
+    etag: String,
+    synced_at: SystemTime,
+}
+
+impl SyncState {
+    fn load() -> Self {
+        let Ok(text) = std::fs::read_to_string(STATE_FILE) else {
+            return Self::default();
+        };
+        if let Ok(state) = serde_json::from_str(&text) {
+            return state;
+        }
+        let Ok(legacy) = serde_json::from_str::<LegacyState>(&text) else {
+            return Self::default();
+        };
+        let notes = legacy
+            .notes
+            .into_iter()
+            .map(|(path, s)| {
+                let synced = Synced { path, etag: s.etag, hash: String::new(), synced_at: s.synced_at };
+                (s.id, synced)
+            })
+            .collect();
+        Self { notes }
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        std::fs::write(STATE_FILE, serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// A note changed on both sides, with what is needed to merge it.
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub path: PathBuf,
+    /// The content at the last sync; empty if it is no longer in the journal.
+    pub base: String,
+    pub remote: String,
+    id: u64,
+    etag: String,
+}
+
+impl Conflict {
+    /// Marks the remote side as seen once the note has been merged, so the
+    /// next sync pushes the merge instead of reporting the conflict again.
+    pub fn resolved(&self) -> std::io::Result<()> {
+        let mut state = SyncState::load();
+        let synced_at = state.notes.get(&self.id).map_or(SystemTime::UNIX_EPOCH, |s| s.synced_at);
+        let synced = Synced {
+            path: self.path.clone(),
+            etag: self.etag.clone(),
+            hash: crate::journal::hash(&self.remote),
+            synced_at,
+        };
+        state.notes.insert(self.id, synced);
+        state.save()
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct SyncReport {
+    pub pulled: usize,
+    pub pushed: usize,
+    pub conflicts: Vec<Conflict>,
+}
+
+pub struct NextcloudClient {
+    client: reqwest::blocking::Client,
+    config: NextcloudConfig,
+}
+
+impl NextcloudClient {
+    pub fn new(config: &NextcloudConfig) -> Self {
+        Self {
+            client: crate::http::client(),
+            config: config.clone(),
+        }
+    }
+
+    fn endpoint(&self, suffix: &str) -> String {
+        format!(
+            "{}/index.php/apps/notes/api/v1/notes{}",
+            self.config.url.trim_end_matches('/'),
+            suffix
+        )
+    }
+
+    fn list(&self) -> reqwest::Result<Vec<RemoteNote>> {
+        let request = self.client.get(self.endpoint("")).basic_auth(&self.config.user, Some(&self.config.app_password));
+        crate::http::send(request)?
+            .error_for_status()?
+            .json()
+    }
+
+    fn upload(&self, id: Option<u64>, body: &NoteBody) -> reqwest::Result<RemoteNote> {
+        let request = match id {
+            Some(id) => self.client.put(self.endpoint(&format!("/{}", id))),
+            None => self.client.post(self.endpoint("")),
+        };
+        crate::http::send(request.basic_auth(&self.config.user, Some(&self.config.app_password)).json(body))?
+            .error_for_status()?
+            .json()
+    }
+
+    /// Pulls remote changes into the vault, then pushes local changes back.
+    /// Notes changed on both sides since the last sync, or made on both
+    /// sides under one name, are left alone and reported as conflicts to
+    /// be merged.
+    pub fn sync(&self) -> Result<SyncReport, String> {
+        let mut state = SyncState::load();
+        let mut report = SyncReport::default();
+        let remote = self.list().map_err(|e| e.to_string())?;
+
+        for note in &remote {
+            let known = state.notes.get(&note.id);
+            if known.is_some_and(|k| k.etag == note.etag) {
+                continue;
+            }
+            // Known notes go back where they were pushed from; new ones get
+            // a file named after their category and title.
+            let path = known.map_or_else(|| local_path(&note.category, &note.title), |k| k.path.clone());
+            if let Some(k) = known
+                && modified_since(&path, k.synced_at)
+            {
+                report.conflicts.push(conflict(note, path, base(k)));
+                continue;
+            }
+            // A local note the server has not seen yet, by the same name.
+            if known.is_none()
+                && let Ok(local) = std::fs::read_to_string(&path)
+            {
+                if local != note.content {
+                    report.conflicts.push(conflict(note, path, String::new()));
+                    continue;
+                }
+                state.notes.insert(note.id, synced(note, path, &note.content));
+                continue;
+            }
+            crate::journal::write(&path, &note.content).map_err(|e| e.to_string())?;
+            state.notes.insert(note.id, synced(note, path, &note.content));
+            report.pulled += 1;
+        }
+
+        let ids: HashMap<PathBuf, u64> = state.notes.iter().map(|(id, s)| (s.path.clone(), *id)).collect();
+        for path in crate::vault::walk_files(Path::new(NOTES_DIR)) {
+            if !crate::vault::is_note(&path) || report.conflicts.iter().any(|c| c.path == path) {
+                continue;
+            }
+            let id = ids.get(&path).copied();
+            if id.is_some_and(|id| !modified_since(&path, state.notes[&id].synced_at)) {
+                continue;
+            }
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let (category, title) = category_and_title(&path);
+            let body = NoteBody { title: &title, category: &category, content: &content };
+            let note = self.upload(id, &body).map_err(|e| e.to_string())?;
+            if let Some(id) = id {
+                state.notes.remove(&id);
+            }
+            state.notes.insert(note.id, synced(&note, path, &content));
+            report.pushed += 1;
+        }
+
+        state.save().map_err(|e| e.to_string())?;
+        Ok(report)
+    }
+}
+
+fn synced(note: &RemoteNote, path: PathBuf, content: &str) -> Synced {
+    Synced {
+        path,
+        etag: note.etag.clone(),
+        hash: crate::journal::hash(content),
+        synced_at: SystemTime::now(),
+    }
+}
+
+fn conflict(note: &RemoteNote, path: PathBuf, base: String) -> Conflict {
+    Conflict {
+        path,
+        base,
+        remote: note.content.clone(),
+        id: note.id,
+        etag: note.etag.clone(),
+    }
+}
+
+/// The content last synced for a note, looked up in its journal history.
+fn base(known: &Synced) -> String {
+    crate::journal::revisions(&known.path)
+        .iter()
+        .rev()
+        .find(|r| r.hash == known.hash)
+        .and_then(|r| crate::journal::content(r).ok())
+        .unwrap_or_default()
+}
+
+fn modified_since(path: &Path, time: SystemTime) -> bool {
+    path.metadata()
+        .and_then(|m| m.modified())
+        .is_ok_and(|modified| modified > time)
+}
+
+/// Nextcloud categories map to folders, with `/` as the separator.
+fn local_path(category: &str, title: &str) -> PathBuf {
+    let mut path = PathBuf::from(NOTES_DIR);
+    for part in category.split('/').filter(|p| !p.is_empty() && *p != "..") {
+        path.push(part);
+    }
+    let title = title.replace(['/', '\\'], "-");
+    path.push(format!("{}.md", title));
+    path
+}
+
+fn category_and_title(path: &Path) -> (String, String) {
+    let relative = path.strip_prefix(NOTES_DIR).unwrap_or(path);
+    let category = relative
+        .parent()
+        .map(|p| p.to_string_lossy().replace('\\', "/"))
+        .unwrap_or_default();
+    let title = relative
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    (category, title)
+}
@@ -0,0 +1,184 @@
+use crate::i18n::{t, tf};
+use crate::journal::{self, Revision};
+use crate::metadata::Metadata;
+use crate::{frontmatter, links, restructure, tags, vault};
+use eframe::egui::{self, RichText};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::error;
+
+fn format_time(time: Option<SystemTime>) -> String {
+    match time {
+        Some(time) => chrono::DateTime::<chrono::Local>::from(time).format("%Y-%m-%d %H:%M").to_string(),
+        None => "unknown".to_owned(),
+    }
+}
+
+/// Details that need a scan of the vault, read when the note changes.
+struct Scan {
+    path: PathBuf,
+    backlinks: usize,
+    snapshots: usize,
+    /// Journal revisions, newest first, as of journal revision `head`.
+    revisions: Vec<Revision>,
+    head: u64,
+}
+
+fn scan(path: &Path) -> Scan {
+    let notes = vault::note_files();
+    let target = vault::note_key(path);
+    let backlinks = notes
+        .iter()
+        .filter(|note| vault::note_key(note) != target)
+        .filter(|note| {
+            std::fs::read_to_string(note)
+                .is_ok_and(|text| links::outgoing(note, &text, &notes).iter().any(|p| vault::note_key(p) == target))
+        })
+        .count();
+    Scan {
+        path: path.to_path_buf(),
+        backlinks,
+        snapshots: vault::snapshots(path).len(),
+        revisions: journal::revisions(path).into_iter().rev().collect(),
+        head: journal::head(),
+    }
+}
+
+/// Info window for the open note, with its title (the file name) and
+/// front matter tags editable.
+#[derive(Default)]
+pub struct InfoPanel {
+    pub open: bool,
+    scan: Option<Scan>,
+    title: String,
+    tags: String,
+    compare: Option<Revision>,
+}
+
+impl InfoPanel {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.scan = None;
+    }
+
+    /// A revision the user asked to compare the note with.
+    pub fn take_compare(&mut self) -> Option<Revision> {
+        self.compare.take()
+    }
+
+    /// Returns the note's new path after a rename. The tags of a `locked`
+    /// note can't be edited.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        path: Option<&Path>,
+        text: &mut String,
+        metadata: &mut Metadata,
+        locked: bool,
+    ) -> Option<PathBuf> {
+        if !self.open {
+            return None;
+        }
+        let mut open = true;
+        let mut renamed = None;
+
+        egui::Window::new(t("ℹ Note Info")).open(&mut open).default_width(320.0).show(ctx, |ui| {
+            let Some(path) = path else {
+                ui.label(t("The note has not been saved yet."));
+                return;
+            };
+            if self.scan.as_ref().is_none_or(|s| s.path != path || s.head != journal::head()) {
+                let scan = scan(path);
+                self.title = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_owned();
+                self.tags = tags::front_matter_tags(text).join(", ");
+                self.scan = Some(scan);
+            }
+            let Some(scan) = &self.scan else {
+                return;
+            };
+            let meta = path.metadata().ok();
+
+            egui::Grid::new("note_info").num_columns(2).striped(true).show(ui, |ui| {
+                ui.label(t("Path"));
+                ui.label(RichText::new(path.display().to_string()).monospace());
+                ui.end_row();
+                ui.label(t("Size"));
+                ui.label(tf("{} bytes", &[&meta.as_ref().map_or(0, |m| m.len())]));
+                ui.end_row();
+                ui.label(t("Created"));
+                ui.label(format_time(meta.as_ref().and_then(|m| m.created().ok())));
+                ui.end_row();
+                ui.label(t("Modified"));
+                ui.label(format_time(meta.as_ref().and_then(|m| m.modified().ok())));
+                ui.end_row();
+                ui.label(t("Words"));
+                ui.label(text.split_whitespace().count().to_string());
+                ui.end_row();
+                ui.label(t("Tags"));
+                ui.label(tags::tags(text).iter().map(|t| format!("#{}", t)).collect::<Vec<_>>().join(" "));
+                ui.end_row();
+                ui.label(t("Backlinks"));
+                ui.label(scan.backlinks.to_string());
+                ui.end_row();
+                ui.label(t("Snapshots"));
+                ui.label(scan.snapshots.to_string());
+                ui.end_row();
+                ui.label(t("Revisions"));
+                ui.label(scan.revisions.len().to_string());
+                ui.end_row();
+            });
+            if !scan.revisions.is_empty() {
+                egui::CollapsingHeader::new(t("History")).show(ui, |ui| {
+                    egui::ScrollArea::vertical().max_height(160.0).show(ui, |ui| {
+                        for revision in &scan.revisions {
+                            ui.horizontal(|ui| {
+                                ui.label(revision.time.get(..16).unwrap_or(&revision.time).replace('T', " "));
+                                ui.label(RichText::new(revision.hash.get(..8).unwrap_or_default()).monospace().weak());
+                                if ui.small_button(t("Compare")).clicked() {
+                                    self.compare = Some(revision.clone());
+                                }
+                            });
+                        }
+                    });
+                });
+            }
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.label(t("Title"));
+                let response = ui.text_edit_singleline(&mut self.title);
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    let dir = path.parent().unwrap_or(Path::new(vault::NOTES_DIR));
+                    let to = dir.join(format!("{}.md", vault::safe_file_name(&self.title)));
+                    if vault::note_key(&to) != vault::note_key(path) {
+                        match restructure::move_note(path, &to, metadata) {
+                            Ok(new) => renamed = Some(new),
+                            Err(err) => error!("Failed to rename note: {}", err),
+                        }
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label(t("Tags"));
+                let response = ui.add_enabled(!locked, egui::TextEdit::singleline(&mut self.tags).hint_text(t("comma, separated")));
+                if response.lost_focus() {
+                    let list = tags::parse_list(&self.tags);
+                    let value = (!list.is_empty()).then(|| format!("[{}]", list.join(", ")));
+                    let updated = frontmatter::set(text, "tags", value.as_deref());
+                    if updated != *text {
+                        *text = updated;
+                    }
+                }
+            });
+            ui.label(RichText::new(t("Press Enter to rename; tag changes are saved with the note.")).small().weak());
+        });
+
+        if !open {
+            self.open = false;
+        }
+        if renamed.is_some() {
+            self.scan = None;
+        }
+        renamed
+    }
+}
@@ -0,0 +1,190 @@
+use crate::vault::{self, ATTACHMENTS_DIR, NOTES_DIR};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::error;
+
+/// Lines recognized below this confidence are reported back to the user.
+const LOW_CONFIDENCE: f32 = 60.0;
+
+pub struct WeakLine {
+    pub page: usize,
+    pub confidence: f32,
+    pub text: String,
+}
+
+pub struct OcrReport {
+    pub note: PathBuf,
+    pub mean_confidence: f32,
+    pub weak_lines: Vec<WeakLine>,
+    pub failed_pages: Vec<usize>,
+}
+
+struct Page {
+    text: String,
+    confidences: Vec<f32>,
+    weak_lines: Vec<(f32, String)>,
+}
+
+/// Runs the `tesseract` CLI in TSV mode and rebuilds paragraphs from it.
+fn recognize(image: &Path, language: &str) -> Result<Page, String> {
+    let output = Command::new("tesseract")
+        .arg(image)
+        .arg("stdout")
+        .args(["-l", language, "tsv"])
+        .output()
+        .map_err(|e| format!("tesseract: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_owned());
+    }
+
+    let tsv = String::from_utf8_lossy(&output.stdout);
+    let mut page = Page {
+        text: String::new(),
+        confidences: Vec::new(),
+        weak_lines: Vec::new(),
+    };
+    let mut current_line = None;
+    let mut current_par = None;
+    let mut line_words: Vec<(f32, &str)> = Vec::new();
+
+    let flush = |words: &mut Vec<(f32, &str)>, page: &mut Page| {
+        if words.is_empty() {
+            return;
+        }
+        let text = words.iter().map(|(_, w)| *w).collect::<Vec<_>>().join(" ");
+        let mean = words.iter().map(|(c, _)| c).sum::<f32>() / words.len() as f32;
+        if mean < LOW_CONFIDENCE {
+            page.weak_lines.push((mean, text.clone()));
+        }
+        page.text.push_str(&text);
+        page.text.push('\n');
+        words.clear();
+    };
+
+    for row in tsv.lines().skip(1) {
+        let cols: Vec<&str> = row.split('\t').collect();
+        if cols.len() < 12 || cols[0] != "5" {
+            continue;
+        }
+        let word = cols[11].trim();
+        let Ok(confidence) = cols[10].parse::<f32>() else {
+            continue;
+        };
+        if word.is_empty() || confidence < 0.0 {
+            continue;
+        }
+        let par = (cols[2], cols[3]);
+        let line = (cols[2], cols[3], cols[4]);
+        if current_line != Some(line) {
+            flush(&mut line_words, &mut page);
+            if current_par.is_some() && current_par != Some(par) {
+                page.text.push('\n');
+            }
+            current_line = Some(line);
+            current_par = Some(par);
+        }
+        page.confidences.push(confidence);
+        line_words.push((confidence, word));
+    }
+    flush(&mut line_words, &mut page);
+    Ok(page)
+}
+
+/// Renders PDF pages to PNGs with `pdftoppm`, returning them in order.
+fn rasterize_pdf(pdf: &Path, dir: &Path) -> Result<Vec<PathBuf>, String> {
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let status = Command::new("pdftoppm")
+        .args(["-r", "300", "-png"])
+        .arg(pdf)
+        .arg(dir.join("page"))
+        .status()
+        .map_err(|e| format!("pdftoppm: {}", e))?;
+    if !status.success() {
+        return Err(format!("pdftoppm exited with {}", status));
+    }
+    let mut pages: Vec<PathBuf> = vault::walk_files(dir)
+        .into_iter()
+        .filter(|p| p.extension().is_some_and(|e| e == "png"))
+        .collect();
+    // pdftoppm zero-pads page numbers, so name order is page order.
+    pages.sort();
+    Ok(pages)
+}
+
+/// OCRs an image or PDF into a new note that links to the original file,
+/// which is copied into the note's attachments folder.
+pub fn import(source: &Path, language: &str) -> Result<OcrReport, String> {
+    let stem = vault::safe_file_name(&source.file_stem().unwrap_or_default().to_string_lossy());
+    let assets = Path::new(ATTACHMENTS_DIR).join(&stem);
+    std::fs::create_dir_all(&assets).map_err(|e| e.to_string())?;
+    let file_name = source.file_name().ok_or("Not a file")?;
+    let attached = assets.join(file_name);
+    std::fs::copy(source, &attached).map_err(|e| e.to_string())?;
+
+    let is_pdf = source.extension().is_some_and(|e| e.eq_ignore_ascii_case("pdf"));
+    let scratch = std::env::temp_dir().join(format!("noteapp-ocr-{}", std::process::id()));
+    let images = if is_pdf {
+        rasterize_pdf(source, &scratch)?
+    } else {
+        vec![source.to_path_buf()]
+    };
+
+    let mut body = String::new();
+    let mut confidences = Vec::new();
+    let mut weak_lines = Vec::new();
+    let mut failed_pages = Vec::new();
+    for (index, image) in images.iter().enumerate() {
+        let number = index + 1;
+        match recognize(image, language) {
+            Ok(page) => {
+                if images.len() > 1 {
+                    body.push_str(&format!("## Page {}\n\n", number));
+                }
+                body.push_str(page.text.trim());
+                body.push_str("\n\n");
+                confidences.extend(page.confidences);
+                weak_lines.extend(page.weak_lines.into_iter().map(|(confidence, text)| WeakLine {
+                    page: number,
+                    confidence,
+                    text,
+                }));
+            }
+            Err(err) => {
+                error!("OCR failed on page {}: {}", number, err);
+                failed_pages.push(number);
+            }
+        }
+    }
+    let _ = std::fs::remove_dir_all(&scratch);
+    if confidences.is_empty() && !failed_pages.is_empty() {
+        return Err("No text could be recognized".to_owned());
+    }
+
+    let link = attached
+        .strip_prefix(NOTES_DIR)
+        .unwrap_or(&attached)
+        .to_string_lossy()
+        .replace('\\', "/")
+        .replace(' ', "%20");
+    let note = format!(
+        "# {}\n\nSource: [{}]({})\n\n{}",
+        stem,
+        file_name.to_string_lossy(),
+        link,
+        body.trim_end()
+    );
+    let path = vault::unique_path(Path::new(NOTES_DIR), &stem, "md");
+    crate::journal::write(&path, &(note + "\n")).map_err(|e| e.to_string())?;
+
+    let mean_confidence = if confidences.is_empty() {
+        0.0
+    } else {
+        confidences.iter().sum::<f32>() / confidences.len() as f32
+    };
+    Ok(OcrReport {
+        note: path,
+        mean_confidence,
+        weak_lines,
+        failed_pages,
+    })
+}
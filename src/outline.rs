@@ -0,0 +1,36 @@
+use crate::i18n::t;
+use crate::markdown;
+use eframe::egui::{self, RichText};
+
+/// The open note's headings in a side panel, indented by level, with the
+/// one the cursor is under highlighted. Returns the byte offset of a
+/// heading that was clicked.
+pub fn show(ctx: &egui::Context, text: &str, cursor: Option<usize>) -> Option<usize> {
+    let headings = markdown::headings(text);
+    let current = cursor.and_then(|at| headings.iter().rposition(|h| h.offset <= at));
+    let mut jump = None;
+    egui::SidePanel::right("outline")
+        .resizable(true)
+        .default_width(200.0)
+        .show(ctx, |ui| {
+            ui.heading(t("Outline"));
+            ui.separator();
+            if headings.is_empty() {
+                ui.label(RichText::new(t("Lines starting with # show here")).weak());
+                return;
+            }
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (index, heading) in headings.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.add_space(12.0 * f32::from(heading.level - 1));
+                        let title = if heading.title.is_empty() { "…" } else { &heading.title };
+                        let response = ui.selectable_label(current == Some(index), title);
+                        if response.clicked() {
+                            jump = Some(heading.offset);
+                        }
+                    });
+                }
+            });
+        });
+    jump
+}
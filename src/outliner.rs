@@ -0,0 +1,415 @@
+use crate::frontmatter;
+use crate::i18n::t;
+use crate::indent;
+use crate::markdown;
+use eframe::egui::{self, text::{CCursor, CCursorRange}, text_edit::TextEditState, Key, Modifiers, RichText, TextEdit};
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// How far each level of the outline is indented, in points.
+const INDENT: f32 = 18.0;
+
+/// A list item of the note, shown as a node of the outline.
+#[derive(Debug, Clone)]
+pub struct Node {
+    /// The item's text and which of the items with that text it is.
+    pub key: String,
+    /// Byte range of the item's line, without the newline.
+    pub line: Range<usize>,
+    /// Byte range of the item's text, after its marker and checkbox.
+    pub text: Range<usize>,
+    /// Byte offset of the mark inside the item's `[ ]` and whether it is
+    /// ticked.
+    pub check: Option<(usize, bool)>,
+    /// The item's line and the lines under it that belong to it.
+    pub subtree: Range<usize>,
+    pub parent: Option<usize>,
+    pub depth: usize,
+}
+
+/// The list items of `text` in order, leaving out those in code blocks
+/// and front matter.
+pub fn nodes(text: &str) -> Vec<Node> {
+    let mut skipped: Vec<Range<usize>> = markdown::fenced_blocks(text).into_iter().map(|b| b.range).collect();
+    skipped.extend(frontmatter::range(text));
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut seen: Vec<&str> = Vec::new();
+    let mut offset = 0;
+    for raw in text.split_inclusive('\n') {
+        let start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\n', '\r']);
+        if indent::content_column(line).is_none() || skipped.iter().any(|r| r.contains(&start)) {
+            continue;
+        }
+        let body = line.trim_start_matches([' ', '\t']);
+        let after = body[body.find(' ').unwrap_or(body.len())..].trim_start_matches(' ');
+        let end = start + line.len();
+        let mut text_start = end - after.len();
+        let mut check = None;
+        if let Some(mark) = after.get(..3).filter(|b| matches!(*b, "[ ]" | "[x]" | "[X]"))
+            && (after.len() == 3 || after[3..].starts_with(' '))
+        {
+            check = Some((text_start + 1, mark != "[ ]"));
+            text_start = end - after[3..].trim_start_matches(' ').len();
+        }
+        let parent = nodes.iter().rposition(|n| n.subtree.contains(&start));
+        let own = &text[text_start..end];
+        let nth = seen.iter().filter(|l| **l == own).count();
+        seen.push(own);
+        nodes.push(Node {
+            key: format!("{}:{}", nth, own),
+            line: start..end,
+            text: text_start..end,
+            check,
+            subtree: start..indent::subtree_end(text, start),
+            parent,
+            depth: parent.map_or(0, |p| nodes[p].depth + 1),
+        });
+    }
+    nodes
+}
+
+/// Swaps the node with the sibling before or after it, each with the
+/// lines under it. Siblings with more than blank lines between them stay
+/// put. Returns the new text and where the node's line now starts.
+pub fn move_node(text: &str, nodes: &[Node], index: usize, up: bool) -> Option<(String, usize)> {
+    let node = nodes.get(index)?;
+    let sibling = if up {
+        nodes[..index].iter().rposition(|n| n.parent == node.parent)?
+    } else {
+        index + 1 + nodes[index + 1..].iter().position(|n| n.parent == node.parent)?
+    };
+    let (first, second) = if up { (&nodes[sibling], node) } else { (node, &nodes[sibling]) };
+    if !text[first.subtree.end..second.line.start].trim().is_empty() {
+        return None;
+    }
+    // Both parts need their newline to swap, the last line of the note
+    // included.
+    let bare = !text.ends_with('\n');
+    let mut text = text.to_owned();
+    if bare {
+        text.push('\n');
+    }
+    let whole = |r: &Range<usize>| r.start..if r.end == text.len() - 1 && bare { r.end + 1 } else { r.end };
+    let (a, b) = (whole(&first.subtree), whole(&second.subtree));
+    let mut swapped = format!("{}{}{}{}{}", &text[..a.start], &text[b.clone()], &text[a.end..b.start], &text[a.clone()], &text[b.end..]);
+    if bare {
+        swapped.pop();
+    }
+    let at = if up { a.start } else { a.start + b.len() + (b.start - a.end) };
+    Some((swapped, at))
+}
+
+/// A new empty item after the node and the items under it, with the same
+/// marker, the next number and a checkbox when the node has one. Returns
+/// the new text and where the new item's line starts.
+pub fn add_after(text: &str, node: &Node) -> (String, usize) {
+    let line = &text[node.line.clone()];
+    let body = line.trim_start_matches([' ', '\t']);
+    let lead = &line[..line.len() - body.len()];
+    let marker = &body[..body.find(' ').unwrap_or(body.len())];
+    let marker = match marker.get(..marker.len() - 1).and_then(|n| n.parse::<u32>().ok()) {
+        Some(number) => format!("{}{}", number + 1, &marker[marker.len() - 1..]),
+        None => marker.to_owned(),
+    };
+    let item = format!("{}{} {}", lead, marker, if node.check.is_some() { "[ ] " } else { "" });
+    let at = node.subtree.end;
+    let mut text = text.to_owned();
+    if at == 0 || text[..at].ends_with('\n') {
+        text.insert_str(at, &format!("{}\n", item));
+        (text, at)
+    } else {
+        text.insert_str(at, &format!("\n{}", item));
+        (text, at + 1)
+    }
+}
+
+/// The byte range to take out to delete `range`'s whole lines, with the
+/// newline before them when they end the note without one.
+fn removal(text: &str, range: &Range<usize>) -> Range<usize> {
+    let end = indent::line_end(text, range.end.saturating_sub(1).max(range.start));
+    if end == text.len() && !text.ends_with('\n') && range.start > 0 {
+        range.start - 1..end
+    } else {
+        range.start..end
+    }
+}
+
+/// What happened in the outline this frame, applied after it is drawn.
+enum Change {
+    /// Replace a byte range of the note.
+    Splice(Range<usize>, String),
+    /// Replace the whole note and focus the node whose line starts at an
+    /// offset.
+    Rewrite(String, usize),
+}
+
+/// The editor as an outline: each list item a node that can be collapsed,
+/// moved with the items under it, zoomed into and ticked off. The note
+/// stays a plain Markdown list.
+#[derive(Default)]
+pub struct Outliner {
+    pub open: bool,
+    /// Keys of the nodes whose children are hidden.
+    collapsed: HashSet<String>,
+    /// The node shown on its own, with only what is under it.
+    zoom: Option<String>,
+    /// Byte offset of a line whose node takes the keyboard focus next.
+    focus: Option<usize>,
+}
+
+fn node_id(index: usize) -> egui::Id {
+    egui::Id::new("outliner_node").with(index)
+}
+
+impl Outliner {
+    pub fn new(open: bool) -> Self {
+        Self { open, ..Default::default() }
+    }
+
+    /// Draws the outline of `text`, editing it in place. Returns whether
+    /// the note changed.
+    pub fn show(&mut self, ui: &mut egui::Ui, text: &mut String, locked: bool) -> bool {
+        let nodes = nodes(text);
+        let zoomed = self.zoom.as_ref().and_then(|key| nodes.iter().position(|n| n.key == *key));
+        if zoomed.is_none() {
+            self.zoom = None;
+        }
+        let collapsed = self.collapsed.clone();
+        let under = |n: &Node, at: usize| n.line.end < at && at < n.subtree.end;
+        let hidden = |at: usize| zoomed.is_some_and(|z| !under(&nodes[z], at)) || nodes.iter().any(|n| collapsed.contains(&n.key) && under(n, at));
+        let visible: Vec<usize> = (0..nodes.len()).filter(|&i| !hidden(nodes[i].line.start)).collect();
+        let base = zoomed.map_or(0, |z| nodes[z].depth + 1);
+
+        let mut change = None;
+        let focused = visible.iter().position(|&i| ui.memory(|m| m.has_focus(node_id(i))));
+        if let Some(position) = focused
+            && !locked
+        {
+            change = self.keys(ui.ctx(), text, &nodes, &visible, position);
+        }
+
+        if let Some(z) = zoomed {
+            ui.horizontal_wrapped(|ui| {
+                if ui.link("🏠").on_hover_text(t("Zoom out to the whole note")).clicked() {
+                    self.zoom = None;
+                }
+                let mut ancestors = Vec::new();
+                let mut parent = nodes[z].parent;
+                while let Some(p) = parent {
+                    ancestors.push(p);
+                    parent = nodes[p].parent;
+                }
+                for &a in ancestors.iter().rev() {
+                    ui.label("›");
+                    if ui.link(&text[nodes[a].text.clone()]).clicked() {
+                        self.zoom = Some(nodes[a].key.clone());
+                    }
+                }
+            });
+            ui.heading(&text[nodes[z].text.clone()]);
+            ui.separator();
+        }
+        if nodes.is_empty() {
+            ui.label(RichText::new(t("This note has no list yet.")).weak());
+            if !locked && ui.button(t("Start a list")).clicked() {
+                let at = text.len();
+                let prefix = if text.is_empty() || text.ends_with('\n') { "" } else { "\n" };
+                change = Some(Change::Rewrite(format!("{}{}- ", text, prefix), at + prefix.len()));
+            }
+        }
+
+        egui::ScrollArea::vertical().id_source("outliner_scroll").auto_shrink([false; 2]).show(ui, |ui| {
+            let front = frontmatter::range(text);
+            let mut offset = 0;
+            let mut next = 0;
+            for raw in text.split_inclusive('\n') {
+                let start = offset;
+                offset += raw.len();
+                if nodes.get(next).is_some_and(|n| n.line.start == start) {
+                    next += 1;
+                    let index = next - 1;
+                    if visible.contains(&index)
+                        && let Some(row) = self.node_row(ui, text, &nodes, index, base, locked)
+                    {
+                        change = Some(row);
+                    }
+                } else if !raw.trim().is_empty() && !hidden(start) && !front.as_ref().is_some_and(|r| r.contains(&start)) {
+                    // Other lines are shown as they are, under the item
+                    // they belong to.
+                    let depth = nodes.iter().rposition(|n| n.subtree.contains(&start)).map_or(0, |n| nodes[n].depth + 1);
+                    ui.horizontal(|ui| {
+                        ui.add_space(INDENT * depth.saturating_sub(base) as f32 + 32.0);
+                        ui.label(RichText::new(raw.trim()).weak());
+                    });
+                }
+            }
+            ui.add_space(8.0);
+            ui.label(
+                RichText::new(t("Enter adds a node · Tab indents · Alt+↑↓ moves · Alt+→ zooms in · Ctrl+↑↓ collapses · Ctrl+Enter ticks"))
+                    .small()
+                    .weak(),
+            );
+        });
+
+        match change {
+            Some(Change::Splice(range, inserted)) => {
+                text.replace_range(range, &inserted);
+                true
+            }
+            Some(Change::Rewrite(new, focus)) => {
+                *text = new;
+                // The node to focus is drawn on the next frame.
+                self.focus = Some(focus);
+                ui.ctx().request_repaint();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Handles the outline's keys for the focused node, the `position`th
+    /// of those `visible`.
+    fn keys(&mut self, ctx: &egui::Context, text: &str, nodes: &[Node], visible: &[usize], position: usize) -> Option<Change> {
+        let index = visible[position];
+        let node = &nodes[index];
+        let pressed = |modifiers, key| ctx.input_mut(|i| i.consume_key(modifiers, key));
+        let has_children = nodes.get(index + 1).is_some_and(|n| n.parent == Some(index));
+        if pressed(Modifiers::ALT, Key::ArrowUp) {
+            return move_node(text, nodes, index, true).map(|(text, at)| Change::Rewrite(text, at));
+        }
+        if pressed(Modifiers::ALT, Key::ArrowDown) {
+            return move_node(text, nodes, index, false).map(|(text, at)| Change::Rewrite(text, at));
+        }
+        if pressed(Modifiers::ALT, Key::ArrowRight) {
+            self.zoom = Some(node.key.clone());
+            self.focus = nodes.get(index + 1).filter(|n| n.parent == Some(index)).map(|n| n.line.start);
+            return None;
+        }
+        if pressed(Modifiers::ALT, Key::ArrowLeft) {
+            let zoomed = self.zoom.as_ref().and_then(|key| nodes.iter().position(|n| n.key == *key));
+            self.zoom = zoomed.and_then(|z| nodes[z].parent).map(|p| nodes[p].key.clone());
+            self.focus = Some(node.line.start);
+            return None;
+        }
+        if pressed(Modifiers::COMMAND, Key::ArrowUp) && has_children {
+            self.collapsed.insert(node.key.clone());
+        }
+        if pressed(Modifiers::COMMAND, Key::ArrowDown) {
+            self.collapsed.remove(&node.key);
+        }
+        if pressed(Modifiers::COMMAND, Key::Enter) {
+            return Some(match node.check {
+                Some((at, ticked)) => Change::Splice(at..at + 1, if ticked { " " } else { "x" }.to_owned()),
+                None => Change::Splice(node.text.start..node.text.start, "[ ] ".to_owned()),
+            });
+        }
+        if pressed(Modifiers::NONE, Key::Enter) {
+            let (text, at) = add_after(text, node);
+            return Some(Change::Rewrite(text, at));
+        }
+        let outdent = pressed(Modifiers::SHIFT, Key::Tab);
+        if outdent || pressed(Modifiers::NONE, Key::Tab) {
+            let at = node.text.start;
+            return indent::shift_list(text, at..at, outdent).map(|(text, selection)| Change::Rewrite(text, selection.start));
+        }
+        if node.text.is_empty() && !has_children && pressed(Modifiers::NONE, Key::Backspace) {
+            let previous = position.checked_sub(1).map(|p| nodes[visible[p]].line.start);
+            let mut text = text.to_owned();
+            text.replace_range(removal(&text, &node.line), "");
+            return Some(Change::Rewrite(text, previous.unwrap_or(0)));
+        }
+        if pressed(Modifiers::NONE, Key::ArrowUp) {
+            self.focus = position.checked_sub(1).map(|p| nodes[visible[p]].line.start);
+        }
+        if pressed(Modifiers::NONE, Key::ArrowDown) {
+            self.focus = visible.get(position + 1).map(|&i| nodes[i].line.start);
+        }
+        None
+    }
+
+    /// One node: its collapse arrow, its bullet, which zooms in, its
+    /// checkbox and its text. Returns how it changed the note.
+    fn node_row(&mut self, ui: &mut egui::Ui, text: &str, nodes: &[Node], index: usize, base: usize, locked: bool) -> Option<Change> {
+        let node = &nodes[index];
+        let has_children = nodes.get(index + 1).is_some_and(|n| n.parent == Some(index));
+        let collapsed = self.collapsed.contains(&node.key);
+        let id = node_id(index);
+        if self.focus.is_some_and(|at| node.line.contains(&at)) {
+            self.focus = None;
+            ui.memory_mut(|m| m.request_focus(id));
+            let mut state = TextEditState::load(ui.ctx(), id).unwrap_or_default();
+            let end = CCursor::new(text[node.text.clone()].chars().count());
+            state.cursor.set_char_range(Some(CCursorRange::one(end)));
+            state.store(ui.ctx(), id);
+        }
+        let mut change = None;
+        ui.horizontal(|ui| {
+            ui.add_space(INDENT * node.depth.saturating_sub(base) as f32);
+            if has_children {
+                let arrow = egui::Button::new(RichText::new(if collapsed { "▶" } else { "▼" }).small()).frame(false);
+                if ui.add_sized([14.0, 14.0], arrow).on_hover_text(if collapsed { t("Expand") } else { t("Collapse") }).clicked()
+                    && !self.collapsed.remove(&node.key)
+                {
+                    self.collapsed.insert(node.key.clone());
+                }
+            } else {
+                ui.add_space(14.0 + ui.spacing().item_spacing.x);
+            }
+            let bullet = ui.add(egui::Button::new(if collapsed { "◉" } else { "•" }).frame(false)).on_hover_text(t("Zoom in"));
+            if bullet.clicked() {
+                self.zoom = Some(node.key.clone());
+            }
+            bullet.context_menu(|ui| {
+                if ui.button(t("Zoom in")).clicked() {
+                    self.zoom = Some(node.key.clone());
+                    ui.close_menu();
+                }
+                if locked {
+                    return;
+                }
+                let checkbox = match node.check {
+                    Some((at, _)) => ui.button(t("Remove checkbox")).clicked().then(|| Change::Splice(at - 1..node.text.start, String::new())),
+                    None => ui.button(t("Add checkbox")).clicked().then(|| Change::Splice(node.text.start..node.text.start, "[ ] ".to_owned())),
+                };
+                if let Some(checkbox) = checkbox {
+                    change = Some(checkbox);
+                    ui.close_menu();
+                }
+                if ui.button(t("Delete with everything under it")).clicked() {
+                    change = Some(Change::Splice(removal(text, &node.subtree), String::new()));
+                    ui.close_menu();
+                }
+            });
+            if let Some((at, ticked)) = node.check {
+                let mut ticked_now = ticked;
+                if ui.add_enabled(!locked, egui::Checkbox::without_text(&mut ticked_now)).changed() {
+                    change = Some(Change::Splice(at..at + 1, if ticked_now { "x" } else { " " }.to_owned()));
+                }
+            }
+            let original = &text[node.text.clone()];
+            let mut value = original.to_owned();
+            let mut edit = TextEdit::singleline(&mut value).id(id).frame(false).desired_width(f32::INFINITY).interactive(!locked);
+            if node.check.is_some_and(|(_, ticked)| ticked) {
+                edit = edit.text_color(ui.visuals().weak_text_color());
+            }
+            ui.add(edit);
+            if value != original {
+                let value = value.replace(['\r', '\n'], " ");
+                // Keeps the node collapsed and zoomed into as its text
+                // changes, which its key is made of.
+                let nth = node.key.split_once(':').map_or("0", |(nth, _)| nth);
+                let key = format!("{}:{}", nth, value);
+                if self.collapsed.remove(&node.key) {
+                    self.collapsed.insert(key.clone());
+                }
+                if self.zoom.as_ref() == Some(&node.key) {
+                    self.zoom = Some(key);
+                }
+                change = Some(Change::Splice(node.text.clone(), value));
+            }
+        });
+        change
+    }
+}
@@ -0,0 +1,107 @@
+use std::ops::Range;
+
+/// Brackets matched at the cursor and jumped between.
+const BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+/// Characters put around a selection when typed over it, with what closes them.
+fn closer(open: char) -> Option<char> {
+    match open {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        '"' | '`' | '*' => Some(open),
+        _ => None,
+    }
+}
+
+/// Whether a pair may be closed before `next`: at the end of a line, before
+/// a space or before punctuation that ends a phrase.
+fn closes_before(next: Option<char>) -> bool {
+    next.is_none_or(|c| c.is_whitespace() || ")]}.,;:!?".contains(c))
+}
+
+/// What typing `typed` over `selection` does when it is one of a pair, as
+/// the new text and selection, both in bytes. A selection is put between
+/// the pair and stays selected; `(`, `[`, `"` and `` ` `` are closed as
+/// they are typed; and typing a closer already after the cursor steps
+/// over it. `None` leaves the typing to the editor.
+pub fn typed(text: &str, selection: Range<usize>, typed: &str) -> Option<(String, Range<usize>)> {
+    let mut chars = typed.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+        return None;
+    };
+    text.get(selection.clone())?;
+    let close = closer(c);
+    let (before, after) = (&text[..selection.start], &text[selection.end..]);
+    let width = c.len_utf8();
+
+    if !selection.is_empty() {
+        let close = close?;
+        let wrapped = format!("{}{}{}{}{}", before, c, &text[selection.clone()], close, after);
+        return Some((wrapped, selection.start + width..selection.end + width));
+    }
+    let at = selection.start;
+    let (prev, next) = (before.chars().next_back(), after.chars().next());
+    if next == Some(c) && matches!(c, ')' | ']' | '"' | '`') {
+        return Some((text.to_owned(), at + width..at + width));
+    }
+    let closes = match c {
+        '(' | '[' => closes_before(next),
+        // Not after a letter, where a quote is more likely an apostrophe
+        // or the end of a word in quotes, nor after a backtick so that a
+        // code fence can be typed.
+        '"' | '`' => closes_before(next) && prev.is_none_or(|p| !p.is_alphanumeric() && p != c),
+        _ => false,
+    };
+    let close = close.filter(|_| closes)?;
+    Some((format!("{}{}{}{}", before, c, close, after), at + width..at + width))
+}
+
+/// Backspace between a pair with nothing in it removes both halves.
+/// Returns the new text and cursor.
+pub fn backspace(text: &str, cursor: usize) -> Option<(String, usize)> {
+    let prev = text.get(..cursor)?.chars().next_back()?;
+    let next = text[cursor..].chars().next()?;
+    if closer(prev) != Some(next) || prev == '*' {
+        return None;
+    }
+    let start = cursor - prev.len_utf8();
+    Some((format!("{}{}", &text[..start], &text[cursor + next.len_utf8()..]), start))
+}
+
+/// The bracket just after or just before `cursor` and the byte offsets of
+/// it and its partner, nested brackets of the same kind skipped.
+pub fn matching(text: &str, cursor: usize) -> Option<(usize, usize)> {
+    let after = text.get(cursor..)?.chars().next().map(|c| (cursor, c));
+    let before = text[..cursor].char_indices().next_back();
+    let is_bracket = |c: char| BRACKETS.iter().any(|&(o, cl)| c == o || c == cl);
+    let (at, c) = after.filter(|&(_, c)| is_bracket(c)).or(before.filter(|&(_, c)| is_bracket(c)))?;
+    let partner = if let Some(&(open, close)) = BRACKETS.iter().find(|(o, _)| *o == c) {
+        let mut depth = 0;
+        text[at..].char_indices().find_map(|(i, ch)| {
+            if ch == open {
+                depth += 1;
+            } else if ch == close {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(at + i);
+                }
+            }
+            None
+        })?
+    } else {
+        let &(open, close) = BRACKETS.iter().find(|(_, cl)| *cl == c)?;
+        let mut depth = 0;
+        text[..at + c.len_utf8()].char_indices().rev().find_map(|(i, ch)| {
+            if ch == close {
+                depth += 1;
+            } else if ch == open {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            None
+        })?
+    };
+    Some((at, partner))
+}
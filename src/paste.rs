@@ -0,0 +1,57 @@
+use tracing::warn;
+#[cfg(target_os = "android")]
+use crate::stubs::arboard;
+
+/// Tags that carry structure worth keeping. HTML with none of them, like
+/// code copied from an editor as coloured spans, is pasted as plain text.
+const STRUCTURE_TAGS: [&str; 20] = [
+    "h1", "h2", "h3", "h4", "h5", "h6", "a", "ul", "ol", "li", "table", "strong", "b", "em", "i", "code", "pre", "img", "blockquote", "hr",
+];
+
+/// Dropped along with their content.
+const SKIPPED_TAGS: [&str; 6] = ["script", "style", "head", "meta", "title", "noscript"];
+
+fn has_structure(html: &str) -> bool {
+    let lower = html.to_ascii_lowercase();
+    STRUCTURE_TAGS.iter().any(|tag| {
+        lower.match_indices(&format!("<{}", tag)).any(|(at, open)| {
+            // `<b` must not match `<br` or `<body`.
+            lower[at + open.len()..].starts_with(|c: char| c == '>' || c.is_ascii_whitespace())
+        })
+    })
+}
+
+/// Markdown for HTML from the clipboard, or `None` when plain text would
+/// do as well.
+pub fn html_to_markdown(html: &str) -> Option<String> {
+    // Windows wraps the copied part in markers within a whole document.
+    let html = match (html.find("<!--StartFragment-->"), html.find("<!--EndFragment-->")) {
+        (Some(start), Some(end)) if start < end => &html[start + "<!--StartFragment-->".len()..end],
+        _ => html,
+    };
+    if !has_structure(html) {
+        return None;
+    }
+    let converter = htmd::HtmlToMarkdown::builder().skip_tags(SKIPPED_TAGS.to_vec()).build();
+    match converter.convert(html) {
+        Ok(markdown) => Some(markdown.trim().to_owned()).filter(|m| !m.is_empty()),
+        Err(err) => {
+            warn!("Failed to convert the pasted HTML: {}", err);
+            None
+        }
+    }
+}
+
+/// The clipboard's HTML as Markdown, when it holds any worth converting.
+pub fn clipboard_markdown() -> Option<String> {
+    let html = arboard::Clipboard::new().and_then(|mut c| c.get().html()).ok()?;
+    html_to_markdown(&html)
+}
+
+/// The clipboard's plain text.
+pub fn clipboard_text() -> Option<String> {
+    arboard::Clipboard::new()
+        .and_then(|mut c| c.get_text())
+        .map_err(|err| warn!("Failed to read the clipboard: {}", err))
+        .ok()
+}
@@ -0,0 +1,331 @@
+use crate::i18n::{t, tf};
+use eframe::egui;
+use mlua::{Function, Lua};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use tracing::error;
+
+pub const PLUGINS_DIR: &str = "plugins";
+
+/// Everything a script registered through the `app` table.
+#[derive(Default)]
+struct Registry {
+    commands: Vec<(String, Function)>,
+    transforms: Vec<(String, Function)>,
+    panels: Vec<(String, Function)>,
+    save_hooks: Vec<Function>,
+}
+
+pub struct Plugin {
+    pub name: String,
+    pub path: PathBuf,
+    /// Set when the script failed to load; such plugins stay inactive.
+    pub error: Option<String>,
+    pub last_error: Option<String>,
+    registry: Rc<RefCell<Registry>>,
+    panel_output: Vec<Option<String>>,
+    // Keeps the interpreter alive for the registered functions.
+    _lua: Lua,
+}
+
+impl Plugin {
+    /// Runs a Lua script, which registers its extension points with:
+    ///
+    /// - `app.register_command(name, fn(note) -> new_note?)`
+    /// - `app.register_transform(name, fn(text) -> text)` (selection or whole note)
+    /// - `app.register_panel(title, fn(note) -> string)`
+    /// - `app.on_save(fn(note) -> new_note?)`
+    fn load(path: &Path) -> Self {
+        let lua = Lua::new();
+        let registry = Rc::new(RefCell::new(Registry::default()));
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let error = install_api(&lua, &registry)
+            .and_then(|()| {
+                let source = std::fs::read_to_string(path).map_err(mlua::Error::external)?;
+                lua.load(&source).set_name(&name).exec()
+            })
+            .err()
+            .map(|e| e.to_string());
+        let panels = registry.borrow().panels.len();
+
+        Self {
+            name,
+            path: path.to_path_buf(),
+            error,
+            last_error: None,
+            registry,
+            panel_output: vec![None; panels],
+            _lua: lua,
+        }
+    }
+
+    pub fn commands(&self) -> Vec<String> {
+        self.registry.borrow().commands.iter().map(|(n, _)| n.clone()).collect()
+    }
+
+    pub fn transforms(&self) -> Vec<String> {
+        self.registry.borrow().transforms.iter().map(|(n, _)| n.clone()).collect()
+    }
+
+    pub fn panels(&self) -> Vec<String> {
+        self.registry.borrow().panels.iter().map(|(n, _)| n.clone()).collect()
+    }
+}
+
+fn install_api(lua: &Lua, registry: &Rc<RefCell<Registry>>) -> mlua::Result<()> {
+    let app = lua.create_table()?;
+
+    let r = registry.clone();
+    app.set(
+        "register_command",
+        lua.create_function(move |_, (name, f): (String, Function)| {
+            r.borrow_mut().commands.push((name, f));
+            Ok(())
+        })?,
+    )?;
+    let r = registry.clone();
+    app.set(
+        "register_transform",
+        lua.create_function(move |_, (name, f): (String, Function)| {
+            r.borrow_mut().transforms.push((name, f));
+            Ok(())
+        })?,
+    )?;
+    let r = registry.clone();
+    app.set(
+        "register_panel",
+        lua.create_function(move |_, (title, f): (String, Function)| {
+            r.borrow_mut().panels.push((title, f));
+            Ok(())
+        })?,
+    )?;
+    let r = registry.clone();
+    app.set(
+        "on_save",
+        lua.create_function(move |_, f: Function| {
+            r.borrow_mut().save_hooks.push(f);
+            Ok(())
+        })?,
+    )?;
+
+    lua.globals().set("app", app)
+}
+
+/// Loads Lua plugins from the plugins directory and dispatches to them.
+pub struct PluginHost {
+    pub plugins: Vec<Plugin>,
+    pub disabled: HashSet<String>,
+}
+
+impl PluginHost {
+    pub fn load(disabled: &[String]) -> Self {
+        let mut host = Self {
+            plugins: Vec::new(),
+            disabled: disabled.iter().cloned().collect(),
+        };
+        host.reload();
+        host
+    }
+
+    pub fn reload(&mut self) {
+        self.plugins = crate::vault::walk_files(Path::new(PLUGINS_DIR))
+            .iter()
+            .filter(|p| p.extension().is_some_and(|e| e == "lua"))
+            .map(|p| Plugin::load(p))
+            .collect();
+    }
+
+    fn enabled(&self) -> impl Iterator<Item = &Plugin> {
+        self.plugins
+            .iter()
+            .filter(|p| p.error.is_none() && !self.disabled.contains(&p.name))
+    }
+
+    pub fn run_command(&mut self, plugin: &str, command: &str, note: &str) -> Option<String> {
+        let result = {
+            let plugin = self.plugins.iter().find(|p| p.name == plugin)?;
+            // Cloned out, so a callback that registers more does not find the registry borrowed.
+            let f = plugin.registry.borrow().commands.iter().find(|(n, _)| n == command)?.1.clone();
+            f.call::<Option<String>>(note)
+        };
+        self.record(plugin, result).flatten()
+    }
+
+    pub fn run_transform(&mut self, plugin: &str, transform: &str, text: &str) -> Option<String> {
+        let result = {
+            let plugin = self.plugins.iter().find(|p| p.name == plugin)?;
+            let f = plugin.registry.borrow().transforms.iter().find(|(n, _)| n == transform)?.1.clone();
+            f.call::<String>(text)
+        };
+        self.record(plugin, result)
+    }
+
+    pub fn refresh_panel(&mut self, plugin: &str, index: usize, note: &str) {
+        let result = {
+            let Some(p) = self.plugins.iter().find(|p| p.name == plugin) else {
+                return;
+            };
+            let Some(f) = p.registry.borrow().panels.get(index).map(|(_, f)| f.clone()) else {
+                return;
+            };
+            f.call::<String>(note)
+        };
+        let output = self.record(plugin, result);
+        if let Some(p) = self.plugins.iter_mut().find(|p| p.name == plugin) {
+            // Panels can be registered after loading, by a callback.
+            if p.panel_output.len() <= index {
+                p.panel_output.resize(index + 1, None);
+            }
+            p.panel_output[index] = output;
+        }
+    }
+
+    /// Passes the note through every enabled plugin's on-save hooks.
+    pub fn on_save(&mut self, note: &str) -> String {
+        let mut text = note.to_owned();
+        let mut failures = Vec::new();
+        for plugin in self.enabled() {
+            let hooks = plugin.registry.borrow().save_hooks.clone();
+            for hook in &hooks {
+                match hook.call::<Option<String>>(text.as_str()) {
+                    Ok(Some(new)) => text = new,
+                    Ok(None) => {}
+                    Err(err) => failures.push((plugin.name.clone(), err.to_string())),
+                }
+            }
+        }
+        for (name, err) in failures {
+            self.set_error(&name, err);
+        }
+        text
+    }
+
+    fn record<T>(&mut self, plugin: &str, result: mlua::Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.set_error(plugin, err.to_string());
+                None
+            }
+        }
+    }
+
+    fn set_error(&mut self, plugin: &str, err: String) {
+        error!("Plugin {} failed: {}", plugin, err);
+        if let Some(p) = self.plugins.iter_mut().find(|p| p.name == plugin) {
+            p.last_error = Some(err);
+        }
+    }
+
+    /// Plugin manager window. Commands and transforms edit `note` in place;
+    /// transforms apply to `selection` (a byte range) when there is one.
+    /// Neither runs on a `locked` note. Returns true when the set of
+    /// disabled plugins changed.
+    pub fn show_manager(
+        &mut self,
+        ctx: &egui::Context,
+        open: &mut bool,
+        note: &mut String,
+        selection: Option<Range<usize>>,
+        locked: bool,
+    ) -> bool {
+        let mut toggle = None;
+        let mut command = None;
+        let mut transform = None;
+
+        egui::Window::new(t("🧩 Plugins")).open(open).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(tf("Lua scripts in ./{}/", &[&PLUGINS_DIR]));
+                if ui.button(t("⟳ Reload")).clicked() {
+                    self.reload();
+                }
+            });
+            ui.separator();
+            if self.plugins.is_empty() {
+                ui.weak(t("No plugins installed."));
+            }
+            for plugin in &self.plugins {
+                let mut enabled = !self.disabled.contains(&plugin.name);
+                let checkbox = ui.checkbox(&mut enabled, &plugin.name);
+                if checkbox.on_hover_text(plugin.path.display().to_string()).changed() {
+                    toggle = Some(plugin.name.clone());
+                }
+                ui.indent(&plugin.name, |ui| {
+                    if let Some(err) = plugin.error.as_ref().or(plugin.last_error.as_ref()) {
+                        ui.colored_label(egui::Color32::LIGHT_RED, err);
+                    }
+                    if !enabled || plugin.error.is_some() {
+                        return;
+                    }
+                    ui.add_enabled_ui(!locked, |ui| ui.horizontal_wrapped(|ui| {
+                        for name in plugin.commands() {
+                            if ui.button(format!("▶ {}", name)).clicked() {
+                                command = Some((plugin.name.clone(), name));
+                            }
+                        }
+                        for name in plugin.transforms() {
+                            if ui.button(format!("✎ {}", name)).clicked() {
+                                transform = Some((plugin.name.clone(), name));
+                            }
+                        }
+                    }));
+                    let hooks = plugin.registry.borrow().save_hooks.len();
+                    if hooks > 0 {
+                        ui.weak(tf("{} on-save hook(s)", &[&hooks]));
+                    }
+                });
+            }
+        });
+
+        if let Some(name) = &toggle
+            && !self.disabled.remove(name)
+        {
+            self.disabled.insert(name.clone());
+        }
+
+        if let Some((plugin, name)) = command
+            && let Some(new) = self.run_command(&plugin, &name, note)
+        {
+            *note = new;
+        }
+        if let Some((plugin, name)) = transform {
+            let range = selection
+                .filter(|r| !r.is_empty() && note.get(r.clone()).is_some())
+                .unwrap_or(0..note.len());
+            if let Some(new) = self.run_transform(&plugin, &name, &note[range.clone()]) {
+                note.replace_range(range, &new);
+            }
+        }
+        toggle.is_some()
+    }
+
+    /// Windows for the panels registered by enabled plugins.
+    pub fn show_panels(&mut self, ctx: &egui::Context, note: &str) {
+        let mut refresh = Vec::new();
+        for plugin in self.enabled() {
+            for (index, title) in plugin.panels().into_iter().enumerate() {
+                egui::Window::new(&title)
+                    .id(egui::Id::new(("plugin_panel", &plugin.name, index)))
+                    .default_width(260.0)
+                    .show(ctx, |ui| {
+                        if ui.small_button(t("⟳ Refresh")).clicked() {
+                            refresh.push((plugin.name.clone(), index));
+                        }
+                        match plugin.panel_output.get(index).and_then(|o| o.as_deref()) {
+                            Some(output) => ui.label(output),
+                            None => ui.weak(t("Press refresh to run.")),
+                        };
+                    });
+            }
+        }
+        for (plugin, index) in refresh {
+            self.refresh_panel(&plugin, index, note);
+        }
+    }
+}
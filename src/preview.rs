@@ -0,0 +1,604 @@
+use crate::bidi::{self, Direction};
+use crate::i18n::t;
+use crate::svg::SvgCache;
+use crate::theme::Syntax;
+use eframe::egui::{self, Color32, RichText};
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Style {
+    pub strong: bool,
+    pub emphasis: bool,
+    pub strike: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Inline {
+    Text(String, Style),
+    Code(String),
+    Math(String),
+    Link { url: String, text: Vec<Inline> },
+    Image { url: String, alt: String },
+    FootnoteRef(String),
+    LineBreak,
+}
+
+#[derive(Debug, Clone)]
+pub struct ListItem {
+    pub checked: Option<bool>,
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Block {
+    Paragraph(Vec<Inline>),
+    Heading(u8, Vec<Inline>),
+    Code { lang: String, code: String },
+    Math(String),
+    Quote(Vec<Block>),
+    List { start: Option<u64>, items: Vec<ListItem> },
+    Table { align: Vec<Alignment>, rows: Vec<Vec<Vec<Inline>>> },
+    FootnoteDefinition(String, Vec<Block>),
+    Html(String),
+    Rule,
+}
+
+/// Open containers while folding parser events into blocks.
+enum Frame {
+    Blocks(Vec<Block>),
+    Quote(Vec<Block>),
+    List(Option<u64>, Vec<ListItem>),
+    Item(Option<bool>, Vec<Block>),
+    Footnote(String, Vec<Block>),
+    Table(Vec<Alignment>, Vec<Vec<Vec<Inline>>>),
+    Row(Vec<Vec<Inline>>),
+    Cell(Vec<Inline>),
+    Paragraph { inlines: Vec<Inline>, implicit: bool },
+    Heading(u8, Vec<Inline>),
+    Link(String, Vec<Inline>),
+    Image(String, String),
+    Code(String, String),
+    Html(String),
+}
+
+pub fn options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+        | Options::ENABLE_MATH
+}
+
+/// Parses Markdown into a small block tree that is easy to lay out with egui.
+pub fn parse(markdown: &str) -> Vec<Block> {
+    let mut builder = Builder {
+        stack: vec![Frame::Blocks(Vec::new())],
+        style: Style::default(),
+        in_metadata: false,
+    };
+    for event in Parser::new_ext(markdown, options()) {
+        builder.event(event);
+    }
+    builder.finish()
+}
+
+struct Builder {
+    stack: Vec<Frame>,
+    style: Style,
+    in_metadata: bool,
+}
+
+impl Builder {
+    fn event(&mut self, event: Event) {
+        if self.in_metadata {
+            if let Event::End(TagEnd::MetadataBlock(_)) = event {
+                self.in_metadata = false;
+            }
+            return;
+        }
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => match self.stack.last_mut() {
+                Some(Frame::Code(_, code)) => code.push_str(&text),
+                Some(Frame::Image(_, alt)) => alt.push_str(&text),
+                Some(Frame::Html(html)) => html.push_str(&text),
+                _ => self.inline(Inline::Text(crate::emoji::replace_shortcodes(&text), self.style)),
+            },
+            Event::Code(code) => self.inline(Inline::Code(code.into_string())),
+            Event::InlineMath(math) => self.inline(Inline::Math(math.into_string())),
+            Event::DisplayMath(math) => {
+                // Display math sits inside a paragraph; split it around the formula.
+                let implicit = match self.stack.last() {
+                    Some(Frame::Paragraph { implicit, .. }) => Some(*implicit),
+                    _ => None,
+                };
+                if implicit.is_some()
+                    && let Some(frame) = self.stack.pop()
+                {
+                    self.close(frame);
+                }
+                self.block(Block::Math(math.into_string()));
+                if let Some(implicit) = implicit {
+                    self.stack.push(Frame::Paragraph { inlines: Vec::new(), implicit });
+                }
+            }
+            Event::Html(html) => match self.stack.last_mut() {
+                Some(Frame::Html(buffer)) => buffer.push_str(&html),
+                _ => self.block(Block::Html(html.into_string())),
+            },
+            Event::InlineHtml(html) => self.inline(Inline::Code(html.into_string())),
+            Event::FootnoteReference(label) => self.inline(Inline::FootnoteRef(label.into_string())),
+            Event::SoftBreak => self.inline(Inline::Text(" ".to_owned(), self.style)),
+            Event::HardBreak => self.inline(Inline::LineBreak),
+            Event::Rule => {
+                self.close_implicit();
+                self.block(Block::Rule);
+            }
+            Event::TaskListMarker(checked) => {
+                if let Some(Frame::Item(marker, _)) = self.stack.last_mut() {
+                    *marker = Some(checked);
+                }
+            }
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        let inline = matches!(
+            tag,
+            Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Link { .. } | Tag::Image { .. }
+        );
+        if !inline {
+            self.close_implicit();
+        }
+        let frame = match tag {
+            Tag::Paragraph => Frame::Paragraph { inlines: Vec::new(), implicit: false },
+            Tag::Heading { level, .. } => Frame::Heading(heading_level(level), Vec::new()),
+            Tag::BlockQuote(_) => Frame::Quote(Vec::new()),
+            Tag::CodeBlock(kind) => {
+                let lang = match kind {
+                    CodeBlockKind::Fenced(lang) => lang.split_whitespace().next().unwrap_or_default().to_owned(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                Frame::Code(lang, String::new())
+            }
+            Tag::HtmlBlock => Frame::Html(String::new()),
+            Tag::List(start) => Frame::List(start, Vec::new()),
+            Tag::Item => Frame::Item(None, Vec::new()),
+            Tag::FootnoteDefinition(label) => Frame::Footnote(label.into_string(), Vec::new()),
+            Tag::Table(align) => Frame::Table(align, Vec::new()),
+            Tag::TableHead | Tag::TableRow => Frame::Row(Vec::new()),
+            Tag::TableCell => Frame::Cell(Vec::new()),
+            Tag::Emphasis => {
+                self.style.emphasis = true;
+                return;
+            }
+            Tag::Strong => {
+                self.style.strong = true;
+                return;
+            }
+            Tag::Strikethrough => {
+                self.style.strike = true;
+                return;
+            }
+            Tag::Link { dest_url, .. } => Frame::Link(dest_url.into_string(), Vec::new()),
+            Tag::Image { dest_url, .. } => Frame::Image(dest_url.into_string(), String::new()),
+            Tag::MetadataBlock(_) => {
+                self.in_metadata = true;
+                return;
+            }
+            _ => return,
+        };
+        self.stack.push(frame);
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Emphasis => self.style.emphasis = false,
+            TagEnd::Strong => self.style.strong = false,
+            TagEnd::Strikethrough => self.style.strike = false,
+            TagEnd::Link | TagEnd::Image => {
+                match self.stack.pop() {
+                    Some(Frame::Link(url, text)) => self.inline(Inline::Link { url, text }),
+                    Some(Frame::Image(url, alt)) => self.inline(Inline::Image { url, alt }),
+                    Some(other) => self.stack.push(other),
+                    None => {}
+                }
+            }
+            TagEnd::Paragraph
+            | TagEnd::Heading(_)
+            | TagEnd::BlockQuote(_)
+            | TagEnd::CodeBlock
+            | TagEnd::HtmlBlock
+            | TagEnd::List(_)
+            | TagEnd::Item
+            | TagEnd::FootnoteDefinition
+            | TagEnd::Table
+            | TagEnd::TableHead
+            | TagEnd::TableRow
+            | TagEnd::TableCell => {
+                if !matches!(tag, TagEnd::Paragraph) {
+                    self.close_implicit();
+                }
+                if let Some(frame) = self.stack.pop() {
+                    self.close(frame);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn close(&mut self, frame: Frame) {
+        match frame {
+            Frame::Paragraph { inlines, .. } if inlines.is_empty() => {}
+            Frame::Paragraph { inlines, .. } => self.block(Block::Paragraph(inlines)),
+            Frame::Heading(level, inlines) => self.block(Block::Heading(level, inlines)),
+            Frame::Quote(blocks) => self.block(Block::Quote(blocks)),
+            Frame::Code(lang, code) => self.block(Block::Code { lang, code }),
+            Frame::Html(html) => self.block(Block::Html(html)),
+            Frame::List(start, items) => self.block(Block::List { start, items }),
+            Frame::Item(checked, blocks) => {
+                if let Some(Frame::List(_, items)) = self.stack.last_mut() {
+                    items.push(ListItem { checked, blocks });
+                }
+            }
+            Frame::Footnote(label, blocks) => self.block(Block::FootnoteDefinition(label, blocks)),
+            Frame::Table(align, rows) => self.block(Block::Table { align, rows }),
+            Frame::Row(cells) => {
+                if let Some(Frame::Table(_, rows)) = self.stack.last_mut() {
+                    rows.push(cells);
+                }
+            }
+            Frame::Cell(inlines) => {
+                if let Some(Frame::Row(cells)) = self.stack.last_mut() {
+                    cells.push(inlines);
+                }
+            }
+            Frame::Blocks(blocks) => {
+                for block in blocks {
+                    self.block(block);
+                }
+            }
+            Frame::Link(..) | Frame::Image(..) => {}
+        }
+    }
+
+    /// Tight list items put text straight into the item; wrap it in a paragraph.
+    fn close_implicit(&mut self) {
+        if let Some(Frame::Paragraph { implicit: true, .. }) = self.stack.last()
+            && let Some(frame) = self.stack.pop()
+        {
+            self.close(frame);
+        }
+    }
+
+    fn block(&mut self, block: Block) {
+        match self.stack.last_mut() {
+            Some(Frame::Blocks(blocks) | Frame::Quote(blocks) | Frame::Item(_, blocks) | Frame::Footnote(_, blocks)) => {
+                blocks.push(block)
+            }
+            _ => {
+                if let Some(Frame::Blocks(blocks)) = self.stack.first_mut() {
+                    blocks.push(block);
+                }
+            }
+        }
+    }
+
+    fn inline(&mut self, inline: Inline) {
+        match self.stack.last_mut() {
+            Some(
+                Frame::Paragraph { inlines, .. }
+                | Frame::Heading(_, inlines)
+                | Frame::Link(_, inlines)
+                | Frame::Cell(inlines),
+            ) => inlines.push(inline),
+            Some(Frame::Image(_, alt)) => {
+                if let Inline::Text(text, _) = inline {
+                    alt.push_str(&text);
+                }
+            }
+            _ => self.stack.push(Frame::Paragraph {
+                inlines: vec![inline],
+                implicit: true,
+            }),
+        }
+    }
+
+    fn finish(mut self) -> Vec<Block> {
+        while self.stack.len() > 1 {
+            if let Some(frame) = self.stack.pop() {
+                self.close(frame);
+            }
+        }
+        match self.stack.pop() {
+            Some(Frame::Blocks(blocks)) => blocks,
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn heading_level(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+/// Plain text of a run of inlines, e.g. for headings in an outline.
+pub fn plain_text(inlines: &[Inline]) -> String {
+    let mut out = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(text, _) | Inline::Code(text) | Inline::Math(text) => out.push_str(text),
+            Inline::Link { text, .. } => out.push_str(&plain_text(text)),
+            Inline::Image { alt, .. } => out.push_str(alt),
+            Inline::FootnoteRef(label) => out.push_str(&format!("[{}]", label)),
+            Inline::LineBreak => out.push(' '),
+        }
+    }
+    out
+}
+
+/// Read-only rendering of a note, with the state that outlives a frame.
+#[derive(Default)]
+pub struct Preview {
+    images: SvgCache,
+    /// Footnote to scroll to on the next frame.
+    jump: Option<FootnoteJump>,
+    /// Set by the note; otherwise each paragraph goes the way its first
+    /// letter does.
+    direction: Option<Direction>,
+    /// Line height as a multiple of the font size and extra letter
+    /// spacing, when set apart from the defaults.
+    pub spacing: Option<(f32, f32)>,
+    /// Markdown colors from the theme.
+    pub syntax: Syntax,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum FootnoteJump {
+    Definition(String),
+    Reference(String),
+}
+
+impl Preview {
+    pub fn show(&mut self, ui: &mut egui::Ui, markdown: &str) {
+        self.direction = bidi::note_override(markdown);
+        self.show_blocks(ui, &parse(markdown));
+    }
+
+    pub fn show_blocks(&mut self, ui: &mut egui::Ui, blocks: &[Block]) {
+        for block in blocks {
+            self.show_block(ui, block);
+        }
+    }
+
+    fn show_block(&mut self, ui: &mut egui::Ui, block: &Block) {
+        match block {
+            Block::Paragraph(inlines) => {
+                self.show_inlines(ui, inlines, None);
+                ui.add_space(6.0);
+            }
+            Block::Heading(level, inlines) => {
+                let size = match level {
+                    1 => 26.0,
+                    2 => 22.0,
+                    3 => 19.0,
+                    _ => 17.0,
+                };
+                ui.add_space(4.0);
+                self.show_inlines(ui, inlines, Some(size));
+                ui.add_space(4.0);
+            }
+            Block::Code { lang, code } if let Some(kind) = crate::diagram::Kind::from_lang(lang) => {
+                crate::diagram::show(ui, &mut self.images, kind, code);
+            }
+            Block::Code { lang, code } => {
+                egui::Frame::group(ui.style())
+                    .fill(ui.visuals().extreme_bg_color)
+                    .show(ui, |ui| {
+                        ui.set_width(ui.available_width());
+                        if !lang.is_empty() {
+                            ui.label(RichText::new(lang).small().weak());
+                        }
+                        let mut code = RichText::new(code.trim_end()).monospace();
+                        if let Some(color) = self.syntax.code() {
+                            code = code.color(color);
+                        }
+                        ui.label(code);
+                    });
+                ui.add_space(6.0);
+            }
+            Block::Math(math) => {
+                ui.vertical_centered(|ui| crate::math::show(ui, &mut self.images, math, true));
+                ui.add_space(6.0);
+            }
+            Block::Quote(blocks) => {
+                let layout = self.row_layout(blocks);
+                ui.with_layout(layout, |ui| {
+                    let (rect, _) = ui.allocate_exact_size(egui::vec2(3.0, 18.0), egui::Sense::hover());
+                    let bar = self.syntax.quote().unwrap_or_else(|| ui.visuals().weak_text_color());
+                    ui.painter().rect_filled(rect, 0.0, bar);
+                    ui.vertical(|ui| self.show_blocks(ui, blocks));
+                });
+            }
+            Block::List { start, items } => {
+                for (index, item) in items.iter().enumerate() {
+                    let layout = self.row_layout(&item.blocks);
+                    ui.with_layout(layout, |ui| {
+                        let marker = match (item.checked, start) {
+                            (Some(true), _) => "☑".to_owned(),
+                            (Some(false), _) => "☐".to_owned(),
+                            (None, Some(start)) => format!("{}.", start + index as u64),
+                            (None, None) => "•".to_owned(),
+                        };
+                        ui.label(marker);
+                        ui.vertical(|ui| self.show_blocks(ui, &item.blocks));
+                    });
+                }
+            }
+            Block::Table { align, rows } => {
+                egui::Grid::new(ui.next_auto_id()).striped(true).show(ui, |ui| {
+                    for (row_index, row) in rows.iter().enumerate() {
+                        for (col, cell) in row.iter().enumerate() {
+                            let cross = match align.get(col) {
+                                Some(Alignment::Center) => egui::Align::Center,
+                                Some(Alignment::Right) => egui::Align::Max,
+                                _ => egui::Align::Min,
+                            };
+                            if row_index == 0 || cross != egui::Align::Min {
+                                // Wrapped inline runs cannot be centered, so
+                                // aligned cells are shown as a single label.
+                                let text = plain_text(cell);
+                                let text = RichText::new(bidi::visual(&text, self.direction_of(&text)));
+                                let text = if row_index == 0 { text.strong() } else { text };
+                                ui.with_layout(egui::Layout::top_down(cross), |ui| ui.label(text));
+                            } else {
+                                self.show_inlines(ui, cell, None);
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+                ui.add_space(6.0);
+            }
+            Block::FootnoteDefinition(label, blocks) => {
+                ui.horizontal(|ui| {
+                    let response = ui
+                        .link(RichText::new(format!("[{}]", label)).small())
+                        .on_hover_text(t("Back to the reference"));
+                    if self.jump.as_ref() == Some(&FootnoteJump::Definition(label.clone())) {
+                        response.scroll_to_me(Some(egui::Align::Center));
+                        self.jump = None;
+                    }
+                    if response.clicked() {
+                        self.jump = Some(FootnoteJump::Reference(label.clone()));
+                    }
+                    ui.vertical(|ui| self.show_blocks(ui, blocks));
+                });
+            }
+            Block::Html(html) => {
+                ui.label(RichText::new(html.trim()).monospace().weak());
+            }
+            Block::Rule => {
+                ui.separator();
+            }
+        }
+    }
+
+    fn direction_of(&self, text: &str) -> Direction {
+        self.direction.or_else(|| bidi::detect(text)).unwrap_or(Direction::Ltr)
+    }
+
+    /// Marker and content side by side, the marker on the right for
+    /// right-to-left text.
+    fn row_layout(&self, blocks: &[Block]) -> egui::Layout {
+        let first = blocks.iter().find_map(|block| match block {
+            Block::Paragraph(inlines) | Block::Heading(_, inlines) => Some(plain_text(inlines)),
+            _ => None,
+        });
+        match self.direction_of(&first.unwrap_or_default()) {
+            Direction::Ltr => egui::Layout::left_to_right(egui::Align::Min),
+            Direction::Rtl => egui::Layout::right_to_left(egui::Align::Min),
+        }
+    }
+
+    fn show_inlines(&mut self, ui: &mut egui::Ui, inlines: &[Inline], size: Option<f32>) {
+        let dir = self.direction_of(&plain_text(inlines));
+        let show = |ui: &mut egui::Ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+            for inline in inlines {
+                self.show_inline(ui, inline, size, dir);
+            }
+        };
+        match dir {
+            Direction::Ltr => ui.horizontal_wrapped(show),
+            Direction::Rtl => ui.with_layout(egui::Layout::right_to_left(egui::Align::Center).with_main_wrap(true), show),
+        };
+    }
+
+    fn show_inline(&mut self, ui: &mut egui::Ui, inline: &Inline, size: Option<f32>, dir: Direction) {
+        let heading = self.syntax.heading();
+        let sized = |text: RichText| match size {
+            Some(size) => {
+                let text = text.size(size).strong();
+                match heading {
+                    Some(color) => text.color(color),
+                    None => text,
+                }
+            }
+            None => text,
+        };
+        match inline {
+            Inline::Text(text, style) => {
+                let body = ui.text_style_height(&egui::TextStyle::Body);
+                let styled = |text: &str| {
+                    let mut rich = sized(RichText::new(text));
+                    if style.strong {
+                        rich = rich.strong();
+                    }
+                    if style.emphasis {
+                        rich = rich.italics();
+                    }
+                    if (style.strong || style.emphasis)
+                        && size.is_none()
+                        && let Some(color) = self.syntax.emphasis()
+                    {
+                        rich = rich.color(color);
+                    }
+                    if style.strike {
+                        rich = rich.strikethrough();
+                    }
+                    if let Some((line, letter)) = self.spacing {
+                        let height = size.unwrap_or(body);
+                        rich = rich.line_height(Some(height * line)).extra_letter_spacing(letter);
+                    }
+                    rich
+                };
+                if dir == Direction::Ltr && !bidi::has_rtl(text) {
+                    ui.label(styled(text));
+                } else {
+                    // Word by word, so right-to-left lines wrap from the right.
+                    for piece in bidi::pieces(text, dir) {
+                        ui.label(styled(&piece));
+                    }
+                }
+            }
+            Inline::Code(code) => {
+                let mut code = sized(RichText::new(code).code());
+                if let Some(color) = self.syntax.code() {
+                    code = code.color(color);
+                }
+                ui.label(code);
+            }
+            Inline::Math(math) => crate::math::show(ui, &mut self.images, math, false),
+            Inline::Link { url, text } => {
+                ui.hyperlink_to(sized(RichText::new(bidi::visual(&plain_text(text), dir))), url)
+                    .on_hover_text(url);
+            }
+            Inline::Image { url, alt } => {
+                ui.label(RichText::new(format!("🖼 {}", if alt.is_empty() { url } else { alt })).weak())
+                    .on_hover_text(url);
+            }
+            Inline::FootnoteRef(label) => {
+                let response = ui.link(RichText::new(format!("[{}]", label)).small().color(Color32::LIGHT_BLUE));
+                if self.jump.as_ref() == Some(&FootnoteJump::Reference(label.clone())) {
+                    response.scroll_to_me(Some(egui::Align::Center));
+                    self.jump = None;
+                }
+                if response.clicked() {
+                    self.jump = Some(FootnoteJump::Definition(label.clone()));
+                }
+            }
+            Inline::LineBreak => {
+                ui.end_row();
+            }
+        }
+    }
+}
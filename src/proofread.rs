@@ -0,0 +1,318 @@
+use crate::config::GrammarConfig;
+use crate::grammar::{self, CheckError, LTMatch, LTRule, LTSuggestion, SPELLING_CATEGORY};
+use crate::jobs;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::sync::{Arc, OnceLock};
+use tracing::{error, warn};
+
+/// Most replacements offered for a misspelled word.
+const MAX_SPELLINGS: usize = 5;
+
+/// Something that finds problems in a note. Offsets in the matches it
+/// returns are byte offsets into the text.
+pub trait GrammarProvider: Send + Sync {
+    /// Shown with its suggestions.
+    fn name(&self) -> &str;
+    fn check(&self, text: &str, language: &str) -> Result<Vec<LTMatch>, CheckError>;
+}
+
+pub struct LanguageTool {
+    endpoint: String,
+    username: String,
+    api_key: String,
+    mother_tongue: String,
+    preferred_variants: String,
+}
+
+/// Byte offset of each position LanguageTool counts in, which is UTF-16
+/// code units since it runs on Java.
+fn utf16_to_byte(text: &str) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(text.len() + 1);
+    for (at, c) in text.char_indices() {
+        offsets.extend(std::iter::repeat_n(at, c.len_utf16()));
+    }
+    offsets.push(text.len());
+    offsets
+}
+
+impl GrammarProvider for LanguageTool {
+    fn name(&self) -> &str {
+        "LanguageTool"
+    }
+
+    fn check(&self, text: &str, language: &str) -> Result<Vec<LTMatch>, CheckError> {
+        let mut params = Vec::new();
+        if !self.username.is_empty() {
+            params.extend([("username", self.username.as_str()), ("apiKey", self.api_key.as_str())]);
+        }
+        if !self.mother_tongue.is_empty() {
+            params.push(("motherTongue", self.mother_tongue.as_str()));
+        }
+        // The server refuses variants unless it detects the language itself.
+        if language == "auto" && !self.preferred_variants.is_empty() {
+            params.push(("preferredVariants", self.preferred_variants.as_str()));
+        }
+        let mut matches = grammar::check_at(&self.endpoint, &params, text, language)?;
+        let offsets = utf16_to_byte(text);
+        matches.retain_mut(|m| {
+            let (Some(&start), Some(&end)) = (offsets.get(m.offset), offsets.get(m.offset + m.length)) else {
+                return false;
+            };
+            m.offset = start;
+            m.length = end - start;
+            true
+        });
+        Ok(matches)
+    }
+}
+
+/// Flags words missing from a word list, and offers the listed words one
+/// edit away.
+pub struct Spellchecker {
+    path: String,
+    /// Read on the first check, off the UI thread.
+    words: OnceLock<HashSet<String>>,
+}
+
+impl Spellchecker {
+    fn words(&self) -> &HashSet<String> {
+        self.words.get_or_init(|| match std::fs::read_to_string(&self.path) {
+            // Hunspell `.dic` files start with a count and mark words with
+            // `/` and their affix flags.
+            Ok(list) => list
+                .lines()
+                .filter(|l| !l.starts_with('#') && !l.chars().all(|c| c.is_ascii_digit()))
+                .map(|l| l.split('/').next().unwrap_or_default().trim().to_lowercase())
+                .collect(),
+            Err(err) => {
+                error!("Failed to read the word list {}: {}", self.path, err);
+                HashSet::new()
+            }
+        })
+    }
+
+    fn known(&self, word: &str) -> bool {
+        let words = self.words();
+        // An empty list means it could not be read; flagging everything
+        // would not help.
+        words.is_empty() || word.chars().all(|c| !c.is_lowercase()) || words.contains(&word.to_lowercase())
+    }
+
+    /// Listed words one deletion, swap, change or insertion away.
+    fn spellings(&self, word: &str) -> Vec<String> {
+        let lower: Vec<char> = word.to_lowercase().chars().collect();
+        let mut letters: Vec<char> = ('a'..='z').collect();
+        letters.extend(lower.iter().filter(|c| !c.is_ascii_lowercase()));
+        let mut candidates = Vec::new();
+        for i in 0..=lower.len() {
+            if i < lower.len() {
+                let mut c = lower.clone();
+                c.remove(i);
+                candidates.push(c);
+            }
+            if i + 1 < lower.len() {
+                let mut c = lower.clone();
+                c.swap(i, i + 1);
+                candidates.push(c);
+            }
+            for &letter in &letters {
+                if i < lower.len() {
+                    let mut c = lower.clone();
+                    c[i] = letter;
+                    candidates.push(c);
+                }
+                let mut c = lower.clone();
+                c.insert(i, letter);
+                candidates.push(c);
+            }
+        }
+        let words = self.words();
+        let mut found: Vec<String> = Vec::new();
+        for candidate in candidates {
+            let candidate: String = candidate.into_iter().collect();
+            if found.len() < MAX_SPELLINGS && words.contains(&candidate) && !found.contains(&candidate) {
+                found.push(candidate);
+            }
+        }
+        if word.starts_with(char::is_uppercase) {
+            for spelling in &mut found {
+                let mut chars = spelling.chars();
+                if let Some(first) = chars.next() {
+                    *spelling = first.to_uppercase().chain(chars).collect();
+                }
+            }
+        }
+        found
+    }
+}
+
+/// Byte ranges of the words worth spellchecking: code, links, addresses
+/// and words run together with digits are left out.
+fn words(text: &str) -> Vec<Range<usize>> {
+    let mut out = Vec::new();
+    let mut fenced = false;
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fenced = !fenced;
+        } else if !fenced {
+            let mut code = false;
+            let mut start = None;
+            for (at, c) in line.char_indices().chain(std::iter::once((line.len(), ' '))) {
+                if c == '`' {
+                    code = !code;
+                }
+                let letter = c.is_alphabetic() || (matches!(c, '\'' | '’') && start.is_some());
+                if letter && !code {
+                    start.get_or_insert(at);
+                } else if let Some(from) = start.take() {
+                    let word = line[from..at].trim_end_matches("'s").trim_end_matches("’s").trim_end_matches(['\'', '’']);
+                    let to = from + word.len();
+                    let before = line[..from].chars().next_back();
+                    let token_start = line[..from].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+                    let token_end = line[to..].find(char::is_whitespace).map_or(line.len(), |i| to + i);
+                    let token = &line[token_start..token_end];
+                    let joined = |c: Option<char>| c.is_some_and(|c| c.is_ascii_digit() || c == '_');
+                    let address = token.contains("://") || token.contains('@') || token.starts_with("www.");
+                    if word.chars().count() > 1 && !joined(before) && !joined(line[to..].chars().next()) && !address {
+                        out.push(line_start + from..line_start + to);
+                    }
+                }
+            }
+        }
+        line_start += line.len();
+    }
+    out
+}
+
+impl GrammarProvider for Spellchecker {
+    fn name(&self) -> &str {
+        "Spelling"
+    }
+
+    fn check(&self, text: &str, _language: &str) -> Result<Vec<LTMatch>, CheckError> {
+        Ok(words(text).into_iter().filter_map(|range| self.check_word(text, range)).collect())
+    }
+}
+
+impl Spellchecker {
+    fn check_word(&self, text: &str, range: Range<usize>) -> Option<LTMatch> {
+        if self.known(&text[range.clone()]) {
+            return None;
+        }
+        Some(LTMatch {
+            message: "Possible spelling mistake".to_owned(),
+            offset: range.start,
+            length: range.len(),
+            replacements: self.spellings(&text[range]).into_iter().map(|value| LTSuggestion { value }).collect(),
+            provider: "Spelling".to_owned(),
+            rule: LTRule::new("WORD_LIST", SPELLING_CATEGORY, "Possible Typo"),
+        })
+    }
+
+    /// Checks the word just finished when the edit from `old` to `new` was
+    /// typing a space or punctuation right after it.
+    pub fn check_typed(&self, old: &str, new: &str) -> Option<LTMatch> {
+        // Not waiting for the list on the UI thread.
+        self.words.get()?;
+        let (start, end, insert) = crate::text::splice(old, new);
+        if start != end || insert.is_empty() || insert.chars().any(char::is_alphanumeric) || !new[..start].ends_with(char::is_alphabetic) {
+            return None;
+        }
+        let word = words(new).into_iter().take_while(|r| r.start < start).last()?;
+        // What is left between the word and the edit can only be a possessive.
+        let rest = &new[word.end..start];
+        if !rest.chars().all(|c| matches!(c, '\'' | '’' | 's')) {
+            return None;
+        }
+        self.check_word(new, word)
+    }
+}
+
+/// The providers enabled in the settings, built again when they change.
+#[derive(Default)]
+pub struct Providers {
+    config: Option<GrammarConfig>,
+    active: Vec<Arc<dyn GrammarProvider>>,
+    /// Shared by the spelling provider and checking as you type, so the
+    /// word list is read once.
+    speller: Option<Arc<Spellchecker>>,
+}
+
+impl Providers {
+    /// The spellchecker for the configured word list. A new one starts
+    /// reading its list in the background.
+    pub fn speller(&mut self, config: &GrammarConfig) -> Arc<Spellchecker> {
+        match &self.speller {
+            Some(speller) if speller.path == config.word_list => speller.clone(),
+            _ => {
+                let speller = Arc::new(Spellchecker {
+                    path: config.word_list.clone(),
+                    words: OnceLock::new(),
+                });
+                let loading = speller.clone();
+                jobs::spawn("Reading the word list", move |_| {
+                    loading.words();
+                });
+                self.speller = Some(speller.clone());
+                speller
+            }
+        }
+    }
+
+    pub fn get(&mut self, config: &GrammarConfig) -> Vec<Arc<dyn GrammarProvider>> {
+        if self.config.as_ref() != Some(config) {
+            self.active.clear();
+            if config.languagetool {
+                self.active.push(Arc::new(LanguageTool {
+                    endpoint: config.languagetool_url.trim().to_owned(),
+                    username: config.languagetool_username.trim().to_owned(),
+                    api_key: config.languagetool_api_key.trim().to_owned(),
+                    mother_tongue: config.mother_tongue.trim().to_owned(),
+                    preferred_variants: config.preferred_variants.split(',').map(str::trim).filter(|v| !v.is_empty()).collect::<Vec<_>>().join(","),
+                }));
+            }
+            if config.spellcheck {
+                let speller = self.speller(config);
+                self.active.push(speller);
+            }
+            self.config = Some(config.clone());
+        }
+        self.active.clone()
+    }
+}
+
+/// Runs every provider and merges what they found, in text order. A
+/// provider failing only fails the check when none of them succeeded.
+pub fn check(providers: &[Arc<dyn GrammarProvider>], text: &str, language: &str) -> Result<Vec<LTMatch>, CheckError> {
+    let mut merged: Vec<LTMatch> = Vec::new();
+    let mut failed = None;
+    let mut succeeded = providers.is_empty();
+    for provider in providers {
+        match provider.check(text, language) {
+            Ok(matches) => {
+                succeeded = true;
+                for mut found in matches {
+                    // Several providers flagging the same words show once.
+                    if !merged.iter().any(|m| m.offset == found.offset && m.length == found.length) {
+                        found.provider = provider.name().to_owned();
+                        merged.push(found);
+                    }
+                }
+            }
+            Err(err) => {
+                warn!("{} check failed: {}", provider.name(), err);
+                failed.get_or_insert(err);
+            }
+        }
+    }
+    match failed {
+        Some(err) if !succeeded => Err(err),
+        _ => {
+            merged.sort_by_key(|m| m.offset);
+            Ok(merged)
+        }
+    }
+}
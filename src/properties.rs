@@ -0,0 +1,223 @@
+use crate::frontmatter::{self, Kind, Value};
+use crate::i18n::t;
+use eframe::egui::{self, RichText};
+use std::collections::HashMap;
+
+pub enum PropertyEvent {
+    /// The front matter was edited.
+    Changed,
+    /// Search the vault for notes with a property, in the query syntax.
+    Search(String),
+}
+
+fn kind_label(kind: Kind) -> String {
+    match kind {
+        Kind::Text => t("Text"),
+        Kind::List => t("List"),
+        Kind::Date => t("Date"),
+        Kind::Checkbox => t("Checkbox"),
+    }
+}
+
+/// A search for notes whose property `key` is `value`.
+fn search_for(key: &str, value: &str) -> String {
+    let term = format!("{}={}", key, value);
+    if term.contains([' ', '"', '(', ')']) { format!("prop:\"{}\"", term.replace('"', "")) } else { format!("prop:{}", term) }
+}
+
+/// The note's front matter as a row of typed fields above the editor:
+/// text, lists of items, dates and checkboxes. The editor hides the YAML
+/// unless asked to show it.
+#[derive(Default)]
+pub struct PropertiesStrip {
+    /// Shown above notes that have front matter.
+    pub open: bool,
+    /// The front matter is shown in the editor as well.
+    pub show_yaml: bool,
+    /// The name and kind of a property being added.
+    adding: Option<(String, Kind)>,
+    /// What is typed in date fields and the new item fields of lists, by
+    /// property, until it can be written.
+    drafts: HashMap<String, String>,
+}
+
+impl PropertiesStrip {
+    pub fn new(open: bool) -> Self {
+        Self { open, ..Default::default() }
+    }
+
+    /// Whether the editor should leave the front matter out.
+    pub fn hides_yaml(&self, text: &str) -> bool {
+        self.open && !self.show_yaml && frontmatter::range(text).is_some()
+    }
+
+    /// Shows the strip with the name field for a new property, for notes
+    /// without front matter yet.
+    pub fn add(&mut self) {
+        self.open = true;
+        self.adding = Some((String::new(), Kind::Text));
+    }
+
+    /// Draws the strip for `text`'s front matter, editing it in place.
+    pub fn show(&mut self, ui: &mut egui::Ui, text: &mut String, locked: bool) -> Option<PropertyEvent> {
+        if !self.open || (frontmatter::range(text).is_none() && self.adding.is_none()) {
+            return None;
+        }
+        let mut updated = None;
+        let mut search = None;
+        egui::Frame::group(ui.style()).inner_margin(egui::Margin::symmetric(6.0, 3.0)).show(ui, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                ui.spacing_mut().item_spacing.x = 4.0;
+                for (key, value) in frontmatter::properties(text) {
+                    let label = ui.add(egui::Label::new(RichText::new(format!("{}:", key)).weak()).sense(egui::Sense::click()));
+                    label.on_hover_text(t("Right-click to change the type")).context_menu(|ui| {
+                        if let Some(current) = value.kind()
+                            && !locked
+                        {
+                            for kind in Kind::ALL {
+                                if ui.radio(current == kind, kind_label(kind)).clicked() {
+                                    self.drafts.remove(&key);
+                                    updated = Some(frontmatter::set_value(text, &key, Some(&value.convert(kind))));
+                                    ui.close_menu();
+                                }
+                            }
+                            ui.separator();
+                        }
+                        if !matches!(value, Value::List(_) | Value::Other) && ui.button(t("🔎 Find notes with this value")).clicked() {
+                            search = Some(search_for(&key, &value.to_string()));
+                            ui.close_menu();
+                        }
+                    });
+                    ui.add_enabled_ui(!locked, |ui| {
+                        if let Some(new) = self.value_field(ui, &key, &value, &mut search) {
+                            updated = Some(frontmatter::set_value(text, &key, Some(&new)));
+                        }
+                    });
+                    if !locked && ui.small_button("✖").on_hover_text(t("Remove property")).clicked() {
+                        updated = Some(frontmatter::set(text, &key, None));
+                    }
+                    ui.add_space(8.0);
+                }
+                if !locked {
+                    self.add_field(ui, text, &mut updated);
+                }
+                ui.toggle_value(&mut self.show_yaml, RichText::new("YAML").small())
+                    .on_hover_text(t("Show the front matter as text at the top of the note"));
+            });
+        });
+        if let Some(query) = search {
+            return Some(PropertyEvent::Search(query));
+        }
+        match updated {
+            Some(new) if new != *text => {
+                *text = new;
+                Some(PropertyEvent::Changed)
+            }
+            _ => None,
+        }
+    }
+
+    /// The field for one property's value, by its kind. Returns the value
+    /// it was changed to.
+    fn value_field(&mut self, ui: &mut egui::Ui, key: &str, value: &Value, search: &mut Option<String>) -> Option<Value> {
+        let id = egui::Id::new("property").with(key);
+        match value {
+            Value::Text(current) => {
+                let mut edited = current.clone();
+                let width = (edited.chars().count() as f32 + 2.0) * 7.0;
+                let field = egui::TextEdit::singleline(&mut edited).id(id).desired_width(width.clamp(40.0, 240.0));
+                (ui.add(field).changed() && edited != *current).then_some(Value::Text(edited))
+            }
+            Value::Checkbox(checked) => {
+                let mut edited = *checked;
+                ui.checkbox(&mut edited, "").changed().then_some(Value::Checkbox(edited))
+            }
+            Value::Date(date) => {
+                // Kept as typed until it is a date again.
+                let shown = date.format("%Y-%m-%d").to_string();
+                let mut draft = self.drafts.get(key).cloned().unwrap_or_else(|| shown.clone());
+                let parsed = frontmatter::parse_date(&draft);
+                let mut field = egui::TextEdit::singleline(&mut draft).id(id).hint_text("YYYY-MM-DD").desired_width(80.0);
+                if parsed.is_none() {
+                    field = field.text_color(ui.visuals().error_fg_color);
+                }
+                let response = ui.add(field);
+                let mut changed = None;
+                if response.changed() {
+                    changed = frontmatter::parse_date(&draft).filter(|d| d != date).map(Value::Date);
+                }
+                if response.has_focus() {
+                    self.drafts.insert(key.to_owned(), draft);
+                } else {
+                    self.drafts.remove(key);
+                }
+                if ui.small_button("📅").on_hover_text(t("Today")).clicked() {
+                    changed = Some(Value::Date(chrono::Local::now().date_naive()));
+                }
+                changed
+            }
+            Value::List(items) => {
+                let mut changed = None;
+                for (index, item) in items.iter().enumerate() {
+                    let chip = ui.add(egui::Button::new(RichText::new(format!("{} ✖", item)).small()).rounding(8.0));
+                    let chip = chip.on_hover_text(t("Click to remove; right-click to search"));
+                    if chip.clicked() {
+                        let mut rest = items.clone();
+                        rest.remove(index);
+                        changed = Some(Value::List(rest));
+                    }
+                    chip.context_menu(|ui| {
+                        if ui.button(t("🔎 Find notes with this value")).clicked() {
+                            *search = Some(search_for(key, item));
+                            ui.close_menu();
+                        }
+                    });
+                }
+                let draft = self.drafts.entry(key.to_owned()).or_default();
+                let response = ui.add(egui::TextEdit::singleline(draft).id(id).hint_text(t("add…")).desired_width(60.0));
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    let mut more = items.clone();
+                    more.extend(draft.split(',').map(|i| i.trim().to_owned()).filter(|i| !i.is_empty() && !items.contains(i)));
+                    draft.clear();
+                    if more.len() > items.len() {
+                        changed = Some(Value::List(more));
+                    }
+                }
+                changed
+            }
+            Value::Other => {
+                ui.label(RichText::new("…").weak()).on_hover_text(t("Show the YAML to edit this property"));
+                None
+            }
+        }
+    }
+
+    /// The ➕ button, and the name field and kind it opens; Enter adds an
+    /// empty property by that name.
+    fn add_field(&mut self, ui: &mut egui::Ui, text: &str, updated: &mut Option<String>) {
+        let id = egui::Id::new("new_property");
+        let Some((name, kind)) = &mut self.adding else {
+            if ui.small_button("➕").on_hover_text(t("Add property")).clicked() {
+                self.adding = Some((String::new(), Kind::Text));
+                ui.memory_mut(|m| m.request_focus(id));
+            }
+            return;
+        };
+        let response = ui.add(egui::TextEdit::singleline(name).id(id).hint_text(t("name")).desired_width(80.0));
+        egui::ComboBox::from_id_source("new_property_kind").width(80.0).selected_text(kind_label(*kind)).show_ui(ui, |ui| {
+            for option in Kind::ALL {
+                ui.selectable_value(kind, option, kind_label(option));
+            }
+        });
+        if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.adding = None;
+        } else if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            let name = name.trim().to_owned();
+            if !name.is_empty() && !name.contains(':') && frontmatter::get(text, &name).is_none() {
+                let value = Value::Text(String::new()).convert(*kind);
+                *updated = Some(frontmatter::set_value(text, &name, Some(&value)));
+            }
+            self.adding = None;
+        }
+    }
+}
@@ -0,0 +1,524 @@
+use crate::config::PublishConfig;
+use crate::i18n::{t, tf};
+use crate::jobs::{self, Job, JobHandle};
+use crate::preview::{self, Block, Inline};
+use crate::restructure::slug;
+use crate::sidebar::{NoteInfo, NoteList, title};
+use crate::{links, vault};
+use eframe::egui::{self, RichText};
+use pulldown_cmark::Alignment;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::TryRecvError;
+#[cfg(target_os = "android")]
+use crate::stubs::rfd;
+
+/// Lists the files the last publish wrote, in the output folder.
+const MANIFEST: &str = ".published";
+
+/// Stylesheet written next to the pages.
+const STYLE: &str = "\
+body { margin: 0; font: 17px/1.6 system-ui, sans-serif; color: #222; background: #fdfdfc; }
+header { padding: 12px 24px; border-bottom: 1px solid #e4e4e0; }
+header a { font-weight: 600; color: inherit; text-decoration: none; }
+main { max-width: 720px; margin: 0 auto; padding: 24px; }
+a { color: #2a6db0; }
+pre { background: #f3f3f0; padding: 12px; overflow-x: auto; border-radius: 4px; }
+code { font: 0.9em ui-monospace, monospace; }
+blockquote { margin: 0; padding-left: 16px; border-left: 3px solid #ddd; color: #555; }
+table { border-collapse: collapse; }
+td, th { border: 1px solid #ddd; padding: 4px 8px; }
+img { max-width: 100%; }
+.tags a { margin-right: 8px; font-size: 0.9em; }
+.footnote { font-size: 0.9em; color: #555; }
+@media (prefers-color-scheme: dark) {
+  body { color: #ddd; background: #1c1c1e; }
+  header { border-color: #333; }
+  pre { background: #2a2a2c; }
+  a { color: #7ab4f0; }
+}
+";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// A note's page, relative to the site root: `journal/day.md` becomes
+/// `journal/day.html`.
+fn page(note: &Path) -> String {
+    let key = vault::note_key(note);
+    match key.rsplit_once('.') {
+        Some((stem, _)) => format!("{}.html", stem),
+        None => format!("{}.html", key),
+    }
+}
+
+fn tag_page(tag: &str) -> String {
+    format!("tags/{}.html", slug(&tag.replace('/', "-")))
+}
+
+/// `../` for every folder a page is in, to reach the site root.
+fn root_of(page: &str) -> String {
+    "../".repeat(page.matches('/').count())
+}
+
+/// Rewrites wiki links as Markdown links to the published pages; links to
+/// notes that are not published become plain text.
+fn resolve_wiki_links(text: &str, from: &str, published: &[PathBuf]) -> String {
+    let mut out = text.to_owned();
+    for link in links::wiki_links(text).into_iter().rev() {
+        let inner = &text[link.range.start + 2..link.range.end - 2];
+        let heading = inner.split('|').next().and_then(|t| t.split_once('#')).map(|(_, h)| slug(h));
+        let label = link.label().replace(['[', ']'], "");
+        let replacement = match links::resolve_wiki(&link.target, published).filter(|p| published.contains(p)) {
+            Some(target) => {
+                let anchor = heading.map(|h| format!("#{}", h)).unwrap_or_default();
+                format!("[{}](<{}{}{}>)", label, root_of(from), page(&target), anchor)
+            }
+            None => label,
+        };
+        out.replace_range(link.range, &replacement);
+    }
+    out
+}
+
+/// Whether a URL may go on a page: web and mail links and relative paths,
+/// but no `javascript:` or other scheme a browser would act on.
+fn allowed(url: &str) -> bool {
+    let url = url.trim();
+    match url.find([':', '/', '?', '#']) {
+        Some(end) if url[end..].starts_with(':') => {
+            matches!(url[..end].to_ascii_lowercase().as_str(), "http" | "https" | "mailto")
+        }
+        _ => true,
+    }
+}
+
+/// Points links to other notes at their pages. `None` for links that are
+/// not [`allowed`].
+fn link_url(url: &str) -> Option<String> {
+    if !allowed(url) {
+        return None;
+    }
+    let (path, anchor) = url.split_once('#').map_or((url, None), |(p, a)| (p, Some(a)));
+    if url.contains("://") || !(path.ends_with(".md") || path.ends_with(".txt")) {
+        return Some(url.to_owned());
+    }
+    let stem = path.rsplit_once('.').map_or(path, |(stem, _)| stem);
+    Some(match anchor {
+        Some(anchor) => format!("{}.html#{}", stem, anchor),
+        None => format!("{}.html", stem),
+    })
+}
+
+fn plain(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(text, _) | Inline::Code(text) | Inline::Math(text) => text.clone(),
+            Inline::Link { text, .. } => plain(text),
+            _ => String::new(),
+        })
+        .collect()
+}
+
+fn inlines_html(out: &mut String, inlines: &[Inline]) {
+    for inline in inlines {
+        match inline {
+            Inline::Text(text, style) => {
+                let mut text = escape(text);
+                if style.strike {
+                    text = format!("<del>{}</del>", text);
+                }
+                if style.emphasis {
+                    text = format!("<em>{}</em>", text);
+                }
+                if style.strong {
+                    text = format!("<strong>{}</strong>", text);
+                }
+                out.push_str(&text);
+            }
+            Inline::Code(code) => {
+                let _ = write!(out, "<code>{}</code>", escape(code));
+            }
+            Inline::Math(math) => {
+                let _ = write!(out, "<code class=\"math\">{}</code>", escape(math));
+            }
+            Inline::Link { url, text } => match link_url(url) {
+                Some(url) => {
+                    let _ = write!(out, "<a href=\"{}\">", escape(&url));
+                    inlines_html(out, text);
+                    out.push_str("</a>");
+                }
+                None => inlines_html(out, text),
+            },
+            Inline::Image { url, alt } if allowed(url) => {
+                let _ = write!(out, "<img src=\"{}\" alt=\"{}\">", escape(url), escape(alt));
+            }
+            Inline::Image { alt, .. } => out.push_str(&escape(alt)),
+            Inline::FootnoteRef(label) => {
+                let id = slug(label);
+                let _ = write!(out, "<sup><a href=\"#fn-{}\">{}</a></sup>", id, escape(label));
+            }
+            Inline::LineBreak => out.push_str("<br>"),
+        }
+    }
+}
+
+fn blocks_html(out: &mut String, blocks: &[Block]) {
+    for block in blocks {
+        match block {
+            Block::Paragraph(inlines) => {
+                out.push_str("<p>");
+                inlines_html(out, inlines);
+                out.push_str("</p>\n");
+            }
+            Block::Heading(level, inlines) => {
+                let _ = write!(out, "<h{} id=\"{}\">", level, slug(&plain(inlines)));
+                inlines_html(out, inlines);
+                let _ = writeln!(out, "</h{}>", level);
+            }
+            Block::Code { lang, code } => {
+                let _ = writeln!(out, "<pre><code class=\"language-{}\">{}</code></pre>", escape(lang), escape(code));
+            }
+            Block::Math(math) => {
+                let _ = writeln!(out, "<pre class=\"math\">{}</pre>", escape(math));
+            }
+            Block::Quote(blocks) => {
+                out.push_str("<blockquote>\n");
+                blocks_html(out, blocks);
+                out.push_str("</blockquote>\n");
+            }
+            Block::List { start, items } => {
+                match start {
+                    Some(1) => out.push_str("<ol>\n"),
+                    Some(start) => {
+                        let _ = writeln!(out, "<ol start=\"{}\">", start);
+                    }
+                    None => out.push_str("<ul>\n"),
+                }
+                for item in items {
+                    out.push_str("<li>");
+                    if let Some(checked) = item.checked {
+                        let _ = write!(out, "<input type=\"checkbox\" disabled{}> ", if checked { " checked" } else { "" });
+                    }
+                    // Tight list items hold a single paragraph; keep them on one line.
+                    match item.blocks.as_slice() {
+                        [Block::Paragraph(inlines)] => inlines_html(out, inlines),
+                        blocks => blocks_html(out, blocks),
+                    }
+                    out.push_str("</li>\n");
+                }
+                out.push_str(if start.is_some() { "</ol>\n" } else { "</ul>\n" });
+            }
+            Block::Table { align, rows } => {
+                out.push_str("<table>\n");
+                for (index, row) in rows.iter().enumerate() {
+                    let cell = if index == 0 { "th" } else { "td" };
+                    out.push_str("<tr>");
+                    for (column, inlines) in row.iter().enumerate() {
+                        let style = match align.get(column) {
+                            Some(Alignment::Center) => " style=\"text-align:center\"",
+                            Some(Alignment::Right) => " style=\"text-align:right\"",
+                            _ => "",
+                        };
+                        let _ = write!(out, "<{}{}>", cell, style);
+                        inlines_html(out, inlines);
+                        let _ = write!(out, "</{}>", cell);
+                    }
+                    out.push_str("</tr>\n");
+                }
+                out.push_str("</table>\n");
+            }
+            Block::FootnoteDefinition(label, blocks) => {
+                let _ = writeln!(out, "<div class=\"footnote\" id=\"fn-{}\"><sup>{}</sup>", slug(label), escape(label));
+                blocks_html(out, blocks);
+                out.push_str("</div>\n");
+            }
+            // Raw HTML is shown as text so a published note cannot inject scripts.
+            Block::Html(html) => {
+                let _ = writeln!(out, "<pre>{}</pre>", escape(html));
+            }
+            Block::Rule => out.push_str("<hr>\n"),
+        }
+    }
+}
+
+/// Renders Markdown, with wiki links already resolved, to HTML.
+pub fn render(markdown: &str) -> String {
+    let mut out = String::new();
+    blocks_html(&mut out, &preview::parse(markdown));
+    out
+}
+
+fn layout(site: &str, page_title: &str, page: &str, body: &str) -> String {
+    let root = root_of(page);
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title} · {site}</title>\n<link rel=\"stylesheet\" href=\"{root}style.css\">\n</head>\n<body>\n\
+         <header><a href=\"{root}index.html\">{site}</a></header>\n<main>\n{body}</main>\n</body>\n</html>\n",
+        title = escape(page_title),
+        site = escape(site),
+        root = root,
+        body = body,
+    )
+}
+
+/// The folder a note is in, or `""` for notes at the top of the vault.
+pub fn top_folder(note: &Path) -> String {
+    let key = vault::note_key(note);
+    key.split_once('/').map(|(folder, _)| folder.to_owned()).unwrap_or_default()
+}
+
+/// Writes a file of the site and adds it to `written`.
+fn write_file(dir: &Path, relative: &str, contents: &str, written: &mut Vec<String>) -> io::Result<()> {
+    let path = dir.join(relative);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, contents)?;
+    written.push(relative.to_owned());
+    Ok(())
+}
+
+/// Deletes the files the last publish wrote that this one did not, such
+/// as pages of notes deleted since, and lists this publish's files for
+/// the next one. Files the app did not write are left alone.
+fn remove_stale(out: &Path, written: &[String]) -> io::Result<()> {
+    let manifest = out.join(MANIFEST);
+    let previous = std::fs::read_to_string(&manifest).unwrap_or_default();
+    let current: HashSet<&str> = written.iter().map(String::as_str).collect();
+    for stale in previous.lines().filter(|f| !current.contains(f)) {
+        let relative = Path::new(stale);
+        if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            continue;
+        }
+        match std::fs::remove_file(out.join(relative)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+    }
+    std::fs::write(manifest, written.join("\n"))
+}
+
+/// Writes the selected folders as a static site: a page per note, an
+/// index, a page per tag and the stylesheet, plus the attachments folder.
+/// Pages from an earlier publish that are no longer part of the site are
+/// removed. Returns how many notes were published.
+pub fn publish(notes: &[NoteInfo], config: &PublishConfig, job: &Job) -> io::Result<usize> {
+    let out = Path::new(&config.output_dir);
+    let selected: Vec<&NoteInfo> = notes
+        .iter()
+        .filter(|n| !vault::is_archived(&n.path) && config.folders.contains(&top_folder(&n.path)))
+        .collect();
+    let published: Vec<PathBuf> = selected.iter().map(|n| n.path.clone()).collect();
+    let mut tags: BTreeMap<String, Vec<&NoteInfo>> = BTreeMap::new();
+    let mut written = Vec::new();
+
+    for (index, note) in selected.iter().enumerate() {
+        job.check()?;
+        job.progress(index, selected.len());
+        let page = page(&note.path);
+        let root = root_of(&page);
+        let markdown = resolve_wiki_links(&note.text, &page, &published);
+        let mut body = format!("<article>\n{}</article>\n", render(&markdown));
+        if !note.tags.is_empty() {
+            body.push_str("<p class=\"tags\">");
+            for tag in &note.tags {
+                let _ = write!(body, "<a href=\"{}{}\">#{}</a>", root, tag_page(tag), escape(tag));
+                tags.entry(tag.clone()).or_default().push(note);
+            }
+            body.push_str("</p>\n");
+        }
+        write_file(out, &page, &layout(&config.site_title, &title(&note.path), &page, &body), &mut written)?;
+    }
+
+    let mut index = format!("<h1>{}</h1>\n", escape(&config.site_title));
+    let mut folders: BTreeMap<String, Vec<&NoteInfo>> = BTreeMap::new();
+    for note in &selected {
+        folders.entry(top_folder(&note.path)).or_default().push(note);
+    }
+    for (folder, notes) in &mut folders {
+        if !folder.is_empty() {
+            let _ = writeln!(index, "<h2>{}</h2>", escape(folder));
+        }
+        notes.sort_by_cached_key(|n| title(&n.path).to_lowercase());
+        index.push_str("<ul>\n");
+        for note in notes.iter() {
+            let _ = writeln!(index, "<li><a href=\"{}\">{}</a></li>", escape(&page(&note.path)), escape(&title(&note.path)));
+        }
+        index.push_str("</ul>\n");
+    }
+    if !tags.is_empty() {
+        index.push_str("<h2>Tags</h2>\n<p class=\"tags\">");
+        for tag in tags.keys() {
+            let _ = write!(index, "<a href=\"{}\">#{}</a>", tag_page(tag), escape(tag));
+        }
+        index.push_str("</p>\n");
+    }
+    write_file(out, "index.html", &layout(&config.site_title, "Index", "index.html", &index), &mut written)?;
+
+    for (tag, notes) in &tags {
+        let tag_page = tag_page(tag);
+        let root = root_of(&tag_page);
+        let mut body = format!("<h1>#{}</h1>\n<ul>\n", escape(tag));
+        for note in notes {
+            let _ = writeln!(body, "<li><a href=\"{}{}\">{}</a></li>", root, escape(&page(&note.path)), escape(&title(&note.path)));
+        }
+        body.push_str("</ul>\n");
+        write_file(out, &tag_page, &layout(&config.site_title, &format!("#{}", tag), &tag_page, &body), &mut written)?;
+    }
+
+    write_file(out, "style.css", STYLE, &mut written)?;
+    let attachments = Path::new(vault::ATTACHMENTS_DIR);
+    for file in vault::walk_files(attachments) {
+        job.check()?;
+        if let Ok(relative) = file.strip_prefix(vault::NOTES_DIR) {
+            let dest = out.join(relative);
+            if let Some(parent) = dest.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::copy(&file, dest)?;
+            written.push(relative.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    remove_stale(out, &written)?;
+    Ok(selected.len())
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<(), String> {
+    let output = Command::new("git").args(args).current_dir(dir).output().map_err(|e| format!("git: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_owned())
+    }
+}
+
+/// Commits the output directory to its own repository on `branch` and
+/// force-pushes it to `remote`.
+pub fn push(config: &PublishConfig) -> Result<(), String> {
+    let dir = Path::new(&config.output_dir);
+    if !dir.join(".git").exists() {
+        git(dir, &["init", "-q"])?;
+    }
+    git(dir, &["checkout", "-q", "-B", &config.git_branch])?;
+    git(dir, &["add", "-A"])?;
+    let message = format!("Publish {}", chrono::Local::now().format("%Y-%m-%d %H:%M"));
+    // Nothing to commit is fine; the push below still brings the remote up to date.
+    let _ = git(dir, &["commit", "-q", "-m", &message]);
+    git(dir, &["push", "-q", "--force", &config.git_remote, &config.git_branch])
+}
+
+/// Window for choosing what to publish and where.
+#[derive(Default)]
+pub struct PublishView {
+    pub open: bool,
+    status: Option<Result<String, String>>,
+    running: Option<JobHandle<Result<String, String>>>,
+}
+
+impl PublishView {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.status = None;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, list: &mut NoteList, config: &mut PublishConfig) {
+        if let Some(running) = &self.running {
+            match running.try_recv() {
+                Ok(status) => {
+                    self.status = Some(status);
+                    self.running = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.running = None,
+            }
+        }
+        if !self.open {
+            return;
+        }
+        let notes = list.notes();
+        let mut folders: Vec<String> = notes.iter().filter(|n| !vault::is_archived(&n.path)).map(|n| top_folder(&n.path)).collect();
+        folders.sort();
+        folders.dedup();
+        let mut open = true;
+        let mut run = false;
+
+        egui::Window::new(t("🌍 Publish Site")).open(&mut open).default_width(380.0).show(ctx, |ui| {
+            ui.label(RichText::new(t("Folders to publish")).strong());
+            for folder in &folders {
+                let mut selected = config.folders.contains(folder);
+                let label = if folder.is_empty() { t("(notes at the top level)") } else { folder.clone() };
+                if ui.checkbox(&mut selected, label).changed() {
+                    if selected {
+                        config.folders.push(folder.clone());
+                    } else {
+                        config.folders.retain(|f| f != folder);
+                    }
+                }
+            }
+            ui.separator();
+            egui::Grid::new("publish_settings").num_columns(2).show(ui, |ui| {
+                ui.label(t("Site title"));
+                ui.text_edit_singleline(&mut config.site_title);
+                ui.end_row();
+                ui.label(t("Output folder"));
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut config.output_dir);
+                    if ui.button("📁").clicked()
+                        && let Some(dir) = rfd::FileDialog::new().pick_folder()
+                    {
+                        config.output_dir = dir.to_string_lossy().into_owned();
+                    }
+                });
+                ui.end_row();
+                ui.label(t("Git remote"));
+                ui.add(egui::TextEdit::singleline(&mut config.git_remote).hint_text(t("optional, e.g. git@host:me/garden.git")));
+                ui.end_row();
+                ui.label(t("Branch"));
+                ui.add(egui::TextEdit::singleline(&mut config.git_branch).hint_text("gh-pages"));
+                ui.end_row();
+            });
+            ui.separator();
+            let ready = !config.folders.is_empty() && !config.output_dir.trim().is_empty();
+            if ui.add_enabled(ready && self.running.is_none(), egui::Button::new(t("🌍 Publish"))).clicked() {
+                run = true;
+            }
+            if let Some(running) = &self.running {
+                running.show_progress(ui);
+            }
+            match &self.status {
+                Some(Ok(message)) => {
+                    ui.label(message);
+                }
+                Some(Err(err)) => {
+                    ui.colored_label(egui::Color32::LIGHT_RED, err);
+                }
+                None => {}
+            }
+        });
+
+        if run {
+            let (notes, config) = (list.notes().to_vec(), config.clone());
+            self.status = None;
+            self.running = Some(jobs::spawn("Publishing the site", move |job| match publish(&notes, &config, job) {
+                Ok(count) if config.git_remote.trim().is_empty() || config.git_branch.trim().is_empty() => {
+                    Ok(tf("Published {} notes to {}", &[&count, &config.output_dir]))
+                }
+                Ok(count) => {
+                    job.status("Pushing");
+                    push(&config).map(|()| tf("Published {} notes and pushed to {}", &[&count, &config.git_branch]))
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => Err(t("Publishing was cancelled")),
+                Err(err) => Err(tf("Failed to publish: {}", &[&err])),
+            }));
+        }
+        if !open {
+            self.open = false;
+        }
+    }
+}
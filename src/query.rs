@@ -0,0 +1,319 @@
+use crate::frontmatter::{self, Value};
+use crate::sidebar::{NoteInfo, title};
+use crate::vault;
+use chrono::NaiveDate;
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    /// A word or phrase somewhere in the title or text, lowercased.
+    Text(String),
+    Tag(String),
+    /// Part of the note's path relative to the notes folder.
+    Path(String),
+    Title(String),
+    Created(Ordering, bool, NaiveDate),
+    Modified(Ordering, bool, NaiveDate),
+    /// A front matter property, lowercased, compared with a value or only
+    /// set at all.
+    Property(String, Option<(Ordering, bool, String)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Term(Term),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+/// Words before a `:` that name a field; anything else, like `https:`,
+/// is searched for as text.
+const FIELDS: [&str; 6] = ["tag", "path", "title", "created", "modified", "prop"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Minus,
+    /// A bare word, possibly `field:value`.
+    Word(String),
+    /// A quoted phrase, with the field written before it if any.
+    Quoted(Option<String>, String),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '-' => {
+                chars.next();
+                tokens.push(Token::Minus);
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    if c == '"' {
+                        chars.next();
+                        let phrase: String = chars.by_ref().take_while(|&c| c != '"').collect();
+                        let field = word.strip_suffix(':').map(str::to_owned);
+                        if !word.is_empty() && field.is_none() {
+                            return Err(format!("unexpected quote after {}", word));
+                        }
+                        tokens.push(Token::Quoted(field, phrase));
+                        word.clear();
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                if !word.is_empty() {
+                    tokens.push(Token::Word(word));
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// The comparison a value starts with, `=` when it has none, and the
+/// rest of it.
+fn comparison(value: &str) -> (Ordering, bool, &str) {
+    if let Some(rest) = value.strip_prefix(">=") {
+        (Ordering::Greater, true, rest)
+    } else if let Some(rest) = value.strip_prefix("<=") {
+        (Ordering::Less, true, rest)
+    } else if let Some(rest) = value.strip_prefix('>') {
+        (Ordering::Greater, false, rest)
+    } else if let Some(rest) = value.strip_prefix('<') {
+        (Ordering::Less, false, rest)
+    } else {
+        (Ordering::Equal, true, value.strip_prefix('=').unwrap_or(value))
+    }
+}
+
+fn date_term(value: &str) -> Result<(Ordering, bool, NaiveDate), String> {
+    let (ordering, inclusive, date) = comparison(value);
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| format!("expected a YYYY-MM-DD date, got {}", date))?;
+    Ok((ordering, inclusive, date))
+}
+
+fn term(field: Option<&str>, value: &str) -> Result<Term, String> {
+    if value.is_empty() {
+        return Err(format!("{}: needs a value", field.unwrap_or_default()));
+    }
+    Ok(match field {
+        None => Term::Text(value.to_lowercase()),
+        Some("tag") => Term::Tag(value.trim_start_matches('#').to_lowercase()),
+        Some("path") => Term::Path(value.to_lowercase()),
+        Some("title") => Term::Title(value.to_lowercase()),
+        Some("created") => {
+            let (ordering, inclusive, date) = date_term(value)?;
+            Term::Created(ordering, inclusive, date)
+        }
+        Some("modified") => {
+            let (ordering, inclusive, date) = date_term(value)?;
+            Term::Modified(ordering, inclusive, date)
+        }
+        Some("prop") => {
+            let split = value.find(['<', '>', '=']).unwrap_or(value.len());
+            let name = value[..split].trim().to_lowercase();
+            if name.is_empty() {
+                return Err("prop: needs a property name".to_owned());
+            }
+            let compared = (split < value.len()).then(|| {
+                let (ordering, inclusive, wanted) = comparison(&value[split..]);
+                (ordering, inclusive, wanted.trim().to_lowercase())
+            });
+            Term::Property(name, compared)
+        }
+        Some(other) => return Err(format!("unknown field {}:", other)),
+    })
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    at: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.at)
+    }
+
+    fn keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(w)) if w == keyword)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut terms = vec![self.and()?];
+        while self.keyword("OR") {
+            self.at += 1;
+            terms.push(self.and()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { Expr::Or(terms) })
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut terms = vec![self.unary()?];
+        loop {
+            if self.keyword("AND") {
+                self.at += 1;
+            } else if self.peek().is_none() || self.keyword("OR") || self.peek() == Some(&Token::Close) {
+                break;
+            }
+            terms.push(self.unary()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { Expr::And(terms) })
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.keyword("NOT") || self.peek() == Some(&Token::Minus) {
+            self.at += 1;
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let token = self.peek().cloned().ok_or("the query ends too early")?;
+        self.at += 1;
+        match token {
+            Token::Open => {
+                let expr = self.or()?;
+                if self.peek() != Some(&Token::Close) {
+                    return Err("missing )".to_owned());
+                }
+                self.at += 1;
+                Ok(expr)
+            }
+            Token::Close => Err("unexpected )".to_owned()),
+            Token::Minus => Err("unexpected -".to_owned()),
+            Token::Quoted(field, phrase) => Ok(Expr::Term(term(field.as_deref(), &phrase)?)),
+            Token::Word(word) => match word.split_once(':') {
+                Some((field, value)) if FIELDS.contains(&field) => {
+                    Ok(Expr::Term(term(Some(field), value)?))
+                }
+                _ => Ok(Expr::Term(term(None, &word)?)),
+            },
+        }
+    }
+}
+
+/// Parses a query. Words and `"quoted phrases"` match note text;
+/// `tag:#work`, `path:journal/`, `title:idea`, `created:>2024-01-01` and
+/// `modified:<=2024-06-30` match note details, and `prop:status=done`,
+/// `prop:due<2024-07-01` or `prop:draft` front matter properties. Terms combine with `AND`
+/// (implied between terms), `OR`, `NOT` or a leading `-`, and parentheses.
+/// An empty query gives `None`, which matches every note.
+pub fn parse(input: &str) -> Result<Option<Expr>, String> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Ok(None);
+    }
+    let mut parser = Parser { tokens, at: 0 };
+    let expr = parser.or()?;
+    match parser.peek() {
+        None => Ok(Some(expr)),
+        Some(Token::Close) => Err("unexpected )".to_owned()),
+        Some(token) => Err(format!("unexpected {:?}", token)),
+    }
+}
+
+/// Text the query looks for, leaving out what it excludes with `NOT`, for
+/// ranking and highlighting results.
+pub fn wanted_text(expr: &Expr) -> Vec<String> {
+    match expr {
+        Expr::Term(Term::Text(text) | Term::Title(text)) => vec![text.clone()],
+        Expr::Term(_) | Expr::Not(_) => Vec::new(),
+        Expr::And(terms) | Expr::Or(terms) => terms.iter().flat_map(wanted_text).collect(),
+    }
+}
+
+/// A note prepared for evaluating queries against.
+pub struct Doc<'a> {
+    pub note: &'a NoteInfo,
+    /// Title and text, lowercased.
+    pub haystack: String,
+    pub properties: Vec<(String, Value)>,
+}
+
+impl<'a> Doc<'a> {
+    pub fn new(note: &'a NoteInfo) -> Self {
+        Self {
+            haystack: format!("{}\n{}", title(&note.path), note.text).to_lowercase(),
+            properties: frontmatter::properties(&note.text),
+            note,
+        }
+    }
+}
+
+fn compare(date: Option<std::time::SystemTime>, ordering: Ordering, inclusive: bool, bound: NaiveDate) -> bool {
+    let Some(date) = date.map(|d| chrono::DateTime::<chrono::Local>::from(d).date_naive()) else {
+        return false;
+    };
+    let actual = date.cmp(&bound);
+    actual == ordering || (inclusive && actual == Ordering::Equal)
+}
+
+/// Whether a property's value compares to `wanted` as asked: as dates
+/// when both are, as numbers when both are, and otherwise as lowercase
+/// text. A list matches when any item does.
+fn compare_value(value: &Value, ordering: Ordering, inclusive: bool, wanted: &str) -> bool {
+    let matches = |actual: &str| {
+        let actual = actual.to_lowercase();
+        let order = match (frontmatter::parse_date(&actual), frontmatter::parse_date(wanted)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            _ => match (actual.parse::<f64>(), wanted.parse::<f64>()) {
+                (Ok(a), Ok(b)) => a.total_cmp(&b),
+                _ => actual.as_str().cmp(wanted),
+            },
+        };
+        order == ordering || (inclusive && order == Ordering::Equal)
+    };
+    match value {
+        Value::List(items) => items.iter().any(|item| matches(item)),
+        Value::Other => false,
+        value => matches(&value.to_string()),
+    }
+}
+
+pub fn eval(expr: &Expr, doc: &Doc) -> bool {
+    match expr {
+        Expr::Not(inner) => !eval(inner, doc),
+        Expr::And(terms) => terms.iter().all(|t| eval(t, doc)),
+        Expr::Or(terms) => terms.iter().any(|t| eval(t, doc)),
+        Expr::Term(term) => match term {
+            Term::Text(text) => doc.haystack.contains(text.as_str()),
+            Term::Tag(tag) => doc.note.tags.iter().any(|t| t.to_lowercase() == *tag || t.to_lowercase().starts_with(&format!("{}/", tag))),
+            Term::Path(path) => vault::note_key(&doc.note.path).to_lowercase().contains(path.as_str()),
+            Term::Title(text) => title(&doc.note.path).to_lowercase().contains(text.as_str()),
+            Term::Created(ordering, inclusive, date) => compare(doc.note.created.or(doc.note.modified), *ordering, *inclusive, *date),
+            Term::Modified(ordering, inclusive, date) => compare(doc.note.modified, *ordering, *inclusive, *date),
+            Term::Property(name, compared) => {
+                let value = doc.properties.iter().find(|(key, _)| key.to_lowercase() == *name).map(|(_, value)| value);
+                match (value, compared) {
+                    (Some(value), Some((ordering, inclusive, wanted))) => compare_value(value, *ordering, *inclusive, wanted),
+                    (Some(Value::Checkbox(checked)), None) => *checked,
+                    (Some(value), None) => !value.to_string().is_empty(),
+                    (None, _) => false,
+                }
+            }
+        },
+    }
+}
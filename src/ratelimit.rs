@@ -0,0 +1,81 @@
+use reqwest::StatusCode;
+use reqwest::blocking::Response;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// The window the limits count over.
+const WINDOW: Duration = Duration::from_secs(60);
+/// How long to back off after a 429 that does not say.
+const DEFAULT_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Keeps requests to a service under its per-minute limits on our side,
+/// and backs off when it answers 429 Too Many Requests anyway.
+pub struct RateLimiter {
+    max_requests: usize,
+    max_bytes: usize,
+    /// When each request in the last minute was sent, and its size.
+    sent: VecDeque<(Instant, usize)>,
+    cooling_until: Option<Instant>,
+    /// Requests left as last reported by the server.
+    remaining: Option<u64>,
+}
+
+impl RateLimiter {
+    pub const fn new(max_requests: usize, max_bytes: usize) -> Self {
+        Self {
+            max_requests,
+            max_bytes,
+            sent: VecDeque::new(),
+            cooling_until: None,
+            remaining: None,
+        }
+    }
+
+    /// Records a request of `bytes` and returns `None` if it may go now,
+    /// or how long to wait before asking again.
+    pub fn reserve(&mut self, bytes: usize) -> Option<Duration> {
+        let now = Instant::now();
+        if let Some(until) = self.cooling_until {
+            if until > now {
+                return Some(until - now);
+            }
+            self.cooling_until = None;
+        }
+        while self.sent.front().is_some_and(|(at, _)| now - *at >= WINDOW) {
+            self.sent.pop_front();
+        }
+        let used: usize = self.sent.iter().map(|(_, b)| b).sum();
+        // A request bigger than the whole budget goes once the window is empty.
+        let fits = self.sent.is_empty() || used + bytes <= self.max_bytes;
+        if self.sent.len() < self.max_requests && fits {
+            self.sent.push_back((now, bytes));
+            return None;
+        }
+        self.sent.front().map(|(at, _)| WINDOW - (now - *at))
+    }
+
+    /// Notes the quota the server reports. Returns how long to cool down
+    /// if it refused the request for going too fast.
+    pub fn observe(&mut self, response: &Response) -> Option<Duration> {
+        let header = |name: &str| response.headers().get(name)?.to_str().ok()?.trim().parse::<u64>().ok();
+        if let Some(remaining) = header("x-ratelimit-remaining").or_else(|| header("ratelimit-remaining")) {
+            self.remaining = Some(remaining);
+        }
+        if response.status() != StatusCode::TOO_MANY_REQUESTS {
+            return None;
+        }
+        let wait = header("retry-after").map_or(DEFAULT_COOLDOWN, Duration::from_secs);
+        self.cooling_until = Some(Instant::now() + wait);
+        self.remaining = Some(0);
+        Some(wait)
+    }
+
+    /// Time left before requests go out again after a 429.
+    pub fn cooling_down(&self) -> Option<Duration> {
+        self.cooling_until.and_then(|until| until.checked_duration_since(Instant::now())).filter(|d| !d.is_zero())
+    }
+
+    pub fn remaining(&self) -> Option<u64> {
+        self.remaining
+    }
+}
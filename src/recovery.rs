@@ -0,0 +1,274 @@
+use crate::i18n::t;
+use crate::vault::{NOTES_DIR, RECOVERY_CRASHED_FILE, RECOVERY_FILE};
+use eframe::egui::{self, RichText};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::error;
+
+/// Edits are written out at most this often while typing.
+const WRITE_DELAY: Duration = Duration::from_millis(500);
+/// The journal is rewritten as one snapshot per note after this many records.
+const COMPACT_AFTER: usize = 500;
+
+/// One line of the journal. `note` is the note's path, or `None` for a new
+/// note that has not been saved yet.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum Record {
+    /// The whole unsaved text of a note.
+    Snapshot { note: Option<PathBuf>, text: String },
+    /// Bytes `start..end` of the note's previous text replaced by `insert`.
+    Splice {
+        note: Option<PathBuf>,
+        start: usize,
+        end: usize,
+        insert: String,
+    },
+    /// The note was saved or its edits were dropped.
+    Clean { note: Option<PathBuf> },
+}
+
+/// Unsaved text found in a journal left behind by a crash.
+#[derive(Clone, Debug)]
+pub struct Recovered {
+    pub note: Option<PathBuf>,
+    pub text: String,
+}
+
+/// Replays a journal into the unsaved text of each note.
+fn replay(path: &str, notes: &mut HashMap<Option<PathBuf>, String>) {
+    let Ok(file) = File::open(path) else {
+        return;
+    };
+    let records = BufReader::new(file).lines().map_while(Result::ok).filter_map(|l| serde_json::from_str(&l).ok());
+    for record in records {
+        match record {
+            Record::Snapshot { note, text } => {
+                notes.insert(note, text);
+            }
+            Record::Splice { note, start, end, insert } => {
+                if let Some(text) = notes.get_mut(&note)
+                    && text.get(start..end).is_some()
+                {
+                    text.replace_range(start..end, &insert);
+                }
+            }
+            Record::Clean { note } => {
+                notes.remove(&note);
+            }
+        }
+    }
+}
+
+/// A write-ahead journal of edits that have not been saved, so they can be
+/// brought back after a crash. It is removed when the app closes normally.
+pub struct RecoveryJournal {
+    file: Option<File>,
+    /// The unsaved text of each note as last written to the journal.
+    written: HashMap<Option<PathBuf>, String>,
+    records: usize,
+    last_write: Option<Instant>,
+}
+
+impl RecoveryJournal {
+    /// Starts a new journal. Unsaved text left by a previous run is moved
+    /// aside and returned, and stays on disk until it is dealt with.
+    pub fn start() -> (Self, Vec<Recovered>) {
+        let mut notes = HashMap::new();
+        replay(RECOVERY_CRASHED_FILE, &mut notes);
+        replay(RECOVERY_FILE, &mut notes);
+        let mut recovered: Vec<Recovered> = notes.into_iter().map(|(note, text)| Recovered { note, text }).collect();
+        recovered.sort_by(|a, b| a.note.cmp(&b.note));
+        if let Err(err) = store_crashed(&recovered) {
+            error!("Failed to keep recovered edits: {}", err);
+        }
+        let file = match std::fs::create_dir_all(NOTES_DIR).and_then(|()| File::create(RECOVERY_FILE)) {
+            Ok(file) => Some(file),
+            Err(err) => {
+                error!("Failed to start the recovery journal: {}", err);
+                None
+            }
+        };
+        let journal = Self {
+            file,
+            written: HashMap::new(),
+            records: 0,
+            last_write: None,
+        };
+        (journal, recovered)
+    }
+
+    fn append(&mut self, record: &Record) {
+        let Some(file) = &mut self.file else {
+            return;
+        };
+        let result = serde_json::to_string(record).map_err(std::io::Error::from).and_then(|line| writeln!(file, "{}", line));
+        match result {
+            Ok(()) => self.records += 1,
+            Err(err) => error!("Failed to write the recovery journal: {}", err),
+        }
+    }
+
+    /// Rewrites the journal as one snapshot per note with unsaved text.
+    fn compact(&mut self) {
+        self.file = match File::create(RECOVERY_FILE) {
+            Ok(file) => Some(file),
+            Err(err) => {
+                error!("Failed to compact the recovery journal: {}", err);
+                return;
+            }
+        };
+        self.records = 0;
+        let written: Vec<_> = self.written.iter().map(|(n, t)| (n.clone(), t.clone())).collect();
+        for (note, text) in written {
+            self.append(&Record::Snapshot { note, text });
+        }
+    }
+
+    /// Called every frame with the open note. Returns when to call again
+    /// if an edit is waiting to be written.
+    pub fn track(&mut self, note: Option<&Path>, text: &str, unsaved: bool) -> Option<Duration> {
+        let key = note.map(Path::to_path_buf);
+        // Only the open note can have unsaved edits; those of a note that
+        // was switched away from were saved or discarded.
+        let closed: Vec<_> = self.written.keys().filter(|k| **k != key).cloned().collect();
+        for note in closed {
+            self.written.remove(&note);
+            self.append(&Record::Clean { note });
+        }
+        if !unsaved {
+            if self.written.remove(&key).is_some() {
+                self.append(&Record::Clean { note: key });
+            }
+            return None;
+        }
+        if self.written.get(&key).is_some_and(|t| t == text) {
+            return None;
+        }
+        if let Some(at) = self.last_write
+            && at.elapsed() < WRITE_DELAY
+        {
+            return Some(WRITE_DELAY - at.elapsed());
+        }
+        self.last_write = Some(Instant::now());
+        let record = match self.written.get(&key) {
+            Some(old) => {
+                let (start, end, insert) = crate::text::splice(old, text);
+                Record::Splice {
+                    note: key.clone(),
+                    start,
+                    end,
+                    insert: insert.to_owned(),
+                }
+            }
+            None => Record::Snapshot {
+                note: key.clone(),
+                text: text.to_owned(),
+            },
+        };
+        self.append(&record);
+        self.written.insert(key, text.to_owned());
+        if self.records > COMPACT_AFTER {
+            self.compact();
+        }
+        None
+    }
+
+    /// Removes the journal on a normal exit.
+    pub fn close(&mut self) {
+        self.file = None;
+        if let Err(err) = std::fs::remove_file(RECOVERY_FILE)
+            && err.kind() != std::io::ErrorKind::NotFound
+        {
+            error!("Failed to remove the recovery journal: {}", err);
+        }
+    }
+}
+
+/// Keeps recovered text in a journal of its own until the user restores or
+/// discards it, so it survives another crash in the meantime.
+fn store_crashed(recovered: &[Recovered]) -> std::io::Result<()> {
+    if recovered.is_empty() {
+        return match std::fs::remove_file(RECOVERY_CRASHED_FILE) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        };
+    }
+    let mut out = String::new();
+    for item in recovered {
+        let record = Record::Snapshot {
+            note: item.note.clone(),
+            text: item.text.clone(),
+        };
+        out.push_str(&serde_json::to_string(&record)?);
+        out.push('\n');
+    }
+    std::fs::write(RECOVERY_CRASHED_FILE, out)
+}
+
+/// Offers the unsaved text found at startup for restoring.
+#[derive(Default)]
+pub struct RecoveryView {
+    items: Vec<Recovered>,
+}
+
+impl RecoveryView {
+    pub fn new(items: Vec<Recovered>) -> Self {
+        Self { items }
+    }
+
+    fn remove(&mut self, index: usize) -> Recovered {
+        let item = self.items.remove(index);
+        if let Err(err) = store_crashed(&self.items) {
+            error!("Failed to update recovered edits: {}", err);
+        }
+        item
+    }
+
+    /// Returns the entry to restore into the editor. Restoring waits while
+    /// the open note has unsaved edits of its own.
+    pub fn show(&mut self, ctx: &egui::Context, unsaved: bool) -> Option<Recovered> {
+        if self.items.is_empty() {
+            return None;
+        }
+        let mut restore = None;
+        let mut discard = None;
+        let mut discard_all = false;
+        egui::Window::new(t("🩹 Recover Unsaved Edits")).collapsible(false).default_width(420.0).show(ctx, |ui| {
+            ui.label(t("The app did not close properly last time. These edits were never saved:"));
+            ui.separator();
+            for (index, item) in self.items.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let title = item.note.as_deref().map_or_else(|| "Untitled note".to_owned(), crate::sidebar::title);
+                    ui.label(RichText::new(title).strong());
+                    let first = item.text.lines().find(|l| !l.trim().is_empty()).unwrap_or_default();
+                    ui.label(RichText::new(first.chars().take(40).collect::<String>()).weak());
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button(t("Discard")).clicked() {
+                            discard = Some(index);
+                        }
+                        let button = ui.add_enabled(!unsaved, egui::Button::new(t("Restore")));
+                        if button.on_disabled_hover_text(t("Save or discard the open note's edits first")).clicked() {
+                            restore = Some(index);
+                        }
+                    });
+                });
+            }
+            ui.separator();
+            discard_all = ui.button(t("Discard all")).clicked();
+        });
+        if discard_all {
+            self.items.clear();
+            if let Err(err) = store_crashed(&self.items) {
+                error!("Failed to update recovered edits: {}", err);
+            }
+        } else if let Some(index) = discard {
+            self.remove(index);
+        }
+        restore.map(|index| self.remove(index))
+    }
+}
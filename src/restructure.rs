@@ -0,0 +1,231 @@
+use crate::i18n::t;
+use crate::metadata::Metadata;
+use crate::{links, urls, vault};
+use eframe::egui;
+use std::collections::BTreeSet;
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing::error;
+
+fn stem(path: &Path) -> String {
+    path.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_owned()
+}
+
+/// GitHub-style heading anchor: `My Heading!` becomes `my-heading`.
+pub fn slug(heading: &str) -> String {
+    heading
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Points links in `text` (the content of `note`) that lead to `old` at
+/// `new` instead, optionally at a heading in it.
+pub fn retarget_links(note: &Path, text: &str, notes: &[PathBuf], old: &Path, new: &Path, heading: Option<&str>) -> String {
+    let old = vault::clean_path(old);
+    let mut edits: Vec<(std::ops::Range<usize>, String)> = Vec::new();
+
+    for link in links::wiki_links(text) {
+        if links::resolve_wiki(&link.target, notes).is_some_and(|p| vault::clean_path(&p) == old) {
+            let target = match heading {
+                Some(heading) => format!("{}#{}", stem(new), heading),
+                None => stem(new),
+            };
+            edits.push((link.range.clone(), format!("[[{}|{}]]", target, link.label())));
+        }
+    }
+    for link in urls::links(text) {
+        if links::local_target(note, &link.target).is_some_and(|p| p == old) {
+            let dir = note.parent().unwrap_or(Path::new(vault::NOTES_DIR));
+            let mut target = vault::relative_path(dir, new).replace(' ', "%20");
+            if let Some(heading) = heading {
+                target.push('#');
+                target.push_str(&slug(heading));
+            }
+            let source = &text[link.range.clone()];
+            edits.push((link.range.clone(), source.replacen(&link.target, &target, 1)));
+        }
+    }
+
+    edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    let mut out = text.to_owned();
+    for (range, replacement) in edits {
+        out.replace_range(range, &replacement);
+    }
+    out
+}
+
+/// Rewrites every note in the vault that links to `old`.
+pub fn retarget_vault(old: &Path, new: &Path, heading: Option<&str>) -> io::Result<()> {
+    let notes = vault::note_files();
+    for note in &notes {
+        let text = std::fs::read_to_string(note)?;
+        let updated = retarget_links(note, &text, &notes, old, new, heading);
+        if updated != text {
+            crate::journal::write(note, &updated)?;
+        }
+    }
+    Ok(())
+}
+
+/// Moves `from` to `to` (made unique), updating links and note state.
+pub fn move_note(from: &Path, to: &Path, metadata: &mut Metadata) -> io::Result<PathBuf> {
+    let dir = to.parent().unwrap_or(Path::new(vault::NOTES_DIR));
+    std::fs::create_dir_all(dir)?;
+    let stem = to.file_stem().and_then(|s| s.to_str()).unwrap_or("Untitled");
+    let ext = to.extension().and_then(|s| s.to_str()).unwrap_or("md");
+    let to = vault::unique_path(dir, stem, ext);
+    crate::journal::rename(from, &to)?;
+    retarget_vault(from, &to, None)?;
+    metadata.rename(from, &to);
+    Ok(to)
+}
+
+/// Concatenates notes into `dest`, each under a heading named after it,
+/// and points links to them at their section. With `delete`, the
+/// originals are removed afterwards.
+pub fn merge_notes(sources: &[PathBuf], dest: &Path, delete: bool) -> io::Result<()> {
+    let mut merged = format!("# {}\n", stem(dest));
+    for source in sources {
+        let text = std::fs::read_to_string(source)?;
+        let title = stem(source);
+        // Drop a leading title heading; the section heading replaces it.
+        let body = match text.trim_start().strip_prefix(&format!("# {}", title)) {
+            Some(rest) => rest.trim_start_matches(['\r', '\n']),
+            None => &text,
+        };
+        // Demote the section's own headings below the new section heading.
+        let fences = crate::markdown::fenced_blocks(body);
+        let mut demoted = String::new();
+        let mut offset = 0;
+        for line in body.split_inclusive('\n') {
+            if line.starts_with('#') && !fences.iter().any(|f| f.range.contains(&offset)) {
+                demoted.push('#');
+            }
+            demoted.push_str(line);
+            offset += line.len();
+        }
+        merged.push_str(&format!("\n## {}\n\n{}\n", title, demoted.trim_end()));
+    }
+    crate::journal::write(dest, &merged)?;
+
+    for source in sources {
+        retarget_vault(source, dest, Some(&stem(source)))?;
+        if delete {
+            crate::journal::remove(source)?;
+        }
+    }
+    Ok(())
+}
+
+/// Splits `text` (the content of `path`) into one note per H1/H2 section,
+/// next to it, each linking back. Returns what is left for the original
+/// note: the text before the first heading plus links to the new notes.
+pub fn split_note(path: &Path, text: &str) -> io::Result<String> {
+    let fences = crate::markdown::fenced_blocks(text);
+    let mut sections: Vec<(String, String)> = Vec::new();
+    let mut intro = String::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let in_fence = fences.iter().any(|f| f.range.contains(&offset));
+        offset += line.len();
+        let heading = line
+            .strip_prefix("# ")
+            .or_else(|| line.strip_prefix("## "))
+            .filter(|_| !in_fence)
+            .map(str::trim);
+        match (heading, sections.last_mut()) {
+            (Some(heading), _) => sections.push((heading.to_owned(), String::new())),
+            (None, Some((_, body))) => body.push_str(line),
+            (None, None) => intro.push_str(line),
+        }
+    }
+    if sections.is_empty() {
+        return Err(io::Error::other("the note has no # or ## headings to split at"));
+    }
+
+    let dir = path.parent().unwrap_or(Path::new(vault::NOTES_DIR));
+    let original = stem(path);
+    let mut index = intro.trim_end().to_owned();
+    if !index.is_empty() {
+        index.push_str("\n\n");
+    }
+    for (heading, body) in &sections {
+        let section = vault::unique_path(dir, &vault::safe_file_name(heading), "md");
+        let content = format!("# {}\n\n← [[{}]]\n\n{}\n", heading, original, body.trim());
+        crate::journal::write(&section, &content)?;
+        index.push_str(&format!("- [[{}]]\n", stem(&section)));
+    }
+    Ok(index)
+}
+
+/// Picks the notes to merge and the name of the result.
+#[derive(Default)]
+pub struct MergeNotesView {
+    pub open: bool,
+    notes: Vec<PathBuf>,
+    selected: BTreeSet<PathBuf>,
+    filter: String,
+    name: String,
+    keep_originals: bool,
+}
+
+impl MergeNotesView {
+    pub fn open(&mut self) {
+        self.open = true;
+        self.notes = vault::note_files();
+        self.selected.clear();
+    }
+
+    /// Returns the merged note once created.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<PathBuf> {
+        if !self.open {
+            return None;
+        }
+        let mut created = None;
+        egui::Window::new(t("🧷 Merge Notes")).open(&mut self.open).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(t("Filter"));
+                ui.text_edit_singleline(&mut self.filter);
+            });
+            let filter = self.filter.to_lowercase();
+            egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                for note in self.notes.iter().filter(|n| n.to_string_lossy().to_lowercase().contains(&filter)) {
+                    let mut checked = self.selected.contains(note);
+                    if ui.checkbox(&mut checked, note.display().to_string()).changed() {
+                        if checked {
+                            self.selected.insert(note.clone());
+                        } else {
+                            self.selected.remove(note);
+                        }
+                    }
+                }
+            });
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label(t("Merged note"));
+                ui.text_edit_singleline(&mut self.name);
+            });
+            ui.checkbox(&mut self.keep_originals, t("Keep the original notes"));
+            let ready = self.selected.len() >= 2 && !self.name.trim().is_empty();
+            if ui.add_enabled(ready, egui::Button::new(t("Merge selected notes"))).clicked() {
+                let dest = vault::unique_path(Path::new(vault::NOTES_DIR), &vault::safe_file_name(&self.name), "md");
+                let sources: Vec<PathBuf> = self.selected.iter().cloned().collect();
+                match merge_notes(&sources, &dest, !self.keep_originals) {
+                    Ok(()) => created = Some(dest),
+                    Err(err) => error!("Failed to merge notes: {}", err),
+                }
+            }
+        });
+        if created.is_some() {
+            self.open = false;
+        }
+        created
+    }
+}
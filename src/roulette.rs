@@ -0,0 +1,149 @@
+use crate::config::ReviewConfig;
+use crate::i18n::{t, tf};
+use crate::sidebar::{NoteInfo, title};
+use crate::vault;
+use chrono::{Datelike, Local, NaiveDate};
+use eframe::egui::{self, RichText};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Small xorshift generator; picking notes needs no better randomness.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // Zero would stay zero forever.
+        Self(seed.max(1))
+    }
+
+    fn from_time() -> Self {
+        Self::new(SystemTime::now().duration_since(UNIX_EPOCH).map_or(1, |d| d.as_nanos() as u64))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+/// Whether a note matches a filter: `#tag` (with its subtags), a folder
+/// like `journal/`, or nothing for every note.
+fn matches(note: &NoteInfo, filter: &str) -> bool {
+    let filter = filter.trim().to_lowercase();
+    if filter.is_empty() {
+        return true;
+    }
+    match filter.strip_prefix('#') {
+        Some(tag) => note.tags.iter().any(|t| {
+            let t = t.to_lowercase();
+            t == tag || t.starts_with(&format!("{}/", tag))
+        }),
+        None => vault::note_key(&note.path).to_lowercase().starts_with(filter.trim_start_matches('/')),
+    }
+}
+
+/// A random note outside the archive matching `filter`.
+pub fn random_note(notes: &[NoteInfo], filter: &str) -> Option<PathBuf> {
+    let pool: Vec<&NoteInfo> = notes.iter().filter(|n| !vault::is_archived(&n.path) && matches(n, filter)).collect();
+    if pool.is_empty() {
+        return None;
+    }
+    let index = (Rng::from_time().next() % pool.len() as u64) as usize;
+    Some(pool[index].path.clone())
+}
+
+/// The day's notes to look at again: stale notes first, topped up with
+/// others, in an order that stays the same all day.
+pub fn review_queue(notes: &[NoteInfo], config: &ReviewConfig, date: NaiveDate) -> Vec<PathBuf> {
+    let stale_before = SystemTime::now() - Duration::from_secs(config.stale_days * 86_400);
+    let (mut stale, mut rest): (Vec<&NoteInfo>, Vec<&NoteInfo>) = notes
+        .iter()
+        .filter(|n| !vault::is_archived(&n.path))
+        .partition(|n| n.modified.is_some_and(|m| m < stale_before));
+    let mut rng = Rng::new(date.num_days_from_ce() as u64);
+    rng.shuffle(&mut stale);
+    rng.shuffle(&mut rest);
+    stale.into_iter().chain(rest).take(config.queue_size).map(|n| n.path.clone()).collect()
+}
+
+/// Days since a note last changed, for the queue's labels.
+pub fn age_days(note: &NoteInfo) -> Option<u64> {
+    note.modified?.elapsed().ok().map(|age| age.as_secs() / 86_400)
+}
+
+/// Lists the day's review queue; returns a note the user clicked.
+pub fn queue_list(ui: &mut egui::Ui, notes: &[NoteInfo], config: &ReviewConfig) -> Option<PathBuf> {
+    let mut clicked = None;
+    let queue = review_queue(notes, config, Local::now().date_naive());
+    if queue.is_empty() {
+        ui.label(RichText::new(t("Nothing to review")).weak());
+    }
+    for path in queue {
+        let age = notes.iter().find(|n| n.path == path).and_then(age_days);
+        ui.horizontal(|ui| {
+            if ui.link(title(&path)).on_hover_text(vault::note_key(&path)).clicked() {
+                clicked = Some(path.clone());
+            }
+            if let Some(age) = age {
+                ui.label(RichText::new(tf("{} days old", &[&age])).small().weak());
+            }
+        });
+    }
+    clicked
+}
+
+/// Opens a random note, optionally from one tag or folder.
+#[derive(Default)]
+pub struct RandomNote {
+    pub open: bool,
+    filter: String,
+    /// Shown when nothing matched the filter.
+    empty: bool,
+}
+
+impl RandomNote {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.empty = false;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, notes: &[NoteInfo], config: &ReviewConfig) -> Option<PathBuf> {
+        if !self.open {
+            return None;
+        }
+        let mut clicked = None;
+        let mut open = true;
+
+        egui::Window::new(t("🎲 Random Note")).open(&mut open).default_width(320.0).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.filter).hint_text(t("#tag or folder/")));
+                if ui.button(t("🎲 Open")).clicked() {
+                    clicked = random_note(notes, &self.filter);
+                    self.empty = clicked.is_none();
+                }
+            });
+            if self.empty {
+                ui.label(RichText::new(t("No notes match")).weak());
+            }
+            ui.separator();
+            ui.label(RichText::new(t("Today's review")).strong());
+            if let Some(path) = queue_list(ui, notes, config) {
+                clicked = Some(path);
+            }
+        });
+
+        if !open {
+            self.open = false;
+        }
+        clicked
+    }
+}
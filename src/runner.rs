@@ -0,0 +1,136 @@
+use crate::config::CodeRunner;
+use crate::jobs::Job;
+use crate::markdown::{self, CodeBlock};
+use serde::Deserialize;
+use std::io::{Read, Write};
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
+
+const PLAYGROUND_URL: &str = "https://play.rust-lang.org/execute";
+const OUTPUT_LANG: &str = "output";
+/// How long a block may run before it is killed.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize)]
+struct PlaygroundResponse {
+    stdout: String,
+    stderr: String,
+}
+
+/// Runs a code block with the runner configured for its language and
+/// returns combined stdout/stderr. The process is killed when the job is
+/// cancelled or it runs longer than [`TIMEOUT`].
+pub fn run(block: &CodeBlock, runners: &[CodeRunner], job: &Job) -> Result<String, String> {
+    let runner = runners
+        .iter()
+        .find(|r| r.language == block.lang)
+        .ok_or_else(|| format!("No runner configured for '{}'", block.lang))?;
+    if runner.command == "playground" {
+        return run_playground(&block.code);
+    }
+
+    let mut parts = runner.command.split_whitespace();
+    let program = parts.next().ok_or("Empty runner command")?;
+    let output = pipe(Command::new(program).args(parts), &block.code, TIMEOUT, job)?;
+    Ok(format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr)))
+}
+
+/// Runs `command` with `input` on stdin and collects what it prints. The
+/// process is killed when the job is cancelled or it runs longer than
+/// `timeout`.
+pub fn pipe(command: &mut Command, input: &str, timeout: Duration, job: &Job) -> Result<Output, String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("{}: {}", program, e))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = input.to_owned();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    // Read both pipes while it runs, so a chatty program cannot fill one and stall.
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break status;
+        }
+        let stopped = if job.cancelled() {
+            Some("Cancelled".to_owned())
+        } else if Instant::now() >= deadline {
+            Some(format!("Killed after {} seconds", timeout.as_secs()))
+        } else {
+            None
+        };
+        if let Some(reason) = stopped {
+            let _ = child.kill();
+            let _ = child.wait();
+            // Anything it started may still hold the pipes open, so the output is not waited for.
+            return Err(reason);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    let _ = writer.join();
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// Reads a pipe to the end on its own thread.
+fn drain(pipe: Option<impl Read + Send + 'static>) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        bytes
+    })
+}
+
+fn run_playground(code: &str) -> Result<String, String> {
+    let body = serde_json::json!({
+        "channel": "stable",
+        "mode": "debug",
+        "edition": "2021",
+        "crateType": "bin",
+        "tests": false,
+        "backtrace": false,
+        "code": code,
+    });
+    let resp: PlaygroundResponse = crate::http::send(crate::http::client().post(PLAYGROUND_URL).json(&body))
+        .and_then(|r| r.error_for_status())
+        .and_then(|r| r.json())
+        .map_err(|e| e.to_string())?;
+    Ok(format!("{}{}", resp.stdout, resp.stderr))
+}
+
+/// Writes `output` into an ```output block right after `block`, replacing
+/// the previous output block if there is one.
+pub fn insert_output(text: &mut String, block: &CodeBlock, output: &str) {
+    let mut body = output.to_owned();
+    if !body.ends_with('\n') {
+        body.push('\n');
+    }
+    // Longer than any run of backticks in the output, so none of it closes the block.
+    let longest = body.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    let rendered = format!("{}{}\n{}{}\n", fence, OUTPUT_LANG, body, fence);
+
+    let after = block.range.end;
+    let existing = markdown::fenced_blocks(text)
+        .into_iter()
+        .find(|b| b.lang == OUTPUT_LANG && b.range.start >= after && text[after..b.range.start].trim().is_empty());
+    match existing {
+        Some(previous) => text.replace_range(after..previous.range.end, &format!("\n{}", rendered)),
+        None => {
+            let separator = if text[..after].ends_with('\n') { "\n" } else { "\n\n" };
+            text.insert_str(after, &format!("{}{}", separator, rendered));
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::i18n::t;
+use crate::vault::SCRATCHPAD_FILE;
+use eframe::egui;
+use std::time::{Duration, Instant};
+use tracing::error;
+
+/// How long typing must pause before the scratchpad is written out.
+const SAVE_DELAY: Duration = Duration::from_secs(1);
+
+/// A small buffer for jotting things down without making a note, kept in
+/// one fixed file and saved as you type.
+#[derive(Default)]
+pub struct Scratchpad {
+    pub open: bool,
+    /// Read from disk the first time the scratchpad is shown.
+    text: Option<String>,
+    /// When the text last changed, while it has unsaved edits.
+    edited: Option<Instant>,
+    focus: bool,
+}
+
+impl Scratchpad {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.focus = self.open;
+        if !self.open {
+            self.save();
+        }
+    }
+
+    fn save(&mut self) {
+        let (Some(text), Some(_)) = (&self.text, self.edited.take()) else {
+            return;
+        };
+        if let Err(err) = std::fs::write(SCRATCHPAD_FILE, text) {
+            error!("Failed to save scratchpad: {}", err);
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if !self.open {
+            return;
+        }
+        let text = self.text.get_or_insert_with(|| std::fs::read_to_string(SCRATCHPAD_FILE).unwrap_or_default());
+        let mut open = true;
+        let mut changed = false;
+
+        egui::Window::new(t("📝 Scratchpad"))
+            .open(&mut open)
+            .default_size([360.0, 260.0])
+            .show(ctx, |ui| {
+                let edit = ui.add_sized(ui.available_size(), egui::TextEdit::multiline(text).hint_text(t("Jot something down…")));
+                if std::mem::take(&mut self.focus) {
+                    edit.request_focus();
+                }
+                changed = edit.changed();
+            });
+
+        if changed {
+            self.edited = Some(Instant::now());
+        }
+        let closing = ctx.input(|i| i.viewport().close_requested());
+        match self.edited {
+            Some(at) if at.elapsed() >= SAVE_DELAY || closing => self.save(),
+            Some(at) => ctx.request_repaint_after(SAVE_DELAY - at.elapsed()),
+            None => {}
+        }
+        if !open {
+            self.open = false;
+            self.save();
+        }
+    }
+}
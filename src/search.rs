@@ -0,0 +1,386 @@
+use crate::config::SidebarConfig;
+use crate::i18n::{t, tf};
+use crate::jobs::{self, Job, JobHandle};
+use crate::sidebar::{NoteInfo, NoteList, title};
+use crate::query::{self, Doc, Expr};
+use crate::{frontmatter, journal, vault};
+use chrono::NaiveDate;
+use eframe::egui::text::{LayoutJob, TextFormat};
+use eframe::egui::{self, RichText};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::mpsc::TryRecvError;
+use tracing::{error, info};
+#[cfg(target_os = "android")]
+use crate::stubs::rfd;
+
+/// What to look for: the text in the query syntax (see `query::parse`),
+/// every tag, and a modification date range. Empty parts match everything.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Query {
+    pub text: String,
+    pub tags: Vec<String>,
+    /// Inclusive `YYYY-MM-DD` bounds on the modification date.
+    pub from: String,
+    pub to: String,
+    pub include_archived: bool,
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok()
+}
+
+impl Query {
+    pub fn is_empty(&self) -> bool {
+        self.text.trim().is_empty() && self.tags.is_empty() && self.from.trim().is_empty() && self.to.trim().is_empty()
+    }
+
+    fn matches(&self, expr: Option<&Expr>, note: &NoteInfo) -> bool {
+        if !self.include_archived && vault::is_archived(&note.path) {
+            return false;
+        }
+        let modified = note.modified.map(|m| chrono::DateTime::<chrono::Local>::from(m).date_naive());
+        if let Some(from) = parse_date(&self.from)
+            && modified.is_none_or(|m| m < from)
+        {
+            return false;
+        }
+        if let Some(to) = parse_date(&self.to)
+            && modified.is_none_or(|m| m > to)
+        {
+            return false;
+        }
+        if !self.tags.iter().all(|tag| note.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))) {
+            return false;
+        }
+        expr.is_none_or(|expr| query::eval(expr, &Doc::new(note)))
+    }
+
+    /// Matching notes, best first, or why the text could not be parsed.
+    pub fn ranked(&self, notes: &[NoteInfo]) -> Result<Vec<Hit>, String> {
+        let expr = query::parse(&self.text)?;
+        let terms = expr.as_ref().map(query::wanted_text).unwrap_or_default();
+        let mut hits: Vec<Hit> = notes
+            .iter()
+            .filter(|n| self.matches(expr.as_ref(), n))
+            .map(|note| Hit::new(note, &terms))
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(hits)
+    }
+
+    /// Matching notes, or why the text could not be parsed.
+    pub fn run(&self, notes: &[NoteInfo]) -> Result<Vec<PathBuf>, String> {
+        let expr = query::parse(&self.text)?;
+        Ok(notes.iter().filter(|n| self.matches(expr.as_ref(), n)).map(|n| n.path.clone()).collect())
+    }
+}
+
+/// Most occurrences of one term counted towards a note's score, so one
+/// long note repeating a word does not bury everything else.
+const MAX_COUNT: usize = 20;
+/// Longest excerpt shown under a result, in characters.
+const EXCERPT_LEN: usize = 100;
+
+/// A search result with its relevance and the line it matched on.
+pub struct Hit {
+    pub path: PathBuf,
+    pub score: f32,
+    pub excerpt: String,
+}
+
+impl Hit {
+    /// Scores by how often the terms occur, strongly favoring title
+    /// matches, with a boost for recently modified notes.
+    fn new(note: &NoteInfo, terms: &[String]) -> Self {
+        let title = title(&note.path).to_lowercase();
+        let text = note.text.to_lowercase();
+        let mut score = 0.0;
+        for term in terms {
+            score += text.matches(term.as_str()).take(MAX_COUNT).count() as f32;
+            if title.contains(term.as_str()) {
+                score += 50.0;
+            }
+        }
+        let age_days = note
+            .modified
+            .and_then(|m| m.elapsed().ok())
+            .map_or(f32::INFINITY, |age| age.as_secs_f32() / 86_400.0);
+        score += 10.0 / (1.0 + age_days / 7.0);
+
+        let matches = crate::text::find_terms(&note.text, terms);
+        let excerpt = match matches.first() {
+            Some(first) => {
+                let line_start = note.text[..first.start].rfind('\n').map_or(0, |i| i + 1);
+                let line_end = note.text[first.start..].find('\n').map_or(note.text.len(), |i| first.start + i);
+                let before = note.text[line_start..first.start].chars().count();
+                // Keep the match in view when the line is long.
+                let skip = before.saturating_sub(EXCERPT_LEN / 3);
+                let line: String = note.text[line_start..line_end].chars().skip(skip).take(EXCERPT_LEN).collect();
+                if skip > 0 { format!("…{}", line.trim()) } else { line.trim().to_owned() }
+            }
+            None => note.snippet.clone(),
+        };
+        Self {
+            path: note.path.clone(),
+            score,
+            excerpt,
+        }
+    }
+}
+
+/// `text` with the terms picked out, for result rows.
+fn highlighted(ui: &egui::Ui, job: &mut LayoutJob, text: &str, terms: &[String], style: egui::TextStyle, color: egui::Color32) {
+    let font = style.resolve(ui.style());
+    let normal = TextFormat::simple(font.clone(), color);
+    let marked = TextFormat {
+        background: egui::Color32::from_rgba_unmultiplied(255, 200, 0, 80),
+        ..TextFormat::simple(font, ui.visuals().strong_text_color())
+    };
+    let mut at = 0;
+    for range in crate::text::find_terms(text, terms) {
+        job.append(&text[at..range.start], 0.0, normal.clone());
+        job.append(&text[range.clone()], 0.0, marked.clone());
+        at = range.end;
+    }
+    job.append(&text[at..], 0.0, normal);
+}
+
+/// A named search listed in the sidebar, re-run as notes change.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct SmartFolder {
+    pub name: String,
+    pub query: Query,
+}
+
+/// Replaces `find` with `replace` in each note, reading it afresh from
+/// disk. Locked notes are left alone. Returns how many notes changed;
+/// notes changed before a cancel stay changed.
+fn replace_in_notes(notes: &[PathBuf], find: &str, replace: &str, job: &Job) -> usize {
+    let mut changed = 0;
+    for (index, path) in notes.iter().enumerate() {
+        if job.cancelled() {
+            break;
+        }
+        job.progress(index, notes.len());
+        job.status(title(path));
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) => {
+                error!("Failed to read {}: {}", path.display(), err);
+                continue;
+            }
+        };
+        if !text.contains(find) || frontmatter::is_locked(&text) {
+            continue;
+        }
+        match journal::write(path, &text.replace(find, replace)) {
+            Ok(()) => changed += 1,
+            Err(err) => error!("Failed to write {}: {}", path.display(), err),
+        }
+    }
+    changed
+}
+
+pub enum SearchEvent {
+    Open(PathBuf),
+    /// Notes were changed by Replace.
+    Replaced,
+}
+
+/// Global search window, where a search can be saved as a smart folder
+/// and text replaced across the results.
+#[derive(Default)]
+pub struct SearchView {
+    pub open: bool,
+    query: Query,
+    tags: String,
+    folder_name: String,
+    /// Focus the text field on the next frame.
+    focus: bool,
+    /// Results with the note list generation and query they are for.
+    results: Option<(u64, Query, Result<Vec<Hit>, String>)>,
+    find: String,
+    replace: String,
+    replacing: Option<JobHandle<usize>>,
+}
+
+impl SearchView {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.focus = self.open;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, list: &mut NoteList, sidebar: &mut SidebarConfig) -> Option<SearchEvent> {
+        if let Some(replacing) = &self.replacing {
+            match replacing.try_recv() {
+                Ok(changed) => {
+                    info!("Replaced text in {} notes", changed);
+                    self.replacing = None;
+                    list.invalidate();
+                    return Some(SearchEvent::Replaced);
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => {
+                    self.replacing = None;
+                    list.invalidate();
+                    return Some(SearchEvent::Replaced);
+                }
+            }
+        }
+        if !self.open {
+            return None;
+        }
+        let mut clicked = None;
+        let mut replace = None;
+        let mut open = true;
+        self.query.tags = crate::tags::parse_list(&self.tags);
+        let stale = self
+            .results
+            .as_ref()
+            .is_none_or(|(generation, query, _)| *generation != list.generation || *query != self.query);
+        if stale {
+            let results = self.query.ranked(list.notes());
+            self.results = Some((list.generation, self.query.clone(), results));
+        }
+
+        egui::Window::new(t("🔎 Search")).open(&mut open).default_width(420.0).show(ctx, |ui| {
+            egui::Grid::new("search_query").num_columns(2).show(ui, |ui| {
+                ui.label(t("Text"));
+                let text = ui.text_edit_singleline(&mut self.query.text);
+                if std::mem::take(&mut self.focus) {
+                    text.request_focus();
+                }
+                ui.end_row();
+                ui.label(t("Tags"));
+                ui.add(egui::TextEdit::singleline(&mut self.tags).hint_text(t("work, ideas")));
+                ui.end_row();
+                ui.label(t("Modified"));
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.query.from).hint_text("YYYY-MM-DD").desired_width(90.0));
+                    ui.label(t("to"));
+                    ui.add(egui::TextEdit::singleline(&mut self.query.to).hint_text("YYYY-MM-DD").desired_width(90.0));
+                });
+                ui.end_row();
+            });
+            ui.checkbox(&mut self.query.include_archived, t("Include archived notes"));
+            ui.separator();
+
+            let terms = self.terms();
+            let results = match self.results.as_ref().map(|(_, _, r)| r) {
+                Some(Ok(results)) => results.as_slice(),
+                Some(Err(err)) => {
+                    ui.colored_label(egui::Color32::LIGHT_RED, err);
+                    &[]
+                }
+                None => &[],
+            };
+            if self.query.is_empty() {
+                ui.label(RichText::new(t("Type to search the vault")).weak());
+                ui.label(RichText::new(t("Try \"exact phrase\", tag:#work, path:journal/, created:>2024-01-01, prop:status=done, OR, NOT")).small().weak());
+            } else {
+                ui.label(tf("{} notes", &[&results.len()]));
+                egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                    for hit in results {
+                        let mut job = LayoutJob::default();
+                        highlighted(ui, &mut job, &title(&hit.path), &terms, egui::TextStyle::Body, ui.visuals().text_color());
+                        if !hit.excerpt.is_empty() {
+                            job.append("\n", 0.0, TextFormat::default());
+                            highlighted(ui, &mut job, &hit.excerpt, &terms, egui::TextStyle::Small, ui.visuals().weak_text_color());
+                        }
+                        if ui.selectable_label(false, job).on_hover_text(vault::note_key(&hit.path)).clicked() {
+                            clicked = Some(hit.path.clone());
+                        }
+                    }
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.find).hint_text(t("Find")).desired_width(120.0));
+                    ui.add(egui::TextEdit::singleline(&mut self.replace).hint_text(t("Replace with")).desired_width(120.0));
+                    let enabled = !self.find.is_empty() && !results.is_empty() && self.replacing.is_none();
+                    let button = ui.add_enabled(enabled, egui::Button::new(t("Replace in results")));
+                    if button.on_hover_text(t("Exact, case-sensitive text; locked notes are skipped")).clicked() {
+                        replace = Some(results.iter().map(|h| h.path.clone()).collect::<Vec<_>>());
+                    }
+                });
+                if let Some(replacing) = &self.replacing {
+                    replacing.show_progress(ui);
+                }
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.folder_name).hint_text(t("Smart folder name")));
+                let name = self.folder_name.trim().to_owned();
+                let valid = !name.is_empty() && !self.query.is_empty();
+                if ui.add_enabled(valid, egui::Button::new(t("💾 Save as smart folder"))).clicked() {
+                    let folder = SmartFolder {
+                        name: name.clone(),
+                        query: self.query.clone(),
+                    };
+                    match sidebar.smart_folders.iter_mut().find(|f| f.name == name) {
+                        Some(existing) => *existing = folder,
+                        None => sidebar.smart_folders.push(folder),
+                    }
+                    self.folder_name.clear();
+                }
+            });
+        });
+
+        if let Some(notes) = replace {
+            self.start_replace(list, notes);
+        }
+        if !open {
+            self.open = false;
+        }
+        clicked.map(SearchEvent::Open)
+    }
+
+    /// Asks before replacing, with the number of occurrences from the
+    /// indexed text, then replaces in the background.
+    fn start_replace(&mut self, list: &mut NoteList, notes: Vec<PathBuf>) {
+        let count: usize = list
+            .notes()
+            .iter()
+            .filter(|n| notes.contains(&n.path))
+            .map(|n| n.text.matches(self.find.as_str()).count())
+            .sum();
+        if count == 0 {
+            info!("No \"{}\" in the results to replace", self.find);
+            return;
+        }
+        let confirmed = rfd::MessageDialog::new()
+            .set_title(t("Replace in notes"))
+            .set_description(tf("Replace {} occurrences of \"{}\" in {} notes?", &[&count, &self.find, &notes.len()]))
+            .set_buttons(rfd::MessageButtons::YesNo)
+            .show();
+        if confirmed != rfd::MessageDialogResult::Yes {
+            return;
+        }
+        let (find, replace) = (self.find.clone(), self.replace.clone());
+        self.replacing = Some(jobs::spawn("Replacing across notes", move |job| replace_in_notes(&notes, &find, &replace, job)));
+    }
+
+    /// Lowercase text the current query looks for, to highlight.
+    pub fn terms(&self) -> Vec<String> {
+        query::parse(&self.query.text).ok().flatten().map(|e| query::wanted_text(&e)).unwrap_or_default()
+    }
+
+    /// Opens the window searching for `text` in the query syntax.
+    pub fn search_for(&mut self, text: String) {
+        self.open = true;
+        self.query = Query { text, ..Default::default() };
+        self.tags.clear();
+        self.focus = true;
+    }
+
+    /// Opens the window with a smart folder's query, to edit it.
+    pub fn edit(&mut self, folder: &SmartFolder) {
+        self.open = true;
+        self.query = folder.query.clone();
+        self.tags = folder.query.tags.join(", ");
+        self.folder_name = folder.name.clone();
+        self.focus = true;
+    }
+}
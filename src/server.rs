@@ -0,0 +1,118 @@
+use crate::config::ServerConfig;
+use crate::grammar;
+use crate::links;
+use crate::vault::{self, NOTES_DIR};
+use std::path::{Component, Path, PathBuf};
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::error;
+
+/// A random hex token for authenticating API clients, from the OS's secure
+/// random source.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).expect("the OS random source is unavailable");
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Compares without stopping at the first difference, so the response time
+/// does not give away how much of a token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Starts the local HTTP API on a background thread.
+///
+/// Endpoints (all require `Authorization: Bearer <token>`):
+/// - `GET /notes` lists note paths relative to the vault
+/// - `GET /notes/<path>` returns a note's content
+/// - `PUT /notes/<path>` replaces a note's content with the request body
+/// - `POST /check` runs a grammar check on the request body
+pub fn start(config: &ServerConfig) -> std::io::Result<()> {
+    let server = Server::http(("127.0.0.1", config.port)).map_err(std::io::Error::other)?;
+    let token = config.token.clone();
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            if let Err(err) = handle(request, &token) {
+                error!("API request failed: {}", err);
+            }
+        }
+    });
+    Ok(())
+}
+
+fn handle(mut request: Request, token: &str) -> std::io::Result<()> {
+    let expected = format!("Bearer {}", token);
+    let authorized = request
+        .headers()
+        .iter()
+        .any(|h| h.field.equiv("Authorization") && constant_time_eq(h.value.as_str().as_bytes(), expected.as_bytes()));
+    if !authorized {
+        return request.respond(text(401, "missing or invalid token"));
+    }
+
+    let url = request.url().split('?').next().unwrap_or_default().to_owned();
+    let language = request
+        .url()
+        .split_once('?')
+        .and_then(|(_, query)| query.split('&').find_map(|p| p.strip_prefix("language=")))
+        .map_or_else(|| grammar::DEFAULT_LANGUAGE.to_owned(), links::percent_decode);
+    let method = request.method().clone();
+    let mut body = String::new();
+    if matches!(method, Method::Put | Method::Post) {
+        request.as_reader().read_to_string(&mut body)?;
+    }
+
+    let response = match (method, url.as_str()) {
+        (Method::Get, "/notes") => json(200, &list_notes()),
+        (Method::Post, "/check") => match grammar::check(&body, &language) {
+            Ok(matches) => json(200, &matches),
+            Err(err @ grammar::CheckError::CoolingDown(_)) => text(429, &err.to_string()),
+            Err(err) => text(502, &err.to_string()),
+        },
+        (method, url) => match url.strip_prefix("/notes/").and_then(|p| note_path(&links::percent_decode(p))) {
+            None => text(404, "not found"),
+            Some(path) => match method {
+                Method::Get => match std::fs::read_to_string(&path) {
+                    Ok(content) => text(200, &content),
+                    Err(_) => text(404, "not found"),
+                },
+                Method::Put => match crate::journal::write(&path, &body) {
+                    Ok(()) => text(204, ""),
+                    Err(err) => text(500, &err.to_string()),
+                },
+                _ => text(405, "method not allowed"),
+            },
+        },
+    };
+    request.respond(response)
+}
+
+fn list_notes() -> Vec<String> {
+    vault::walk_files(Path::new(NOTES_DIR))
+        .iter()
+        .filter_map(|p| p.strip_prefix(NOTES_DIR).ok())
+        .filter(|p| !p.to_string_lossy().starts_with('.'))
+        .map(|p| p.to_string_lossy().replace('\\', "/"))
+        .collect()
+}
+
+/// Maps a URL path to a file inside the vault, refusing anything that
+/// would escape it.
+pub fn note_path(relative: &str) -> Option<PathBuf> {
+    let relative = Path::new(relative);
+    // Hidden entries (`.journal`, `.nextcloud.json`, …) are the app's own.
+    let safe = relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(name) if !name.to_string_lossy().starts_with('.')));
+    (safe && !relative.as_os_str().is_empty()).then(|| Path::new(NOTES_DIR).join(relative))
+}
+
+fn text(status: u16, body: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string(body).with_status_code(status)
+}
+
+fn json<T: serde::Serialize>(status: u16, value: &T) -> Response<std::io::Cursor<Vec<u8>>> {
+    let body = serde_json::to_string(value).unwrap_or_default();
+    let header = Header::from_bytes("Content-Type", "application/json").expect("static header");
+    Response::from_string(body).with_status_code(status).with_header(header)
+}
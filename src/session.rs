@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Notes whose place is remembered, so the list does not grow without end.
+const MAX_POSITIONS: usize = 200;
+
+/// Where the cursor and the view were in a note, and what was folded.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Position {
+    /// Byte offset of the cursor.
+    pub cursor: usize,
+    /// How far the editor was scrolled down, in points.
+    pub scroll: f32,
+    /// Keys of the folded sections.
+    pub folds: Vec<String>,
+}
+
+/// The place in each recently open note, most recent first, by note key.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Positions(Vec<(String, Position)>);
+
+impl Positions {
+    pub fn get(&self, key: &str) -> Option<Position> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, p)| p.clone())
+    }
+
+    pub fn remember(&mut self, key: String, position: Position) {
+        self.0.retain(|(k, _)| *k != key);
+        self.0.insert(0, (key, position));
+        self.0.truncate(MAX_POSITIONS);
+    }
+}
+
+/// What the window looked like when the app was last closed, kept in
+/// eframe's storage. Panel widths are part of egui's own memory, which
+/// eframe stores next to it.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct Session {
+    /// The note that was open, reopened on start.
+    pub open_note: Option<PathBuf>,
+    pub show_sidebar: bool,
+    pub show_preview: bool,
+    pub show_outline: bool,
+    pub show_minimap: bool,
+    /// The editor showing the note's lists as an outline.
+    pub show_outliner: bool,
+    pub live_preview: bool,
+    /// Front matter shown as fields above the note instead of as YAML.
+    pub show_properties: bool,
+    pub show_suggestions: bool,
+    /// Suggestions in a side panel rather than a window.
+    pub dock_suggestions: bool,
+    pub show_assistant: bool,
+    pub show_comments: bool,
+    pub show_info: bool,
+    pub grammar_language: String,
+    pub positions: Positions,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            open_note: None,
+            show_sidebar: true,
+            show_preview: false,
+            show_outline: false,
+            show_minimap: false,
+            show_outliner: false,
+            live_preview: false,
+            show_properties: true,
+            show_suggestions: true,
+            dock_suggestions: false,
+            show_assistant: false,
+            show_comments: false,
+            show_info: false,
+            grammar_language: crate::grammar::DEFAULT_LANGUAGE.to_owned(),
+            positions: Positions::default(),
+        }
+    }
+}
+
+impl Session {
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        storage.and_then(|s| eframe::get_value(s, eframe::APP_KEY)).unwrap_or_default()
+    }
+
+    pub fn store(&self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, self);
+    }
+}
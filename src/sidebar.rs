@@ -0,0 +1,322 @@
+use crate::config::{NoteSort, SidebarConfig};
+use crate::i18n::t;
+use crate::jobs::{self, JobHandle};
+use crate::metadata::Metadata;
+use crate::search::SmartFolder;
+use crate::vault;
+use eframe::egui::{self, RichText};
+use eframe::egui::text::{LayoutJob, TextFormat};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc::TryRecvError;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often the list is re-read to pick up notes changed outside the app.
+const REFRESH: Duration = Duration::from_secs(5);
+/// Longest preview shown under a note's title, in characters.
+const SNIPPET_LEN: usize = 120;
+
+pub fn title(path: &Path) -> String {
+    path.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_owned()
+}
+
+/// A note with the file details the list sorts by and the text search
+/// runs against.
+#[derive(Clone)]
+pub struct NoteInfo {
+    pub path: PathBuf,
+    /// Not every file system records creation times.
+    pub created: Option<SystemTime>,
+    pub modified: Option<SystemTime>,
+    pub size: u64,
+    /// The start of the note's text, for the list preview.
+    pub snippet: String,
+    pub text: Arc<str>,
+    pub tags: Vec<String>,
+}
+
+impl NoteInfo {
+    /// Reads a note's details, reusing the text from `cached` when the
+    /// file has not changed since.
+    fn read(path: PathBuf, cached: Option<&NoteInfo>) -> Self {
+        let meta = path.metadata().ok();
+        let modified = meta.as_ref().and_then(|m| m.modified().ok());
+        let (text, snippet, tags) = match cached {
+            Some(cached) if cached.modified == modified => (cached.text.clone(), cached.snippet.clone(), cached.tags.clone()),
+            _ => {
+                let text = std::fs::read_to_string(&path).unwrap_or_default();
+                (Arc::from(text.as_str()), snippet(&text), crate::tags::tags(&text))
+            }
+        };
+        Self {
+            created: meta.as_ref().and_then(|m| m.created().ok()),
+            modified,
+            size: meta.map_or(0, |m| m.len()),
+            snippet,
+            text,
+            tags,
+            path,
+        }
+    }
+}
+
+/// The first lines of body text, skipping front matter, headings and
+/// code fences, with Markdown punctuation dropped.
+fn snippet(text: &str) -> String {
+    let start = crate::frontmatter::range(text).map_or(0, |r| r.end);
+    let mut out = String::new();
+    for line in text[start..].lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with("```") || line.starts_with("~~~") {
+            continue;
+        }
+        let line = line.trim_start_matches(['-', '*', '>', '+', ' ']);
+        let line: String = line.chars().filter(|c| !matches!(c, '*' | '_' | '`' | '[' | ']')).collect();
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(&line);
+        if out.chars().count() >= SNIPPET_LEN {
+            break;
+        }
+    }
+    if out.chars().count() > SNIPPET_LEN {
+        out = out.chars().take(SNIPPET_LEN).collect::<String>() + "…";
+    }
+    out
+}
+
+fn sort(notes: &mut [NoteInfo], config: &SidebarConfig) {
+    match config.sort {
+        NoteSort::Title => notes.sort_by_cached_key(|n| title(&n.path).to_lowercase()),
+        NoteSort::Created => notes.sort_by_key(|n| n.created.or(n.modified)),
+        NoteSort::Modified => notes.sort_by_key(|n| n.modified),
+        NoteSort::Size => notes.sort_by_key(|n| n.size),
+    }
+    if config.descending {
+        notes.reverse();
+    }
+}
+
+/// The vault's notes, re-read now and then rather than every frame.
+#[derive(Default)]
+pub struct NoteList {
+    notes: Vec<NoteInfo>,
+    refreshed: Option<Instant>,
+    /// Bumped on every re-read, so results computed from the list can be
+    /// cached until it changes.
+    pub generation: u64,
+    filter: String,
+    /// Smart folder results by folder name, with the generation and query
+    /// they were computed for.
+    folders: HashMap<String, (u64, crate::search::Query, Vec<PathBuf>)>,
+    indexing: Option<JobHandle<Option<Vec<NoteInfo>>>>,
+}
+
+impl NoteList {
+    /// Forces a re-read on the next use, e.g. after creating a note.
+    pub fn invalidate(&mut self) {
+        self.refreshed = None;
+    }
+
+    /// The notes as last read. Re-reading runs as a background job, so
+    /// this returns the previous list until it is done.
+    pub fn notes(&mut self) -> &[NoteInfo] {
+        if let Some(job) = &self.indexing {
+            match job.try_recv() {
+                Ok(Some(notes)) => {
+                    self.notes = notes;
+                    self.generation += 1;
+                    self.indexing = None;
+                }
+                Ok(None) | Err(TryRecvError::Disconnected) => self.indexing = None,
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+        if self.indexing.is_none() && self.refreshed.is_none_or(|at| at.elapsed() > REFRESH) {
+            let cache: HashMap<PathBuf, NoteInfo> = self.notes.iter().map(|n| (n.path.clone(), n.clone())).collect();
+            self.indexing = Some(jobs::spawn("Indexing notes", move |job| {
+                let files = vault::note_files();
+                let mut notes = Vec::with_capacity(files.len());
+                for (i, path) in files.iter().enumerate() {
+                    if job.cancelled() {
+                        return None;
+                    }
+                    job.progress(i, files.len());
+                    job.status(title(path));
+                    notes.push(NoteInfo::read(path.clone(), cache.get(path)));
+                }
+                Some(notes)
+            }));
+            self.refreshed = Some(Instant::now());
+        }
+        &self.notes
+    }
+}
+
+fn note_row(ui: &mut egui::Ui, metadata: &mut Metadata, note: &NoteInfo, current: Option<&Path>, clicked: &mut Option<PathBuf>) {
+    let meta = metadata.get(&note.path);
+    let mut label = title(&note.path);
+    if meta.favorite {
+        label = format!("⭐ {}", label);
+    }
+    let visuals = ui.visuals();
+    let small = egui::TextStyle::Small.resolve(ui.style());
+    let mut job = LayoutJob::default();
+    job.append(&label, 0.0, TextFormat::simple(egui::TextStyle::Body.resolve(ui.style()), visuals.strong_text_color()));
+    if !note.snippet.is_empty() {
+        job.append(&format!("\n{}", note.snippet), 0.0, TextFormat::simple(small.clone(), visuals.text_color()));
+    }
+    if let Some(modified) = note.modified {
+        let date = chrono::DateTime::<chrono::Local>::from(modified).format("%Y-%m-%d");
+        job.append(&format!("\n{}", date), 0.0, TextFormat::simple(small, visuals.weak_text_color()));
+    }
+    job.wrap = egui::text::TextWrapping {
+        max_rows: 4,
+        break_anywhere: false,
+        overflow_character: Some('…'),
+        max_width: ui.available_width(),
+    };
+
+    let is_current = current.is_some_and(|c| vault::note_key(c) == vault::note_key(&note.path));
+    let response = ui.selectable_label(is_current, job).on_hover_text(vault::note_key(&note.path));
+    if response.clicked() {
+        *clicked = Some(note.path.clone());
+    }
+    let note = &note.path;
+    response.context_menu(|ui| {
+        if ui.button(if meta.pinned { "Unpin" } else { "📌 Pin to top" }).clicked() {
+            metadata.update(note, |m| m.pinned = !m.pinned);
+            ui.close_menu();
+        }
+        if ui.button(if meta.favorite { "Remove from favorites" } else { "⭐ Add to favorites" }).clicked() {
+            metadata.update(note, |m| m.favorite = !m.favorite);
+            ui.close_menu();
+        }
+    });
+}
+
+/// What the app should do after the user acted in the sidebar.
+pub enum SidebarEvent {
+    Open(PathBuf),
+    EditFolder(SmartFolder),
+}
+
+/// Each smart folder's notes, recomputed when the list has been re-read.
+fn folder_notes(list: &mut NoteList, folders: &[SmartFolder]) -> Vec<Vec<NoteInfo>> {
+    let generation = list.generation;
+    let all = list.notes().to_vec();
+    folders
+        .iter()
+        .map(|folder| {
+            let cached = list.folders.get(&folder.name).filter(|(g, q, _)| *g == generation && *q == folder.query);
+            let paths = match cached {
+                Some((_, _, paths)) => paths.clone(),
+                None => {
+                    let paths = folder.query.run(&all).unwrap_or_default();
+                    list.folders.insert(folder.name.clone(), (generation, folder.query.clone(), paths.clone()));
+                    paths
+                }
+            };
+            all.iter().filter(|n| paths.contains(&n.path)).cloned().collect()
+        })
+        .collect()
+}
+
+/// The note list on the left, leaving out archived notes.
+pub fn show(
+    ctx: &egui::Context,
+    list: &mut NoteList,
+    config: &mut SidebarConfig,
+    metadata: &mut Metadata,
+    current: Option<&Path>,
+) -> Option<SidebarEvent> {
+    let mut clicked = None;
+    let mut event = None;
+    let folders = folder_notes(list, &config.smart_folders);
+    let filter = list.filter.to_lowercase();
+    let mut notes: Vec<NoteInfo> = list
+        .notes()
+        .iter()
+        .filter(|n| !vault::is_archived(&n.path))
+        .filter(|n| filter.is_empty() || vault::note_key(&n.path).to_lowercase().contains(&filter))
+        .cloned()
+        .collect();
+    sort(&mut notes, config);
+
+    egui::SidePanel::left("notes")
+        .resizable(true)
+        .default_width(220.0)
+        .show(ctx, |ui| {
+            let filter = ui.add(egui::TextEdit::singleline(&mut list.filter).hint_text(t("Filter notes")).desired_width(f32::INFINITY));
+            crate::a11y::region(&filter, crate::a11y::Region::Sidebar);
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source("note_sort")
+                    .selected_text(config.sort.label())
+                    .show_ui(ui, |ui| {
+                        for sort in NoteSort::ALL {
+                            ui.selectable_value(&mut config.sort, sort, sort.label());
+                        }
+                    });
+                let direction = if config.descending { "⬇" } else { "⬆" };
+                let reverse = ui.button(direction).on_hover_text(t("Reverse order"));
+                crate::a11y::name(&reverse, egui::WidgetType::Button, t("Reverse order"));
+                if reverse.clicked() {
+                    config.descending = !config.descending;
+                }
+            });
+            ui.separator();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                let (pinned, rest): (Vec<&NoteInfo>, Vec<&NoteInfo>) = notes.iter().partition(|n| metadata.get(&n.path).pinned);
+                let favorites: Vec<&NoteInfo> = notes.iter().filter(|n| metadata.get(&n.path).favorite).collect();
+                if !pinned.is_empty() {
+                    ui.label(RichText::new(t("📌 Pinned")).strong());
+                    for note in pinned {
+                        ui.push_id(("pinned", &note.path), |ui| note_row(ui, metadata, note, current, &mut clicked));
+                    }
+                    ui.separator();
+                }
+                if !favorites.is_empty() {
+                    egui::CollapsingHeader::new(RichText::new(t("⭐ Favorites")).strong())
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for note in favorites {
+                                ui.push_id(("favorite", &note.path), |ui| note_row(ui, metadata, note, current, &mut clicked));
+                            }
+                        });
+                    ui.separator();
+                }
+                if !config.smart_folders.is_empty() {
+                    let mut delete = None;
+                    for (index, (folder, notes)) in config.smart_folders.iter().zip(&folders).enumerate() {
+                        let response = egui::CollapsingHeader::new(RichText::new(format!("🔎 {} ({})", folder.name, notes.len())).strong())
+                            .id_source(("smart_folder", &folder.name))
+                            .show(ui, |ui| {
+                                for note in notes {
+                                    ui.push_id(("folder", index, &note.path), |ui| note_row(ui, metadata, note, current, &mut clicked));
+                                }
+                            });
+                        response.header_response.context_menu(|ui| {
+                            if ui.button(t("✏ Edit search")).clicked() {
+                                event = Some(SidebarEvent::EditFolder(folder.clone()));
+                                ui.close_menu();
+                            }
+                            if ui.button(t("🗑 Delete smart folder")).clicked() {
+                                delete = Some(index);
+                                ui.close_menu();
+                            }
+                        });
+                    }
+                    if let Some(index) = delete {
+                        config.smart_folders.remove(index);
+                    }
+                    ui.separator();
+                }
+                ui.label(RichText::new(t("Notes")).strong());
+                for note in rest {
+                    ui.push_id(&note.path, |ui| note_row(ui, metadata, note, current, &mut clicked));
+                }
+            });
+        });
+    clicked.map(SidebarEvent::Open).or(event)
+}
@@ -0,0 +1,158 @@
+use crate::i18n::{t, tf};
+use crate::text;
+use eframe::egui;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "speech")]
+type Engine = tts::Tts;
+
+/// Stand-in when built without the `speech` feature.
+#[cfg(not(feature = "speech"))]
+struct Engine;
+
+#[cfg(not(feature = "speech"))]
+impl Engine {
+    fn default() -> Result<Self, String> {
+        Err("built without the `speech` feature".to_owned())
+    }
+}
+
+/// Reads the note aloud one sentence at a time, highlighting each sentence
+/// in the editor as it is spoken.
+pub struct ReadAloud {
+    pub open: bool,
+    engine: Option<Result<Engine, String>>,
+    sentences: Vec<Range<usize>>,
+    current: usize,
+    playing: bool,
+    started: Instant,
+    /// Speaking rate as a fraction of the normal rate.
+    pub speed: f32,
+}
+
+impl Default for ReadAloud {
+    fn default() -> Self {
+        Self {
+            open: false,
+            engine: None,
+            sentences: Vec::new(),
+            current: 0,
+            playing: false,
+            started: Instant::now(),
+            speed: 1.0,
+        }
+    }
+}
+
+impl ReadAloud {
+    fn engine(&mut self) -> Result<&mut Engine, String> {
+        let engine = self
+            .engine
+            .get_or_insert_with(|| Engine::default().map_err(|e| e.to_string()));
+        engine.as_mut().map_err(|e| e.clone())
+    }
+
+    #[cfg(feature = "speech")]
+    fn speak(&mut self, sentence: &str) -> Result<(), String> {
+        let speed = self.speed;
+        let engine = self.engine()?;
+        let rate = (engine.normal_rate() * speed).clamp(engine.min_rate(), engine.max_rate());
+        engine.set_rate(rate).map_err(|e| e.to_string())?;
+        engine.speak(sentence, true).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "speech"))]
+    fn speak(&mut self, _sentence: &str) -> Result<(), String> {
+        self.engine().map(|_| ())
+    }
+
+    #[cfg(feature = "speech")]
+    fn is_speaking(&mut self) -> bool {
+        self.engine().is_ok_and(|e| e.is_speaking().unwrap_or(false))
+    }
+
+    #[cfg(not(feature = "speech"))]
+    fn is_speaking(&mut self) -> bool {
+        false
+    }
+
+    #[cfg(feature = "speech")]
+    fn stop(&mut self) {
+        if let Ok(engine) = self.engine() {
+            let _ = engine.stop();
+        }
+    }
+
+    #[cfg(not(feature = "speech"))]
+    fn stop(&mut self) {}
+
+    fn play_current(&mut self, ctx: &egui::Context, note: &str) {
+        let Some(range) = self.sentences.get(self.current).cloned() else {
+            self.playing = false;
+            return;
+        };
+        let Some(sentence) = note.get(range.clone()).map(str::to_owned) else {
+            self.playing = false;
+            return;
+        };
+        self.playing = self.speak(&sentence).is_ok();
+        self.started = Instant::now();
+        if self.playing {
+            let chars = note[..range.start].chars().count()..note[..range.end].chars().count();
+            crate::editor::set_cursor(ctx, chars);
+        }
+    }
+
+    /// Small player window; advances to the next sentence when one finishes.
+    pub fn show(&mut self, ctx: &egui::Context, note: &str) {
+        // Engines report "not speaking" briefly before an utterance starts.
+        let settled = self.started.elapsed() > Duration::from_millis(300);
+        if self.playing && settled && !self.is_speaking() {
+            self.current += 1;
+            self.play_current(ctx, note);
+        }
+        if self.playing {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+        if !self.open {
+            return;
+        }
+
+        let mut open = true;
+        egui::Window::new(t("🔊 Read Aloud")).open(&mut open).show(ctx, |ui| {
+            if let Some(Err(err)) = &self.engine {
+                ui.colored_label(egui::Color32::LIGHT_RED, tf("Speech unavailable: {}", &[&err]));
+            }
+            ui.horizontal(|ui| {
+                if self.playing {
+                    if ui.button(t("⏸ Pause")).clicked() {
+                        self.playing = false;
+                        self.stop();
+                    }
+                } else if ui.button(t("▶ Play")).clicked() {
+                    if self.sentences.is_empty() || self.current >= self.sentences.len() {
+                        self.sentences = text::sentences(note);
+                        self.current = 0;
+                    }
+                    self.play_current(ctx, note);
+                }
+                if ui.button(t("⏹ Stop")).clicked() {
+                    self.playing = false;
+                    self.sentences.clear();
+                    self.stop();
+                }
+            });
+            ui.add(egui::Slider::new(&mut self.speed, 0.5..=2.0).text(t("speed")));
+            if !self.sentences.is_empty() {
+                ui.weak(tf("Sentence {} of {}", &[&(self.current + 1), &self.sentences.len()]));
+            }
+        });
+        if !open {
+            self.open = false;
+            self.playing = false;
+            self.stop();
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use crate::config::ReviewConfig;
+use crate::i18n::t;
+use crate::metadata::Metadata;
+use crate::sidebar::{NoteList, title};
+use crate::{roulette, vault};
+use eframe::egui::{self, RichText};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+/// Recently modified notes listed on the start screen.
+const RECENT: usize = 8;
+
+/// What the app should do after the user acted on the start screen.
+pub enum StartEvent {
+    Open(PathBuf),
+    NewNote,
+    DailyNote,
+    RandomNote,
+    Search,
+    Settings,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Stats {
+    notes: usize,
+    words: usize,
+    tags: usize,
+    archived: usize,
+}
+
+/// Shown in place of the editor while no note is open.
+#[derive(Default)]
+pub struct StartScreen {
+    /// Vault statistics with the note list generation they were counted at.
+    stats: Option<(u64, Stats)>,
+}
+
+impl StartScreen {
+    fn stats(&mut self, list: &mut NoteList) -> Stats {
+        let generation = list.generation;
+        if let Some((g, stats)) = self.stats
+            && g == generation
+        {
+            return stats;
+        }
+        let notes = list.notes();
+        let tags: BTreeSet<String> = notes.iter().flat_map(|n| n.tags.iter().map(|t| t.to_lowercase())).collect();
+        let stats = Stats {
+            notes: notes.len(),
+            words: notes.iter().map(|n| n.text.split_whitespace().count()).sum(),
+            tags: tags.len(),
+            archived: notes.iter().filter(|n| vault::is_archived(&n.path)).count(),
+        };
+        self.stats = Some((list.generation, stats));
+        stats
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, list: &mut NoteList, metadata: &Metadata, review: &ReviewConfig) -> Option<StartEvent> {
+        let stats = self.stats(list);
+        let mut notes: Vec<_> = list.notes().iter().filter(|n| !vault::is_archived(&n.path)).cloned().collect();
+        notes.sort_by_key(|n| std::cmp::Reverse(n.modified));
+        let mut event = None;
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.add_space(12.0);
+            ui.heading(t("Welcome back"));
+            ui.add_space(8.0);
+            ui.horizontal_wrapped(|ui| {
+                if ui.button(t("📝 New Note")).clicked() {
+                    event = Some(StartEvent::NewNote);
+                }
+                if ui.button(t("📅 Today's Daily Note")).clicked() {
+                    event = Some(StartEvent::DailyNote);
+                }
+                if ui.button(t("🎲 Random Note")).clicked() {
+                    event = Some(StartEvent::RandomNote);
+                }
+                if ui.button(t("🔎 Search")).on_hover_text("Ctrl+Shift+F").clicked() {
+                    event = Some(StartEvent::Search);
+                }
+                if ui.button(t("⚙ Settings")).clicked() {
+                    event = Some(StartEvent::Settings);
+                }
+            });
+            ui.add_space(12.0);
+
+            ui.columns(2, |cols| {
+                let mut link = |ui: &mut egui::Ui, path: &PathBuf| {
+                    if ui.link(title(path)).on_hover_text(vault::note_key(path)).clicked() {
+                        event = Some(StartEvent::Open(path.clone()));
+                    }
+                };
+                cols[0].label(RichText::new(t("🕘 Recent")).strong());
+                if notes.is_empty() {
+                    cols[0].label(RichText::new(t("No notes yet")).weak());
+                }
+                for note in notes.iter().take(RECENT) {
+                    link(&mut cols[0], &note.path);
+                }
+                let pinned: Vec<_> = notes.iter().filter(|n| metadata.get(&n.path).pinned).collect();
+                if !pinned.is_empty() {
+                    cols[0].add_space(8.0);
+                    cols[0].label(RichText::new(t("📌 Pinned")).strong());
+                    for note in pinned {
+                        link(&mut cols[0], &note.path);
+                    }
+                }
+
+                cols[1].label(RichText::new(t("🔁 Today's review")).strong());
+                if let Some(path) = roulette::queue_list(&mut cols[1], &notes, review) {
+                    event = Some(StartEvent::Open(path));
+                }
+                cols[1].add_space(8.0);
+                cols[1].label(RichText::new(t("📊 Vault")).strong());
+                egui::Grid::new("vault_stats").num_columns(2).show(&mut cols[1], |ui| {
+                    for (label, value) in [("Notes", stats.notes), ("Words", stats.words), ("Tags", stats.tags), ("Archived", stats.archived)] {
+                        ui.label(label);
+                        ui.label(value.to_string());
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+        event
+    }
+}
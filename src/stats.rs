@@ -0,0 +1,204 @@
+use crate::i18n::{t, tf};
+use crate::sidebar::{NoteInfo, NoteList, title};
+use crate::{links, vault};
+use eframe::egui::{self, RichText};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+
+/// Entries in the top tags and largest notes lists.
+const TOP: usize = 10;
+/// Most recent months shown in the chart.
+const MONTHS: usize = 24;
+
+/// Vault-wide numbers for the dashboard.
+#[derive(Default)]
+pub struct Stats {
+    pub notes: usize,
+    pub words: usize,
+    /// Notes created in each `YYYY-MM`.
+    pub per_month: BTreeMap<String, usize>,
+    pub top_tags: Vec<(String, usize)>,
+    /// Largest notes by word count.
+    pub largest: Vec<(PathBuf, usize)>,
+    /// Links between notes.
+    pub links: usize,
+    /// Notes with no links in or out.
+    pub unlinked: usize,
+}
+
+impl Stats {
+    /// Links per note.
+    pub fn link_density(&self) -> f32 {
+        if self.notes == 0 { 0.0 } else { self.links as f32 / self.notes as f32 }
+    }
+}
+
+pub fn build(notes: &[NoteInfo]) -> Stats {
+    let notes: Vec<&NoteInfo> = notes.iter().filter(|n| !vault::is_archived(&n.path)).collect();
+    let paths: Vec<PathBuf> = notes.iter().map(|n| n.path.clone()).collect();
+    let mut stats = Stats {
+        notes: notes.len(),
+        ..Stats::default()
+    };
+    let mut tags: HashMap<String, usize> = HashMap::new();
+    let mut linked = vec![false; notes.len()];
+    for (index, note) in notes.iter().enumerate() {
+        let words = note.text.split_whitespace().count();
+        stats.words += words;
+        stats.largest.push((note.path.clone(), words));
+        if let Some(created) = note.created.or(note.modified) {
+            let month = chrono::DateTime::<chrono::Local>::from(created).format("%Y-%m").to_string();
+            *stats.per_month.entry(month).or_default() += 1;
+        }
+        for tag in &note.tags {
+            *tags.entry(tag.to_lowercase()).or_default() += 1;
+        }
+        for target in links::outgoing(&note.path, &note.text, &paths) {
+            if target == note.path {
+                continue;
+            }
+            stats.links += 1;
+            linked[index] = true;
+            if let Some(other) = paths.iter().position(|p| *p == target) {
+                linked[other] = true;
+            }
+        }
+    }
+    stats.unlinked = linked.iter().filter(|l| !**l).count();
+    stats.largest.sort_by_key(|(_, words)| std::cmp::Reverse(*words));
+    stats.largest.truncate(TOP);
+    stats.top_tags = tags.into_iter().collect();
+    stats.top_tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    stats.top_tags.truncate(TOP);
+    stats
+}
+
+/// Bar chart of notes created per month.
+fn month_chart(ui: &mut egui::Ui, per_month: &BTreeMap<String, usize>) {
+    let months: Vec<(&String, &usize)> = per_month.iter().rev().take(MONTHS).collect::<Vec<_>>().into_iter().rev().collect();
+    let max = months.iter().map(|(_, c)| **c).max().unwrap_or(0).max(1);
+    let height = 100.0;
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width(), height + 16.0), egui::Sense::hover());
+    let slot = rect.width() / months.len().max(1) as f32;
+    let painter = ui.painter_at(rect);
+    let color = ui.visuals().selection.bg_fill;
+    let font = egui::TextStyle::Small.resolve(ui.style());
+    for (i, (month, count)) in months.iter().enumerate() {
+        let x = rect.left() + slot * i as f32;
+        let bar = height * **count as f32 / max as f32;
+        let bar_rect = egui::Rect::from_min_max(egui::pos2(x + 2.0, rect.top() + height - bar), egui::pos2(x + slot - 2.0, rect.top() + height));
+        painter.rect_filled(bar_rect, 2.0, color);
+        let hover = egui::Rect::from_min_max(egui::pos2(x, rect.top()), egui::pos2(x + slot, rect.top() + height));
+        if ui.rect_contains_pointer(hover) {
+            egui::show_tooltip_at_pointer(ui.ctx(), egui::Id::new("month_chart"), |ui| {
+                ui.label(tf("{}: {} notes", &[&month, &count]));
+            });
+        }
+        // Label January and the first month so the axis stays readable.
+        if i == 0 || month.ends_with("-01") {
+            painter.text(egui::pos2(x, rect.bottom()), egui::Align2::LEFT_BOTTOM, month.as_str(), font.clone(), ui.visuals().weak_text_color());
+        }
+    }
+}
+
+/// Statistics window, counted on a background thread from the note list.
+#[derive(Default)]
+pub struct Dashboard {
+    pub open: bool,
+    /// The latest statistics with the note list generation they are for.
+    stats: Option<(u64, Stats)>,
+    pending: Option<(u64, Receiver<Stats>)>,
+}
+
+impl Dashboard {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// Returns a note the user clicked.
+    pub fn show(&mut self, ctx: &egui::Context, list: &mut NoteList) -> Option<PathBuf> {
+        if !self.open {
+            return None;
+        }
+        if let Some((generation, rx)) = &self.pending
+            && let Ok(stats) = rx.try_recv()
+        {
+            self.stats = Some((*generation, stats));
+            self.pending = None;
+        }
+        list.notes();
+        let generation = list.generation;
+        let current = self.stats.as_ref().is_some_and(|(g, _)| *g == generation);
+        let counting = self.pending.as_ref().is_some_and(|(g, _)| *g == generation);
+        if !current && !counting {
+            let (tx, rx) = mpsc::channel();
+            let ctx = ctx.clone();
+            let notes = list.notes().to_vec();
+            std::thread::spawn(move || {
+                let _ = tx.send(build(&notes));
+                ctx.request_repaint();
+            });
+            self.pending = Some((generation, rx));
+        }
+        let mut clicked = None;
+        let mut open = true;
+
+        egui::Window::new(t("📊 Vault Statistics")).open(&mut open).default_width(480.0).show(ctx, |ui| {
+            let Some((_, stats)) = &self.stats else {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(t("Counting…"));
+                });
+                return;
+            };
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("stats_totals").num_columns(2).show(ui, |ui| {
+                    ui.label(t("Notes"));
+                    ui.label(stats.notes.to_string());
+                    ui.end_row();
+                    ui.label(t("Words"));
+                    ui.label(stats.words.to_string());
+                    ui.end_row();
+                    ui.label(t("Links"));
+                    ui.label(tf("{} ({} per note)", &[&stats.links, &format!("{:.1}", stats.link_density())]));
+                    ui.end_row();
+                    ui.label(t("Unlinked notes"));
+                    ui.label(stats.unlinked.to_string());
+                    ui.end_row();
+                });
+                ui.separator();
+                ui.label(RichText::new(t("Notes created per month")).strong());
+                if stats.per_month.is_empty() {
+                    ui.label(RichText::new(t("No dates available")).weak());
+                } else {
+                    month_chart(ui, &stats.per_month);
+                }
+                ui.separator();
+                ui.columns(2, |cols| {
+                    cols[0].label(RichText::new(t("Top tags")).strong());
+                    for (tag, count) in &stats.top_tags {
+                        cols[0].label(format!("#{} ({})", tag, count));
+                    }
+                    cols[1].label(RichText::new(t("Largest notes")).strong());
+                    for (path, words) in &stats.largest {
+                        cols[1].horizontal(|ui| {
+                            if ui.link(title(path)).on_hover_text(vault::note_key(path)).clicked() {
+                                clicked = Some(path.clone());
+                            }
+                            ui.label(RichText::new(tf("{} words", &[&words])).small().weak());
+                        });
+                    }
+                });
+            });
+            if self.pending.is_some() {
+                ui.label(RichText::new(t("Updating…")).small().weak());
+            }
+        });
+
+        if !open {
+            self.open = false;
+        }
+        clicked
+    }
+}
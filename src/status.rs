@@ -0,0 +1,60 @@
+use crate::i18n::{t, tf};
+use std::time::{Duration, Instant};
+
+/// Where the grammar check of the open note stands.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum CheckState {
+    #[default]
+    Idle,
+    Running,
+    Done,
+    Failed(String),
+}
+
+impl CheckState {
+    /// The status bar entry, with `issues` suggestions showing, and what it
+    /// says on hover.
+    pub fn label(&self, issues: usize) -> (String, String) {
+        match self {
+            CheckState::Running => (t("⏳ Checking…"), t("Grammar check running")),
+            CheckState::Failed(err) => (t("⚠ Check failed"), tf("The grammar check failed: {}; click to try again", &[err])),
+            CheckState::Idle if issues == 0 => (t("✎ Not checked"), t("Click to check grammar")),
+            _ if issues == 0 => (t("✔ No issues"), t("Click to check again")),
+            _ if issues == 1 => (t("⚠ 1 issue"), t("Click to check again")),
+            _ => (tf("⚠ {} issues", &[&issues]), t("Click to check again")),
+        }
+    }
+}
+
+/// Saves the open note a set number of seconds after the last edit.
+#[derive(Default)]
+pub struct Autosave {
+    edited: Option<Instant>,
+}
+
+impl Autosave {
+    pub fn edited(&mut self) {
+        self.edited = Some(Instant::now());
+    }
+
+    /// Nothing left to save, e.g. after saving or opening another note.
+    pub fn reset(&mut self) {
+        self.edited = None;
+    }
+
+    /// Time until the next save, or `None` with autosave off or no edits.
+    pub fn remaining(&self, seconds: u64) -> Option<Duration> {
+        let edited = self.edited.filter(|_| seconds > 0)?;
+        Some(Duration::from_secs(seconds).saturating_sub(edited.elapsed()))
+    }
+
+    /// Whether it is time to save. Asks once per edit, so a save that
+    /// fails or is refused is not tried again every frame.
+    pub fn due(&mut self, seconds: u64) -> bool {
+        let due = self.remaining(seconds).is_some_and(|left| left.is_zero());
+        if due {
+            self.edited = None;
+        }
+        due
+    }
+}
@@ -0,0 +1,120 @@
+// Stand-ins for the crates that have no Android backend, with the parts of
+// their API the app uses. Modules import them in place of the real crates
+// on Android.
+
+/// File pickers pick nothing, and questions are cancelled, so nothing is
+/// overwritten or thrown away without asking.
+pub mod rfd {
+    use std::path::{Path, PathBuf};
+
+    #[derive(Default)]
+    pub struct FileDialog;
+
+    impl FileDialog {
+        pub fn new() -> Self {
+            Self
+        }
+
+        pub fn add_filter(self, _name: impl Into<String>, _extensions: &[impl ToString]) -> Self {
+            self
+        }
+
+        pub fn set_directory<P: AsRef<Path>>(self, _path: P) -> Self {
+            self
+        }
+
+        pub fn set_file_name(self, _name: impl Into<String>) -> Self {
+            self
+        }
+
+        pub fn pick_file(self) -> Option<PathBuf> {
+            None
+        }
+
+        pub fn pick_folder(self) -> Option<PathBuf> {
+            None
+        }
+
+        pub fn save_file(self) -> Option<PathBuf> {
+            None
+        }
+    }
+
+    pub enum MessageButtons {
+        YesNo,
+        YesNoCancelCustom(String, String, String),
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum MessageDialogResult {
+        Yes,
+        No,
+        Cancel,
+        Custom(String),
+    }
+
+    #[derive(Default)]
+    pub struct MessageDialog;
+
+    impl MessageDialog {
+        pub fn new() -> Self {
+            Self
+        }
+
+        pub fn set_title(self, _title: impl Into<String>) -> Self {
+            self
+        }
+
+        pub fn set_description(self, _description: impl Into<String>) -> Self {
+            self
+        }
+
+        pub fn set_buttons(self, _buttons: MessageButtons) -> Self {
+            self
+        }
+
+        pub fn show(self) -> MessageDialogResult {
+            MessageDialogResult::Cancel
+        }
+    }
+}
+
+/// The clipboard can't be opened; egui's own copy and paste still work.
+pub mod arboard {
+    use std::fmt;
+
+    #[derive(Debug)]
+    pub struct Error;
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("the system clipboard is not available on Android")
+        }
+    }
+
+    impl std::error::Error for Error {}
+
+    pub struct Clipboard;
+
+    pub struct Get;
+
+    impl Clipboard {
+        pub fn new() -> Result<Self, Error> {
+            Err(Error)
+        }
+
+        pub fn get_text(&mut self) -> Result<String, Error> {
+            Err(Error)
+        }
+
+        pub fn get(&mut self) -> Get {
+            Get
+        }
+    }
+
+    impl Get {
+        pub fn html(self) -> Result<String, Error> {
+            Err(Error)
+        }
+    }
+}
@@ -0,0 +1,284 @@
+use crate::grammar::LTMatch;
+use crate::i18n::{t, tf};
+use eframe::egui::text::{LayoutJob, TextFormat};
+use eframe::egui::{self, RichText};
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// Longest context shown on each side of a problem, in characters.
+const CONTEXT_CHARS: usize = 80;
+/// Replacements offered per problem.
+const MAX_REPLACEMENTS: usize = 3;
+
+pub enum SuggestionEvent {
+    /// Select the problem in the editor.
+    Select(Range<usize>),
+    /// Replace each range with its text.
+    Apply(Vec<(Range<usize>, String)>),
+}
+
+fn range(suggestion: &LTMatch) -> Range<usize> {
+    suggestion.offset..suggestion.offset + suggestion.length
+}
+
+/// How a problem is marked: spelling red, grammar orange, style and
+/// typography blue, anything else yellow.
+pub fn color(suggestion: &LTMatch) -> egui::Color32 {
+    match suggestion.rule.category.id.as_str() {
+        crate::grammar::SPELLING_CATEGORY => egui::Color32::from_rgb(230, 80, 80),
+        "GRAMMAR" | "CONFUSED_WORDS" => egui::Color32::from_rgb(240, 150, 40),
+        "TYPOGRAPHY" | "STYLE" | "CASING" | "PUNCTUATION" => egui::Color32::from_rgb(90, 150, 230),
+        _ => egui::Color32::from_rgb(220, 200, 60),
+    }
+}
+
+/// The first replacement of each suggestion that has one.
+fn fixes<'a>(suggestions: impl Iterator<Item = &'a LTMatch>) -> Vec<(Range<usize>, String)> {
+    suggestions.filter_map(|s| Some((range(s), s.replacements.first()?.value.clone()))).collect()
+}
+
+/// Makes the replacements in one pass from the end of the text, so each
+/// leaves the offsets before it alone. A replacement overlapping one
+/// already made is skipped. Returns the ranges replaced, last first, with
+/// the length of what replaced them.
+pub fn apply(text: &mut String, mut fixes: Vec<(Range<usize>, String)>) -> Vec<(Range<usize>, usize)> {
+    fixes.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    let mut done = Vec::new();
+    let mut limit = text.len();
+    for (range, replacement) in fixes {
+        if range.end > limit || text.get(range.clone()).is_none() {
+            continue;
+        }
+        text.replace_range(range.clone(), &replacement);
+        limit = range.start;
+        done.push((range, replacement.len()));
+    }
+    done
+}
+
+/// Carries suggestions found in `old` over to `new`, the same note after
+/// some typing: those the edit touched are dropped and the rest moved.
+pub fn remap(suggestions: &mut Vec<LTMatch>, old: &str, new: &str) {
+    if old == new {
+        return;
+    }
+    let (start, end, insert) = crate::text::splice(old, new);
+    // Typing onto either end of a flagged word makes it another word.
+    let joins = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+    suggestions.retain(|s| {
+        let extended = s.offset + s.length == start && joins(insert.chars().next());
+        let prefixed = s.offset == end && joins(insert.chars().next_back());
+        !extended && !prefixed
+    });
+    shift(suggestions, &(start..end), insert.len());
+}
+
+/// Drops the suggestions a replacement of `range` touched and moves the
+/// ones after it, keeping them usable until the next check comes back.
+pub fn shift(suggestions: &mut Vec<LTMatch>, range: &Range<usize>, inserted: usize) {
+    let added = inserted as isize - range.len() as isize;
+    suggestions.retain_mut(|s| {
+        if s.offset >= range.end {
+            s.offset = s.offset.saturating_add_signed(added);
+            true
+        } else {
+            s.offset + s.length <= range.start
+        }
+    });
+}
+
+/// The sentence around `range`, out of the note's `sentences`.
+fn sentence(sentences: &[Range<usize>], range: &Range<usize>) -> Range<usize> {
+    let start = sentences.iter().find(|s| s.end > range.start).map_or(range.start, |s| s.start.min(range.start));
+    let end = sentences.iter().find(|s| s.end >= range.end).map_or(range.end, |s| s.end.max(range.end));
+    start..end
+}
+
+/// At most `max` characters of `text`, from its end or its start.
+fn clip(text: &str, max: usize, from_end: bool) -> String {
+    let count = text.chars().count();
+    if count <= max {
+        text.to_owned()
+    } else if from_end {
+        format!("…{}", text.chars().skip(count - max).collect::<String>())
+    } else {
+        format!("{}…", text.chars().take(max).collect::<String>())
+    }
+}
+
+/// The sentence with the problem picked out.
+fn context_job(ui: &egui::Ui, text: &str, sentences: &[Range<usize>], range: &Range<usize>) -> LayoutJob {
+    let around = sentence(sentences, range);
+    let font = egui::TextStyle::Body.resolve(ui.style());
+    let normal = TextFormat::simple(font.clone(), ui.visuals().text_color());
+    let marked = TextFormat {
+        underline: egui::Stroke::new(1.5, egui::Color32::LIGHT_RED),
+        ..TextFormat::simple(font, ui.visuals().strong_text_color())
+    };
+    let mut job = LayoutJob::default();
+    job.append(clip(text[around.start..range.start].trim_start(), CONTEXT_CHARS, true).as_str(), 0.0, normal.clone());
+    job.append(&text[range.clone()], 0.0, marked);
+    job.append(clip(text[range.end..around.end].trim_end(), CONTEXT_CHARS, false).as_str(), 0.0, normal);
+    job.wrap.max_width = ui.available_width();
+    job
+}
+
+/// Lists the problems a check found, each with its sentence and line;
+/// clicking one selects it in the editor, and its buttons fix it. Several
+/// can be fixed at once: a whole category, all spelling, or a selection.
+#[derive(Default)]
+pub struct SuggestionsView {
+    pub open: bool,
+    /// Only show this category.
+    category: Option<String>,
+    selecting: bool,
+    /// Chosen suggestions by offset and length.
+    chosen: HashSet<(usize, usize)>,
+    /// Only show problems whose text or message has this in it.
+    filter: String,
+    /// In a side panel instead of a window over the note.
+    pub docked: bool,
+}
+
+impl SuggestionsView {
+    pub fn show(&mut self, ctx: &egui::Context, text: &str, suggestions: &[LTMatch]) -> Option<SuggestionEvent> {
+        if suggestions.is_empty() || !self.open {
+            return None;
+        }
+        let mut event = None;
+        let mut categories: Vec<&str> = suggestions.iter().map(LTMatch::category).collect();
+        categories.sort_unstable();
+        categories.dedup();
+        if self.category.as_deref().is_some_and(|c| !categories.contains(&c)) {
+            self.category = None;
+        }
+        let filter = self.filter.trim().to_lowercase();
+        self.chosen.retain(|&(offset, length)| suggestions.iter().any(|s| s.offset == offset && s.length == length));
+        let shown: Vec<&LTMatch> = suggestions
+            .iter()
+            // The note may have changed while the check ran.
+            .filter(|s| text.get(range(s)).is_some())
+            .filter(|s| self.category.as_deref().is_none_or(|c| s.category() == c))
+            .filter(|s| filter.is_empty() || s.message.to_lowercase().contains(&filter) || text[range(s)].to_lowercase().contains(&filter))
+            .collect();
+
+        let mut open = true;
+        if self.docked {
+            egui::SidePanel::right("suggestions").resizable(true).default_width(340.0).show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.heading(t("💡 Suggestions"));
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.small_button("✖").on_hover_text(t("Close")).clicked() {
+                            open = false;
+                        }
+                    });
+                });
+                event = self.contents(ui, text, suggestions, &categories, shown);
+            });
+        } else {
+            egui::Window::new(t("💡 Suggestions")).open(&mut open).default_width(360.0).collapsible(false).show(ctx, |ui| {
+                event = self.contents(ui, text, suggestions, &categories, shown);
+            });
+        }
+        self.open = open;
+        event
+    }
+
+    fn contents(&mut self, ui: &mut egui::Ui, text: &str, suggestions: &[LTMatch], categories: &[&str], shown: Vec<&LTMatch>) -> Option<SuggestionEvent> {
+        let mut event = None;
+        ui.horizontal_wrapped(|ui| {
+            let category = egui::ComboBox::from_id_source("suggestion_category")
+                .selected_text(self.category.clone().unwrap_or_else(|| t("All categories")))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.category, None, t("All categories"));
+                    for category in categories {
+                        let count = suggestions.iter().filter(|s| s.category() == *category).count();
+                        ui.selectable_value(&mut self.category, Some(category.to_string()), format!("{} ({})", category, count));
+                    }
+                });
+            crate::a11y::region(&category.response, crate::a11y::Region::Suggestions);
+            if let Some(category) = &self.category {
+                let all = fixes(shown.iter().copied());
+                if ui.add_enabled(!all.is_empty(), egui::Button::new(tf("Fix all {} in {}", &[&all.len(), &category]))).clicked() {
+                    event = Some(SuggestionEvent::Apply(all));
+                }
+            }
+            let spelling = fixes(suggestions.iter().filter(|s| s.is_spelling() && text.get(range(s)).is_some()));
+            if !spelling.is_empty() && ui.button(tf("Fix all spelling ({})", &[&spelling.len()])).clicked() {
+                event = Some(SuggestionEvent::Apply(spelling));
+            }
+            if ui.selectable_label(self.selecting, t("☑ Select")).on_hover_text(t("Choose several to fix at once")).clicked() {
+                self.selecting = !self.selecting;
+                self.chosen.clear();
+            }
+            if self.selecting {
+                let chosen = fixes(suggestions.iter().filter(|s| self.chosen.contains(&(s.offset, s.length))));
+                if ui.add_enabled(!chosen.is_empty(), egui::Button::new(tf("Fix {} selected", &[&chosen.len()]))).clicked() {
+                    event = Some(SuggestionEvent::Apply(chosen));
+                    self.chosen.clear();
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.filter).hint_text(t("Filter suggestions")).desired_width(180.0));
+            let (dock, hover) = if self.docked { (t("⧉ Float"), t("Show in a window")) } else { (t("📌 Dock"), t("Show in a panel beside the note")) };
+            if ui.button(dock).on_hover_text(hover).clicked() {
+                self.docked = !self.docked;
+            }
+        });
+        ui.separator();
+        let sentences = crate::text::sentences(text);
+        // Docked, the list takes the rest of the window's height.
+        let max_height = if self.docked { f32::INFINITY } else { 420.0 };
+        egui::ScrollArea::vertical().max_height(max_height).show(ui, |ui| {
+            for suggestion in shown {
+                let range = range(suggestion);
+                let line = text[..range.start].matches('\n').count() + 1;
+                let mut heading = tf("Line {}", &[&line]);
+                if !suggestion.provider.is_empty() {
+                    heading = format!("{} · {}", heading, suggestion.provider);
+                }
+                if self.selecting {
+                    let key = (suggestion.offset, suggestion.length);
+                    let mut chosen = self.chosen.contains(&key);
+                    let enabled = !suggestion.replacements.is_empty();
+                    if ui.add_enabled(enabled, egui::Checkbox::new(&mut chosen, RichText::new(heading).small().weak())).changed() {
+                        if chosen {
+                            self.chosen.insert(key);
+                        } else {
+                            self.chosen.remove(&key);
+                        }
+                    }
+                } else {
+                    ui.label(RichText::new(heading).small().weak());
+                }
+                let context = ui.add(egui::Label::new(context_job(ui, text, &sentences, &range)).sense(egui::Sense::click()));
+                crate::a11y::name(&context, egui::WidgetType::Button, tf("Select “{}” in the note", &[&&text[range.clone()]]));
+                if context.on_hover_text(t("Select in the note")).clicked() {
+                    event = Some(SuggestionEvent::Select(range.clone()));
+                }
+                ui.label(RichText::new(&suggestion.message).small());
+                ui.horizontal_wrapped(|ui| {
+                    if suggestion.replacements.is_empty() {
+                        ui.weak(t("No suggestion"));
+                    }
+                    for replacement in suggestion.replacements.iter().take(MAX_REPLACEMENTS) {
+                        let label = if replacement.value.is_empty() { t("(remove)") } else { replacement.value.clone() };
+                        let button = ui.button(format!("→ {}", label));
+                        let spoken = if replacement.value.is_empty() {
+                            tf("Remove “{}”", &[&&text[range.clone()]])
+                        } else {
+                            tf("Replace “{}” with “{}”", &[&&text[range.clone()], &label])
+                        };
+                        crate::a11y::name(&button, egui::WidgetType::Button, spoken);
+                        if button.clicked() {
+                            event = Some(SuggestionEvent::Apply(vec![(range.clone(), replacement.value.clone())]));
+                        }
+                    }
+                });
+                ui.separator();
+            }
+        });
+        event
+    }
+}
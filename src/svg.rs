@@ -0,0 +1,135 @@
+use eframe::egui;
+use std::collections::{HashMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+use tracing::{debug, error};
+
+/// How long an input must stay the same before it is rendered, so typing
+/// a formula does not start a renderer for every keystroke.
+const DEBOUNCE: Duration = Duration::from_millis(400);
+/// Images kept; the least recently shown go first.
+const MAX_IMAGES: usize = 256;
+
+/// `rrggbb` for a color, as external renderers expect it.
+pub fn hex_color(color: egui::Color32) -> String {
+    format!("{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
+enum Image {
+    /// Asked for at this time, and not rendered until [`DEBOUNCE`] later.
+    Waiting(Instant),
+    Pending,
+    Rendered { uri: String, svg: Arc<[u8]> },
+    Failed,
+}
+
+/// SVGs produced by external tools (TeX, Graphviz, ...), keyed by whatever
+/// identifies their input. Rendering happens off the UI thread.
+pub struct SvgCache {
+    /// Each image with the tick it was last shown at.
+    images: HashMap<String, (Image, u64)>,
+    tick: u64,
+    /// Renderers whose failure was logged; later ones only go to the debug
+    /// log, as a missing tool fails for every formula or diagram.
+    reported: HashSet<String>,
+    tx: Sender<(String, Result<String, String>)>,
+    rx: Receiver<(String, Result<String, String>)>,
+}
+
+impl Default for SvgCache {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::channel();
+        Self {
+            images: HashMap::new(),
+            tick: 0,
+            reported: HashSet::new(),
+            tx,
+            rx,
+        }
+    }
+}
+
+impl SvgCache {
+    fn receive(&mut self) {
+        for (key, result) in self.rx.try_iter() {
+            let image = match result {
+                Ok(svg) => {
+                    let mut hasher = DefaultHasher::new();
+                    key.hash(&mut hasher);
+                    Image::Rendered {
+                        uri: format!("bytes://svg/{:x}.svg", hasher.finish()),
+                        svg: svg.into_bytes().into(),
+                    }
+                }
+                Err(err) => {
+                    // Errors start with the program that failed.
+                    let renderer = err.split(':').next().unwrap_or_default().to_owned();
+                    if self.reported.insert(renderer) {
+                        error!("Rendering failed: {}", err);
+                    } else {
+                        debug!("Rendering failed: {}", err);
+                    }
+                    Image::Failed
+                }
+            };
+            // Images dropped from the cache while rendering are not kept.
+            if let Some((slot, _)) = self.images.get_mut(&key) {
+                *slot = image;
+            }
+        }
+    }
+
+    /// Drops the least recently shown images beyond [`MAX_IMAGES`], along
+    /// with their textures.
+    fn evict(&mut self, ctx: &egui::Context) {
+        while self.images.len() > MAX_IMAGES {
+            let Some(oldest) = self.images.iter().min_by_key(|(_, (_, used))| *used).map(|(key, _)| key.clone()) else {
+                break;
+            };
+            if let Some((Image::Rendered { uri, .. }, _)) = self.images.remove(&oldest) {
+                ctx.forget_image(&uri);
+            }
+        }
+    }
+
+    /// Shows the image for `key`, calling `render` on a worker thread once
+    /// it has been asked for over [`DEBOUNCE`]. Returns `None` while the
+    /// image is not available (waiting, still rendering, or the renderer
+    /// failed), so the caller can show the source instead.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        key: String,
+        scale: f32,
+        render: impl FnOnce() -> Result<String, String> + Send + 'static,
+    ) -> Option<egui::Response> {
+        self.receive();
+        self.tick += 1;
+        let (image, used) = self.images.entry(key.clone()).or_insert_with(|| (Image::Waiting(Instant::now()), 0));
+        *used = self.tick;
+        if let Image::Waiting(since) = image {
+            let waited = since.elapsed();
+            if waited < DEBOUNCE {
+                ui.ctx().request_repaint_after(DEBOUNCE - waited);
+            } else {
+                let tx = self.tx.clone();
+                let ctx = ui.ctx().clone();
+                std::thread::spawn(move || {
+                    let _ = tx.send((key, render()));
+                    ctx.request_repaint();
+                });
+                *image = Image::Pending;
+            }
+        }
+        let shown = match image {
+            Image::Rendered { uri, svg } => {
+                Some(ui.add(egui::Image::from_bytes(uri.clone(), svg.clone()).fit_to_original_size(scale)))
+            }
+            Image::Waiting(_) | Image::Pending | Image::Failed => None,
+        };
+        self.evict(ui.ctx());
+        shown
+    }
+}
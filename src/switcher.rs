@@ -0,0 +1,107 @@
+use crate::i18n::t;
+use crate::metadata::Metadata;
+use crate::sidebar::{NoteInfo, title};
+use crate::vault;
+use eframe::egui::{self, Key, Modifiers};
+use std::path::PathBuf;
+
+/// Most results listed at once.
+const LIMIT: usize = 20;
+
+/// Whether every character of `query` appears in `text`, in order.
+fn fuzzy_match(text: &str, query: &str) -> bool {
+    let mut chars = text.chars();
+    query.chars().all(|q| chars.any(|c| c == q))
+}
+
+/// Notes matching `query`: favorites first, then title substring
+/// matches, then fuzzy path matches.
+fn results(notes: &[NoteInfo], metadata: &Metadata, query: &str, archived: bool) -> Vec<PathBuf> {
+    let query = query.to_lowercase();
+    let mut scored: Vec<(u8, &PathBuf)> = notes
+        .iter()
+        .map(|note| &note.path)
+        .filter(|note| archived || !vault::is_archived(note))
+        .filter_map(|note| {
+            let title = title(note).to_lowercase();
+            let path = note.to_string_lossy().to_lowercase();
+            let rank = if title.contains(&query) {
+                1
+            } else if fuzzy_match(&path, &query) {
+                2
+            } else {
+                return None;
+            };
+            Some((if metadata.get(note).favorite { 0 } else { rank }, note))
+        })
+        .collect();
+    scored.sort_by_key(|(rank, note)| (*rank, title(note).to_lowercase()));
+    scored.into_iter().take(LIMIT).map(|(_, note)| note.clone()).collect()
+}
+
+/// Ctrl+P window for jumping to a note by name.
+#[derive(Default)]
+pub struct QuickSwitcher {
+    pub open: bool,
+    query: String,
+    selected: usize,
+    /// Also lists notes in the archive.
+    include_archived: bool,
+}
+
+impl QuickSwitcher {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    /// Returns the note to open once one is picked.
+    pub fn show(&mut self, ctx: &egui::Context, notes: &[NoteInfo], metadata: &Metadata) -> Option<PathBuf> {
+        if !self.open {
+            return None;
+        }
+        let results = results(notes, metadata, &self.query, self.include_archived);
+        self.selected = self.selected.min(results.len().saturating_sub(1));
+        let (enter, escape) = ctx.input_mut(|i| {
+            if i.consume_key(Modifiers::NONE, Key::ArrowDown) {
+                self.selected = (self.selected + 1).min(results.len().saturating_sub(1));
+            }
+            if i.consume_key(Modifiers::NONE, Key::ArrowUp) {
+                self.selected = self.selected.saturating_sub(1);
+            }
+            (i.consume_key(Modifiers::NONE, Key::Enter), i.consume_key(Modifiers::NONE, Key::Escape))
+        });
+        let mut picked = enter.then(|| results.get(self.selected).cloned()).flatten();
+
+        egui::Window::new(t("Go to note"))
+            .title_bar(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
+            .fixed_size([420.0, 0.0])
+            .show(ctx, |ui| {
+                let input = ui.add(egui::TextEdit::singleline(&mut self.query).hint_text(t("Note name")).desired_width(f32::INFINITY));
+                input.request_focus();
+                if input.changed() {
+                    self.selected = 0;
+                }
+                ui.checkbox(&mut self.include_archived, t("Include archived"));
+                for (index, note) in results.iter().enumerate() {
+                    let mut label = title(note);
+                    if metadata.get(note).favorite {
+                        label = format!("⭐ {}", label);
+                    }
+                    if ui.selectable_label(index == self.selected, label).on_hover_text(note.display().to_string()).clicked() {
+                        picked = Some(note.clone());
+                    }
+                }
+                if results.is_empty() {
+                    ui.label(egui::RichText::new(t("No matching notes")).weak());
+                }
+            });
+
+        if picked.is_some() || escape {
+            self.open = false;
+        }
+        picked
+    }
+}
@@ -0,0 +1,311 @@
+use std::ops::Range;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Align {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+/// Edits offered by the Table menu, applied to the table at the cursor.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TableEdit {
+    Format,
+    InsertRowBelow,
+    DeleteRow,
+    InsertColumnRight,
+    DeleteColumn,
+    Align(Align),
+}
+
+pub const TEMPLATE: &str = "| Column 1 | Column 2 |\n| -------- | -------- |\n|          |          |\n";
+
+/// A pipe table. `rows[0]` is the header; the delimiter row is implied by
+/// `align`.
+#[derive(Debug, Clone)]
+pub struct Table {
+    /// Byte range of the table's lines, including the final line break.
+    pub range: Range<usize>,
+    pub rows: Vec<Vec<String>>,
+    pub align: Vec<Align>,
+}
+
+fn split_cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = if line.ends_with('|') && !line.ends_with("\\|") { &line[..line.len() - 1] } else { line };
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push_str("\\|");
+                chars.next();
+            }
+            '|' => cells.push(std::mem::take(&mut cell).trim().to_owned()),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_owned());
+    cells
+}
+
+fn parse_delimiter(line: &str) -> Option<Vec<Align>> {
+    if !line.contains('-') {
+        return None;
+    }
+    split_cells(line)
+        .iter()
+        .map(|cell| {
+            let dashes = cell.trim_matches(':');
+            if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+                return None;
+            }
+            Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+                (true, true) => Align::Center,
+                (true, false) => Align::Left,
+                (false, true) => Align::Right,
+                (false, false) => Align::None,
+            })
+        })
+        .collect()
+}
+
+/// Finds the pipe tables in `text`, skipping fenced code blocks.
+pub fn find_tables(text: &str) -> Vec<Table> {
+    let fences = crate::markdown::fenced_blocks(text);
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        lines.push((offset, line));
+        offset += line.len();
+    }
+
+    let mut tables = Vec::new();
+    let mut i = 0;
+    while i + 1 < lines.len() {
+        let (start, header) = lines[i];
+        let in_fence = fences.iter().any(|f| f.range.contains(&start));
+        let align = parse_delimiter(lines[i + 1].1);
+        match align {
+            Some(align) if !in_fence && header.contains('|') && lines[i + 1].1.contains('|') => {
+                let mut rows = vec![split_cells(header)];
+                let mut end = i + 2;
+                while end < lines.len() && lines[end].1.contains('|') && !lines[end].1.trim().is_empty() {
+                    rows.push(split_cells(lines[end].1));
+                    end += 1;
+                }
+                let range_end = lines.get(end).map_or(text.len(), |&(offset, _)| offset);
+                let mut table = Table {
+                    range: start..range_end,
+                    rows,
+                    align,
+                };
+                table.normalize();
+                tables.push(table);
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    tables
+}
+
+impl Table {
+    pub fn columns(&self) -> usize {
+        self.align.len()
+    }
+
+    /// Pads every row to the same number of columns.
+    fn normalize(&mut self) {
+        let columns = self.rows.iter().map(Vec::len).chain([self.align.len()]).max().unwrap_or(1);
+        self.align.resize(columns, Align::None);
+        for row in &mut self.rows {
+            row.resize(columns, String::new());
+        }
+    }
+
+    fn widths(&self) -> Vec<usize> {
+        (0..self.columns())
+            .map(|col| self.rows.iter().map(|row| row[col].chars().count()).max().unwrap_or(0).max(3))
+            .collect()
+    }
+
+    /// The table with every column padded to the same width.
+    pub fn to_markdown(&self) -> String {
+        let widths = self.widths();
+        let mut out = String::new();
+        for (index, row) in self.rows.iter().enumerate() {
+            out.push('|');
+            for (col, cell) in row.iter().enumerate() {
+                out.push(' ');
+                out.push_str(&pad(cell, widths[col], self.align[col]));
+                out.push_str(" |");
+            }
+            out.push('\n');
+            if index == 0 {
+                out.push('|');
+                for (col, width) in widths.iter().enumerate() {
+                    let (left, right) = match self.align[col] {
+                        Align::None => ("-", "-"),
+                        Align::Left => (":", "-"),
+                        Align::Center => (":", ":"),
+                        Align::Right => ("-", ":"),
+                    };
+                    out.push(' ');
+                    out.push_str(left);
+                    out.push_str(&"-".repeat(width - 2));
+                    out.push_str(right);
+                    out.push_str(" |");
+                }
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    /// Byte offset of a cell's text in `formatted` (this table's
+    /// `to_markdown()`).
+    fn cell_offset(&self, formatted: &str, row: usize, col: usize) -> usize {
+        let line = if row == 0 { 0 } else { row + 1 };
+        let line_start: usize = formatted.split_inclusive('\n').take(line).map(str::len).sum();
+        let text = &formatted[line_start..];
+        let mut pipes = text.match_indices('|').filter(|&(i, _)| i == 0 || !text[..i].ends_with('\\'));
+        let pipe = pipes.nth(col).map_or(0, |(i, _)| i);
+        let widths = self.widths();
+        line_start + pipe + 2 + leading_pad(&self.rows[row][col], widths[col], self.align[col])
+    }
+
+    /// The row and column the byte offset `at` (relative to the table's
+    /// source) falls in. The delimiter row counts as the header.
+    fn cell_at(&self, source: &str, at: usize) -> (usize, usize) {
+        let before = &source[..at.min(source.len())];
+        let line = before.matches('\n').count();
+        let row = line.saturating_sub(1).min(self.rows.len() - 1);
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let in_line = &before[line_start..];
+        let pipes = in_line.matches('|').count() - in_line.matches("\\|").count();
+        let col = if in_line.trim_start().starts_with('|') { pipes.saturating_sub(1) } else { pipes };
+        (row, col.min(self.columns() - 1))
+    }
+}
+
+fn leading_pad(cell: &str, width: usize, align: Align) -> usize {
+    let space = width - cell.chars().count();
+    match align {
+        Align::Right => space,
+        Align::Center => space / 2,
+        _ => 0,
+    }
+}
+
+fn pad(cell: &str, width: usize, align: Align) -> String {
+    let space = width - cell.chars().count();
+    let left = leading_pad(cell, width, align);
+    format!("{}{}{}", " ".repeat(left), cell, " ".repeat(space - left))
+}
+
+/// Reformats every pipe table in `text`.
+pub fn format_all(text: &str) -> String {
+    let mut out = text.to_owned();
+    for table in find_tables(text).into_iter().rev() {
+        let mut formatted = table.to_markdown();
+        if !text[table.range.clone()].ends_with('\n') {
+            formatted.pop();
+        }
+        out.replace_range(table.range.clone(), &formatted);
+    }
+    out
+}
+
+fn table_at(text: &str, cursor: usize) -> Option<Table> {
+    find_tables(text)
+        .into_iter()
+        .find(|t| t.range.contains(&cursor) || (t.range.end == cursor && !text[..cursor].ends_with('\n')))
+}
+
+/// Replaces the table in `text` with its formatted form and returns the new
+/// text and the byte offset of `(row, col)` in it.
+fn rewrite(text: &str, table: &Table, row: usize, col: usize) -> (String, usize) {
+    let mut formatted = table.to_markdown();
+    let cursor = table.range.start + table.cell_offset(&formatted, row, col);
+    if !text[table.range.clone()].ends_with('\n') {
+        formatted.pop();
+    }
+    let mut out = text.to_owned();
+    out.replace_range(table.range.clone(), &formatted);
+    (out, cursor)
+}
+
+/// Tab in a table: formats it and moves to the next cell (or the previous
+/// one with `backwards`), adding a row after the last. Returns the new text
+/// and cursor, or `None` when the cursor is not in a table.
+pub fn next_cell(text: &str, cursor: usize, backwards: bool) -> Option<(String, usize)> {
+    let mut table = table_at(text, cursor)?;
+    let (row, col) = table.cell_at(&text[table.range.clone()], cursor - table.range.start);
+    let columns = table.columns();
+    let (row, col) = if backwards {
+        match (row, col) {
+            (0, 0) => (0, 0),
+            (row, 0) => (row - 1, columns - 1),
+            (row, col) => (row, col - 1),
+        }
+    } else if col + 1 < columns {
+        (row, col + 1)
+    } else {
+        if row + 1 == table.rows.len() {
+            table.rows.push(vec![String::new(); columns]);
+        }
+        (row + 1, 0)
+    };
+    Some(rewrite(text, &table, row, col))
+}
+
+/// Applies a table edit at the cursor. Returns the new text and cursor, or
+/// `None` when the cursor is not in a table.
+pub fn apply(text: &str, cursor: usize, edit: TableEdit) -> Option<(String, usize)> {
+    let mut table = table_at(text, cursor)?;
+    let (mut row, mut col) = table.cell_at(&text[table.range.clone()], cursor - table.range.start);
+    let columns = table.columns();
+    match edit {
+        TableEdit::Format => {}
+        TableEdit::InsertRowBelow => {
+            row += 1;
+            table.rows.insert(row, vec![String::new(); columns]);
+        }
+        TableEdit::DeleteRow => {
+            if row == 0 || table.rows.len() <= 2 {
+                // Keep the header and at least one body row.
+                return None;
+            }
+            table.rows.remove(row);
+            row = row.min(table.rows.len() - 1);
+        }
+        TableEdit::InsertColumnRight => {
+            col += 1;
+            table.align.insert(col, Align::None);
+            for cells in &mut table.rows {
+                cells.insert(col, String::new());
+            }
+        }
+        TableEdit::DeleteColumn => {
+            if columns <= 1 {
+                return None;
+            }
+            table.align.remove(col);
+            for cells in &mut table.rows {
+                cells.remove(col);
+            }
+            col = col.min(columns - 2);
+        }
+        TableEdit::Align(align) => table.align[col] = align,
+    }
+    Some(rewrite(text, &table, row, col))
+}
+
+/// Whether the cursor is in a table, for enabling the table commands.
+pub fn in_table(text: &str, cursor: usize) -> bool {
+    table_at(text, cursor).is_some()
+}
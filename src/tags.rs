@@ -0,0 +1,63 @@
+use crate::frontmatter;
+
+/// Splits a front matter list, `[a, b]` or `a, b`, into its items.
+pub fn parse_list(value: &str) -> Vec<String> {
+    value
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(|item| item.trim().trim_matches(['"', '\'']).trim_start_matches('#').to_owned())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Tags listed in the front matter's `tags:` field.
+pub fn front_matter_tags(text: &str) -> Vec<String> {
+    frontmatter::get(text, "tags").map(|v| parse_list(&v)).unwrap_or_default()
+}
+
+/// `#tags` written in the body, outside code. A `#` must follow
+/// whitespace or start a line, and be followed by a letter, so headings
+/// and URL fragments are not mistaken for tags.
+pub fn inline_tags(text: &str) -> Vec<String> {
+    let fences = crate::markdown::fenced_blocks(text);
+    let start = frontmatter::range(text).map_or(0, |r| r.end);
+    let mut tags = Vec::new();
+    let mut offset = start;
+    for line in text[start..].split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        if fences.iter().any(|f| f.range.contains(&line_start)) {
+            continue;
+        }
+        let mut in_code = false;
+        let mut previous = ' ';
+        for (i, c) in line.char_indices() {
+            if c == '`' {
+                in_code = !in_code;
+            } else if c == '#' && !in_code && previous.is_whitespace() {
+                let rest = &line[i + 1..];
+                let len = rest
+                    .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '-' | '/')))
+                    .unwrap_or(rest.len());
+                if rest.chars().next().is_some_and(char::is_alphabetic) {
+                    tags.push(rest[..len].to_owned());
+                }
+            }
+            previous = c;
+        }
+    }
+    tags
+}
+
+/// Every tag of a note, front matter first, without duplicates.
+pub fn tags(text: &str) -> Vec<String> {
+    let mut all = front_matter_tags(text);
+    for tag in inline_tags(text) {
+        if !all.contains(&tag) {
+            all.push(tag);
+        }
+    }
+    all
+}
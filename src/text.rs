@@ -0,0 +1,82 @@
+use std::ops::Range;
+
+/// Byte offset of the `index`th char, clamped to the end of the text.
+pub fn char_to_byte(text: &str, index: usize) -> usize {
+    text.char_indices().nth(index).map_or(text.len(), |(i, _)| i)
+}
+
+/// The part of `new` that differs from `old`, as the byte range of `old`
+/// it replaces and the text replacing it.
+pub fn splice<'a>(old: &str, new: &'a str) -> (usize, usize, &'a str) {
+    let mut start = old.bytes().zip(new.bytes()).take_while(|(a, b)| a == b).count();
+    while !old.is_char_boundary(start) || !new.is_char_boundary(start) {
+        start -= 1;
+    }
+    let max_suffix = old.len().min(new.len()) - start;
+    let mut suffix = old.bytes().rev().zip(new.bytes().rev()).take(max_suffix).take_while(|(a, b)| a == b).count();
+    while !old.is_char_boundary(old.len() - suffix) || !new.is_char_boundary(new.len() - suffix) {
+        suffix -= 1;
+    }
+    (start, old.len() - suffix, &new[start..new.len() - suffix])
+}
+
+/// Byte range of the word around or just before `at`.
+pub fn word_at(text: &str, at: usize) -> Option<Range<usize>> {
+    let at = (0..=at.min(text.len())).rev().find(|&i| text.is_char_boundary(i))?;
+    let is_word = |c: char| c.is_alphanumeric() || matches!(c, '-' | '\'' | '’');
+    let start = text[..at].char_indices().rev().take_while(|&(_, c)| is_word(c)).last().map_or(at, |(i, _)| i);
+    let end = text[at..].find(|c: char| !is_word(c)).map_or(text.len(), |i| at + i);
+    // Dashes and quotes only count inside a word.
+    let span = &text[start..end];
+    let start = start + (span.len() - span.trim_start_matches(['-', '\'', '’']).len());
+    let end = end - (span.len() - span.trim_end_matches(['-', '\'', '’']).len());
+    (start < end).then_some(start..end)
+}
+
+/// Byte ranges where any of `terms` occur, ignoring case, in order and
+/// without overlaps. Terms are expected in lowercase.
+pub fn find_terms(text: &str, terms: &[String]) -> Vec<Range<usize>> {
+    let lower = text.to_lowercase();
+    // Lowercasing changed some lengths; offsets would not line up.
+    let haystack = if lower.len() == text.len() { lower.as_str() } else { text };
+    let mut ranges: Vec<Range<usize>> = terms
+        .iter()
+        .filter(|t| !t.is_empty())
+        .flat_map(|term| haystack.match_indices(term.as_str()).map(|(i, m)| i..i + m.len()))
+        .collect();
+    ranges.sort_by_key(|r| (r.start, std::cmp::Reverse(r.end)));
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in ranges {
+        if merged.last().is_none_or(|last| range.start >= last.end) {
+            merged.push(range);
+        }
+    }
+    merged
+}
+
+/// Byte ranges of the sentences in `text`: runs ending in `.`, `!` or `?`
+/// followed by whitespace, or at a line break. Surrounding space is trimmed.
+pub fn sentences(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, n)| n);
+        let end_of_sentence = matches!(c, '.' | '!' | '?') && next.is_none_or(char::is_whitespace);
+        if end_of_sentence || c == '\n' {
+            push_trimmed(text, start..i + c.len_utf8(), &mut ranges);
+            start = i + c.len_utf8();
+        }
+    }
+    push_trimmed(text, start..text.len(), &mut ranges);
+    ranges
+}
+
+fn push_trimmed(text: &str, range: Range<usize>, ranges: &mut Vec<Range<usize>>) {
+    let slice = &text[range.clone()];
+    let leading = slice.len() - slice.trim_start().len();
+    let trailing = slice.len() - slice.trim_end().len();
+    if leading < slice.len() {
+        ranges.push(range.start + leading..range.end - trailing);
+    }
+}
@@ -0,0 +1,231 @@
+use crate::vault::THEMES_DIR;
+use eframe::egui::{self, Color32, FontFamily, FontId, TextStyle};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{info, warn};
+
+/// How often the theme file is checked for changes.
+const RECHECK: Duration = Duration::from_secs(1);
+
+/// Colors of the app's chrome, as `#rrggbb` or `#rrggbbaa`.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Colors {
+    pub text: Option<String>,
+    pub weak_text: Option<String>,
+    pub background: Option<String>,
+    /// Side panels and bars.
+    pub panel: Option<String>,
+    pub window: Option<String>,
+    /// Buttons and other controls.
+    pub widget: Option<String>,
+    pub selection: Option<String>,
+    pub link: Option<String>,
+    pub warning: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Font sizes in points by text style.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct FontSizes {
+    pub body: Option<f32>,
+    pub heading: Option<f32>,
+    pub monospace: Option<f32>,
+    pub button: Option<f32>,
+    pub small: Option<f32>,
+}
+
+/// Colors of Markdown in the editor and the preview.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Syntax {
+    pub heading: Option<String>,
+    /// Bold and italic text.
+    pub emphasis: Option<String>,
+    /// Inline code and code blocks.
+    pub code: Option<String>,
+    pub quote: Option<String>,
+}
+
+/// A theme file from the themes folder, in TOML or JSON. Everything in it
+/// is optional and laid over egui's dark or light theme.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Theme {
+    pub dark: bool,
+    pub colors: Colors,
+    pub fonts: FontSizes,
+    pub syntax: Syntax,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            dark: true,
+            colors: Colors::default(),
+            fonts: FontSizes::default(),
+            syntax: Syntax::default(),
+        }
+    }
+}
+
+/// A color from a theme file, or `None` when unset or not a color.
+fn color(value: &Option<String>) -> Option<Color32> {
+    let value = value.as_deref()?.trim();
+    let hex = if value.starts_with('#') { value.to_owned() } else { format!("#{}", value) };
+    Color32::from_hex(&hex).ok()
+}
+
+impl Syntax {
+    pub fn heading(&self) -> Option<Color32> {
+        color(&self.heading)
+    }
+
+    pub fn emphasis(&self) -> Option<Color32> {
+        color(&self.emphasis)
+    }
+
+    pub fn code(&self) -> Option<Color32> {
+        color(&self.code)
+    }
+
+    pub fn quote(&self) -> Option<Color32> {
+        color(&self.quote)
+    }
+
+    pub fn is_set(&self) -> bool {
+        *self != Syntax::default()
+    }
+}
+
+impl Theme {
+    fn parse(path: &Path, text: &str) -> Result<Self, String> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => serde_json::from_str(text).map_err(|e| e.to_string()),
+            _ => toml::from_str(text).map_err(|e| e.to_string()),
+        }
+    }
+
+    /// The base visuals with this theme's colors laid over them.
+    pub fn visuals(&self) -> egui::Visuals {
+        let mut visuals = if self.dark { egui::Visuals::dark() } else { egui::Visuals::light() };
+        let colors = &self.colors;
+        if let Some(text) = color(&colors.text) {
+            visuals.override_text_color = Some(text);
+        }
+        if let Some(weak) = color(&colors.weak_text) {
+            visuals.widgets.noninteractive.fg_stroke.color = weak;
+        }
+        if let Some(background) = color(&colors.background) {
+            visuals.extreme_bg_color = background;
+        }
+        if let Some(panel) = color(&colors.panel) {
+            visuals.panel_fill = panel;
+        }
+        if let Some(window) = color(&colors.window) {
+            visuals.window_fill = window;
+        }
+        if let Some(widget) = color(&colors.widget) {
+            visuals.widgets.inactive.bg_fill = widget;
+            visuals.widgets.inactive.weak_bg_fill = widget;
+        }
+        if let Some(selection) = color(&colors.selection) {
+            visuals.selection.bg_fill = selection;
+        }
+        if let Some(link) = color(&colors.link) {
+            visuals.hyperlink_color = link;
+        }
+        if let Some(warning) = color(&colors.warning) {
+            visuals.warn_fg_color = warning;
+        }
+        if let Some(error) = color(&colors.error) {
+            visuals.error_fg_color = error;
+        }
+        visuals
+    }
+
+    /// Sets the font sizes the theme gives on top of `style`'s own.
+    pub fn apply_fonts(&self, style: &mut egui::Style) {
+        let sizes = [
+            (TextStyle::Body, self.fonts.body, FontFamily::Proportional),
+            (TextStyle::Heading, self.fonts.heading, FontFamily::Proportional),
+            (TextStyle::Monospace, self.fonts.monospace, FontFamily::Monospace),
+            (TextStyle::Button, self.fonts.button, FontFamily::Proportional),
+            (TextStyle::Small, self.fonts.small, FontFamily::Proportional),
+        ];
+        for (text_style, size, family) in sizes {
+            if let Some(size) = size.filter(|s| *s > 0.0) {
+                style.text_styles.insert(text_style, FontId::new(size, family));
+            }
+        }
+    }
+}
+
+/// Names of the theme files in the themes folder, without extensions.
+pub fn list() -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(THEMES_DIR)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == "toml" || e == "json"))
+        .filter_map(|path| path.file_stem().and_then(|s| s.to_str()).map(str::to_owned))
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn path_of(name: &str) -> Option<PathBuf> {
+    ["toml", "json"].iter().map(|ext| Path::new(THEMES_DIR).join(format!("{}.{}", name, ext))).find(|p| p.exists())
+}
+
+/// The chosen theme, read again whenever its file changes.
+#[derive(Default)]
+pub struct ThemeWatcher {
+    name: String,
+    modified: Option<SystemTime>,
+    checked: Option<Instant>,
+    pub theme: Theme,
+}
+
+impl ThemeWatcher {
+    /// Picks up a different choice at once and edits to the file within a
+    /// second. Returns whether the theme changed.
+    pub fn refresh(&mut self, name: &str) -> bool {
+        if name == self.name && self.checked.is_some_and(|at| at.elapsed() < RECHECK) {
+            return false;
+        }
+        self.checked = Some(Instant::now());
+        let path = path_of(name).filter(|_| !name.is_empty());
+        let modified = path.as_ref().and_then(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok());
+        if name == self.name && modified == self.modified {
+            return false;
+        }
+        let theme = match path.map(|p| (std::fs::read_to_string(&p), p)) {
+            Some((Ok(text), path)) => match Theme::parse(&path, &text) {
+                Ok(theme) => {
+                    info!("Loaded the theme {}", path.display());
+                    theme
+                }
+                Err(err) => {
+                    // Keep the last good version while the file is being edited.
+                    warn!("Failed to read the theme {}: {}", path.display(), err);
+                    self.theme.clone()
+                }
+            },
+            Some((Err(err), path)) => {
+                warn!("Failed to read the theme {}: {}", path.display(), err);
+                self.theme.clone()
+            }
+            None => Theme::default(),
+        };
+        self.name = name.to_owned();
+        self.modified = modified;
+        let changed = theme != self.theme;
+        self.theme = theme;
+        changed
+    }
+}
@@ -0,0 +1,188 @@
+use crate::i18n::{t, tf};
+use eframe::egui;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::OnceLock;
+use tracing::error;
+
+/// One meaning of a word and the words sharing it.
+#[derive(Clone)]
+pub struct Sense {
+    /// Part of speech, or empty when the file does not say.
+    pub part: String,
+    pub words: Vec<String>,
+}
+
+/// Synonyms from an offline thesaurus file: a MyThes `.dat` file as
+/// LibreOffice ships them (WordNet for English), or an OpenThesaurus text
+/// export with one `;`-separated group per line.
+pub struct Thesaurus {
+    pub path: String,
+    /// Read once, off the UI thread.
+    entries: OnceLock<HashMap<String, Vec<Sense>>>,
+}
+
+/// Drops notes like "(ugs.)" or "(generic term)" from an entry.
+fn clean(word: &str) -> String {
+    let mut out = String::new();
+    let mut depth = 0;
+    for c in word.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ if depth == 0 => out.push(c),
+            _ => {}
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn parse_mythes(text: &str) -> HashMap<String, Vec<Sense>> {
+    let mut entries = HashMap::new();
+    // The first line names the encoding.
+    let mut lines = text.lines().skip(1);
+    while let Some(line) = lines.next() {
+        let Some((word, count)) = line.rsplit_once('|') else {
+            continue;
+        };
+        let count = count.trim().parse::<usize>().unwrap_or(0);
+        let senses: Vec<Sense> = lines
+            .by_ref()
+            .take(count)
+            .filter_map(|line| {
+                let mut fields = line.split('|');
+                let part = fields.next()?.trim().trim_matches(['(', ')']).to_owned();
+                let words: Vec<String> = fields.map(clean).filter(|w| !w.is_empty()).collect();
+                (!words.is_empty()).then_some(Sense { part, words })
+            })
+            .collect();
+        if !senses.is_empty() {
+            entries.insert(word.trim().to_lowercase(), senses);
+        }
+    }
+    entries
+}
+
+fn parse_groups(text: &str) -> HashMap<String, Vec<Sense>> {
+    let mut entries: HashMap<String, Vec<Sense>> = HashMap::new();
+    for line in text.lines().filter(|l| !l.starts_with('#')) {
+        let group: Vec<String> = line.split(';').map(clean).filter(|w| !w.is_empty()).collect();
+        for word in &group {
+            let words: Vec<String> = group.iter().filter(|w| *w != word).cloned().collect();
+            if !words.is_empty() {
+                entries.entry(word.to_lowercase()).or_default().push(Sense { part: String::new(), words });
+            }
+        }
+    }
+    entries
+}
+
+impl Thesaurus {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_owned(),
+            entries: OnceLock::new(),
+        }
+    }
+
+    /// Reads the file if it has not been read yet.
+    pub fn load(&self) {
+        self.entries.get_or_init(|| match std::fs::read_to_string(&self.path) {
+            Ok(text) if text.lines().nth(1).is_some_and(|l| l.contains('|')) => parse_mythes(&text),
+            Ok(text) => parse_groups(&text),
+            Err(err) => {
+                error!("Failed to read the thesaurus {}: {}", self.path, err);
+                HashMap::new()
+            }
+        });
+    }
+
+    /// The meanings of `word`, or `None` while the file is still being read.
+    pub fn lookup(&self, word: &str) -> Option<&[Sense]> {
+        let entries = self.entries.get()?;
+        Some(entries.get(&word.to_lowercase()).map_or(&[], Vec::as_slice))
+    }
+}
+
+/// `replacement` capitalized like `original`.
+fn same_case(original: &str, replacement: &str) -> String {
+    if original.starts_with(char::is_uppercase) {
+        let mut chars = replacement.chars();
+        chars.next().map_or_else(String::new, |c| c.to_uppercase().chain(chars).collect())
+    } else {
+        replacement.to_owned()
+    }
+}
+
+/// Synonyms of the word at the cursor, grouped by meaning; clicking one
+/// puts it in place of the word.
+#[derive(Default)]
+pub struct SynonymsView {
+    pub open: bool,
+    /// Byte range of the word in the note.
+    word: Range<usize>,
+    pos: Option<egui::Pos2>,
+}
+
+impl SynonymsView {
+    pub fn open_for(&mut self, note: &str, cursor: usize, pos: Option<egui::Pos2>) {
+        if let Some(word) = crate::text::word_at(note, cursor) {
+            self.word = word;
+            self.pos = pos;
+            self.open = true;
+        }
+    }
+
+    /// Returns the word's range and the synonym picked for it.
+    pub fn show(&mut self, ctx: &egui::Context, note: &str, thesaurus: &Thesaurus) -> Option<(Range<usize>, String)> {
+        if !self.open {
+            return None;
+        }
+        let Some(word) = note.get(self.word.clone()) else {
+            self.open = false;
+            return None;
+        };
+        let mut picked = None;
+        let mut open = true;
+        let mut window = egui::Window::new(tf("📚 Synonyms for \"{}\"", &[&word]))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .default_width(280.0);
+        if let Some(pos) = self.pos {
+            window = window.default_pos(pos);
+        }
+        window.show(ctx, |ui| match thesaurus.lookup(word) {
+            None => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(t("Reading the thesaurus…"));
+                });
+                ctx.request_repaint_after(std::time::Duration::from_millis(200));
+            }
+            Some([]) => {
+                ui.weak(t("No synonyms found."));
+            }
+            Some(senses) => {
+                egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                    for sense in senses {
+                        if !sense.part.is_empty() {
+                            ui.label(egui::RichText::new(&sense.part).small().weak());
+                        }
+                        ui.horizontal_wrapped(|ui| {
+                            for synonym in &sense.words {
+                                if ui.button(synonym).clicked() {
+                                    picked = Some((self.word.clone(), same_case(word, synonym)));
+                                }
+                            }
+                        });
+                    }
+                });
+            }
+        });
+        if picked.is_some() || !open || ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.open = false;
+        }
+        picked
+    }
+}
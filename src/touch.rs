@@ -0,0 +1,196 @@
+use eframe::egui::{self, Pos2, Vec2};
+use std::ops::Range;
+
+/// How far a finger has to travel sideways for a swipe.
+const SWIPE_DISTANCE: f32 = 80.0;
+/// Swipes that open the note list start this close to the left edge, so
+/// dragging across text still selects it.
+const EDGE: f32 = 32.0;
+
+/// Sizes controls for fingers instead of a mouse pointer, or back.
+pub fn apply_style(ctx: &egui::Context, touch: bool) {
+    let mut spacing = egui::style::Spacing::default();
+    if touch {
+        spacing.interact_size = Vec2::new(48.0, 40.0);
+        spacing.button_padding = Vec2::new(12.0, 8.0);
+        spacing.item_spacing = Vec2::new(10.0, 8.0);
+        spacing.icon_width = 24.0;
+        spacing.icon_width_inner = 14.0;
+        spacing.scroll.bar_width = 16.0;
+        spacing.combo_height = 320.0;
+    }
+    if ctx.style().spacing.interact_size != spacing.interact_size {
+        ctx.style_mut(|style| style.spacing = spacing);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Swipe {
+    /// From the left edge towards the right: bring in the note list.
+    Right,
+    Left,
+}
+
+/// Follows a finger on the screen and reports horizontal swipes once it
+/// lifts.
+#[derive(Default)]
+pub struct SwipeDetector {
+    start: Option<Pos2>,
+    last: Option<Pos2>,
+}
+
+impl SwipeDetector {
+    pub fn detect(&mut self, ctx: &egui::Context) -> Option<Swipe> {
+        ctx.input(|i| {
+            if i.any_touches() {
+                if let Some(pos) = i.pointer.latest_pos() {
+                    self.start.get_or_insert(pos);
+                    self.last = Some(pos);
+                }
+                return None;
+            }
+            let (start, end) = (self.start.take()?, self.last.take()?);
+            let delta = end - start;
+            if delta.x.abs() < SWIPE_DISTANCE || delta.x.abs() < 2.0 * delta.y.abs() {
+                None
+            } else if delta.x > 0.0 {
+                (start.x <= i.screen_rect().left() + EDGE).then_some(Swipe::Right)
+            } else {
+                Some(Swipe::Left)
+            }
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Bold,
+    Italic,
+    Code,
+    Link,
+    /// Cycles the line through `#`, `##`, `###` and back to plain text.
+    Heading,
+    Bullet,
+    Task,
+}
+
+/// The formatting bar shown along the bottom in touch mode, which is just
+/// above the on-screen keyboard while it is up.
+pub fn show_bar(ctx: &egui::Context) -> Option<Format> {
+    let mut clicked = None;
+    egui::TopBottomPanel::bottom("format_bar").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            let buttons = [
+                (egui::RichText::new("B").strong(), Format::Bold, "Bold"),
+                (egui::RichText::new("I").italics(), Format::Italic, "Italic"),
+                (egui::RichText::new("H"), Format::Heading, "Heading"),
+                (egui::RichText::new("•"), Format::Bullet, "Bullet list"),
+                (egui::RichText::new("☐"), Format::Task, "Task"),
+                (egui::RichText::new("🔗"), Format::Link, "Link"),
+                (egui::RichText::new("</>").monospace(), Format::Code, "Inline code"),
+            ];
+            for (label, format, hint) in buttons {
+                if ui.button(label).on_hover_text(hint).clicked() {
+                    clicked = Some(format);
+                }
+            }
+        });
+    });
+    clicked
+}
+
+/// Applies `format` to the byte range `selection` of `text`. Returns the new
+/// text and the range to select in it.
+pub fn apply(text: &str, selection: Range<usize>, format: Format) -> (String, Range<usize>) {
+    match format {
+        Format::Bold => wrap(text, selection, "**", "**"),
+        Format::Italic => wrap(text, selection, "*", "*"),
+        Format::Code => wrap(text, selection, "`", "`"),
+        Format::Link => {
+            let mut out = text.to_owned();
+            out.insert_str(selection.end, "]()");
+            out.insert(selection.start, '[');
+            // Into the brackets for a title, or the parentheses for the URL.
+            let at = if selection.is_empty() { selection.start + 1 } else { selection.end + 3 };
+            (out, at..at)
+        }
+        Format::Heading | Format::Bullet | Format::Task => prefix_lines(text, selection, format),
+    }
+}
+
+/// Surrounds the selection with markers, or removes them when they are
+/// already there.
+fn wrap(text: &str, selection: Range<usize>, open: &str, close: &str) -> (String, Range<usize>) {
+    let before = &text[..selection.start];
+    let after = &text[selection.end..];
+    // `*` right inside `**` is bold, not italic.
+    let wrapped = before.strip_suffix(open).is_some_and(|b| !b.ends_with(open))
+        && after.strip_prefix(close).is_some_and(|a| !a.starts_with(close));
+    if wrapped {
+        let start = selection.start - open.len();
+        let out = format!("{}{}{}", &before[..start], &text[selection.clone()], &after[close.len()..]);
+        return (out, start..selection.end - open.len());
+    }
+    let out = format!("{}{}{}{}{}", before, open, &text[selection.clone()], close, after);
+    (out, selection.start + open.len()..selection.end + open.len())
+}
+
+/// The marker a line starts with for `format` after its indentation, as
+/// the length of what it has now and what it should have instead.
+fn line_marker(body: &str, format: Format) -> (usize, &'static str) {
+    let task = ["- [ ] ", "- [x] ", "- [X] "].iter().any(|m| body.starts_with(m));
+    let bullet = body.starts_with("- ") || body.starts_with("* ");
+    match format {
+        Format::Heading => {
+            let level = body.len() - body.trim_start_matches('#').len();
+            let marker = if body[level..].starts_with(' ') { level } else { 0 };
+            let next = match marker {
+                0 => "# ",
+                1 => "## ",
+                2 => "### ",
+                _ => "",
+            };
+            (if marker == 0 { 0 } else { marker + 1 }, next)
+        }
+        Format::Bullet if task => (6, "- "),
+        Format::Bullet if bullet => (2, ""),
+        Format::Bullet => (0, "- "),
+        Format::Task if task => (6, ""),
+        Format::Task if bullet => (2, "- [ ] "),
+        _ => (0, "- [ ] "),
+    }
+}
+
+/// Updates the markers of every line the selection touches.
+fn prefix_lines(text: &str, selection: Range<usize>, format: Format) -> (String, Range<usize>) {
+    let first = text[..selection.start].rfind('\n').map_or(0, |i| i + 1);
+    let rest = &text[first..];
+    let lines: Vec<&str> = if rest.is_empty() { vec![""] } else { rest.split_inclusive('\n').collect() };
+    let mut out = text[..first].to_owned();
+    let (mut start, mut end) = (None, None);
+    let mut pos = first;
+    for line in lines {
+        if pos > first && pos >= selection.end {
+            break;
+        }
+        let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+        let (old, new) = line_marker(&line[indent..], format);
+        let old = indent + old;
+        // Where a position in this line ends up: text inside the replaced
+        // marker moves to just after the new one.
+        let moved = |at: usize| out.len() + indent + new.len() + (at - pos).saturating_sub(old);
+        if start.is_none() {
+            start = Some(moved(selection.start));
+        }
+        if end.is_none() && selection.end <= pos + line.len() {
+            end = Some(moved(selection.end));
+        }
+        out.push_str(&line[..indent]);
+        out.push_str(new);
+        out.push_str(&line[old..]);
+        pos += line.len();
+    }
+    out.push_str(&text[pos..]);
+    let start = start.unwrap_or(out.len());
+    (out, start..end.unwrap_or(start).max(start))
+}
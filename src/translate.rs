@@ -0,0 +1,178 @@
+use crate::config::{TranslateConfig, TranslateProvider};
+use crate::i18n::t;
+use crate::jobs::{self, JobHandle};
+use eframe::egui;
+use serde_json::{Value, json};
+use std::ops::Range;
+use std::sync::mpsc::TryRecvError;
+
+pub const LANGUAGES: [(&str, &str); 10] = [
+    ("EN", "English"),
+    ("DE", "German"),
+    ("FR", "French"),
+    ("ES", "Spanish"),
+    ("IT", "Italian"),
+    ("PT", "Portuguese"),
+    ("NL", "Dutch"),
+    ("PL", "Polish"),
+    ("JA", "Japanese"),
+    ("ZH", "Chinese"),
+];
+
+pub fn translate(config: &TranslateConfig, text: &str, target: &str) -> Result<String, String> {
+    let client = crate::http::client();
+    let request = match config.provider {
+        TranslateProvider::DeepL => client
+            .post(&config.endpoint)
+            .header("Authorization", format!("DeepL-Auth-Key {}", config.api_key))
+            .form(&[("text", text), ("target_lang", target)]),
+        TranslateProvider::LibreTranslate => client
+            .post(&config.endpoint)
+            .json(&json!({
+                "q": text,
+                "source": "auto",
+                "target": target.to_lowercase(),
+                "format": "text",
+                "api_key": config.api_key,
+            })),
+    };
+    let body: Value = crate::http::send(request)
+        .and_then(|r| r.error_for_status())
+        .and_then(|r| r.json())
+        .map_err(|e| e.to_string())?;
+
+    let translated = match config.provider {
+        TranslateProvider::DeepL => body.pointer("/translations/0/text"),
+        TranslateProvider::LibreTranslate => body.get("translatedText"),
+    };
+    translated
+        .and_then(Value::as_str)
+        .map(str::to_owned)
+        .ok_or_else(|| "Unexpected response from translation service".to_owned())
+}
+
+pub enum TranslateEdit {
+    Replace(Range<usize>, String),
+    InsertAfter(usize, String),
+}
+
+/// Side-by-side translation window for the selection or the whole note.
+#[derive(Default)]
+pub struct TranslateView {
+    pub open: bool,
+    /// Byte range of the note being translated, and the text it held then.
+    source: Range<usize>,
+    source_text: String,
+    result: Option<Result<String, String>>,
+    running: Option<JobHandle<Result<String, String>>>,
+}
+
+impl TranslateView {
+    pub fn open_for(&mut self, note: &str, selection: Option<Range<usize>>) {
+        self.source = selection
+            .filter(|r| !r.is_empty() && note.get(r.clone()).is_some())
+            .unwrap_or(0..note.len());
+        self.source_text = note[self.source.clone()].to_owned();
+        self.result = None;
+        self.running = None;
+        self.open = true;
+    }
+
+    /// Where the translated text is in `note` now: where it was, or the
+    /// occurrence nearest to that after edits moved it.
+    fn source_in(&self, note: &str) -> Option<Range<usize>> {
+        if note.get(self.source.clone()) == Some(self.source_text.as_str()) {
+            return Some(self.source.clone());
+        }
+        let at = note.match_indices(&self.source_text).map(|(at, _)| at).min_by_key(|at| at.abs_diff(self.source.start))?;
+        Some(at..at + self.source_text.len())
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, config: &mut TranslateConfig, note: &str) -> Option<TranslateEdit> {
+        if let Some(running) = &self.running {
+            match running.try_recv() {
+                Ok(result) => {
+                    self.result = Some(result);
+                    self.running = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.running = None,
+            }
+        }
+        if !self.open {
+            return None;
+        }
+        let source = self.source_text.clone();
+        let mut edit = None;
+        let mut open = true;
+
+        egui::Window::new(t("🔤 Translate"))
+            .open(&mut open)
+            .default_width(640.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(t("Into"));
+                    let current = LANGUAGES
+                        .iter()
+                        .find(|(code, _)| *code == config.target_lang)
+                        .map_or(config.target_lang.as_str(), |(_, name)| name);
+                    egui::ComboBox::from_id_source("translate_target")
+                        .selected_text(current)
+                        .show_ui(ui, |ui| {
+                            for (code, name) in LANGUAGES {
+                                ui.selectable_value(&mut config.target_lang, code.to_owned(), name);
+                            }
+                        });
+                    if let Some(running) = &self.running {
+                        running.show_progress(ui);
+                    } else if ui.button(t("Translate")).clicked() {
+                        let (config, source) = (config.clone(), source.clone());
+                        self.result = None;
+                        self.running = Some(jobs::spawn(t("Translating"), move |_| translate(&config, &source, &config.target_lang)));
+                    }
+                });
+                ui.separator();
+                ui.columns(2, |cols| {
+                    egui::ScrollArea::vertical().id_source("translate_source").max_height(360.0).show(&mut cols[0], |ui| {
+                        ui.label(&source);
+                    });
+                    match &self.result {
+                        Some(Ok(text)) => {
+                            egui::ScrollArea::vertical().id_source("translate_result").max_height(360.0).show(&mut cols[1], |ui| {
+                                ui.label(text);
+                            });
+                        }
+                        Some(Err(err)) => {
+                            cols[1].colored_label(egui::Color32::LIGHT_RED, err);
+                        }
+                        None => {
+                            cols[1].weak(t("Choose a language and press Translate."));
+                        }
+                    }
+                });
+                if let Some(Ok(text)) = &self.result {
+                    ui.separator();
+                    match self.source_in(note) {
+                        Some(range) => {
+                            ui.horizontal(|ui| {
+                                if ui.button(t("Replace")).clicked() {
+                                    edit = Some(TranslateEdit::Replace(range.clone(), text.clone()));
+                                }
+                                if ui.button(t("Insert below")).clicked() {
+                                    edit = Some(TranslateEdit::InsertAfter(range.end, format!("\n\n{}", text)));
+                                }
+                            });
+                        }
+                        None => {
+                            ui.colored_label(ui.visuals().warn_fg_color, t("The translated text was changed in the note since; translate it again"));
+                        }
+                    }
+                }
+            });
+
+        if edit.is_some() || !open {
+            self.open = false;
+        }
+        edit
+    }
+}
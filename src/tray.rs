@@ -0,0 +1,113 @@
+use eframe::egui;
+use std::sync::mpsc::Receiver;
+
+#[cfg_attr(not(feature = "tray"), allow(dead_code))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrayAction {
+    NewNote,
+    QuickCapture,
+    OpenLastNote,
+    Quit,
+}
+
+/// Keeps the tray icon alive; dropping it removes the icon.
+pub struct Tray {
+    pub actions: Receiver<TrayAction>,
+    #[cfg(all(feature = "tray", not(target_os = "linux")))]
+    _icon: tray_icon::TrayIcon,
+}
+
+#[cfg(not(feature = "tray"))]
+pub fn start(_ctx: &egui::Context) -> Option<Tray> {
+    None
+}
+
+#[cfg(feature = "tray")]
+pub fn start(ctx: &egui::Context) -> Option<Tray> {
+    use tray_icon::menu::MenuEvent;
+
+    let (tx, actions) = std::sync::mpsc::channel();
+    let repaint = ctx.clone();
+    let items = imp::ITEMS;
+    MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+        if let Some((_, action)) = items.iter().find(|(id, _)| event.id == *id) {
+            let _ = tx.send(*action);
+            repaint.request_repaint();
+        }
+    }));
+
+    // GTK owns the tray on Linux and needs its own main loop.
+    #[cfg(target_os = "linux")]
+    {
+        std::thread::spawn(|| {
+            if let Err(err) = gtk::init() {
+                tracing::warn!("Tray unavailable: {}", err);
+                return;
+            }
+            match imp::build() {
+                Ok(_icon) => gtk::main(),
+                Err(err) => tracing::warn!("Tray unavailable: {}", err),
+            }
+        });
+        Some(Tray { actions })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    match imp::build() {
+        Ok(icon) => Some(Tray { actions, _icon: icon }),
+        Err(err) => {
+            tracing::warn!("Tray unavailable: {}", err);
+            None
+        }
+    }
+}
+
+#[cfg(feature = "tray")]
+mod imp {
+    use super::TrayAction;
+    use crate::i18n::t;
+    use tray_icon::menu::{Menu, MenuItem, PredefinedMenuItem};
+    use tray_icon::{Icon, TrayIcon, TrayIconBuilder};
+
+    pub const ITEMS: [(&str, TrayAction); 4] = [
+        ("new_note", TrayAction::NewNote),
+        ("quick_capture", TrayAction::QuickCapture),
+        ("open_last", TrayAction::OpenLastNote),
+        ("quit", TrayAction::Quit),
+    ];
+
+    pub fn build() -> Result<TrayIcon, Box<dyn std::error::Error>> {
+        let menu = Menu::with_items(&[
+            &MenuItem::with_id(ITEMS[0].0, t("New Note"), true, None),
+            &MenuItem::with_id(ITEMS[1].0, t("Quick Capture"), true, None),
+            &MenuItem::with_id(ITEMS[2].0, t("Open Last Note"), true, None),
+            &PredefinedMenuItem::separator(),
+            &MenuItem::with_id(ITEMS[3].0, t("Quit"), true, None),
+        ])?;
+        Ok(TrayIconBuilder::new()
+            .with_menu(Box::new(menu))
+            .with_tooltip("Rust Note App")
+            .with_icon(icon()?)
+            .build()?)
+    }
+
+    /// A plain 16×16 note glyph, so no image decoder is needed.
+    fn icon() -> Result<Icon, tray_icon::BadIcon> {
+        let size = 16;
+        let mut rgba = Vec::with_capacity(size * size * 4);
+        for y in 0..size {
+            for x in 0..size {
+                let border = x == 2 || x == 13 || y == 1 || y == 14;
+                let line = (4..=11).contains(&x) && matches!(y, 4 | 7 | 10);
+                let inside = (2..=13).contains(&x) && (1..=14).contains(&y);
+                let pixel: [u8; 4] = match (inside, border || line) {
+                    (true, true) => [40, 40, 40, 255],
+                    (true, false) => [250, 230, 140, 255],
+                    _ => [0, 0, 0, 0],
+                };
+                rgba.extend_from_slice(&pixel);
+            }
+        }
+        Icon::from_rgba(rgba, size as u32, size as u32)
+    }
+}
@@ -0,0 +1,115 @@
+use std::ops::Range;
+
+/// A link in a note: `[text](target)`, `<target>` or a bare URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    /// Byte range of the whole link as written.
+    pub range: Range<usize>,
+    pub target: String,
+    /// Written as a plain URL, not yet wrapped in Markdown.
+    pub bare: bool,
+}
+
+const SCHEMES: [&str; 4] = ["https://", "http://", "mailto:", "noteapp://"];
+
+pub fn is_url(text: &str) -> bool {
+    let text = text.trim();
+    SCHEMES.iter().any(|s| text.starts_with(s)) && !text.contains(char::is_whitespace)
+}
+
+/// Length of a bare URL starting at `text`, leaving out trailing
+/// punctuation and an unbalanced closing parenthesis.
+fn url_len(text: &str) -> usize {
+    let end = text.find(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"')).unwrap_or(text.len());
+    let mut url = text[..end].trim_end_matches(['.', ',', ';', ':', '!', '?', '\'', '*', '_']);
+    while url.ends_with(')') && url.matches('(').count() < url.matches(')').count() {
+        url = &url[..url.len() - 1];
+    }
+    url.len()
+}
+
+/// Every link in `text`, in order, skipping fenced code blocks and code spans.
+pub fn links(text: &str) -> Vec<Link> {
+    let fences = crate::markdown::fenced_blocks(text);
+    let mut links = Vec::new();
+    let mut i = 0;
+    while i < text.len() {
+        if let Some(fence) = fences.iter().find(|f| f.range.contains(&i)) {
+            i = fence.range.end;
+            continue;
+        }
+        let rest = &text[i..];
+        let c = rest.chars().next().unwrap_or_default();
+        if c == '`' {
+            let ticks = rest.len() - rest.trim_start_matches('`').len();
+            let close = rest[ticks..].find(&rest[..ticks]).map_or(ticks, |end| ticks + end + ticks);
+            i += close;
+            continue;
+        }
+        if c == '['
+            && let Some(close) = rest.find("](")
+            && !rest[1..close].contains('\n')
+            && let Some(end) = rest[close + 2..].find(')')
+        {
+            let target = rest[close + 2..close + 2 + end].trim();
+            let target = target.split_once(" \"").map_or(target, |(url, _)| url);
+            links.push(Link {
+                range: i..i + close + 2 + end + 1,
+                target: target.trim_matches(['<', '>']).to_owned(),
+                bare: false,
+            });
+            i += close + 2 + end + 1;
+            continue;
+        }
+        if c == '<'
+            && let Some(end) = rest.find('>')
+            && is_url(&rest[1..end])
+        {
+            links.push(Link {
+                range: i..i + end + 1,
+                target: rest[1..end].to_owned(),
+                bare: false,
+            });
+            i += end + 1;
+            continue;
+        }
+        let at_word_start = text[..i].chars().next_back().is_none_or(|p| !p.is_alphanumeric());
+        if at_word_start && SCHEMES.iter().any(|s| rest.starts_with(s)) {
+            let len = url_len(rest);
+            if len > 0 {
+                links.push(Link {
+                    range: i..i + len,
+                    target: rest[..len].to_owned(),
+                    bare: true,
+                });
+                i += len;
+                continue;
+            }
+        }
+        i += c.len_utf8();
+    }
+    links
+}
+
+/// The link under a byte offset, if any.
+pub fn link_at(text: &str, at: usize) -> Option<Link> {
+    links(text).into_iter().find(|l| l.range.start <= at && at < l.range.end)
+}
+
+/// Wraps bare URLs in angle brackets so every Markdown renderer links them.
+pub fn fix_bare_urls(text: &str) -> String {
+    let mut out = text.to_owned();
+    for link in links(text).into_iter().rev().filter(|l| l.bare) {
+        out.replace_range(link.range, &format!("<{}>", link.target));
+    }
+    out
+}
+
+/// `[selection](url)`, or `<url>` when nothing is selected.
+pub fn make_link(selection: &str, url: &str) -> String {
+    if selection.is_empty() {
+        format!("<{}>", url)
+    } else {
+        format!("[{}]({})", selection, url)
+    }
+}
@@ -0,0 +1,165 @@
+use std::path::{Component, Path, PathBuf};
+
+pub const NOTES_DIR: &str = "notes";
+pub const HISTORY_DIR: &str = "notes/.history";
+/// Change journal: a log of note revisions and their contents by hash.
+pub const JOURNAL_DIR: &str = "notes/.history/journal";
+pub const DAILY_DIR: &str = "notes/daily";
+pub const ATTACHMENTS_DIR: &str = "notes/attachments";
+pub const ARCHIVE_DIR: &str = "notes/archive";
+pub const CONFIG_FILE: &str = "config.json";
+pub const LOG_DIR: &str = "logs";
+/// Translations of the UI as gettext `.po` files named by language code.
+pub const LOCALES_DIR: &str = "locales";
+/// Theme files in TOML or JSON, picked in the settings.
+pub const THEMES_DIR: &str = "themes";
+pub const METADATA_FILE: &str = "notes/.metadata.json";
+pub const FLASHCARDS_FILE: &str = "notes/.flashcards.json";
+pub const SCRATCHPAD_FILE: &str = "notes/.scratchpad.md";
+pub const LAN_SYNC_FILE: &str = "notes/.lansync.json";
+pub const RECOVERY_FILE: &str = "notes/.recovery.jsonl";
+/// Unsaved edits from a run that crashed, until they are restored or dropped.
+pub const RECOVERY_CRASHED_FILE: &str = "notes/.recovery-crashed.jsonl";
+
+pub fn daily_note_path(date: chrono::NaiveDate) -> PathBuf {
+    Path::new(DAILY_DIR).join(format!("{}.md", date.format("%Y-%m-%d")))
+}
+
+/// Recursively collects every file below `dir`, in a stable order.
+pub fn walk_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Markdown and text files, outside hidden folders like `.history`.
+pub fn is_note(path: &Path) -> bool {
+    let hidden = path
+        .components()
+        .any(|c| c.as_os_str().to_str().is_some_and(|s| s.starts_with('.')));
+    let text = path.extension().is_some_and(|e| e == "md" || e == "txt");
+    text && !hidden
+}
+
+/// Every note in the vault.
+pub fn note_files() -> Vec<PathBuf> {
+    walk_files(Path::new(NOTES_DIR)).into_iter().filter(|p| is_note(p)).collect()
+}
+
+/// A note's path relative to the notes folder, with `/` separators, for
+/// keying per-note state. Works for absolute paths into the vault too.
+pub fn note_key(path: &Path) -> String {
+    let relative = match path.strip_prefix(NOTES_DIR) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => match (path.canonicalize(), Path::new(NOTES_DIR).canonicalize()) {
+            (Ok(path), Ok(root)) => path.strip_prefix(&root).map(Path::to_path_buf).unwrap_or(path),
+            _ => path.to_path_buf(),
+        },
+    };
+    relative.to_string_lossy().replace('\\', "/")
+}
+
+/// Saved earlier versions of a note: files in the history folder named
+/// after it.
+pub fn snapshots(note: &Path) -> Vec<PathBuf> {
+    let Some(stem) = note.file_stem().and_then(|s| s.to_str()) else {
+        return Vec::new();
+    };
+    walk_files(Path::new(HISTORY_DIR))
+        .into_iter()
+        .filter(|p| !p.starts_with(JOURNAL_DIR))
+        .filter(|p| p.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with(stem)))
+        .collect()
+}
+
+/// Whether a note has been moved into the archive.
+pub fn is_archived(path: &Path) -> bool {
+    note_key(path).starts_with("archive/")
+}
+
+/// Resolves `.` and `..` in a path without touching the file system, so
+/// links spelled differently compare equal.
+pub fn clean_path(path: &Path) -> PathBuf {
+    let mut clean = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir if clean.file_name().is_some() => {
+                clean.pop();
+            }
+            other => clean.push(other),
+        }
+    }
+    clean
+}
+
+/// Turns a title into something usable as a file name on every platform.
+pub fn safe_file_name(title: &str) -> String {
+    let cleaned: String = title
+        .chars()
+        .map(|c| if "/\\:*?\"<>|".contains(c) || c.is_control() { '-' } else { c })
+        .collect();
+    let trimmed = cleaned.trim().trim_matches('.');
+    if trimmed.is_empty() {
+        "Untitled".to_owned()
+    } else {
+        trimmed.chars().take(80).collect()
+    }
+}
+
+/// `dir/stem.ext`, or `dir/stem 2.ext` and so on if that already exists;
+/// without the `.ext` when `ext` is empty.
+pub fn unique_path(dir: &Path, stem: &str, ext: &str) -> PathBuf {
+    let file = |name: String| if ext.is_empty() { dir.join(name) } else { dir.join(format!("{}.{}", name, ext)) };
+    let mut path = file(stem.to_owned());
+    let mut n = 2;
+    while path.exists() {
+        path = file(format!("{} {}", stem, n));
+        n += 1;
+    }
+    path
+}
+
+/// `to` relative to the directory `from`, with `/` separators.
+pub fn relative_path(from: &Path, to: &Path) -> String {
+    let (from, to) = (clean_path(from), clean_path(to));
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut parts: Vec<String> = vec!["..".to_owned(); from.len() - common];
+    parts.extend(to[common..].iter().map(|c| c.as_os_str().to_string_lossy().into_owned()));
+    parts.join("/")
+}
+
+/// Copies `source` into the attachments folder of `note` (a new, unsaved
+/// note when `None`) and returns the Markdown linking to it from the note,
+/// as an image if it is one.
+pub fn attach(source: &Path, note: Option<&Path>) -> std::io::Result<String> {
+    let name = note.and_then(|n| n.file_stem()).map_or_else(|| "Untitled".into(), |s| s.to_string_lossy());
+    let dir = Path::new(ATTACHMENTS_DIR).join(safe_file_name(&name));
+    std::fs::create_dir_all(&dir)?;
+    let name = source.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let stem = source.file_stem().map_or_else(|| "attachment".to_owned(), |s| safe_file_name(&s.to_string_lossy()));
+    let ext = source.extension().map(|e| e.to_string_lossy().into_owned()).unwrap_or_default();
+    let attached = unique_path(&dir, &stem, &ext);
+    std::fs::copy(source, &attached)?;
+    // Links resolve from the note's folder.
+    let from = note.and_then(Path::parent).unwrap_or(Path::new(NOTES_DIR));
+    let link = crate::links::percent_encode(&relative_path(from, &attached));
+    let image = ["png", "jpg", "jpeg", "gif", "webp", "svg", "bmp"].contains(&ext.to_lowercase().as_str());
+    Ok(format!("{}[{}]({})", if image { "!" } else { "" }, name, link))
+}
@@ -0,0 +1,300 @@
+// Browser build of the note app, run by eframe's web runner. Build and
+// serve it with `trunk serve --features web` from the repository root.
+//
+// This is a smaller editor of its own, not the desktop `NoteApp`, which
+// is built on the file system, threads and native dialogs throughout. It
+// keeps notes in the browser's IndexedDB, edits them as plain text, checks
+// grammar through LanguageTool and opens and downloads files through the
+// browser's pickers. The preview, search, plugins, sync and the rest of the
+// desktop features are not part of it.
+
+#[cfg(target_arch = "wasm32")]
+#[path = "../grammar.rs"]
+mod grammar;
+#[cfg(target_arch = "wasm32")]
+mod storage;
+
+#[cfg(target_arch = "wasm32")]
+mod app {
+    use crate::grammar::{self, LTMatch};
+    use crate::storage::Storage;
+    use eframe::egui::{self, FontData, FontDefinitions, FontFamily, RichText};
+    use std::collections::BTreeMap;
+    use std::sync::mpsc::{self, Receiver, Sender};
+
+    /// Work finished in the background: browser APIs answer asynchronously.
+    enum Done {
+        Checked(Result<Vec<LTMatch>, String>),
+        /// A file picked for opening, with its name and text.
+        Opened(String, String),
+    }
+
+    pub struct WebApp {
+        storage: Storage,
+        /// Every note, by name, once storage has loaded them.
+        notes: Option<BTreeMap<String, String>>,
+        selected: Option<String>,
+        text: String,
+        /// The selected note's text as last stored.
+        saved: String,
+        new_name: String,
+        suggestions: Vec<LTMatch>,
+        checking: bool,
+        status: Option<String>,
+        tx: Sender<Done>,
+        done: Receiver<Done>,
+    }
+
+    impl WebApp {
+        pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+            let mut fonts = FontDefinitions::default();
+            fonts
+                .font_data
+                .insert("Minigap".to_owned(), FontData::from_static(include_bytes!("../../fonts/Minigap-Regular.ttf")));
+            for family in [FontFamily::Proportional, FontFamily::Monospace] {
+                fonts.families.entry(family).or_default().insert(0, "Minigap".to_owned());
+            }
+            cc.egui_ctx.set_fonts(fonts);
+            cc.egui_ctx.set_visuals(egui::Visuals::dark());
+
+            let (tx, done) = mpsc::channel();
+            Self {
+                storage: Storage::open(&cc.egui_ctx),
+                notes: None,
+                selected: None,
+                text: String::new(),
+                saved: String::new(),
+                new_name: String::new(),
+                suggestions: Vec::new(),
+                checking: false,
+                status: None,
+                tx,
+                done,
+            }
+        }
+
+        fn select(&mut self, name: &str) {
+            let text = self.notes.as_ref().and_then(|n| n.get(name)).cloned().unwrap_or_default();
+            self.selected = Some(name.to_owned());
+            self.saved = text.clone();
+            self.text = text;
+            self.suggestions.clear();
+        }
+
+        fn save(&mut self) {
+            let (Some(name), Some(notes)) = (self.selected.clone(), self.notes.as_mut()) else {
+                return;
+            };
+            match self.storage.put(&name, &self.text) {
+                Ok(()) => {
+                    notes.insert(name, self.text.clone());
+                    self.saved = self.text.clone();
+                }
+                Err(err) => self.status = Some(format!("Failed to save: {}", err)),
+            }
+        }
+
+        fn create(&mut self, name: String, text: String) {
+            let Some(notes) = self.notes.as_mut() else {
+                return;
+            };
+            notes.insert(name.clone(), text);
+            self.select(&name);
+            self.save();
+        }
+
+        fn delete(&mut self) {
+            let (Some(name), Some(notes)) = (self.selected.take(), self.notes.as_mut()) else {
+                return;
+            };
+            match self.storage.delete(&name) {
+                Ok(()) => {
+                    notes.remove(&name);
+                    self.text.clear();
+                    self.saved.clear();
+                }
+                Err(err) => {
+                    self.status = Some(format!("Failed to delete: {}", err));
+                    self.selected = Some(name);
+                }
+            }
+        }
+
+        fn check(&mut self, ctx: &egui::Context) {
+            self.checking = true;
+            let (text, tx, ctx) = (self.text.clone(), self.tx.clone(), ctx.clone());
+            wasm_bindgen_futures::spawn_local(async move {
+                let result = grammar::check_async(&text, grammar::DEFAULT_LANGUAGE).await.map_err(|e| e.to_string());
+                let _ = tx.send(Done::Checked(result));
+                ctx.request_repaint();
+            });
+        }
+
+        /// Opens a file from the computer through the browser's picker.
+        fn open_file(&self, ctx: &egui::Context) {
+            let (tx, ctx) = (self.tx.clone(), ctx.clone());
+            wasm_bindgen_futures::spawn_local(async move {
+                let Some(file) = rfd::AsyncFileDialog::new().add_filter("Notes", &["md", "txt"]).pick_file().await else {
+                    return;
+                };
+                let text = String::from_utf8_lossy(&file.read().await).into_owned();
+                let _ = tx.send(Done::Opened(file.file_name(), text));
+                ctx.request_repaint();
+            });
+        }
+
+        /// Downloads the open note through the browser's save dialog.
+        fn save_file(&self) {
+            let name = self.selected.clone().unwrap_or_else(|| "note.md".to_owned());
+            let text = self.text.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Some(file) = rfd::AsyncFileDialog::new().set_file_name(&name).save_file().await
+                    && let Err(err) = file.write(text.as_bytes()).await
+                {
+                    web_sys::console::error_1(&err.to_string().into());
+                }
+            });
+        }
+
+        fn receive(&mut self) {
+            if self.notes.is_none()
+                && let Some(loaded) = self.storage.take_loaded()
+            {
+                match loaded {
+                    Ok(notes) => self.notes = Some(notes),
+                    Err(err) => {
+                        self.status = Some(format!("Notes are not stored: {}", err));
+                        self.notes = Some(BTreeMap::new());
+                    }
+                }
+            }
+            while let Ok(done) = self.done.try_recv() {
+                match done {
+                    Done::Checked(result) => {
+                        self.checking = false;
+                        match result {
+                            Ok(matches) => self.suggestions = matches,
+                            Err(err) => self.status = Some(format!("Suggestion error: {}", err)),
+                        }
+                    }
+                    Done::Opened(name, text) => self.create(name, text),
+                }
+            }
+        }
+    }
+
+    impl eframe::App for WebApp {
+        fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+            self.receive();
+
+            egui::TopBottomPanel::top("web_menu").show(ctx, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    let open = self.selected.is_some();
+                    if ui.add_enabled(open && self.text != self.saved, egui::Button::new("💾 Save")).clicked() {
+                        self.save();
+                    }
+                    if ui.add_enabled(open && !self.checking, egui::Button::new("✔ Check Grammar")).clicked() {
+                        self.check(ctx);
+                    }
+                    if ui.button("📂 Open File…").clicked() {
+                        self.open_file(ctx);
+                    }
+                    if ui.add_enabled(open, egui::Button::new("⬇ Save to File…")).clicked() {
+                        self.save_file();
+                    }
+                    if ui.add_enabled(open, egui::Button::new("🗑 Delete")).clicked() {
+                        self.delete();
+                    }
+                    if let Some(status) = &self.status {
+                        ui.separator();
+                        ui.label(RichText::new(status).weak());
+                    }
+                });
+            });
+
+            egui::SidePanel::left("web_notes").default_width(200.0).show(ctx, |ui| {
+                let Some(notes) = &self.notes else {
+                    ui.spinner();
+                    return;
+                };
+                let mut clicked = None;
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for name in notes.keys() {
+                        if ui.selectable_label(self.selected.as_ref() == Some(name), name).clicked() {
+                            clicked = Some(name.clone());
+                        }
+                    }
+                });
+                ui.separator();
+                let response = ui.add(egui::TextEdit::singleline(&mut self.new_name).hint_text("New note"));
+                let name = self.new_name.trim().to_owned();
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) && !name.is_empty() {
+                    let name = if name.ends_with(".md") || name.ends_with(".txt") { name } else { format!("{}.md", name) };
+                    self.new_name.clear();
+                    self.create(name, String::new());
+                }
+                if let Some(name) = clicked {
+                    if self.text != self.saved {
+                        self.save();
+                    }
+                    self.select(&name);
+                }
+            });
+
+            if !self.suggestions.is_empty() {
+                let mut apply = None;
+                egui::Window::new("💡 Suggestions").default_width(300.0).collapsible(false).show(ctx, |ui| {
+                    for suggestion in &self.suggestions {
+                        let range = suggestion.offset..suggestion.offset + suggestion.length;
+                        let Some(snippet) = self.text.get(range.clone()) else {
+                            continue;
+                        };
+                        let replacement = suggestion.replacements.first().map(|r| r.value.as_str()).unwrap_or("❌");
+                        if ui.button(format!("{} → {}", snippet, replacement)).on_hover_text(&suggestion.message).clicked() {
+                            apply = Some((range, replacement.to_owned()));
+                        }
+                    }
+                });
+                if let Some((range, replacement)) = apply {
+                    self.text.replace_range(range, &replacement);
+                    self.check(ctx);
+                }
+            }
+
+            egui::CentralPanel::default().show(ctx, |ui| {
+                if self.selected.is_none() {
+                    ui.centered_and_justified(|ui| {
+                        ui.label(
+                            RichText::new(
+                                "Pick a note or create one.\n\nNotes are kept in this browser. This version edits and checks them; \
+                                 the desktop app has the rest.",
+                            )
+                            .weak(),
+                        )
+                    });
+                    return;
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.add_sized(ui.available_size(), egui::TextEdit::multiline(&mut self.text).frame(false));
+                });
+            });
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    wasm_bindgen_futures::spawn_local(async {
+        let started = eframe::WebRunner::new()
+            .start("note_app_canvas", eframe::WebOptions::default(), Box::new(|cc| Box::new(app::WebApp::new(cc))))
+            .await;
+        if let Err(err) = started {
+            web_sys::console::error_1(&err);
+        }
+    });
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    eprintln!("This is the browser build; run `trunk serve --features web` to try it, or `cargo run` for the desktop app.");
+}
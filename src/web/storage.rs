@@ -0,0 +1,123 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use web_sys::{IdbDatabase, IdbOpenDbRequest, IdbRequest, IdbTransactionMode};
+
+const DATABASE: &str = "note_app";
+const STORE: &str = "notes";
+
+/// Set once the notes have been read, or the reason they could not be.
+type Loaded = Rc<RefCell<Option<Result<BTreeMap<String, String>, String>>>>;
+
+/// The vault in the browser: an IndexedDB object store with each note's
+/// text keyed by its name. Everything is read once at startup and kept in
+/// memory; changes are written through.
+#[derive(Clone, Default)]
+pub struct Storage {
+    db: Rc<RefCell<Option<IdbDatabase>>>,
+    loaded: Loaded,
+}
+
+fn describe(err: JsValue) -> String {
+    err.as_string().or_else(|| err.dyn_ref::<js_sys::Error>().map(|e| e.message().into())).unwrap_or_else(|| format!("{:?}", err))
+}
+
+impl Storage {
+    /// Opens the database in the background; `ctx` is repainted once the
+    /// notes are in.
+    pub fn open(ctx: &eframe::egui::Context) -> Self {
+        let storage = Self::default();
+        if let Err(err) = storage.start(ctx.clone()) {
+            *storage.loaded.borrow_mut() = Some(Err(describe(err)));
+        }
+        storage
+    }
+
+    fn start(&self, ctx: eframe::egui::Context) -> Result<(), JsValue> {
+        let factory = web_sys::window()
+            .and_then(|w| w.indexed_db().ok().flatten())
+            .ok_or_else(|| JsValue::from_str("this browser has no IndexedDB"))?;
+        let request: IdbOpenDbRequest = factory.open_with_u32(DATABASE, 1)?;
+
+        let upgrade = Closure::once_into_js(move |event: web_sys::Event| {
+            let db = event.target().and_then(|t| t.dyn_into::<IdbRequest>().ok()).and_then(|r| r.result().ok());
+            if let Some(db) = db.and_then(|db| db.dyn_into::<IdbDatabase>().ok())
+                && let Err(err) = db.create_object_store(STORE)
+            {
+                web_sys::console::error_1(&err);
+            }
+        });
+        request.set_onupgradeneeded(Some(upgrade.unchecked_ref()));
+
+        let (db_slot, loaded) = (self.db.clone(), self.loaded.clone());
+        let failed = loaded.clone();
+        let success = Closure::once_into_js(move |event: web_sys::Event| {
+            let result = event
+                .target()
+                .and_then(|t| t.dyn_into::<IdbRequest>().ok())
+                .ok_or_else(|| JsValue::from_str("no database"))
+                .and_then(|r| r.result())
+                .and_then(|db| db.dyn_into::<IdbDatabase>());
+            match result {
+                Ok(db) => {
+                    if let Err(err) = read_all(&db, loaded, ctx) {
+                        web_sys::console::error_1(&err);
+                    }
+                    *db_slot.borrow_mut() = Some(db);
+                }
+                Err(err) => *loaded.borrow_mut() = Some(Err(describe(err))),
+            }
+        });
+        request.set_onsuccess(Some(success.unchecked_ref()));
+
+        let error = Closure::once_into_js(move |_: web_sys::Event| {
+            *failed.borrow_mut() = Some(Err("the browser refused to open the notes database".to_owned()));
+        });
+        request.set_onerror(Some(error.unchecked_ref()));
+        Ok(())
+    }
+
+    /// The notes once they have been read; taken by the first caller.
+    pub fn take_loaded(&self) -> Option<Result<BTreeMap<String, String>, String>> {
+        self.loaded.borrow_mut().take()
+    }
+
+    pub fn put(&self, name: &str, text: &str) -> Result<(), String> {
+        self.with_store(|store| store.put_with_key(&JsValue::from_str(text), &JsValue::from_str(name)).map(|_| ()))
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), String> {
+        self.with_store(|store| store.delete(&JsValue::from_str(name)).map(|_| ()))
+    }
+
+    fn with_store(&self, f: impl FnOnce(&web_sys::IdbObjectStore) -> Result<(), JsValue>) -> Result<(), String> {
+        let db = self.db.borrow();
+        let db = db.as_ref().ok_or("the notes database is not open yet")?;
+        db.transaction_with_str_and_mode(STORE, IdbTransactionMode::Readwrite)
+            .and_then(|tx| tx.object_store(STORE))
+            .and_then(|store| f(&store))
+            .map_err(describe)
+    }
+}
+
+/// Reads every key and value; both requests return them in key order.
+fn read_all(db: &IdbDatabase, loaded: Loaded, ctx: eframe::egui::Context) -> Result<(), JsValue> {
+    let store = db.transaction_with_str(STORE)?.object_store(STORE)?;
+    let keys = store.get_all_keys()?;
+    let values = store.get_all()?;
+    let done = Closure::once_into_js(move |_: web_sys::Event| {
+        let result = keys.result().and_then(|keys| values.result().map(|values| (keys, values)));
+        let notes = result.map_err(describe).map(|(keys, values)| {
+            let keys = js_sys::Array::from(&keys);
+            let values = js_sys::Array::from(&values);
+            keys.iter().zip(values.iter()).filter_map(|(k, v)| Some((k.as_string()?, v.as_string()?))).collect()
+        });
+        *loaded.borrow_mut() = Some(notes);
+        ctx.request_repaint();
+    });
+    // The transaction completes once both requests have their results.
+    store.transaction().set_oncomplete(Some(done.unchecked_ref()));
+    Ok(())
+}